@@ -0,0 +1,236 @@
+//! Extract the token of an `Authorization: Bearer` header into a request extension.
+//!
+//! [`ExtractBearer`] inserts a [`BearerToken`] extension into requests with an `Authorization:
+//! Bearer {token}` header, for handlers that need the raw token, for example to forward it to
+//! another service. Requests without such a header are passed through unchanged.
+//!
+//! The token isn't validated. Use [`ValidateRequestHeader::bearer`] for that.
+//!
+//! # Example
+//!
+//! ```
+//! use tower_http::auth::extract_bearer::{BearerToken, ExtractBearerLayer};
+//! use http::{Request, Response, header::AUTHORIZATION};
+//! use http_body_util::Full;
+//! use bytes::Bytes;
+//! use tower::{Service, ServiceExt, ServiceBuilder, BoxError};
+//!
+//! async fn handle(request: Request<Full<Bytes>>) -> Result<Response<Full<Bytes>>, BoxError> {
+//!     let token = request.extensions().get::<BearerToken>().unwrap();
+//!     assert_eq!(token.0, "hunter2");
+//!
+//!     // the header was removed
+//!     assert!(request.headers().get(AUTHORIZATION).is_none());
+//!
+//!     Ok(Response::new(Full::default()))
+//! }
+//!
+//! # #[tokio::main]
+//! # async fn main() -> Result<(), BoxError> {
+//! let mut service = ServiceBuilder::new()
+//!     .layer(ExtractBearerLayer::new().remove_header(true))
+//!     .service_fn(handle);
+//!
+//! let request = Request::builder()
+//!     .header(AUTHORIZATION, "Bearer hunter2")
+//!     .body(Full::default())?;
+//!
+//! service.ready().await?.call(request).await?;
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! [`ValidateRequestHeader::bearer`]: crate::validate_request::ValidateRequestHeader::bearer
+
+use super::require_authorization::parse_bearer;
+use http::{header, Request};
+use std::{
+    fmt,
+    task::{Context, Poll},
+};
+use tower_layer::Layer;
+use tower_service::Service;
+
+/// Layer that applies [`ExtractBearer`] which extracts bearer tokens into a request extension.
+///
+/// See the [module docs](crate::auth::extract_bearer) for more details.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ExtractBearerLayer {
+    remove_header: bool,
+}
+
+impl ExtractBearerLayer {
+    /// Create a new [`ExtractBearerLayer`].
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Remove the `Authorization` header from requests after extracting the token, so it isn't
+    /// seen by the inner service.
+    ///
+    /// Defaults to `false`.
+    pub fn remove_header(mut self, remove_header: bool) -> Self {
+        self.remove_header = remove_header;
+        self
+    }
+}
+
+impl<S> Layer<S> for ExtractBearerLayer {
+    type Service = ExtractBearer<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        ExtractBearer {
+            inner,
+            remove_header: self.remove_header,
+        }
+    }
+}
+
+/// Middleware that extracts bearer tokens into a request extension.
+///
+/// See the [module docs](crate::auth::extract_bearer) for more details.
+#[derive(Debug, Clone, Copy)]
+pub struct ExtractBearer<S> {
+    inner: S,
+    remove_header: bool,
+}
+
+impl<S> ExtractBearer<S> {
+    /// Create a new [`ExtractBearer`].
+    pub fn new(inner: S) -> Self {
+        ExtractBearerLayer::new().layer(inner)
+    }
+
+    /// Remove the `Authorization` header from requests after extracting the token, so it isn't
+    /// seen by the inner service.
+    ///
+    /// Defaults to `false`.
+    pub fn remove_header(mut self, remove_header: bool) -> Self {
+        self.remove_header = remove_header;
+        self
+    }
+
+    define_inner_service_accessors!();
+
+    /// Returns a new [`Layer`] that wraps services with an `ExtractBearer` middleware.
+    ///
+    /// [`Layer`]: tower_layer::Layer
+    pub fn layer() -> ExtractBearerLayer {
+        ExtractBearerLayer::new()
+    }
+}
+
+impl<S, ReqBody> Service<Request<ReqBody>> for ExtractBearer<S>
+where
+    S: Service<Request<ReqBody>>,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = S::Future;
+
+    #[inline]
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, mut req: Request<ReqBody>) -> Self::Future {
+        let token = req
+            .headers()
+            .get(header::AUTHORIZATION)
+            .and_then(parse_bearer)
+            .map(|token| BearerToken(token.to_owned()));
+
+        if let Some(token) = token {
+            if self.remove_header {
+                req.headers_mut().remove(header::AUTHORIZATION);
+            }
+            req.extensions_mut().insert(token);
+        }
+
+        self.inner.call(req)
+    }
+}
+
+/// The token of the `Authorization: Bearer {token}` header of a request.
+///
+/// Inserted into requests by [`ExtractBearer`]. The `Debug` implementation doesn't show the
+/// token, so it doesn't end up in logs.
+///
+/// See the [module docs](crate::auth::extract_bearer) for more details.
+#[derive(Clone, PartialEq, Eq)]
+pub struct BearerToken(pub String);
+
+impl fmt::Debug for BearerToken {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("BearerToken").field(&"[redacted]").finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use http::{HeaderMap, HeaderValue};
+    use std::convert::Infallible;
+    use tower::{ServiceBuilder, ServiceExt};
+
+    async fn extract(
+        layer: ExtractBearerLayer,
+        authorization: Option<&'static str>,
+    ) -> (Option<BearerToken>, HeaderMap) {
+        let svc = ServiceBuilder::new()
+            .layer(layer)
+            .service_fn(|req: Request<()>| async move {
+                let token = req.extensions().get::<BearerToken>().cloned();
+                Ok::<_, Infallible>((token, req.headers().clone()))
+            });
+
+        let mut req = Request::new(());
+        if let Some(authorization) = authorization {
+            req.headers_mut().insert(
+                header::AUTHORIZATION,
+                HeaderValue::from_static(authorization),
+            );
+        }
+        svc.oneshot(req).await.unwrap()
+    }
+
+    #[tokio::test]
+    async fn extracts_token() {
+        let (token, headers) = extract(ExtractBearerLayer::new(), Some("Bearer foobar")).await;
+        assert_eq!(token.unwrap().0, "foobar");
+        assert_eq!(headers[header::AUTHORIZATION], "Bearer foobar");
+
+        let (token, headers) = extract(
+            ExtractBearerLayer::new().remove_header(true),
+            Some("Bearer foobar"),
+        )
+        .await;
+        assert_eq!(token.unwrap().0, "foobar");
+        assert!(headers.get(header::AUTHORIZATION).is_none());
+    }
+
+    #[tokio::test]
+    async fn passes_through_without_token() {
+        let layer = ExtractBearerLayer::new().remove_header(true);
+
+        let (token, _) = extract(layer, None).await;
+        assert!(token.is_none());
+
+        for malformed in [
+            "Basic Zm9vOmJhcg==",
+            "bearer foobar",
+            "Bearer ",
+            "Bearerfoobar",
+        ] {
+            let (token, headers) = extract(layer, Some(malformed)).await;
+            assert!(token.is_none(), "{}", malformed);
+            assert_eq!(headers[header::AUTHORIZATION], malformed);
+        }
+    }
+
+    #[test]
+    fn debug_hides_token() {
+        let token = BearerToken("foobar".to_owned());
+        assert!(!format!("{:?}", token).contains("foobar"));
+    }
+}
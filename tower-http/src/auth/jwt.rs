@@ -0,0 +1,361 @@
+//! Authorize requests with JSON Web Tokens using [`ValidateRequest`].
+//!
+//! [`ValidateJwt`] requires requests to have an `Authorization: Bearer {token}` header where
+//! `token` is a [JSON Web Token][rfc] signed with a configured [`JwtKey`]. The `alg` of the token
+//! has to match the algorithm of the key, and the token is rejected if it has expired according
+//! to its `exp` claim, or isn't valid yet according to its `nbf` claim. The claims of valid tokens
+//! are deserialized into a `C` and inserted into the request extensions, and other requests get a
+//! `401 Unauthorized` response.
+//!
+//! # Example
+//!
+//! ```
+//! use tower_http::{
+//!     auth::jwt::{JwtKey, ValidateJwt},
+//!     validate_request::ValidateRequestHeaderLayer,
+//! };
+//! use http::{Request, Response, StatusCode, header::AUTHORIZATION};
+//! use http_body_util::Full;
+//! use bytes::Bytes;
+//! use serde_json::Value;
+//! use tower::{Service, ServiceExt, ServiceBuilder, BoxError};
+//!
+//! async fn handle(request: Request<Full<Bytes>>) -> Result<Response<Full<Bytes>>, BoxError> {
+//!     // the claims of the token
+//!     let claims = request.extensions().get::<Value>().unwrap();
+//!     assert_eq!(claims["sub"], "1234567890");
+//!     Ok(Response::new(Full::default()))
+//! }
+//!
+//! # #[tokio::main]
+//! # async fn main() -> Result<(), BoxError> {
+//! let key = JwtKey::hs256("your-256-bit-secret");
+//! let mut service = ServiceBuilder::new()
+//!     .layer(ValidateRequestHeaderLayer::custom(ValidateJwt::<Value, _>::new(key)))
+//!     .service_fn(handle);
+//!
+//! let request = Request::builder()
+//!     .header(
+//!         AUTHORIZATION,
+//!         "Bearer eyJhbGciOiJIUzI1NiIsInR5cCI6IkpXVCJ9.\
+//!          eyJzdWIiOiIxMjM0NTY3ODkwIiwibmFtZSI6IkpvaG4gRG9lIiwiaWF0IjoxNTE2MjM5MDIyfQ.\
+//!          SflKxwRJSMeKKF2QT4fwpMeJf36POk6yJV_adQssw5c",
+//!     )
+//!     .body(Full::default())?;
+//! let response = service.ready().await?.call(request).await?;
+//! assert_eq!(response.status(), StatusCode::OK);
+//!
+//! let request = Request::builder()
+//!     .header(AUTHORIZATION, "Bearer not-a-jwt")
+//!     .body(Full::default())?;
+//! let response = service.ready().await?.call(request).await?;
+//! assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! [rfc]: https://www.rfc-editor.org/rfc/rfc7519
+
+use super::require_authorization::parse_bearer;
+use crate::{validate_request::ValidateRequest, BoxError};
+use base64::Engine as _;
+use http::{header, Request, Response, StatusCode};
+use openssl::{
+    hash::MessageDigest,
+    memcmp,
+    pkey::{Id, PKey, Public},
+    sign::{Signer, Verifier},
+};
+use serde::de::DeserializeOwned;
+use serde_json::Value;
+use std::{
+    fmt,
+    marker::PhantomData,
+    sync::Arc,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+const BASE64: base64::engine::GeneralPurpose = base64::engine::general_purpose::URL_SAFE_NO_PAD;
+
+/// A key that JSON Web Tokens are signed with.
+///
+/// The key decides which algorithm is accepted in the `alg` header of tokens, so that a token
+/// can't pick a weaker one.
+#[derive(Clone)]
+pub struct JwtKey(KeyRepr);
+
+#[derive(Clone)]
+enum KeyRepr {
+    Hs256(Vec<u8>),
+    Rs256(PKey<Public>),
+}
+
+impl JwtKey {
+    /// Create a key for tokens signed with HMAC using SHA-256 (`HS256`) and the shared `secret`.
+    pub fn hs256(secret: impl Into<Vec<u8>>) -> Self {
+        JwtKey(KeyRepr::Hs256(secret.into()))
+    }
+
+    /// Create a key for tokens signed with RSASSA-PKCS1-v1_5 using SHA-256 (`RS256`), from the
+    /// PEM encoded public key of the signer.
+    ///
+    /// Returns an error if `pem` isn't an RSA public key.
+    pub fn rs256_pem(pem: &[u8]) -> Result<Self, BoxError> {
+        let key = PKey::public_key_from_pem(pem)?;
+        if key.id() != Id::RSA {
+            return Err("not an RSA public key".into());
+        }
+        Ok(JwtKey(KeyRepr::Rs256(key)))
+    }
+
+    fn algorithm(&self) -> &'static str {
+        match self.0 {
+            KeyRepr::Hs256(_) => "HS256",
+            KeyRepr::Rs256(_) => "RS256",
+        }
+    }
+
+    fn verify(&self, message: &[u8], signature: &[u8]) -> bool {
+        let verified = match &self.0 {
+            KeyRepr::Hs256(secret) => PKey::hmac(secret).and_then(|key| {
+                let mut signer = Signer::new(MessageDigest::sha256(), &key)?;
+                signer.update(message)?;
+                let expected = signer.sign_to_vec()?;
+                Ok(expected.len() == signature.len() && memcmp::eq(&expected, signature))
+            }),
+            KeyRepr::Rs256(key) => Verifier::new(MessageDigest::sha256(), key).and_then(|mut v| {
+                v.update(message)?;
+                v.verify(signature)
+            }),
+        };
+        verified.unwrap_or(false)
+    }
+}
+
+impl fmt::Debug for JwtKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // don't print secrets
+        f.debug_tuple("JwtKey").field(&self.algorithm()).finish()
+    }
+}
+
+/// Type that authorizes requests with JSON Web Tokens, inserting their claims as a `C` into the
+/// request extensions.
+///
+/// See the [module docs](crate::auth::jwt) for more details.
+pub struct ValidateJwt<C, ResBody> {
+    key: Arc<JwtKey>,
+    _ty: PhantomData<fn() -> (C, ResBody)>,
+}
+
+impl<C, ResBody> ValidateJwt<C, ResBody> {
+    /// Create a new [`ValidateJwt`] that accepts tokens signed with `key`.
+    pub fn new(key: JwtKey) -> Self {
+        Self {
+            key: Arc::new(key),
+            _ty: PhantomData,
+        }
+    }
+
+    fn claims(&self, token: &str) -> Option<Value> {
+        let mut parts = token.split('.');
+        let (header, payload, signature) = match (parts.next(), parts.next(), parts.next()) {
+            (Some(header), Some(payload), Some(signature)) => (header, payload, signature),
+            _ => return None,
+        };
+        if parts.next().is_some() {
+            return None;
+        }
+
+        let jose_header: Value = serde_json::from_slice(&BASE64.decode(header).ok()?).ok()?;
+        if jose_header.get("alg").and_then(Value::as_str) != Some(self.key.algorithm()) {
+            return None;
+        }
+        let message = &token[..header.len() + 1 + payload.len()];
+        if !self
+            .key
+            .verify(message.as_bytes(), &BASE64.decode(signature).ok()?)
+        {
+            return None;
+        }
+
+        let claims: Value = serde_json::from_slice(&BASE64.decode(payload).ok()?).ok()?;
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .ok()?
+            .as_secs_f64();
+        let exp = claims.get("exp");
+        let nbf = claims.get("nbf");
+        let expired = match exp {
+            Some(exp) => exp.as_f64().map_or(true, |exp| now >= exp),
+            None => false,
+        };
+        let not_yet_valid = match nbf {
+            Some(nbf) => nbf.as_f64().map_or(true, |nbf| now < nbf),
+            None => false,
+        };
+        if expired || not_yet_valid {
+            return None;
+        }
+
+        Some(claims)
+    }
+}
+
+impl<C, ResBody> Clone for ValidateJwt<C, ResBody> {
+    fn clone(&self) -> Self {
+        Self {
+            key: self.key.clone(),
+            _ty: PhantomData,
+        }
+    }
+}
+
+impl<C, ResBody> fmt::Debug for ValidateJwt<C, ResBody> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ValidateJwt")
+            .field("key", &self.key)
+            .finish()
+    }
+}
+
+impl<B, C, ResBody> ValidateRequest<B> for ValidateJwt<C, ResBody>
+where
+    C: DeserializeOwned + Clone + Send + Sync + 'static,
+    ResBody: Default,
+{
+    type ResponseBody = ResBody;
+
+    fn validate(&mut self, request: &mut Request<B>) -> Result<(), Response<Self::ResponseBody>> {
+        let claims = request
+            .headers()
+            .get(header::AUTHORIZATION)
+            .and_then(parse_bearer)
+            .and_then(|token| self.claims(token))
+            .and_then(|claims| serde_json::from_value::<C>(claims).ok());
+
+        match claims {
+            Some(claims) => {
+                request.extensions_mut().insert(claims);
+                Ok(())
+            }
+            None => {
+                let mut res = Response::new(ResBody::default());
+                *res.status_mut() = StatusCode::UNAUTHORIZED;
+                Err(res)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{test_helpers::Body, validate_request::ValidateRequestHeaderLayer};
+    use openssl::{pkey::Private, rsa::Rsa};
+    use serde_json::json;
+    use tower::{ServiceBuilder, ServiceExt};
+
+    const SECRET: &[u8] = b"secret";
+
+    fn now() -> u64 {
+        SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_secs()
+    }
+
+    fn hs256_token(secret: &[u8], claims: Value) -> String {
+        token("HS256", claims, |message| {
+            let key = PKey::hmac(secret).unwrap();
+            let mut signer = Signer::new(MessageDigest::sha256(), &key).unwrap();
+            signer.update(message).unwrap();
+            signer.sign_to_vec().unwrap()
+        })
+    }
+
+    fn rs256_token(key: &PKey<Private>, claims: Value) -> String {
+        token("RS256", claims, |message| {
+            let mut signer = Signer::new(MessageDigest::sha256(), key).unwrap();
+            signer.update(message).unwrap();
+            signer.sign_to_vec().unwrap()
+        })
+    }
+
+    fn token(alg: &str, claims: Value, sign: impl FnOnce(&[u8]) -> Vec<u8>) -> String {
+        let header = BASE64.encode(json!({ "alg": alg, "typ": "JWT" }).to_string());
+        let payload = BASE64.encode(claims.to_string());
+        let message = format!("{}.{}", header, payload);
+        let signature = BASE64.encode(sign(message.as_bytes()));
+        format!("{}.{}", message, signature)
+    }
+
+    async fn validate(key: JwtKey, token: &str) -> (StatusCode, Option<Value>) {
+        let svc = ServiceBuilder::new()
+            .layer(ValidateRequestHeaderLayer::custom(
+                ValidateJwt::<Value, _>::new(key),
+            ))
+            .service_fn(|req: Request<Body>| async move {
+                let claims = req.extensions().get::<Value>().cloned();
+                Ok::<_, BoxError>(Response::new(claims))
+            });
+        let req = Request::builder()
+            .header(header::AUTHORIZATION, format!("Bearer {}", token))
+            .body(Body::empty())
+            .unwrap();
+        let res = svc.oneshot(req).await.unwrap();
+        (res.status(), res.into_body())
+    }
+
+    #[tokio::test]
+    async fn valid_token() {
+        let claims = json!({ "sub": "alice", "exp": now() + 60, "nbf": now() - 60 });
+        let token = hs256_token(SECRET, claims.clone());
+        let (status, extension) = validate(JwtKey::hs256(SECRET), &token).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(extension, Some(claims));
+    }
+
+    #[tokio::test]
+    async fn valid_rs256_token() {
+        let key = PKey::from_rsa(Rsa::generate(2048).unwrap()).unwrap();
+        let public_key = JwtKey::rs256_pem(&key.public_key_to_pem().unwrap()).unwrap();
+        let claims = json!({ "sub": "alice" });
+        let (status, extension) = validate(public_key.clone(), &rs256_token(&key, claims)).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(extension.unwrap()["sub"], "alice");
+
+        // the algorithm has to match the key
+        let token = hs256_token(&key.public_key_to_pem().unwrap(), json!({}));
+        let (status, _) = validate(public_key, &token).await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+    }
+
+    #[tokio::test]
+    async fn expired_token() {
+        let token = hs256_token(SECRET, json!({ "sub": "alice", "exp": now() - 1 }));
+        let (status, extension) = validate(JwtKey::hs256(SECRET), &token).await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+        assert_eq!(extension, None);
+
+        let token = hs256_token(SECRET, json!({ "sub": "alice", "nbf": now() + 60 }));
+        let (status, _) = validate(JwtKey::hs256(SECRET), &token).await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+    }
+
+    #[tokio::test]
+    async fn bad_signature() {
+        let token = hs256_token(b"other secret", json!({ "sub": "alice" }));
+        let (status, extension) = validate(JwtKey::hs256(SECRET), &token).await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+        assert_eq!(extension, None);
+
+        // a tampered payload
+        let token = hs256_token(SECRET, json!({ "sub": "alice" }));
+        let mut parts = token.split('.').collect::<Vec<_>>();
+        let payload = BASE64.encode(json!({ "sub": "admin" }).to_string());
+        parts[1] = &payload;
+        let (status, _) = validate(JwtKey::hs256(SECRET), &parts.join(".")).await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+    }
+}
@@ -2,6 +2,9 @@
 
 pub mod add_authorization;
 pub mod async_require_authorization;
+pub mod extract_bearer;
+#[cfg(feature = "jwt")]
+pub mod jwt;
 pub mod require_authorization;
 
 #[doc(inline)]
@@ -10,4 +13,5 @@ pub use self::{
     async_require_authorization::{
         AsyncAuthorizeRequest, AsyncRequireAuthorization, AsyncRequireAuthorizationLayer,
     },
+    extract_bearer::{BearerToken, ExtractBearer, ExtractBearerLayer},
 };
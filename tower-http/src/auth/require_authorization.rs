@@ -185,6 +185,18 @@ where
     }
 }
 
+/// Get the token of an `Authorization: Bearer {token}` header value.
+///
+/// Like [`Bearer`], the `Bearer` prefix is case sensitive.
+pub(super) fn parse_bearer(value: &HeaderValue) -> Option<&str> {
+    let token = value.to_str().ok()?.strip_prefix("Bearer ")?.trim();
+    if token.is_empty() {
+        None
+    } else {
+        Some(token)
+    }
+}
+
 /// Type that performs basic authorization.
 ///
 /// See [`ValidateRequestHeader::basic`] for more details.
@@ -0,0 +1,421 @@
+//! Middleware that decodes base64 encoded request bodies.
+//!
+//! Some webhook providers encode the body of their requests with base64, and mark it with a
+//! `Content-Transfer-Encoding: base64` header. [`Base64Decode`] decodes the body of such requests
+//! before calling the inner service, removes the marker header, and sets the `Content-Length`
+//! header to the length of the decoded body. Requests without the marker are passed through
+//! unchanged.
+//!
+//! The body is buffered in memory to be decoded, so consider limiting its size, for example with
+//! [`RequestBodyLimit`](crate::limit::RequestBodyLimit). Line breaks and other whitespace in the
+//! encoded body are ignored. If the body isn't valid base64, or reading it fails, a `400 Bad
+//! Request` response is returned without calling the inner service.
+//!
+//! # Example
+//!
+//! ```
+//! use tower_http::{base64_decode::{Base64DecodeLayer, DecodedBody}, BoxError};
+//! use http::{Request, Response};
+//! use http_body_util::{BodyExt, Full};
+//! use bytes::Bytes;
+//! use tower::{ServiceBuilder, Service, ServiceExt};
+//!
+//! async fn handle(req: Request<DecodedBody<Full<Bytes>>>) -> Result<Response<Full<Bytes>>, BoxError> {
+//!     // The handler sees the decoded body.
+//!     let body = req.into_body().collect().await?.to_bytes();
+//!     assert_eq!(body, "hello");
+//!     Ok(Response::new(Full::default()))
+//! }
+//!
+//! # #[tokio::main]
+//! # async fn main() -> Result<(), BoxError> {
+//! let mut service = ServiceBuilder::new()
+//!     .layer(Base64DecodeLayer::new())
+//!     .service_fn(handle);
+//!
+//! let request = Request::builder()
+//!     .header("content-transfer-encoding", "base64")
+//!     .body(Full::from("aGVsbG8="))?;
+//! let response = service.ready().await?.call(request).await?;
+//! #
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::body::Full;
+use base64::Engine as _;
+use bytes::{Buf, Bytes};
+use http::{header, request::Parts, HeaderName, HeaderValue, Request, Response, StatusCode};
+use http_body::{Body, Frame, SizeHint};
+use http_body_util::{combinators::Collect, BodyExt};
+use pin_project_lite::pin_project;
+use std::{
+    future::Future,
+    mem,
+    pin::Pin,
+    task::{ready, Context, Poll},
+};
+use tower_layer::Layer;
+use tower_service::Service;
+
+const BASE64: base64::engine::GeneralPurpose = base64::engine::general_purpose::STANDARD;
+
+const CONTENT_TRANSFER_ENCODING: HeaderName = HeaderName::from_static("content-transfer-encoding");
+
+/// Layer that applies [`Base64Decode`] which decodes base64 encoded request bodies.
+///
+/// See the [module docs](self) for more details.
+#[derive(Debug, Clone)]
+pub struct Base64DecodeLayer {
+    header: HeaderName,
+}
+
+impl Base64DecodeLayer {
+    /// Create a new [`Base64DecodeLayer`] that decodes requests with a
+    /// `Content-Transfer-Encoding: base64` header.
+    pub fn new() -> Self {
+        Self {
+            header: CONTENT_TRANSFER_ENCODING,
+        }
+    }
+
+    /// Decode requests whose `header` has the value `base64`, instead of
+    /// `Content-Transfer-Encoding`.
+    pub fn header(mut self, header: HeaderName) -> Self {
+        self.header = header;
+        self
+    }
+}
+
+impl Default for Base64DecodeLayer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<S> Layer<S> for Base64DecodeLayer {
+    type Service = Base64Decode<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        Base64Decode {
+            inner,
+            header: self.header.clone(),
+        }
+    }
+}
+
+/// Middleware that decodes base64 encoded request bodies.
+///
+/// See the [module docs](self) for more details.
+#[derive(Debug, Clone)]
+pub struct Base64Decode<S> {
+    inner: S,
+    header: HeaderName,
+}
+
+impl<S> Base64Decode<S> {
+    /// Create a new [`Base64Decode`] that decodes requests with a
+    /// `Content-Transfer-Encoding: base64` header.
+    pub fn new(inner: S) -> Self {
+        Base64DecodeLayer::new().layer(inner)
+    }
+
+    /// Decode requests whose `header` has the value `base64`, instead of
+    /// `Content-Transfer-Encoding`.
+    pub fn header(mut self, header: HeaderName) -> Self {
+        self.header = header;
+        self
+    }
+
+    define_inner_service_accessors!();
+
+    /// Returns a new [`Layer`] that wraps services with a `Base64Decode` middleware.
+    ///
+    /// [`Layer`]: tower_layer::Layer
+    pub fn layer() -> Base64DecodeLayer {
+        Base64DecodeLayer::new()
+    }
+}
+
+impl<ReqBody, ResBody, S> Service<Request<ReqBody>> for Base64Decode<S>
+where
+    S: Service<Request<DecodedBody<ReqBody>>, Response = Response<ResBody>> + Clone,
+    ReqBody: Body,
+    ResBody: Default,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = ResponseFuture<ReqBody, S>;
+
+    #[inline]
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, req: Request<ReqBody>) -> Self::Future {
+        let encoded = req
+            .headers()
+            .get(&self.header)
+            .and_then(|value| value.to_str().ok())
+            .map_or(false, |value| value.trim().eq_ignore_ascii_case("base64"));
+        if !encoded {
+            let req = req.map(|body| DecodedBody {
+                inner: DecodedBodyInner::Passthrough { body },
+            });
+            return ResponseFuture {
+                inner: ResponseFutureInner::Future {
+                    future: self.inner.call(req),
+                },
+            };
+        }
+
+        // the inner service is called once the body is decoded, so take the ready service and
+        // leave a clone in its place
+        let clone = self.inner.clone();
+        let service = mem::replace(&mut self.inner, clone);
+
+        let (mut parts, body) = req.into_parts();
+        parts.headers.remove(&self.header);
+        ResponseFuture {
+            inner: ResponseFutureInner::Collect {
+                collect: body.collect(),
+                parts: Some((parts, service)),
+            },
+        }
+    }
+}
+
+// Decodes base64 ignoring ASCII whitespace, such as the line breaks of MIME encoded bodies.
+fn decode(mut encoded: impl Buf) -> Option<Bytes> {
+    let mut buf = Vec::with_capacity(encoded.remaining());
+    while encoded.has_remaining() {
+        let chunk = encoded.chunk();
+        buf.extend(chunk.iter().filter(|b| !b.is_ascii_whitespace()));
+        let len = chunk.len();
+        encoded.advance(len);
+    }
+    BASE64.decode(buf).ok().map(Bytes::from)
+}
+
+pin_project! {
+    /// Response future for [`Base64Decode`].
+    pub struct ResponseFuture<B, S>
+    where
+        B: Body,
+        S: Service<Request<DecodedBody<B>>>,
+    {
+        #[pin]
+        inner: ResponseFutureInner<B, S>,
+    }
+}
+
+pin_project! {
+    #[project = ResFutProj]
+    enum ResponseFutureInner<B, S>
+    where
+        B: Body,
+        S: Service<Request<DecodedBody<B>>>,
+    {
+        Collect {
+            #[pin]
+            collect: Collect<B>,
+            parts: Option<(Parts, S)>,
+        },
+        BadRequest,
+        Future {
+            #[pin]
+            future: S::Future,
+        }
+    }
+}
+
+impl<B, S, ResBody> Future for ResponseFuture<B, S>
+where
+    B: Body,
+    S: Service<Request<DecodedBody<B>>, Response = Response<ResBody>>,
+    ResBody: Default,
+{
+    type Output = Result<Response<ResBody>, S::Error>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let mut this = self.project();
+        loop {
+            let next = match this.inner.as_mut().project() {
+                ResFutProj::Collect { collect, parts } => {
+                    let decoded = match ready!(collect.poll(cx)) {
+                        Ok(collected) => decode(collected.aggregate()),
+                        Err(_) => None,
+                    };
+                    match (decoded, parts.take()) {
+                        (Some(decoded), Some((mut parts, mut service))) => {
+                            parts.headers.remove(header::TRANSFER_ENCODING);
+                            parts
+                                .headers
+                                .insert(header::CONTENT_LENGTH, HeaderValue::from(decoded.len()));
+                            let body = DecodedBody {
+                                inner: DecodedBodyInner::Decoded {
+                                    body: Full::new(http_body_util::Full::new(decoded)),
+                                },
+                            };
+                            ResponseFutureInner::Future {
+                                future: service.call(Request::from_parts(parts, body)),
+                            }
+                        }
+                        _ => ResponseFutureInner::BadRequest,
+                    }
+                }
+                ResFutProj::BadRequest => {
+                    let mut res = Response::new(ResBody::default());
+                    *res.status_mut() = StatusCode::BAD_REQUEST;
+                    return Poll::Ready(Ok(res));
+                }
+                ResFutProj::Future { future } => return future.poll(cx),
+            };
+            this.inner.set(next);
+        }
+    }
+}
+
+pin_project! {
+    /// Request body for [`Base64Decode`].
+    ///
+    /// Holds the decoded body of requests that were base64 encoded, and the original body of other
+    /// requests.
+    pub struct DecodedBody<B> {
+        #[pin]
+        inner: DecodedBodyInner<B>,
+    }
+}
+
+pin_project! {
+    #[project = DecodedBodyProj]
+    enum DecodedBodyInner<B> {
+        Passthrough {
+            #[pin]
+            body: B,
+        },
+        Decoded {
+            #[pin]
+            body: Full,
+        },
+    }
+}
+
+impl<B> Body for DecodedBody<B>
+where
+    B: Body,
+{
+    type Data = Bytes;
+    type Error = B::Error;
+
+    fn poll_frame(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Result<Frame<Self::Data>, Self::Error>>> {
+        match self.project().inner.project() {
+            DecodedBodyProj::Passthrough { body } => {
+                let frame = ready!(body.poll_frame(cx));
+                Poll::Ready(frame.map(|frame| {
+                    frame.map(|frame| {
+                        frame.map_data(|mut data| data.copy_to_bytes(data.remaining()))
+                    })
+                }))
+            }
+            DecodedBodyProj::Decoded { body } => match ready!(body.poll_frame(cx)) {
+                Some(Ok(frame)) => Poll::Ready(Some(Ok(frame))),
+                Some(Err(infallible)) => match infallible {},
+                None => Poll::Ready(None),
+            },
+        }
+    }
+
+    fn is_end_stream(&self) -> bool {
+        match &self.inner {
+            DecodedBodyInner::Passthrough { body } => body.is_end_stream(),
+            DecodedBodyInner::Decoded { body } => body.is_end_stream(),
+        }
+    }
+
+    fn size_hint(&self) -> SizeHint {
+        match &self.inner {
+            DecodedBodyInner::Passthrough { body } => body.size_hint(),
+            DecodedBodyInner::Decoded { body } => body.size_hint(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_helpers::Body;
+    use std::{
+        convert::Infallible,
+        sync::{
+            atomic::{AtomicBool, Ordering},
+            Arc,
+        },
+    };
+    use tower::{service_fn, ServiceExt};
+
+    async fn echo(req: Request<DecodedBody<Body>>) -> Result<Response<Body>, Infallible> {
+        let length = req.headers().get(header::CONTENT_LENGTH).cloned();
+        let encoding = req.headers().get(CONTENT_TRANSFER_ENCODING).cloned();
+        let body = req.into_body().collect().await.unwrap().to_bytes();
+        let mut res = Response::new(Body::from(body));
+        if let Some(length) = length {
+            res.headers_mut().insert(header::CONTENT_LENGTH, length);
+        }
+        if let Some(encoding) = encoding {
+            res.headers_mut()
+                .insert(CONTENT_TRANSFER_ENCODING, encoding);
+        }
+        Ok(res)
+    }
+
+    fn request(body: &'static str) -> Request<Body> {
+        Request::builder()
+            .header(CONTENT_TRANSFER_ENCODING, "BASE64")
+            .body(Body::from(body))
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn decodes_body() {
+        let svc = Base64Decode::new(service_fn(echo));
+
+        let res = svc.oneshot(request("aGVsbG8g\r\nd29ybGQ=")).await.unwrap();
+        assert_eq!(res.status(), StatusCode::OK);
+        assert_eq!(res.headers()[header::CONTENT_LENGTH], "11");
+        assert!(!res.headers().contains_key(CONTENT_TRANSFER_ENCODING));
+        let body = res.into_body().collect().await.unwrap().to_bytes();
+        assert_eq!(body, "hello world");
+    }
+
+    #[tokio::test]
+    async fn rejects_invalid_body() {
+        let called = Arc::new(AtomicBool::new(false));
+        let svc = Base64Decode::new(service_fn({
+            let called = called.clone();
+            move |req| {
+                called.store(true, Ordering::SeqCst);
+                echo(req)
+            }
+        }));
+
+        let res = svc.oneshot(request("not base64!")).await.unwrap();
+        assert_eq!(res.status(), StatusCode::BAD_REQUEST);
+        assert!(!called.load(Ordering::SeqCst));
+    }
+
+    #[tokio::test]
+    async fn passes_through_other_requests() {
+        let svc = Base64DecodeLayer::new()
+            .header(HeaderName::from_static("x-encoding"))
+            .layer(service_fn(echo));
+
+        let res = svc.oneshot(request("aGVsbG8=")).await.unwrap();
+        assert_eq!(res.headers()[CONTENT_TRANSFER_ENCODING], "BASE64");
+        let body = res.into_body().collect().await.unwrap().to_bytes();
+        assert_eq!(body, "aGVsbG8=");
+    }
+}
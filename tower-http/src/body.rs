@@ -5,17 +5,32 @@
 //!
 //! They exist because we don't want to expose types from `http-body-util` in `tower-http`s public
 //! API.
+//!
+//! The module also contains a few small combinators, such as [`map_frame`], [`inspect`],
+//! [`WithPrefix`] and [`Rechunk`], for transforming bodies without writing a full [`Body`]
+//! implementation. With the `sse` feature, [`Sse`] serializes Server-Sent Events.
 
 #![allow(missing_docs)]
 
-use std::convert::Infallible;
+use std::{
+    convert::Infallible,
+    pin::Pin,
+    task::{ready, Context, Poll},
+};
 
-use bytes::{Buf, Bytes};
-use http_body::Body;
+use bytes::{Buf, BufMut, Bytes, BytesMut};
+use http::HeaderMap;
+use http_body::{Body, Frame, SizeHint};
 use pin_project_lite::pin_project;
 
 use crate::BoxError;
 
+#[cfg(feature = "sse")]
+mod sse;
+
+#[cfg(feature = "sse")]
+pub use self::sse::{Event, Sse};
+
 macro_rules! body_methods {
     () => {
         #[inline]
@@ -119,3 +134,500 @@ where
 
     body_methods!();
 }
+
+pin_project! {
+    /// Body returned by [`map_frame`].
+    pub struct MapFrame<B, F> {
+        #[pin]
+        inner: B,
+        f: F,
+    }
+}
+
+/// Transform each frame of `body` with `f`.
+///
+/// `f` receives both data and trailers frames, so trailers can be inspected or replaced as well.
+/// Use [`Frame::map_data`] inside `f` to only transform data frames.
+///
+/// Since `f` may change the length of data frames, the size of the returned body is only known
+/// once `body` has reached the end of the stream.
+///
+/// # Example
+///
+/// ```
+/// use bytes::Bytes;
+/// use http_body_util::{BodyExt, Full};
+/// use tower_http::body::map_frame;
+///
+/// # #[tokio::main]
+/// # async fn main() {
+/// let body = map_frame(Full::new(Bytes::from("hello")), |frame| {
+///     frame.map_data(|data| Bytes::from(data.to_ascii_uppercase()))
+/// });
+///
+/// let bytes = body.collect().await.unwrap().to_bytes();
+/// assert_eq!(bytes, "HELLO");
+/// # }
+/// ```
+pub fn map_frame<B, F, D>(body: B, f: F) -> MapFrame<B, F>
+where
+    B: Body,
+    F: FnMut(Frame<B::Data>) -> Frame<D>,
+    D: Buf,
+{
+    MapFrame { inner: body, f }
+}
+
+impl<B, F, D> Body for MapFrame<B, F>
+where
+    B: Body,
+    F: FnMut(Frame<B::Data>) -> Frame<D>,
+    D: Buf,
+{
+    type Data = D;
+    type Error = B::Error;
+
+    fn poll_frame(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Result<Frame<Self::Data>, Self::Error>>> {
+        let this = self.project();
+        match ready!(this.inner.poll_frame(cx)) {
+            Some(Ok(frame)) => Poll::Ready(Some(Ok((this.f)(frame)))),
+            Some(Err(err)) => Poll::Ready(Some(Err(err))),
+            None => Poll::Ready(None),
+        }
+    }
+
+    fn is_end_stream(&self) -> bool {
+        self.inner.is_end_stream()
+    }
+
+    fn size_hint(&self) -> SizeHint {
+        if self.inner.is_end_stream() {
+            SizeHint::with_exact(0)
+        } else {
+            SizeHint::default()
+        }
+    }
+}
+
+pin_project! {
+    /// Body returned by [`inspect`].
+    pub struct Inspect<B, F> {
+        #[pin]
+        inner: B,
+        f: F,
+    }
+}
+
+/// Call `f` with the bytes of each data frame of `body`, without changing them.
+///
+/// This is useful for observing a body as it is streamed, for example to hash it or scan its
+/// contents, without buffering it. Trailers are passed through without calling `f`.
+///
+/// Unlike [`map_frame`], the size of the returned body is the same as the size of `body`.
+///
+/// # Example
+///
+/// ```
+/// use bytes::Bytes;
+/// use http_body_util::{BodyExt, Full};
+/// use tower_http::body::inspect;
+///
+/// # #[tokio::main]
+/// # async fn main() {
+/// let mut len = 0;
+/// let body = inspect(Full::new(Bytes::from("hello")), |data| len += data.len());
+///
+/// let bytes = body.collect().await.unwrap().to_bytes();
+/// assert_eq!(bytes, "hello");
+/// assert_eq!(len, 5);
+/// # }
+/// ```
+pub fn inspect<B, F>(body: B, f: F) -> Inspect<B, F>
+where
+    B: Body,
+    B::Data: AsRef<[u8]>,
+    F: FnMut(&[u8]),
+{
+    Inspect { inner: body, f }
+}
+
+impl<B, F> Inspect<B, F> {
+    /// Get the inner body, dropping `f`.
+    pub fn into_inner(self) -> B {
+        self.inner
+    }
+}
+
+impl<B, F> Body for Inspect<B, F>
+where
+    B: Body,
+    B::Data: AsRef<[u8]>,
+    F: FnMut(&[u8]),
+{
+    type Data = B::Data;
+    type Error = B::Error;
+
+    fn poll_frame(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Result<Frame<Self::Data>, Self::Error>>> {
+        let this = self.project();
+        let frame = ready!(this.inner.poll_frame(cx));
+        if let Some(Ok(frame)) = &frame {
+            if let Some(data) = frame.data_ref() {
+                (this.f)(data.as_ref());
+            }
+        }
+        Poll::Ready(frame)
+    }
+
+    fn is_end_stream(&self) -> bool {
+        self.inner.is_end_stream()
+    }
+
+    fn size_hint(&self) -> SizeHint {
+        self.inner.size_hint()
+    }
+}
+
+pin_project! {
+    /// Body that emits a fixed prefix before the data of another body.
+    ///
+    /// This is useful for prepending a byte order mark or a length header to an existing body.
+    /// The prefix is sent as the first data frame, followed by the frames of the inner body,
+    /// including its trailers.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use bytes::Bytes;
+    /// use http_body_util::{BodyExt, Full};
+    /// use tower_http::body::WithPrefix;
+    ///
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// let body = WithPrefix::new(Bytes::from_static(b"\xEF\xBB\xBF"), Full::new(Bytes::from("a,b,c")));
+    ///
+    /// let bytes = body.collect().await.unwrap().to_bytes();
+    /// assert_eq!(bytes, &b"\xEF\xBB\xBFa,b,c"[..]);
+    /// # }
+    /// ```
+    pub struct WithPrefix<B> {
+        prefix: Option<Bytes>,
+        #[pin]
+        inner: B,
+    }
+}
+
+impl<B> WithPrefix<B> {
+    /// Create a new [`WithPrefix`] that emits `prefix` before the data of `body`.
+    pub fn new(prefix: Bytes, body: B) -> Self {
+        Self {
+            prefix: Some(prefix).filter(|prefix| !prefix.is_empty()),
+            inner: body,
+        }
+    }
+
+    /// Consumes `self`, returning the inner body.
+    ///
+    /// The prefix is lost if it hasn't been emitted yet.
+    pub fn into_inner(self) -> B {
+        self.inner
+    }
+}
+
+impl<B> Body for WithPrefix<B>
+where
+    B: Body<Data = Bytes>,
+{
+    type Data = Bytes;
+    type Error = B::Error;
+
+    fn poll_frame(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Result<Frame<Self::Data>, Self::Error>>> {
+        let this = self.project();
+        if let Some(prefix) = this.prefix.take() {
+            return Poll::Ready(Some(Ok(Frame::data(prefix))));
+        }
+        this.inner.poll_frame(cx)
+    }
+
+    fn is_end_stream(&self) -> bool {
+        self.prefix.is_none() && self.inner.is_end_stream()
+    }
+
+    fn size_hint(&self) -> SizeHint {
+        let mut hint = self.inner.size_hint();
+        if let Some(prefix) = &self.prefix {
+            let len = prefix.len() as u64;
+            if let Some(upper) = hint.upper() {
+                hint.set_upper(upper + len);
+            }
+            hint.set_lower(hint.lower() + len);
+        }
+        hint
+    }
+}
+
+pin_project! {
+    /// Body that re-emits the data of another body in frames of a fixed size.
+    ///
+    /// Data is buffered until `chunk_size` bytes are available, so every data frame has exactly
+    /// `chunk_size` bytes, except for the last one which may be smaller. Trailers of the inner
+    /// body are emitted after the last data frame.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use bytes::Bytes;
+    /// use http_body_util::{BodyExt, Full};
+    /// use tower_http::body::Rechunk;
+    ///
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// let mut body = Rechunk::new(Full::new(Bytes::from("hello world")), 4);
+    ///
+    /// let mut chunks = Vec::new();
+    /// while let Some(frame) = body.frame().await {
+    ///     chunks.push(frame.unwrap().into_data().unwrap());
+    /// }
+    /// assert_eq!(chunks, ["hell", "o wo", "rld"]);
+    /// # }
+    /// ```
+    pub struct Rechunk<B> {
+        #[pin]
+        inner: B,
+        chunk_size: usize,
+        buf: BytesMut,
+        trailers: Option<HeaderMap>,
+        inner_done: bool,
+    }
+}
+
+impl<B> Rechunk<B> {
+    /// Create a new [`Rechunk`] that emits the data of `body` in frames of `chunk_size` bytes.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `chunk_size` is zero.
+    pub fn new(body: B, chunk_size: usize) -> Self {
+        assert!(chunk_size > 0, "chunk size must be greater than zero");
+        Self {
+            inner: body,
+            chunk_size,
+            buf: BytesMut::new(),
+            trailers: None,
+            inner_done: false,
+        }
+    }
+
+    /// Consumes `self`, returning the inner body.
+    ///
+    /// Buffered data that hasn't been emitted yet is lost.
+    pub fn into_inner(self) -> B {
+        self.inner
+    }
+}
+
+impl<B> Body for Rechunk<B>
+where
+    B: Body,
+{
+    type Data = Bytes;
+    type Error = B::Error;
+
+    fn poll_frame(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Result<Frame<Self::Data>, Self::Error>>> {
+        let mut this = self.project();
+        loop {
+            if this.buf.len() >= *this.chunk_size {
+                let chunk = this.buf.split_to(*this.chunk_size).freeze();
+                return Poll::Ready(Some(Ok(Frame::data(chunk))));
+            }
+
+            if *this.inner_done {
+                if !this.buf.is_empty() {
+                    return Poll::Ready(Some(Ok(Frame::data(this.buf.split().freeze()))));
+                }
+                return Poll::Ready(
+                    this.trailers
+                        .take()
+                        .map(|trailers| Ok(Frame::trailers(trailers))),
+                );
+            }
+
+            match ready!(this.inner.as_mut().poll_frame(cx)) {
+                Some(Ok(frame)) => match frame.into_data() {
+                    Ok(data) => this.buf.put(data),
+                    Err(frame) => {
+                        // trailers are the last frame of a body
+                        *this.trailers = frame.into_trailers().ok();
+                        *this.inner_done = true;
+                    }
+                },
+                Some(Err(err)) => return Poll::Ready(Some(Err(err))),
+                None => *this.inner_done = true,
+            }
+        }
+    }
+
+    fn is_end_stream(&self) -> bool {
+        let inner_done = self.inner_done || self.inner.is_end_stream();
+        inner_done && self.buf.is_empty() && self.trailers.is_none()
+    }
+
+    fn size_hint(&self) -> SizeHint {
+        let len = self.buf.len() as u64;
+        if self.inner_done {
+            return SizeHint::with_exact(len);
+        }
+
+        let mut hint = self.inner.size_hint();
+        if let Some(upper) = hint.upper() {
+            hint.set_upper(upper + len);
+        }
+        hint.set_lower(hint.lower() + len);
+        hint
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_helpers::Body;
+    use futures_util::StreamExt;
+    use http::HeaderName;
+    use http_body_util::BodyExt;
+
+    #[tokio::test]
+    async fn map_frame_transforms_data_frames() {
+        let body = map_frame(Body::from("hello world"), |frame| {
+            frame.map_data(|data| Bytes::from(data.to_ascii_uppercase()))
+        });
+
+        let bytes = body.collect().await.unwrap().to_bytes();
+        assert_eq!(bytes, "HELLO WORLD");
+    }
+
+    #[tokio::test]
+    async fn map_frame_passes_trailers_through() {
+        let mut trailers = HeaderMap::new();
+        trailers.insert(HeaderName::from_static("foo"), "bar".parse().unwrap());
+        let body = Body::from("hello").with_trailers(trailers);
+
+        let body = map_frame(body, |frame| frame.map_data(|data| data.slice(1..)));
+
+        let collected = body.collect().await.unwrap();
+        assert_eq!(collected.trailers().unwrap()["foo"], "bar");
+        assert_eq!(collected.to_bytes(), "ello");
+    }
+
+    #[tokio::test]
+    async fn inspect_observes_data_frames() {
+        let stream = futures_util::stream::iter(["hello", " ", "world"])
+            .map(|chunk| Ok::<_, BoxError>(Bytes::from(chunk)));
+        let mut trailers = HeaderMap::new();
+        trailers.insert(HeaderName::from_static("foo"), "bar".parse().unwrap());
+        let body = Body::from_stream(stream).with_trailers(trailers);
+
+        // FNV-1a, computed over the frames as they are streamed
+        let mut hash = 0xcbf29ce484222325_u64;
+        let mut frames = 0;
+        let body = inspect(body, |data| {
+            frames += 1;
+            for byte in data {
+                hash = (hash ^ u64::from(*byte)).wrapping_mul(0x100000001b3);
+            }
+        });
+
+        let collected = body.collect().await.unwrap();
+        assert_eq!(collected.trailers().unwrap()["foo"], "bar");
+        let bytes = collected.to_bytes();
+        assert_eq!(bytes, "hello world");
+
+        let expected = bytes.iter().fold(0xcbf29ce484222325_u64, |hash, byte| {
+            (hash ^ u64::from(*byte)).wrapping_mul(0x100000001b3)
+        });
+        assert_eq!(hash, expected);
+        assert_eq!(frames, 3);
+    }
+
+    #[tokio::test]
+    async fn with_prefix_emits_prefix_first() {
+        let mut trailers = HeaderMap::new();
+        trailers.insert(HeaderName::from_static("foo"), "bar".parse().unwrap());
+        let body = Body::from("world").with_trailers(trailers);
+
+        let body = WithPrefix::new(Bytes::from_static(b"hello "), body);
+
+        let collected = body.collect().await.unwrap();
+        assert_eq!(collected.trailers().unwrap()["foo"], "bar");
+        assert_eq!(collected.to_bytes(), "hello world");
+    }
+
+    #[test]
+    fn with_prefix_size_hint() {
+        let body = WithPrefix::new(Bytes::from_static(b"hello "), Body::from("world"));
+        assert_eq!(http_body::Body::size_hint(&body).exact(), Some(11));
+    }
+
+    #[test]
+    fn with_prefix_is_end_stream() {
+        let body = WithPrefix::new(Bytes::from_static(b"prefix"), Body::empty());
+        assert!(!http_body::Body::is_end_stream(&body));
+
+        let body = WithPrefix::new(Bytes::new(), Body::empty());
+        assert!(http_body::Body::is_end_stream(&body));
+    }
+
+    #[tokio::test]
+    async fn rechunk_emits_uniform_chunks() {
+        let stream = futures_util::stream::iter(["abc", "defghij", "k", "lmnopq"])
+            .map(|chunk| Ok::<_, BoxError>(Bytes::from(chunk)));
+        let mut trailers = HeaderMap::new();
+        trailers.insert(HeaderName::from_static("foo"), "bar".parse().unwrap());
+        let body = Body::from_stream(stream).with_trailers(trailers);
+
+        let mut body = Rechunk::new(body, 4);
+        let mut chunks = Vec::new();
+        let mut received_trailers = None;
+        while let Some(frame) = body.frame().await {
+            match frame.unwrap().into_data() {
+                Ok(data) => chunks.push(data),
+                Err(frame) => received_trailers = frame.into_trailers().ok(),
+            }
+        }
+
+        assert_eq!(chunks, ["abcd", "efgh", "ijkl", "mnop", "q"]);
+        assert_eq!(received_trailers.unwrap()["foo"], "bar");
+        assert!(http_body::Body::is_end_stream(&body));
+    }
+
+    #[tokio::test]
+    async fn rechunk_exact_multiple() {
+        let mut body = Rechunk::new(Body::from("abcdef"), 3);
+        assert_eq!(http_body::Body::size_hint(&body).exact(), Some(6));
+
+        let mut chunks = Vec::new();
+        while let Some(frame) = body.frame().await {
+            chunks.push(frame.unwrap().into_data().unwrap());
+        }
+        assert_eq!(chunks, ["abc", "def"]);
+    }
+
+    #[test]
+    fn map_frame_size_hint() {
+        let body = map_frame(Body::empty(), |frame| frame);
+        assert_eq!(http_body::Body::size_hint(&body).exact(), Some(0));
+
+        let body = map_frame(Body::from("hello"), |frame| frame);
+        assert_eq!(http_body::Body::size_hint(&body).exact(), None);
+    }
+}
@@ -0,0 +1,321 @@
+use bytes::{BufMut, Bytes, BytesMut};
+use futures_core::Stream;
+use http_body::{Body, Frame};
+use pin_project_lite::pin_project;
+use std::{
+    fmt,
+    future::Future,
+    pin::Pin,
+    task::{Context, Poll},
+    time::Duration,
+};
+use tokio::time::{Instant, Sleep};
+
+pin_project! {
+    /// Body of a [Server-Sent Events] response.
+    ///
+    /// Serializes a [`Stream`] of [`Event`]s in the `text/event-stream` format. Optionally, a
+    /// comment is sent when no event has been sent for a while, to keep the connection from
+    /// being closed by proxies.
+    ///
+    /// The response should have a `Content-Type: text/event-stream` header.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use bytes::Bytes;
+    /// use http::{header::CONTENT_TYPE, Response};
+    /// use http_body_util::BodyExt;
+    /// use std::{convert::Infallible, time::Duration};
+    /// use tower_http::body::{Event, Sse};
+    ///
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// let events = futures_util::stream::iter([
+    ///     Ok::<_, Infallible>(Event::default().event("greeting").data("hello")),
+    ///     Ok(Event::default().id("2").data("multi\nline")),
+    /// ]);
+    ///
+    /// let response = Response::builder()
+    ///     .header(CONTENT_TYPE, "text/event-stream")
+    ///     .body(Sse::new(events).keep_alive(Duration::from_secs(15)))
+    ///     .unwrap();
+    ///
+    /// let bytes = response.into_body().collect().await.unwrap().to_bytes();
+    /// assert_eq!(
+    ///     bytes,
+    ///     "event: greeting\ndata: hello\n\nid: 2\ndata: multi\ndata: line\n\n"
+    /// );
+    /// # }
+    /// ```
+    ///
+    /// [Server-Sent Events]: https://html.spec.whatwg.org/multipage/server-sent-events.html
+    pub struct Sse<S> {
+        #[pin]
+        stream: S,
+        keep_alive: Option<KeepAlive>,
+        done: bool,
+    }
+}
+
+struct KeepAlive {
+    interval: Duration,
+    // created on the first poll, so that `Sse` can be created outside of a runtime
+    sleep: Option<Pin<Box<Sleep>>>,
+}
+
+impl<S> Sse<S> {
+    /// Create a new [`Sse`] body from a stream of events.
+    pub fn new(stream: S) -> Self {
+        Self {
+            stream,
+            keep_alive: None,
+            done: false,
+        }
+    }
+
+    /// Send a keep-alive comment when no event has been sent for `interval`.
+    ///
+    /// By default, no keep-alive comments are sent.
+    pub fn keep_alive(mut self, interval: Duration) -> Self {
+        self.keep_alive = Some(KeepAlive {
+            interval,
+            sleep: None,
+        });
+        self
+    }
+}
+
+impl<S> fmt::Debug for Sse<S> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Sse")
+            .field(
+                "keep_alive",
+                &self
+                    .keep_alive
+                    .as_ref()
+                    .map(|keep_alive| keep_alive.interval),
+            )
+            .finish()
+    }
+}
+
+impl<S, E> Body for Sse<S>
+where
+    S: Stream<Item = Result<Event, E>>,
+{
+    type Data = Bytes;
+    type Error = E;
+
+    fn poll_frame(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Result<Frame<Self::Data>, Self::Error>>> {
+        let mut this = self.project();
+        if *this.done {
+            return Poll::Ready(None);
+        }
+
+        loop {
+            match this.stream.as_mut().poll_next(cx) {
+                // events without any fields aren't sent
+                Poll::Ready(Some(Ok(event))) if event.buf.is_empty() => {}
+                Poll::Ready(Some(Ok(event))) => {
+                    if let Some(keep_alive) = this.keep_alive {
+                        keep_alive.reset();
+                    }
+                    return Poll::Ready(Some(Ok(Frame::data(event.buf.freeze()))));
+                }
+                Poll::Ready(Some(Err(err))) => return Poll::Ready(Some(Err(err))),
+                Poll::Ready(None) => {
+                    *this.done = true;
+                    return Poll::Ready(None);
+                }
+                Poll::Pending => break,
+            }
+        }
+
+        if let Some(keep_alive) = this.keep_alive {
+            let interval = keep_alive.interval;
+            let sleep = keep_alive
+                .sleep
+                .get_or_insert_with(|| Box::pin(tokio::time::sleep(interval)));
+            if sleep.as_mut().poll(cx).is_ready() {
+                keep_alive.reset();
+                return Poll::Ready(Some(Ok(Frame::data(Bytes::from_static(b":\n\n")))));
+            }
+        }
+
+        Poll::Pending
+    }
+
+    fn is_end_stream(&self) -> bool {
+        self.done
+    }
+}
+
+impl KeepAlive {
+    fn reset(&mut self) {
+        if let Some(sleep) = &mut self.sleep {
+            sleep.as_mut().reset(Instant::now() + self.interval);
+        }
+    }
+}
+
+/// An event of a [`Sse`] body.
+///
+/// An event without any fields isn't sent.
+#[derive(Clone, Debug, Default)]
+pub struct Event {
+    buf: BytesMut,
+}
+
+impl Event {
+    /// Set the data of the event.
+    ///
+    /// Data with multiple lines is sent as multiple `data` fields, which clients join with
+    /// newlines again. Lines are split on `\r\n`, `\n` and `\r`, like clients do.
+    pub fn data(mut self, data: &str) -> Self {
+        let mut rest = data;
+        while let Some(end) = rest.find(['\r', '\n']) {
+            self.field("data", &rest[..end]);
+            let len = if rest[end..].starts_with("\r\n") {
+                2
+            } else {
+                1
+            };
+            rest = &rest[end + len..];
+        }
+        self.field("data", rest);
+        self
+    }
+
+    /// Set the name of the event.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `event` contains a newline or a carriage return.
+    pub fn event(mut self, event: &str) -> Self {
+        assert_single_line("event", event);
+        self.field("event", event);
+        self
+    }
+
+    /// Set the id of the event.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `id` contains a newline, a carriage return or a null character.
+    pub fn id(mut self, id: &str) -> Self {
+        assert_single_line("id", id);
+        assert!(!id.contains('\0'), "SSE id cannot contain null characters");
+        self.field("id", id);
+        self
+    }
+
+    /// Set the time clients should wait before reconnecting after the connection is lost.
+    pub fn retry(mut self, retry: Duration) -> Self {
+        self.field("retry", &retry.as_millis().to_string());
+        self
+    }
+
+    fn field(&mut self, name: &str, value: &str) {
+        // the blank line ending the previous field is moved to the end
+        if self.buf.ends_with(b"\n\n") {
+            self.buf.truncate(self.buf.len() - 1);
+        }
+        self.buf.put_slice(name.as_bytes());
+        self.buf.put_slice(b": ");
+        self.buf.put_slice(value.as_bytes());
+        self.buf.put_slice(b"\n\n");
+    }
+}
+
+fn assert_single_line(name: &str, value: &str) {
+    assert!(
+        !value.contains(['\n', '\r']),
+        "SSE {} cannot contain newlines or carriage returns",
+        name
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures_util::StreamExt;
+    use http_body_util::BodyExt;
+    use std::convert::Infallible;
+
+    #[tokio::test]
+    async fn serializes_events() {
+        let events = futures_util::stream::iter([
+            Event::default().data("hello"),
+            Event::default()
+                .event("update")
+                .id("42")
+                .retry(Duration::from_secs(3))
+                .data("first\nsecond\r\nthird"),
+            Event::default(),
+        ])
+        .map(Ok::<_, Infallible>);
+
+        let bytes = Sse::new(events).collect().await.unwrap().to_bytes();
+        assert_eq!(
+            bytes,
+            "data: hello\n\n\
+             event: update\nid: 42\nretry: 3000\ndata: first\ndata: second\ndata: third\n\n"
+        );
+    }
+
+    #[tokio::test]
+    async fn splits_data_on_carriage_returns() {
+        let events = futures_util::stream::iter([
+            Event::default().data("a\rid: injected\r\rb\r\n"),
+            Event::default(),
+        ])
+        .map(Ok::<_, Infallible>);
+        let mut body = Sse::new(events);
+
+        let frame = body.frame().await.unwrap().unwrap().into_data().unwrap();
+        assert_eq!(
+            frame,
+            "data: a\ndata: id: injected\ndata: \ndata: b\ndata: \n\n"
+        );
+        // the empty event isn't sent as an empty frame
+        assert!(body.frame().await.is_none());
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn sends_keep_alive_comments() {
+        let (tx, rx) = tokio::sync::mpsc::unbounded_channel::<Event>();
+        let events = futures_util::stream::unfold(rx, |mut rx| async move {
+            rx.recv()
+                .await
+                .map(|event| (Ok::<_, Infallible>(event), rx))
+        });
+        let mut body = Sse::new(Box::pin(events)).keep_alive(Duration::from_secs(10));
+
+        let collect = tokio::spawn(async move {
+            let mut frames = Vec::new();
+            while let Some(frame) = body.frame().await {
+                frames.push(frame.unwrap().into_data().unwrap());
+            }
+            frames
+        });
+
+        tokio::time::sleep(Duration::from_secs(25)).await;
+        tx.send(Event::default().data("one")).unwrap();
+        tokio::time::sleep(Duration::from_secs(5)).await;
+        tx.send(Event::default().data("two")).unwrap();
+        drop(tx);
+
+        let frames = collect.await.unwrap();
+        assert_eq!(frames, [":\n\n", ":\n\n", "data: one\n\n", "data: two\n\n"]);
+    }
+
+    #[test]
+    #[should_panic]
+    fn event_name_with_newline_panics() {
+        let _ = Event::default().event("a\nb");
+    }
+}
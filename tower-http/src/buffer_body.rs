@@ -0,0 +1,539 @@
+//! Middleware that buffers request bodies, spilling large ones to disk.
+//!
+//! Some handlers need the whole request body before they can process it, for example to read it
+//! more than once, but large bodies shouldn't be kept in memory. [`BufferBody`] reads the whole
+//! body before calling the inner service. The first bytes of the body, up to a memory limit, are
+//! kept in memory, and the rest is written to a temporary file. The inner service receives a
+//! [`BufferedBody`] that replays the body from memory, then from the file, which is removed when
+//! the body is dropped.
+//!
+//! Since the length of the body is then known, the `Content-Length` header of the request is set
+//! to it, and the `Transfer-Encoding` header is removed. If reading the body fails, a `400 Bad
+//! Request` response is returned without calling the inner service, and if writing the temporary
+//! file fails, a `500 Internal Server Error` response is.
+//!
+//! The whole body is read however large it is, so consider limiting its size, for example with
+//! [`RequestBodyLimit`](crate::limit::RequestBodyLimit).
+//!
+//! # Example
+//!
+//! ```
+//! use tower_http::{buffer_body::{BufferBodyLayer, BufferedBody}, BoxError};
+//! use http::{Request, Response};
+//! use http_body_util::{BodyExt, Full};
+//! use bytes::Bytes;
+//! use tower::{ServiceBuilder, ServiceExt};
+//!
+//! async fn handle(req: Request<BufferedBody>) -> Result<Response<Full<Bytes>>, BoxError> {
+//!     // The body was written to disk after the first 4 bytes.
+//!     assert!(req.body().is_spilled());
+//!     let body = req.into_body().collect().await?.to_bytes();
+//!     assert_eq!(body, "hello world");
+//!     Ok(Response::new(Full::default()))
+//! }
+//!
+//! # #[tokio::main]
+//! # async fn main() -> Result<(), BoxError> {
+//! let service = ServiceBuilder::new()
+//!     .layer(BufferBodyLayer::new(4))
+//!     .service_fn(handle);
+//!
+//! let request = Request::new(Full::<Bytes>::from("hello world"));
+//! let response = service.oneshot(request).await?;
+//! #
+//! # Ok(())
+//! # }
+//! ```
+
+use bytes::{Buf, Bytes, BytesMut};
+use http::{header, request::Parts, HeaderMap, HeaderValue, Request, Response, StatusCode};
+use http_body::{Body, Frame, SizeHint};
+use http_body_util::BodyExt;
+use pin_project_lite::pin_project;
+use std::{
+    fmt,
+    future::Future,
+    io, mem,
+    path::PathBuf,
+    pin::Pin,
+    sync::atomic::{AtomicU64, Ordering},
+    sync::Arc,
+    task::{ready, Context, Poll},
+};
+use tokio::{
+    fs::{File, OpenOptions},
+    io::{AsyncRead, AsyncSeekExt, AsyncWriteExt, ReadBuf},
+};
+use tower_layer::Layer;
+use tower_service::Service;
+
+// chunk size when replaying from the temporary file, 64KiB
+const CHUNK_SIZE: usize = 65536;
+
+/// Layer that applies [`BufferBody`] which buffers request bodies, spilling large ones to disk.
+///
+/// See the [module docs](self) for more details.
+#[derive(Debug, Clone)]
+pub struct BufferBodyLayer {
+    memory_limit: usize,
+    temp_dir: Option<Arc<PathBuf>>,
+}
+
+impl BufferBodyLayer {
+    /// Create a new [`BufferBodyLayer`] that keeps up to `memory_limit` bytes of each body in
+    /// memory.
+    pub fn new(memory_limit: usize) -> Self {
+        Self {
+            memory_limit,
+            temp_dir: None,
+        }
+    }
+
+    /// Set the directory temporary files are created in.
+    ///
+    /// Defaults to [`std::env::temp_dir`].
+    pub fn temp_dir(mut self, temp_dir: impl Into<PathBuf>) -> Self {
+        self.temp_dir = Some(Arc::new(temp_dir.into()));
+        self
+    }
+}
+
+impl<S> Layer<S> for BufferBodyLayer {
+    type Service = BufferBody<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        BufferBody {
+            inner,
+            memory_limit: self.memory_limit,
+            temp_dir: self.temp_dir.clone(),
+        }
+    }
+}
+
+/// Middleware that buffers request bodies, spilling large ones to disk.
+///
+/// See the [module docs](self) for more details.
+#[derive(Debug, Clone)]
+pub struct BufferBody<S> {
+    inner: S,
+    memory_limit: usize,
+    temp_dir: Option<Arc<PathBuf>>,
+}
+
+impl<S> BufferBody<S> {
+    /// Create a new [`BufferBody`] that keeps up to `memory_limit` bytes of each body in memory.
+    pub fn new(inner: S, memory_limit: usize) -> Self {
+        BufferBodyLayer::new(memory_limit).layer(inner)
+    }
+
+    /// Set the directory temporary files are created in.
+    ///
+    /// Defaults to [`std::env::temp_dir`].
+    pub fn temp_dir(mut self, temp_dir: impl Into<PathBuf>) -> Self {
+        self.temp_dir = Some(Arc::new(temp_dir.into()));
+        self
+    }
+
+    define_inner_service_accessors!();
+
+    /// Returns a new [`Layer`] that wraps services with a `BufferBody` middleware.
+    ///
+    /// [`Layer`]: tower_layer::Layer
+    pub fn layer(memory_limit: usize) -> BufferBodyLayer {
+        BufferBodyLayer::new(memory_limit)
+    }
+}
+
+impl<ReqBody, ResBody, S> Service<Request<ReqBody>> for BufferBody<S>
+where
+    S: Service<Request<BufferedBody>, Response = Response<ResBody>> + Clone,
+    ReqBody: Body + Send + 'static,
+    ReqBody::Data: Send,
+    ResBody: Default,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = ResponseFuture<S>;
+
+    #[inline]
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, req: Request<ReqBody>) -> Self::Future {
+        // the inner service is called once the body is buffered, so take the ready service and
+        // leave a clone in its place
+        let clone = self.inner.clone();
+        let service = mem::replace(&mut self.inner, clone);
+
+        let (parts, body) = req.into_parts();
+        let temp_dir = match &self.temp_dir {
+            Some(temp_dir) => PathBuf::clone(temp_dir),
+            None => std::env::temp_dir(),
+        };
+        ResponseFuture {
+            inner: ResponseFutureInner::Buffer {
+                buffer: Box::pin(buffer(body, self.memory_limit, temp_dir)),
+                parts: Some((parts, service)),
+            },
+        }
+    }
+}
+
+enum BufferError {
+    Body,
+    Io(io::Error),
+}
+
+async fn buffer<B>(
+    body: B,
+    memory_limit: usize,
+    temp_dir: PathBuf,
+) -> Result<BufferedBody, BufferError>
+where
+    B: Body,
+{
+    let mut memory = BytesMut::new();
+    let mut spill: Option<(File, TempPath)> = None;
+    let mut len = 0;
+    let mut trailers = None;
+
+    futures_util::pin_mut!(body);
+    loop {
+        let frame = match body.frame().await {
+            Some(frame) => frame.map_err(|_| BufferError::Body)?,
+            None => break,
+        };
+        let mut data = match frame.into_data() {
+            Ok(data) => data,
+            Err(frame) => {
+                if let Ok(frame_trailers) = frame.into_trailers() {
+                    trailers
+                        .get_or_insert_with(HeaderMap::new)
+                        .extend(frame_trailers);
+                }
+                continue;
+            }
+        };
+        len += data.remaining() as u64;
+
+        let in_memory = data.remaining().min(memory_limit - memory.len());
+        memory.extend_from_slice(&data.copy_to_bytes(in_memory));
+        if !data.has_remaining() {
+            continue;
+        }
+
+        if spill.is_none() {
+            spill = Some(create_temp_file(&temp_dir).await.map_err(BufferError::Io)?);
+        }
+        let (file, _) = spill.as_mut().expect("temporary file was just created");
+        while data.has_remaining() {
+            file.write_all(data.chunk())
+                .await
+                .map_err(BufferError::Io)?;
+            let chunk_len = data.chunk().len();
+            data.advance(chunk_len);
+        }
+    }
+
+    let file = match spill {
+        Some((mut file, path)) => {
+            file.flush().await.map_err(BufferError::Io)?;
+            file.rewind().await.map_err(BufferError::Io)?;
+            Some((file, path))
+        }
+        None => None,
+    };
+
+    Ok(BufferedBody {
+        memory: memory.freeze(),
+        file,
+        trailers,
+        len,
+        remaining: len,
+    })
+}
+
+async fn create_temp_file(temp_dir: &std::path::Path) -> io::Result<(File, TempPath)> {
+    static COUNTER: AtomicU64 = AtomicU64::new(0);
+
+    loop {
+        let name = format!(
+            "tower-http-body-{}-{}",
+            std::process::id(),
+            COUNTER.fetch_add(1, Ordering::Relaxed)
+        );
+        let path = temp_dir.join(name);
+        match OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create_new(true)
+            .open(&path)
+            .await
+        {
+            Ok(file) => return Ok((file, TempPath(path))),
+            Err(err) if err.kind() == io::ErrorKind::AlreadyExists => {}
+            Err(err) => return Err(err),
+        }
+    }
+}
+
+// Removes the temporary file when dropped.
+struct TempPath(PathBuf);
+
+impl Drop for TempPath {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(&self.0);
+    }
+}
+
+pin_project! {
+    /// Response future for [`BufferBody`].
+    pub struct ResponseFuture<S>
+    where
+        S: Service<Request<BufferedBody>>,
+    {
+        #[pin]
+        inner: ResponseFutureInner<S>,
+    }
+}
+
+pin_project! {
+    #[project = ResFutProj]
+    enum ResponseFutureInner<S>
+    where
+        S: Service<Request<BufferedBody>>,
+    {
+        Buffer {
+            buffer: Pin<Box<dyn Future<Output = Result<BufferedBody, BufferError>> + Send>>,
+            parts: Option<(Parts, S)>,
+        },
+        Error {
+            status: StatusCode,
+        },
+        Future {
+            #[pin]
+            future: S::Future,
+        }
+    }
+}
+
+impl<S, ResBody> Future for ResponseFuture<S>
+where
+    S: Service<Request<BufferedBody>, Response = Response<ResBody>>,
+    ResBody: Default,
+{
+    type Output = Result<Response<ResBody>, S::Error>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let mut this = self.project();
+        loop {
+            let next = match this.inner.as_mut().project() {
+                ResFutProj::Buffer { buffer, parts } => {
+                    let buffered = ready!(buffer.as_mut().poll(cx));
+                    match (buffered, parts.take()) {
+                        (Ok(body), Some((mut parts, mut service))) => {
+                            parts.headers.remove(header::TRANSFER_ENCODING);
+                            parts
+                                .headers
+                                .insert(header::CONTENT_LENGTH, HeaderValue::from(body.len));
+                            ResponseFutureInner::Future {
+                                future: service.call(Request::from_parts(parts, body)),
+                            }
+                        }
+                        (Err(BufferError::Body), _) => ResponseFutureInner::Error {
+                            status: StatusCode::BAD_REQUEST,
+                        },
+                        (Err(BufferError::Io(err)), _) => {
+                            tracing::error!(error = %err, "Failed to buffer request body");
+                            ResponseFutureInner::Error {
+                                status: StatusCode::INTERNAL_SERVER_ERROR,
+                            }
+                        }
+                        (Ok(_), None) => panic!("future polled after completion"),
+                    }
+                }
+                ResFutProj::Error { status } => {
+                    let mut res = Response::new(ResBody::default());
+                    *res.status_mut() = *status;
+                    return Poll::Ready(Ok(res));
+                }
+                ResFutProj::Future { future } => return future.poll(cx),
+            };
+            this.inner.set(next);
+        }
+    }
+}
+
+/// Request body for [`BufferBody`].
+///
+/// Replays the buffered body from memory, then from the temporary file if the body didn't fit in
+/// memory. The temporary file is removed when the body is dropped.
+pub struct BufferedBody {
+    memory: Bytes,
+    file: Option<(File, TempPath)>,
+    trailers: Option<HeaderMap>,
+    len: u64,
+    remaining: u64,
+}
+
+impl BufferedBody {
+    /// Returns the length of the whole body, in bytes.
+    pub fn len(&self) -> u64 {
+        self.len
+    }
+
+    /// Returns `true` if the body is empty.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Returns `true` if the body didn't fit in memory, and was partly written to a temporary file.
+    pub fn is_spilled(&self) -> bool {
+        self.file.is_some()
+    }
+}
+
+impl fmt::Debug for BufferedBody {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("BufferedBody")
+            .field("len", &self.len)
+            .field("remaining", &self.remaining)
+            .field("path", &self.file.as_ref().map(|(_, path)| &path.0))
+            .finish()
+    }
+}
+
+impl Body for BufferedBody {
+    type Data = Bytes;
+    type Error = io::Error;
+
+    fn poll_frame(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Result<Frame<Self::Data>, Self::Error>>> {
+        let this = self.get_mut();
+
+        if !this.memory.is_empty() {
+            let data = mem::take(&mut this.memory);
+            this.remaining -= data.len() as u64;
+            return Poll::Ready(Some(Ok(Frame::data(data))));
+        }
+
+        if let Some((file, _)) = &mut this.file {
+            let mut buf = vec![0; CHUNK_SIZE];
+            let mut read_buf = ReadBuf::new(&mut buf);
+            ready!(Pin::new(file).poll_read(cx, &mut read_buf))?;
+            let read = read_buf.filled().len();
+            if read > 0 {
+                buf.truncate(read);
+                this.remaining = this.remaining.saturating_sub(read as u64);
+                return Poll::Ready(Some(Ok(Frame::data(Bytes::from(buf)))));
+            }
+            // removes the temporary file
+            this.file = None;
+        }
+
+        Poll::Ready(
+            this.trailers
+                .take()
+                .map(|trailers| Ok(Frame::trailers(trailers))),
+        )
+    }
+
+    fn is_end_stream(&self) -> bool {
+        self.memory.is_empty() && self.file.is_none() && self.trailers.is_none()
+    }
+
+    fn size_hint(&self) -> SizeHint {
+        SizeHint::with_exact(self.remaining)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_helpers::Body;
+    use std::convert::Infallible;
+    use tower::{service_fn, ServiceExt};
+
+    async fn echo(req: Request<BufferedBody>) -> Result<Response<Body>, Infallible> {
+        let length = req.headers()[header::CONTENT_LENGTH].clone();
+        let spilled = req.body().is_spilled();
+        let body = req.into_body().collect().await.unwrap().to_bytes();
+        let mut res = Response::new(Body::from(body));
+        res.headers_mut().insert(header::CONTENT_LENGTH, length);
+        res.extensions_mut().insert(spilled);
+        Ok(res)
+    }
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!(
+            "tower-http-buffer-body-{}-{}",
+            name,
+            std::process::id()
+        ));
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[tokio::test]
+    async fn buffers_small_body_in_memory() {
+        let dir = temp_dir("small");
+        let svc = BufferBodyLayer::new(64)
+            .temp_dir(&dir)
+            .layer(service_fn(echo));
+
+        let res = svc
+            .oneshot(Request::new(Body::from("hello")))
+            .await
+            .unwrap();
+        assert_eq!(res.status(), StatusCode::OK);
+        assert_eq!(res.headers()[header::CONTENT_LENGTH], "5");
+        assert_eq!(res.extensions().get(), Some(&false));
+        let body = res.into_body().collect().await.unwrap().to_bytes();
+        assert_eq!(body, "hello");
+        assert_eq!(std::fs::read_dir(&dir).unwrap().count(), 0);
+    }
+
+    #[tokio::test]
+    async fn spills_large_body_to_disk() {
+        let dir = temp_dir("large");
+        let svc = BufferBodyLayer::new(1000)
+            .temp_dir(&dir)
+            .layer(service_fn(echo));
+
+        let chunks: Vec<Result<_, Infallible>> =
+            (0..100u8).map(|i| Ok(Bytes::from(vec![i; 1000]))).collect();
+        let expected: Vec<u8> = (0..100u8).flat_map(|i| vec![i; 1000]).collect();
+        let body = Body::from_stream(futures_util::stream::iter(chunks));
+
+        let res = svc.oneshot(Request::new(body)).await.unwrap();
+        assert_eq!(res.status(), StatusCode::OK);
+        assert_eq!(res.headers()[header::CONTENT_LENGTH], "100000");
+        assert_eq!(res.extensions().get(), Some(&true));
+        let body = res.into_body().collect().await.unwrap().to_bytes();
+        assert_eq!(body, expected);
+
+        // the temporary file is removed with the body
+        assert_eq!(std::fs::read_dir(&dir).unwrap().count(), 0);
+    }
+
+    #[tokio::test]
+    async fn removes_temp_file_on_drop() {
+        let dir = temp_dir("drop");
+        let svc = BufferBodyLayer::new(1).temp_dir(&dir).layer(service_fn({
+            let dir = dir.clone();
+            move |req: Request<BufferedBody>| {
+                assert!(req.body().is_spilled());
+                assert_eq!(std::fs::read_dir(&dir).unwrap().count(), 1);
+                async { Ok::<_, Infallible>(Response::new(Body::empty())) }
+            }
+        }));
+
+        svc.oneshot(Request::new(Body::from("hello")))
+            .await
+            .unwrap();
+        assert_eq!(std::fs::read_dir(&dir).unwrap().count(), 0);
+    }
+}
@@ -0,0 +1,893 @@
+//! Middleware that caches responses in memory.
+//!
+//! [`Cache`] stores the successful responses to `GET` requests for a fixed time to live, and
+//! serves copies of them to the following requests for the same URI without calling the inner
+//! service. Requests are told apart by their method and URI, and by the values of the request
+//! headers configured with [`CacheLayer::vary`].
+//!
+//! Only `200 OK` responses are stored, unless they have a `no-store`, `no-cache` or `private`
+//! `Cache-Control` directive, a `Set-Cookie` or a `Vary: *` header, or their body may be larger
+//! than the size of the cache. The responses to requests with an `Authorization` header are
+//! only stored, and served from the cache, if they have a `public` directive. The bodies of the
+//! responses that may be stored are read entirely before they are returned. A cached response
+//! has an `Age` header with the number of seconds since it was stored, and a request with an
+//! `If-None-Match` header matching its `ETag` gets a `304 Not Modified` response instead.
+//!
+//! The total size of the stored responses is bounded, 16 MiB by default. Once it is reached,
+//! the least recently used responses are evicted. The cache is shared by all the services
+//! created from a [`CacheLayer`], and their clones.
+//!
+//! Other `Cache-Control` directives are ignored, and the cache is never invalidated by requests
+//! with other methods, so it is only suitable for resources that can be served stale for the
+//! time to live.
+//!
+//! # Example
+//!
+//! ```
+//! use tower_http::cache::CacheLayer;
+//! use http::{header, Request, Response};
+//! use http_body_util::Full;
+//! use bytes::Bytes;
+//! use std::{convert::Infallible, time::Duration};
+//! use tower::{ServiceBuilder, Service, ServiceExt};
+//!
+//! async fn handle(req: Request<Full<Bytes>>) -> Result<Response<Full<Bytes>>, Infallible> {
+//!     Ok(Response::new(Full::from("expensive")))
+//! }
+//!
+//! # #[tokio::main]
+//! # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+//! let mut service = ServiceBuilder::new()
+//!     .layer(CacheLayer::new(Duration::from_secs(60)).vary([header::ACCEPT_LANGUAGE]))
+//!     .service_fn(handle);
+//!
+//! let response = service.ready().await?.call(Request::new(Full::default())).await?;
+//! assert!(!response.headers().contains_key(header::AGE));
+//!
+//! // served from the cache
+//! let response = service.ready().await?.call(Request::new(Full::default())).await?;
+//! assert_eq!(response.headers()[header::AGE], "0");
+//! #
+//! # Ok(())
+//! # }
+//! ```
+
+use bytes::{Buf, Bytes};
+use http::{
+    header::{self, HeaderName},
+    response::Parts,
+    HeaderMap, HeaderValue, Method, Request, Response, StatusCode, Uri, Version,
+};
+use http_body::{Body, Frame, SizeHint};
+use http_body_util::{combinators::Collect, BodyExt};
+use pin_project_lite::pin_project;
+use std::{
+    collections::HashMap,
+    fmt,
+    future::Future,
+    pin::Pin,
+    sync::{Arc, Mutex},
+    task::{ready, Context, Poll},
+    time::Duration,
+};
+use tokio::time::Instant;
+use tower_layer::Layer;
+use tower_service::Service;
+
+const DEFAULT_MAX_SIZE: usize = 16 * 1024 * 1024;
+
+/// Layer that applies [`Cache`] which caches responses in memory.
+///
+/// See the [module docs](self) for more details.
+#[derive(Debug, Clone)]
+pub struct CacheLayer {
+    config: Config,
+    store: Arc<Mutex<Store>>,
+}
+
+impl CacheLayer {
+    /// Create a new [`CacheLayer`] that stores responses for `ttl`.
+    pub fn new(ttl: Duration) -> Self {
+        Self {
+            config: Config {
+                ttl,
+                max_size: DEFAULT_MAX_SIZE,
+                vary: Vec::new(),
+            },
+            store: Default::default(),
+        }
+    }
+
+    /// Set the maximum total size of the stored responses, in bytes.
+    ///
+    /// The size of a response is the length of its body and headers. Defaults to 16 MiB.
+    pub fn max_size(mut self, max_size: usize) -> Self {
+        self.config.max_size = max_size;
+        self
+    }
+
+    /// Set the request headers whose values are part of the cache key.
+    ///
+    /// Requests that only differ in other headers get the same cached response. Defaults to no
+    /// headers.
+    pub fn vary<I>(mut self, headers: I) -> Self
+    where
+        I: IntoIterator<Item = HeaderName>,
+    {
+        self.config.vary = headers.into_iter().collect();
+        self
+    }
+}
+
+impl<S> Layer<S> for CacheLayer {
+    type Service = Cache<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        Cache {
+            inner,
+            config: self.config.clone(),
+            store: self.store.clone(),
+        }
+    }
+}
+
+/// Middleware that caches responses in memory.
+///
+/// See the [module docs](self) for more details.
+#[derive(Debug, Clone)]
+pub struct Cache<S> {
+    inner: S,
+    config: Config,
+    store: Arc<Mutex<Store>>,
+}
+
+impl<S> Cache<S> {
+    /// Create a new [`Cache`] that stores responses for `ttl`.
+    pub fn new(inner: S, ttl: Duration) -> Self {
+        CacheLayer::new(ttl).layer(inner)
+    }
+
+    /// Set the maximum total size of the stored responses, in bytes.
+    ///
+    /// See [`CacheLayer::max_size`] for more details.
+    pub fn max_size(mut self, max_size: usize) -> Self {
+        self.config.max_size = max_size;
+        self
+    }
+
+    /// Set the request headers whose values are part of the cache key.
+    ///
+    /// See [`CacheLayer::vary`] for more details.
+    pub fn vary<I>(mut self, headers: I) -> Self
+    where
+        I: IntoIterator<Item = HeaderName>,
+    {
+        self.config.vary = headers.into_iter().collect();
+        self
+    }
+
+    define_inner_service_accessors!();
+
+    /// Returns a new [`Layer`] that wraps services with a [`Cache`] middleware.
+    ///
+    /// [`Layer`]: tower_layer::Layer
+    pub fn layer(ttl: Duration) -> CacheLayer {
+        CacheLayer::new(ttl)
+    }
+}
+
+impl<ReqBody, ResBody, S> Service<Request<ReqBody>> for Cache<S>
+where
+    S: Service<Request<ReqBody>, Response = Response<ResBody>>,
+    ResBody: Body,
+{
+    type Response = Response<CacheBody<ResBody>>;
+    type Error = S::Error;
+    type Future = ResponseFuture<S::Future, ResBody>;
+
+    #[inline]
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, req: Request<ReqBody>) -> Self::Future {
+        if req.method() != Method::GET {
+            return ResponseFuture {
+                inner: ResponseFutureInner::Fetch {
+                    future: self.inner.call(req),
+                    pending: None,
+                },
+            };
+        }
+
+        let key = Key {
+            method: req.method().clone(),
+            uri: req.uri().clone(),
+            vary: self
+                .config
+                .vary
+                .iter()
+                .map(|name| req.headers().get(name).cloned())
+                .collect(),
+        };
+
+        let authorized = req.headers().contains_key(header::AUTHORIZATION);
+        let cached = {
+            let mut store = self.store.lock().unwrap();
+            store.get(&key, Instant::now())
+        }
+        .filter(|cached| !authorized || has_directive(&cached.headers, "public"));
+        if let Some(cached) = cached {
+            let response = if not_modified(req.headers(), &cached.headers) {
+                cached.not_modified()
+            } else {
+                cached.into_response()
+            };
+            return ResponseFuture {
+                inner: ResponseFutureInner::Cached {
+                    response: Some(response),
+                },
+            };
+        }
+
+        ResponseFuture {
+            inner: ResponseFutureInner::Fetch {
+                future: self.inner.call(req),
+                pending: Some(Pending {
+                    key,
+                    ttl: self.config.ttl,
+                    max_size: self.config.max_size,
+                    authorized,
+                    store: self.store.clone(),
+                }),
+            },
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+struct Config {
+    ttl: Duration,
+    max_size: usize,
+    vary: Vec<HeaderName>,
+}
+
+#[derive(Debug, PartialEq, Eq, Hash)]
+struct Key {
+    method: Method,
+    uri: Uri,
+    vary: Vec<Option<HeaderValue>>,
+}
+
+#[derive(Debug, Default)]
+struct Store {
+    entries: HashMap<Key, Entry>,
+    size: usize,
+    // incremented every time an entry is used, for the LRU eviction
+    clock: u64,
+}
+
+#[derive(Debug)]
+struct Entry {
+    response: Cached,
+    size: usize,
+    expires: Instant,
+    used: u64,
+}
+
+#[derive(Debug, Clone)]
+struct Cached {
+    status: StatusCode,
+    version: Version,
+    headers: HeaderMap,
+    body: Bytes,
+    stored: Instant,
+}
+
+impl Store {
+    fn get(&mut self, key: &Key, now: Instant) -> Option<Cached> {
+        let expired = self.entries.get(key)?.expires <= now;
+        if expired {
+            self.remove(key);
+            return None;
+        }
+
+        self.clock += 1;
+        let entry = self.entries.get_mut(key)?;
+        entry.used = self.clock;
+        Some(entry.response.clone())
+    }
+
+    fn insert(&mut self, key: Key, response: Cached, ttl: Duration, max_size: usize) {
+        let size = response.body.len()
+            + response
+                .headers
+                .iter()
+                .map(|(name, value)| name.as_str().len() + value.len())
+                .sum::<usize>();
+        if size > max_size {
+            return;
+        }
+        self.remove(&key);
+
+        if self.size + size > max_size {
+            let now = response.stored;
+            let expired = self
+                .entries
+                .iter()
+                .filter(|(_, entry)| entry.expires <= now)
+                .map(|(_, entry)| entry.size)
+                .sum::<usize>();
+            self.entries.retain(|_, entry| entry.expires > now);
+            self.size -= expired;
+        }
+        while self.size + size > max_size {
+            let oldest = match self.entries.values().map(|entry| entry.used).min() {
+                Some(oldest) => oldest,
+                None => break,
+            };
+            if let Some(evicted) = self.entries.values().find(|entry| entry.used == oldest) {
+                self.size -= evicted.size;
+            }
+            self.entries.retain(|_, entry| entry.used != oldest);
+        }
+
+        self.clock += 1;
+        self.size += size;
+        self.entries.insert(
+            key,
+            Entry {
+                expires: response.stored + ttl,
+                response,
+                size,
+                used: self.clock,
+            },
+        );
+    }
+
+    fn remove(&mut self, key: &Key) {
+        if let Some(entry) = self.entries.remove(key) {
+            self.size -= entry.size;
+        }
+    }
+}
+
+impl Cached {
+    fn age(&self) -> HeaderValue {
+        HeaderValue::from(self.stored.elapsed().as_secs())
+    }
+
+    fn into_response<B: Body>(mut self) -> Response<CacheBody<B>> {
+        self.headers.insert(header::AGE, self.age());
+
+        let mut response = Response::new(CacheBody::cached(self.body));
+        *response.status_mut() = self.status;
+        *response.version_mut() = self.version;
+        *response.headers_mut() = self.headers;
+        response
+    }
+
+    fn not_modified<B: Body>(&self) -> Response<CacheBody<B>> {
+        // the headers a `304 Not Modified` response must have (RFC 9110 section 15.4.5)
+        const HEADERS: [HeaderName; 6] = [
+            header::CACHE_CONTROL,
+            header::CONTENT_LOCATION,
+            header::DATE,
+            header::ETAG,
+            header::EXPIRES,
+            header::VARY,
+        ];
+
+        let mut response = Response::new(CacheBody::cached(Bytes::new()));
+        *response.status_mut() = StatusCode::NOT_MODIFIED;
+        *response.version_mut() = self.version;
+        for name in HEADERS {
+            for value in self.headers.get_all(&name) {
+                response.headers_mut().append(name.clone(), value.clone());
+            }
+        }
+        response.headers_mut().insert(header::AGE, self.age());
+        response
+    }
+}
+
+// Returns `true` if the `If-None-Match` header of a request matches the `ETag` of a response,
+// with the weak comparison.
+fn not_modified(request: &HeaderMap, response: &HeaderMap) -> bool {
+    let etag = match response.get(header::ETAG).and_then(|v| v.to_str().ok()) {
+        Some(etag) => etag.trim_start_matches("W/"),
+        None => return false,
+    };
+
+    request
+        .get_all(header::IF_NONE_MATCH)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(','))
+        .map(str::trim)
+        .any(|tag| tag == "*" || tag.trim_start_matches("W/") == etag)
+}
+
+fn has_directive(headers: &HeaderMap, name: &str) -> bool {
+    headers
+        .get_all(header::CACHE_CONTROL)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(','))
+        // `private` and `no-cache` may have a list of header names as argument
+        .filter_map(|directive| directive.split('=').next())
+        .any(|directive| directive.trim().eq_ignore_ascii_case(name))
+}
+
+fn is_cacheable<B: Body>(response: &Response<B>, max_size: usize, authorized: bool) -> bool {
+    let headers = response.headers();
+    let no_store = ["no-store", "no-cache", "private"]
+        .iter()
+        .any(|name| has_directive(headers, name));
+    let public = !authorized || has_directive(headers, "public");
+    let set_cookie = headers.contains_key(header::SET_COOKIE);
+    let vary_any = response
+        .headers()
+        .get_all(header::VARY)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(','))
+        .any(|name| name.trim() == "*");
+    let fits = response
+        .body()
+        .size_hint()
+        .upper()
+        .map_or(false, |upper| upper <= max_size as u64);
+
+    response.status() == StatusCode::OK && !no_store && public && !set_cookie && !vary_any && fits
+}
+
+// What is needed to store the response of a request that missed the cache.
+struct Pending {
+    key: Key,
+    ttl: Duration,
+    max_size: usize,
+    authorized: bool,
+    store: Arc<Mutex<Store>>,
+}
+
+pin_project! {
+    /// Response future for [`Cache`].
+    pub struct ResponseFuture<F, B>
+    where
+        B: Body,
+    {
+        #[pin]
+        inner: ResponseFutureInner<F, B>,
+    }
+}
+
+pin_project! {
+    #[project = ResFutProj]
+    enum ResponseFutureInner<F, B>
+    where
+        B: Body,
+    {
+        Cached {
+            response: Option<Response<CacheBody<B>>>,
+        },
+        Fetch {
+            #[pin]
+            future: F,
+            pending: Option<Pending>,
+        },
+        Collect {
+            #[pin]
+            collect: Collect<B>,
+            parts: Option<Parts>,
+            pending: Option<Pending>,
+        },
+    }
+}
+
+impl<F, B, E> Future for ResponseFuture<F, B>
+where
+    F: Future<Output = Result<Response<B>, E>>,
+    B: Body,
+{
+    type Output = Result<Response<CacheBody<B>>, E>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let mut this = self.project();
+        loop {
+            let next = match this.inner.as_mut().project() {
+                ResFutProj::Cached { response } => {
+                    let response = response.take().expect("future polled after completion");
+                    return Poll::Ready(Ok(response));
+                }
+                ResFutProj::Fetch { future, pending } => {
+                    let response = ready!(future.poll(cx))?;
+                    match pending.take() {
+                        Some(pending)
+                            if is_cacheable(&response, pending.max_size, pending.authorized) =>
+                        {
+                            let (parts, body) = response.into_parts();
+                            ResponseFutureInner::Collect {
+                                collect: body.collect(),
+                                parts: Some(parts),
+                                pending: Some(pending),
+                            }
+                        }
+                        _ => return Poll::Ready(Ok(response.map(CacheBody::inner))),
+                    }
+                }
+                ResFutProj::Collect {
+                    collect,
+                    parts,
+                    pending,
+                } => {
+                    let collected = ready!(collect.poll(cx));
+                    let mut parts = parts.take().expect("future polled after completion");
+                    let body = match collected {
+                        Ok(collected) => collected.to_bytes(),
+                        Err(error) => {
+                            let body = CacheBody::error(error);
+                            return Poll::Ready(Ok(Response::from_parts(parts, body)));
+                        }
+                    };
+
+                    parts.headers.remove(header::TRANSFER_ENCODING);
+                    parts
+                        .headers
+                        .insert(header::CONTENT_LENGTH, HeaderValue::from(body.len()));
+                    if let Some(pending) = pending.take() {
+                        let cached = Cached {
+                            status: parts.status,
+                            version: parts.version,
+                            headers: parts.headers.clone(),
+                            body: body.clone(),
+                            stored: Instant::now(),
+                        };
+                        let mut store = pending.store.lock().unwrap();
+                        store.insert(pending.key, cached, pending.ttl, pending.max_size);
+                    }
+
+                    let body = CacheBody::cached(body);
+                    return Poll::Ready(Ok(Response::from_parts(parts, body)));
+                }
+            };
+            this.inner.set(next);
+        }
+    }
+}
+
+pin_project! {
+    /// Response body of [`Cache`].
+    pub struct CacheBody<B>
+    where
+        B: Body,
+    {
+        #[pin]
+        inner: CacheBodyInner<B>,
+    }
+}
+
+pin_project! {
+    #[project = BodyProj]
+    enum CacheBodyInner<B>
+    where
+        B: Body,
+    {
+        Cached {
+            data: Option<Bytes>,
+        },
+        Inner {
+            #[pin]
+            body: B,
+        },
+        Error {
+            error: Option<B::Error>,
+        },
+    }
+}
+
+impl<B: Body> CacheBody<B> {
+    fn cached(data: Bytes) -> Self {
+        let data = if data.is_empty() { None } else { Some(data) };
+        Self {
+            inner: CacheBodyInner::Cached { data },
+        }
+    }
+
+    fn inner(body: B) -> Self {
+        Self {
+            inner: CacheBodyInner::Inner { body },
+        }
+    }
+
+    fn error(error: B::Error) -> Self {
+        Self {
+            inner: CacheBodyInner::Error { error: Some(error) },
+        }
+    }
+}
+
+impl<B: Body> Body for CacheBody<B> {
+    type Data = Bytes;
+    type Error = B::Error;
+
+    fn poll_frame(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Result<Frame<Self::Data>, Self::Error>>> {
+        match self.project().inner.project() {
+            BodyProj::Cached { data } => Poll::Ready(data.take().map(|data| Ok(Frame::data(data)))),
+            BodyProj::Inner { body } => {
+                let frame = ready!(body.poll_frame(cx));
+                Poll::Ready(frame.map(|frame| {
+                    frame.map(|frame| {
+                        frame.map_data(|mut data| data.copy_to_bytes(data.remaining()))
+                    })
+                }))
+            }
+            BodyProj::Error { error } => Poll::Ready(error.take().map(Err)),
+        }
+    }
+
+    fn is_end_stream(&self) -> bool {
+        match &self.inner {
+            CacheBodyInner::Cached { data } => data.is_none(),
+            CacheBodyInner::Inner { body } => body.is_end_stream(),
+            CacheBodyInner::Error { error } => error.is_none(),
+        }
+    }
+
+    fn size_hint(&self) -> SizeHint {
+        match &self.inner {
+            CacheBodyInner::Cached { data } => {
+                SizeHint::with_exact(data.as_ref().map_or(0, |data| data.len() as u64))
+            }
+            CacheBodyInner::Inner { body } => body.size_hint(),
+            CacheBodyInner::Error { .. } => SizeHint::default(),
+        }
+    }
+}
+
+impl<B> fmt::Debug for CacheBody<B>
+where
+    B: Body + fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.inner {
+            CacheBodyInner::Cached { data } => f.debug_tuple("Cached").field(data).finish(),
+            CacheBodyInner::Inner { body } => f.debug_tuple("Inner").field(body).finish(),
+            CacheBodyInner::Error { .. } => f.debug_tuple("Error").finish(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_helpers::{to_bytes, Body};
+    use std::{
+        convert::Infallible,
+        sync::atomic::{AtomicUsize, Ordering},
+    };
+    use tower::{service_fn, ServiceExt};
+
+    // A service that counts its calls, and answers with the path of the request.
+    fn service(
+        layer: CacheLayer,
+        calls: &Arc<AtomicUsize>,
+    ) -> impl Service<Request<Body>, Response = Response<CacheBody<Body>>, Error = Infallible> + Clone
+    {
+        let calls = calls.clone();
+        layer.layer(service_fn(move |req: Request<Body>| {
+            calls.fetch_add(1, Ordering::SeqCst);
+            async move {
+                let mut res = Response::new(Body::from(req.uri().path().to_owned()));
+                res.headers_mut()
+                    .insert(header::ETAG, HeaderValue::from_static("\"v1\""));
+                match req.uri().path() {
+                    "/no-store" | "/no-cache" | "/public" => {
+                        let directive = &req.uri().path()[1..];
+                        res.headers_mut()
+                            .insert(header::CACHE_CONTROL, directive.parse().unwrap());
+                    }
+                    "/private" => {
+                        res.headers_mut().insert(
+                            header::CACHE_CONTROL,
+                            HeaderValue::from_static("max-age=60, private=\"set-cookie\""),
+                        );
+                    }
+                    "/set-cookie" => {
+                        res.headers_mut()
+                            .insert(header::SET_COOKIE, HeaderValue::from_static("id=1"));
+                    }
+                    _ => {}
+                }
+                Ok::<_, Infallible>(res)
+            }
+        }))
+    }
+
+    async fn get(
+        svc: impl Service<Request<Body>, Response = Response<CacheBody<Body>>, Error = Infallible>,
+        uri: &str,
+    ) -> Response<CacheBody<Body>> {
+        let req = Request::builder().uri(uri).body(Body::empty()).unwrap();
+        svc.oneshot(req).await.unwrap()
+    }
+
+    async fn get_authorized(
+        svc: impl Service<Request<Body>, Response = Response<CacheBody<Body>>, Error = Infallible>,
+        uri: &str,
+    ) -> Response<CacheBody<Body>> {
+        let req = Request::builder()
+            .uri(uri)
+            .header(header::AUTHORIZATION, "Bearer token")
+            .body(Body::empty())
+            .unwrap();
+        svc.oneshot(req).await.unwrap()
+    }
+
+    #[tokio::test]
+    async fn miss_then_hit() {
+        let calls = Arc::new(AtomicUsize::new(0));
+        let svc = service(CacheLayer::new(Duration::from_secs(60)), &calls);
+
+        let res = get(svc.clone(), "/a").await;
+        assert!(!res.headers().contains_key(header::AGE));
+        assert_eq!(res.headers()[header::CONTENT_LENGTH], "2");
+        assert_eq!(to_bytes(res.into_body()).await.unwrap(), "/a");
+
+        let res = get(svc.clone(), "/a").await;
+        assert_eq!(res.headers()[header::AGE], "0");
+        assert_eq!(to_bytes(res.into_body()).await.unwrap(), "/a");
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+
+        get(svc, "/b").await;
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn expires_after_ttl() {
+        let calls = Arc::new(AtomicUsize::new(0));
+        let svc = service(CacheLayer::new(Duration::from_secs(60)), &calls);
+
+        get(svc.clone(), "/a").await;
+        tokio::time::advance(Duration::from_secs(59)).await;
+        let res = get(svc.clone(), "/a").await;
+        assert_eq!(res.headers()[header::AGE], "59");
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+
+        tokio::time::advance(Duration::from_secs(1)).await;
+        let res = get(svc, "/a").await;
+        assert!(!res.headers().contains_key(header::AGE));
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn does_not_store_no_store_responses() {
+        let calls = Arc::new(AtomicUsize::new(0));
+        let svc = service(CacheLayer::new(Duration::from_secs(60)), &calls);
+
+        get(svc.clone(), "/no-store").await;
+        let res = get(svc, "/no-store").await;
+        assert!(!res.headers().contains_key(header::AGE));
+        assert_eq!(to_bytes(res.into_body()).await.unwrap(), "/no-store");
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn does_not_store_no_cache_responses() {
+        let calls = Arc::new(AtomicUsize::new(0));
+        let svc = service(CacheLayer::new(Duration::from_secs(60)), &calls);
+
+        get(svc.clone(), "/no-cache").await;
+        let res = get(svc, "/no-cache").await;
+        assert!(!res.headers().contains_key(header::AGE));
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn does_not_store_private_responses() {
+        let calls = Arc::new(AtomicUsize::new(0));
+        let svc = service(CacheLayer::new(Duration::from_secs(60)), &calls);
+
+        get(svc.clone(), "/private").await;
+        let res = get(svc, "/private").await;
+        assert!(!res.headers().contains_key(header::AGE));
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn does_not_store_responses_setting_cookies() {
+        let calls = Arc::new(AtomicUsize::new(0));
+        let svc = service(CacheLayer::new(Duration::from_secs(60)), &calls);
+
+        get(svc.clone(), "/set-cookie").await;
+        let res = get(svc, "/set-cookie").await;
+        assert!(!res.headers().contains_key(header::AGE));
+        assert_eq!(res.headers()[header::SET_COOKIE], "id=1");
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn authorized_requests() {
+        let calls = Arc::new(AtomicUsize::new(0));
+        let svc = service(CacheLayer::new(Duration::from_secs(60)), &calls);
+
+        // responses to authorized requests are not stored ...
+        get_authorized(svc.clone(), "/a").await;
+        let res = get(svc.clone(), "/a").await;
+        assert!(!res.headers().contains_key(header::AGE));
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+
+        // ... nor served from the cache
+        let res = get_authorized(svc.clone(), "/a").await;
+        assert!(!res.headers().contains_key(header::AGE));
+        assert_eq!(calls.load(Ordering::SeqCst), 3);
+
+        // unless they are public
+        get_authorized(svc.clone(), "/public").await;
+        let res = get_authorized(svc.clone(), "/public").await;
+        assert_eq!(res.headers()[header::AGE], "0");
+        let res = get(svc, "/public").await;
+        assert_eq!(res.headers()[header::AGE], "0");
+        assert_eq!(calls.load(Ordering::SeqCst), 4);
+    }
+
+    #[tokio::test]
+    async fn not_modified() {
+        let calls = Arc::new(AtomicUsize::new(0));
+        let svc = service(CacheLayer::new(Duration::from_secs(60)), &calls);
+
+        get(svc.clone(), "/a").await;
+        let req = Request::builder()
+            .uri("/a")
+            .header(header::IF_NONE_MATCH, "\"v0\", W/\"v1\"")
+            .body(Body::empty())
+            .unwrap();
+        let res = svc.oneshot(req).await.unwrap();
+        assert_eq!(res.status(), StatusCode::NOT_MODIFIED);
+        assert_eq!(res.headers()[header::ETAG], "\"v1\"");
+        assert!(!res.headers().contains_key(header::CONTENT_LENGTH));
+        assert!(to_bytes(res.into_body()).await.unwrap().is_empty());
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn evicts_least_recently_used() {
+        let calls = Arc::new(AtomicUsize::new(0));
+        // room for two responses, of 2 bytes of body and 23 bytes of headers
+        let layer = CacheLayer::new(Duration::from_secs(60)).max_size(2 * 25);
+        let svc = service(layer, &calls);
+
+        get(svc.clone(), "/a").await;
+        get(svc.clone(), "/b").await;
+        get(svc.clone(), "/a").await;
+        get(svc.clone(), "/c").await;
+        assert_eq!(calls.load(Ordering::SeqCst), 3);
+
+        // `/b` was evicted
+        get(svc.clone(), "/a").await;
+        get(svc.clone(), "/c").await;
+        assert_eq!(calls.load(Ordering::SeqCst), 3);
+        get(svc, "/b").await;
+        assert_eq!(calls.load(Ordering::SeqCst), 4);
+    }
+
+    #[tokio::test]
+    async fn varies_on_headers() {
+        let calls = Arc::new(AtomicUsize::new(0));
+        let layer = CacheLayer::new(Duration::from_secs(60)).vary([header::ACCEPT_LANGUAGE]);
+        let svc = service(layer, &calls);
+
+        for language in ["en", "fr", "en"] {
+            let req = Request::builder()
+                .uri("/a")
+                .header(header::ACCEPT_LANGUAGE, language)
+                .body(Body::empty())
+                .unwrap();
+            svc.clone().oneshot(req).await.unwrap();
+        }
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+    }
+}
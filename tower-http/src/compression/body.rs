@@ -1,6 +1,6 @@
 #![allow(unused_imports)]
 
-use crate::compression::CompressionLevel;
+use crate::compression::{encode::BufferedBody, CompressionLevel};
 use crate::{
     compression_utils::{AsyncReadBody, BodyIntoStream, DecorateAsyncRead, WrapBody},
     BoxError,
@@ -73,6 +73,7 @@ where
             BodyInner::Brotli { inner } => inner.read.get_ref().get_ref().get_ref().get_ref(),
             #[cfg(feature = "compression-zstd")]
             BodyInner::Zstd { inner } => inner.read.get_ref().get_ref().get_ref().get_ref(),
+            BodyInner::Buffered { inner } => inner.get_ref(),
             BodyInner::Identity { inner } => inner,
         }
     }
@@ -88,6 +89,7 @@ where
             BodyInner::Brotli { inner } => inner.read.get_mut().get_mut().get_mut().get_mut(),
             #[cfg(feature = "compression-zstd")]
             BodyInner::Zstd { inner } => inner.read.get_mut().get_mut().get_mut().get_mut(),
+            BodyInner::Buffered { inner } => inner.get_mut(),
             BodyInner::Identity { inner } => inner,
         }
     }
@@ -127,6 +129,7 @@ where
                 .get_pin_mut()
                 .get_pin_mut()
                 .get_pin_mut(),
+            BodyInnerProj::Buffered { inner } => inner.get_pin_mut(),
             BodyInnerProj::Identity { inner } => inner,
         }
     }
@@ -162,6 +165,7 @@ where
                 .into_inner()
                 .into_inner()
                 .into_inner(),
+            BodyInner::Buffered { inner } => inner.into_inner(),
             BodyInner::Identity { inner } => inner,
         }
     }
@@ -205,6 +209,10 @@ pin_project_cfg! {
             #[pin]
             inner: ZstdBody<B>,
         },
+        Buffered {
+            #[pin]
+            inner: BufferedBody<B>,
+        },
         Identity {
             #[pin]
             inner: B,
@@ -233,6 +241,10 @@ impl<B: Body> BodyInner<B> {
         Self::Zstd { inner }
     }
 
+    pub(crate) fn buffered(inner: BufferedBody<B>) -> Self {
+        Self::Buffered { inner }
+    }
+
     pub(crate) fn identity(inner: B) -> Self {
         Self::Identity { inner }
     }
@@ -259,6 +271,7 @@ where
             BodyInnerProj::Brotli { inner } => inner.poll_frame(cx),
             #[cfg(feature = "compression-zstd")]
             BodyInnerProj::Zstd { inner } => inner.poll_frame(cx),
+            BodyInnerProj::Buffered { inner } => inner.poll_frame(cx),
             BodyInnerProj::Identity { inner } => match ready!(inner.poll_frame(cx)) {
                 Some(Ok(frame)) => {
                     let frame = frame.map_data(|mut buf| buf.copy_to_bytes(buf.remaining()));
@@ -305,6 +318,19 @@ where
     }
 }
 
+#[cfg(feature = "compression-br")]
+pub(super) fn brotli_level(quality: CompressionLevel) -> async_compression::Level {
+    // The brotli crate used under the hood here has a default compression level of 11,
+    // which is the max for brotli. This causes extremely slow compression times, so we
+    // manually set a default of 4 here.
+    //
+    // This is the same default used by NGINX for on-the-fly brotli compression.
+    match quality {
+        CompressionLevel::Default => async_compression::Level::Precise(4),
+        other => other.into_async_compression(),
+    }
+}
+
 #[cfg(feature = "compression-br")]
 impl<B> DecorateAsyncRead for BrotliEncoder<B>
 where
@@ -314,16 +340,7 @@ where
     type Output = BrotliEncoder<Self::Input>;
 
     fn apply(input: Self::Input, quality: CompressionLevel) -> Self::Output {
-        // The brotli crate used under the hood here has a default compression level of 11,
-        // which is the max for brotli. This causes extremely slow compression times, so we
-        // manually set a default of 4 here.
-        //
-        // This is the same default used by NGINX for on-the-fly brotli compression.
-        let level = match quality {
-            CompressionLevel::Default => async_compression::Level::Precise(4),
-            other => other.into_async_compression(),
-        };
-        BrotliEncoder::with_quality(input, level)
+        BrotliEncoder::with_quality(input, brotli_level(quality))
     }
 
     fn get_pin_mut(pinned: Pin<&mut Self::Output>) -> Pin<&mut Self::Input> {
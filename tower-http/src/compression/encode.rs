@@ -0,0 +1,298 @@
+#[cfg(feature = "compression-br")]
+use super::body::brotli_level;
+use super::{quality::Quality, CompressionLevel};
+use crate::BoxError;
+#[cfg(feature = "compression-br")]
+use async_compression::tokio::bufread::BrotliEncoder;
+#[cfg(feature = "compression-gzip")]
+use async_compression::tokio::bufread::GzipEncoder;
+#[cfg(feature = "compression-deflate")]
+use async_compression::tokio::bufread::ZlibEncoder;
+#[cfg(feature = "compression-zstd")]
+use async_compression::tokio::bufread::ZstdEncoder;
+use bytes::{Buf, Bytes, BytesMut};
+use http::HeaderMap;
+use http_body::{Body, Frame, SizeHint};
+use pin_project_lite::pin_project;
+use std::{
+    io,
+    pin::Pin,
+    sync::Arc,
+    task::{ready, Context, Poll, Wake, Waker},
+};
+use tokio::io::{AsyncRead, ReadBuf};
+
+// with `buffer_small_bodies`, response bodies of at most this size are compressed at once with
+// `compress_bytes`, 64KiB
+pub(super) const MAX_BUFFERED_LEN: u64 = 65536;
+
+/// An encoding supported by [`compress_bytes`] and [`Compression::force`].
+///
+/// [`Compression::force`]: super::Compression::force
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum Encoding {
+    /// The `gzip` encoding.
+    #[cfg(feature = "compression-gzip")]
+    Gzip,
+    /// The `deflate` encoding.
+    #[cfg(feature = "compression-deflate")]
+    Deflate,
+    /// The `br` encoding.
+    #[cfg(feature = "compression-br")]
+    Brotli,
+    /// The `zstd` encoding.
+    #[cfg(feature = "compression-zstd")]
+    Zstd,
+}
+
+impl From<Encoding> for crate::content_encoding::Encoding {
+    fn from(encoding: Encoding) -> Self {
+        match encoding {
+            #[cfg(feature = "compression-gzip")]
+            Encoding::Gzip => Self::Gzip,
+            #[cfg(feature = "compression-deflate")]
+            Encoding::Deflate => Self::Deflate,
+            #[cfg(feature = "compression-br")]
+            Encoding::Brotli => Self::Brotli,
+            #[cfg(feature = "compression-zstd")]
+            Encoding::Zstd => Self::Zstd,
+        }
+    }
+}
+
+impl Encoding {
+    pub(super) fn from_content_encoding(
+        encoding: crate::content_encoding::Encoding,
+    ) -> Option<Self> {
+        use crate::content_encoding::Encoding as ContentEncoding;
+
+        match encoding {
+            #[cfg(feature = "compression-gzip")]
+            ContentEncoding::Gzip => Some(Self::Gzip),
+            #[cfg(feature = "compression-deflate")]
+            ContentEncoding::Deflate => Some(Self::Deflate),
+            #[cfg(feature = "compression-br")]
+            ContentEncoding::Brotli => Some(Self::Brotli),
+            #[cfg(feature = "compression-zstd")]
+            ContentEncoding::Zstd => Some(Self::Zstd),
+            #[allow(unreachable_patterns)]
+            _ => None,
+        }
+    }
+
+    pub(super) fn level(self, quality: Quality) -> CompressionLevel {
+        match self {
+            #[cfg(feature = "compression-gzip")]
+            Encoding::Gzip => quality.gzip(),
+            #[cfg(feature = "compression-deflate")]
+            Encoding::Deflate => quality.deflate(),
+            #[cfg(feature = "compression-br")]
+            Encoding::Brotli => quality.br(),
+            #[cfg(feature = "compression-zstd")]
+            Encoding::Zstd => quality.zstd(),
+        }
+    }
+}
+
+/// Compress `bytes` with the given encoding and level.
+///
+/// With [`buffer_small_bodies`](super::Compression::buffer_small_bodies), [`Compression`]
+/// compresses response bodies whose size is known to be at most 64 KiB with this function, once
+/// they have been read, so the output is the same as the body of such a response compressed with
+/// the same encoding and level. Since the output only depends on the input, callers that serve
+/// the same body repeatedly can cache it, instead of compressing the body for every response.
+///
+/// # Errors
+///
+/// Returns an error if the encoder fails.
+///
+/// # Example
+///
+/// ```
+/// use bytes::Bytes;
+/// use tower_http::compression::{compress_bytes, CompressionLevel, Encoding};
+///
+/// # fn main() -> std::io::Result<()> {
+/// let body = Bytes::from_static(b"Hello, World!");
+/// let compressed = compress_bytes(Encoding::Gzip, CompressionLevel::Default, &body)?;
+/// # Ok(())
+/// # }
+/// ```
+///
+/// [`Compression`]: super::Compression
+pub fn compress_bytes(
+    encoding: Encoding,
+    level: CompressionLevel,
+    bytes: &Bytes,
+) -> io::Result<Bytes> {
+    let input = &bytes[..];
+    match encoding {
+        #[cfg(feature = "compression-gzip")]
+        Encoding::Gzip => read_to_end(GzipEncoder::with_quality(
+            input,
+            level.into_async_compression(),
+        )),
+        #[cfg(feature = "compression-deflate")]
+        Encoding::Deflate => read_to_end(ZlibEncoder::with_quality(
+            input,
+            level.into_async_compression(),
+        )),
+        #[cfg(feature = "compression-br")]
+        Encoding::Brotli => read_to_end(BrotliEncoder::with_quality(input, brotli_level(level))),
+        #[cfg(feature = "compression-zstd")]
+        Encoding::Zstd => read_to_end(ZstdEncoder::with_quality(
+            input,
+            level.into_async_compression(),
+        )),
+    }
+}
+
+struct NoopWaker;
+
+impl Wake for NoopWaker {
+    fn wake(self: Arc<Self>) {}
+}
+
+// The encoders read from an in-memory buffer, so they are always ready and can be driven
+// to completion without an executor.
+fn read_to_end<R>(reader: R) -> io::Result<Bytes>
+where
+    R: AsyncRead,
+{
+    let waker = Waker::from(Arc::new(NoopWaker));
+    let mut cx = Context::from_waker(&waker);
+    let mut reader = Box::pin(reader);
+
+    let mut output = BytesMut::new();
+    let mut buf = [0; 8 * 1024];
+    loop {
+        let mut read_buf = ReadBuf::new(&mut buf);
+        match Pin::as_mut(&mut reader).poll_read(&mut cx, &mut read_buf) {
+            Poll::Ready(Ok(())) if read_buf.filled().is_empty() => break,
+            Poll::Ready(Ok(())) => output.extend_from_slice(read_buf.filled()),
+            Poll::Ready(Err(err)) => return Err(err),
+            Poll::Pending => {
+                return Err(io::Error::new(
+                    io::ErrorKind::Other,
+                    "compressing in-memory data was pending",
+                ))
+            }
+        }
+    }
+    Ok(output.freeze())
+}
+
+pin_project! {
+    // A response body that is read to the end and compressed at once with `compress_bytes`,
+    // followed by its trailers.
+    pub(crate) struct BufferedBody<B> {
+        #[pin]
+        inner: B,
+        encoding: Encoding,
+        level: CompressionLevel,
+        buf: BytesMut,
+        trailers: Option<HeaderMap>,
+        state: State,
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum State {
+    Reading,
+    Trailers,
+    Done,
+}
+
+impl<B> BufferedBody<B> {
+    pub(crate) fn new(inner: B, encoding: Encoding, level: CompressionLevel) -> Self {
+        Self {
+            inner,
+            encoding,
+            level,
+            buf: BytesMut::new(),
+            trailers: None,
+            state: State::Reading,
+        }
+    }
+
+    pub(crate) fn get_ref(&self) -> &B {
+        &self.inner
+    }
+
+    pub(crate) fn get_mut(&mut self) -> &mut B {
+        &mut self.inner
+    }
+
+    pub(crate) fn get_pin_mut(self: Pin<&mut Self>) -> Pin<&mut B> {
+        self.project().inner
+    }
+
+    pub(crate) fn into_inner(self) -> B {
+        self.inner
+    }
+}
+
+impl<B> Body for BufferedBody<B>
+where
+    B: Body,
+    B::Error: Into<BoxError>,
+{
+    type Data = Bytes;
+    type Error = BoxError;
+
+    fn poll_frame(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Result<Frame<Self::Data>, Self::Error>>> {
+        let mut this = self.project();
+        loop {
+            match *this.state {
+                State::Reading => match ready!(this.inner.as_mut().poll_frame(cx)) {
+                    Some(Ok(frame)) => match frame.into_data() {
+                        Ok(mut data) => {
+                            while data.has_remaining() {
+                                let chunk = data.chunk();
+                                let len = chunk.len();
+                                this.buf.extend_from_slice(chunk);
+                                data.advance(len);
+                            }
+                        }
+                        Err(frame) => {
+                            if let Ok(trailers) = frame.into_trailers() {
+                                *this.trailers = Some(trailers);
+                            }
+                        }
+                    },
+                    Some(Err(err)) => return Poll::Ready(Some(Err(err.into()))),
+                    None => {
+                        *this.state = State::Trailers;
+                        let bytes = this.buf.split().freeze();
+                        return match compress_bytes(*this.encoding, *this.level, &bytes) {
+                            Ok(compressed) => Poll::Ready(Some(Ok(Frame::data(compressed)))),
+                            Err(err) => {
+                                *this.state = State::Done;
+                                Poll::Ready(Some(Err(err.into())))
+                            }
+                        };
+                    }
+                },
+                State::Trailers => {
+                    *this.state = State::Done;
+                    if let Some(trailers) = this.trailers.take() {
+                        return Poll::Ready(Some(Ok(Frame::trailers(trailers))));
+                    }
+                }
+                State::Done => return Poll::Ready(None),
+            }
+        }
+    }
+
+    fn is_end_stream(&self) -> bool {
+        self.state == State::Done
+    }
+
+    fn size_hint(&self) -> SizeHint {
+        SizeHint::default()
+    }
+}
@@ -1,11 +1,12 @@
 #![allow(unused_imports)]
 
+use super::encode::{self, BufferedBody, MAX_BUFFERED_LEN};
+use super::quality::Quality;
 use super::{body::BodyInner, CompressionBody};
 use crate::compression::predicate::Predicate;
-use crate::compression::CompressionLevel;
 use crate::compression_utils::WrapBody;
 use crate::content_encoding::Encoding;
-use http::{header, HeaderMap, HeaderValue, Response};
+use http::{header, HeaderMap, HeaderName, HeaderValue, Response};
 use http_body::Body;
 use pin_project_lite::pin_project;
 use std::{
@@ -14,6 +15,8 @@ use std::{
     task::{ready, Context, Poll},
 };
 
+const DEBUG_HEADER: &str = "x-tower-http-compression";
+
 pin_project! {
     /// Response future of [`Compression`].
     ///
@@ -24,7 +27,11 @@ pin_project! {
         pub(crate) inner: F,
         pub(crate) encoding: Encoding,
         pub(crate) predicate: P,
-        pub(crate) quality: CompressionLevel,
+        pub(crate) quality: Quality,
+        // `Some` if the debug header is enabled, with the reason for skipping compression if it
+        // was already decided by the request
+        pub(crate) debug_header: Option<Option<&'static str>>,
+        pub(crate) buffer_small_bodies: bool,
     }
 }
 
@@ -40,68 +47,116 @@ where
         let res = ready!(self.as_mut().project().inner.poll(cx)?);
 
         // never recompress responses that are already compressed
-        let should_compress = !res.headers().contains_key(header::CONTENT_ENCODING)
-            // never compress responses that are ranges
-            && !res.headers().contains_key(header::CONTENT_RANGE)
-            && self.predicate.should_compress(&res);
+        let already_encoded = res
+            .headers()
+            .get(header::CONTENT_ENCODING)
+            .map_or(false, |value| {
+                !value.as_bytes().eq_ignore_ascii_case(b"identity")
+            });
+
+        // never compress responses that are ranges
+        let is_range = res.headers().contains_key(header::CONTENT_RANGE);
+
+        let should_compress = !already_encoded && !is_range && self.predicate.should_compress(&res);
+
+        // which part of the predicate rejected the response, for the debug header
+        let predicate_skipped = match self.debug_header {
+            Some(None) if !already_encoded && !is_range && !should_compress => {
+                self.predicate.skip_reason(&res).unwrap_or("predicate")
+            }
+            _ => "predicate",
+        };
 
         let (mut parts, body) = res.into_parts();
 
+        if let Some(skipped) = self.debug_header {
+            let value = match skipped {
+                Some(reason) => HeaderValue::from_static(reason),
+                None if already_encoded => HeaderValue::from_static("skipped: already encoded"),
+                None if is_range => HeaderValue::from_static("skipped: content-range"),
+                None if !should_compress => {
+                    HeaderValue::from_str(&format!("skipped: {}", predicate_skipped))
+                        .unwrap_or_else(|_| HeaderValue::from_static("skipped: predicate"))
+                }
+                None => self.encoding.into_header_value(),
+            };
+            parts
+                .headers
+                .insert(HeaderName::from_static(DEBUG_HEADER), value);
+        }
+
         if should_compress {
             parts
                 .headers
                 .append(header::VARY, header::ACCEPT_ENCODING.into());
         }
 
-        let body = match (should_compress, self.encoding) {
-            // if compression is _not_ supported or the client doesn't accept it
-            (false, _) | (_, Encoding::Identity) => {
-                return Poll::Ready(Ok(Response::from_parts(
-                    parts,
-                    CompressionBody::new(BodyInner::identity(body)),
-                )))
-            }
-
-            #[cfg(feature = "compression-gzip")]
-            (_, Encoding::Gzip) => {
-                CompressionBody::new(BodyInner::gzip(WrapBody::new(body, self.quality)))
-            }
-            #[cfg(feature = "compression-deflate")]
-            (_, Encoding::Deflate) => {
-                CompressionBody::new(BodyInner::deflate(WrapBody::new(body, self.quality)))
-            }
-            #[cfg(feature = "compression-br")]
-            (_, Encoding::Brotli) => {
-                CompressionBody::new(BodyInner::brotli(WrapBody::new(body, self.quality)))
-            }
-            #[cfg(feature = "compression-zstd")]
-            (_, Encoding::Zstd) => {
-                CompressionBody::new(BodyInner::zstd(WrapBody::new(body, self.quality)))
-            }
-            #[cfg(feature = "fs")]
-            #[allow(unreachable_patterns)]
-            (true, _) => {
-                // This should never happen because the `AcceptEncoding` struct which is used to determine
-                // `self.encoding` will only enable the different compression algorithms if the
-                // corresponding crate feature has been enabled. This means
-                // Encoding::[Gzip|Brotli|Deflate] should be impossible at this point without the
-                // features enabled.
-                //
-                // The match arm is still required though because the `fs` feature uses the
-                // Encoding struct independently and requires no compression logic to be enabled.
-                // This means a combination of an individual compression feature and `fs` will fail
-                // to compile without this branch even though it will never be reached.
-                //
-                // To safeguard against refactors that changes this relationship or other bugs the
-                // server will return an uncompressed response instead of panicking since that could
-                // become a ddos attack vector.
-                return Poll::Ready(Ok(Response::from_parts(
-                    parts,
-                    CompressionBody::new(BodyInner::identity(body)),
-                )));
-            }
+        // small bodies are read and compressed at once, if enabled
+        let buffered = self.buffer_small_bodies
+            && body
+                .size_hint()
+                .exact()
+                .map_or(false, |len| len <= MAX_BUFFERED_LEN);
+        let buffered_encoding = if should_compress && buffered {
+            encode::Encoding::from_content_encoding(self.encoding)
+        } else {
+            None
         };
 
+        let body =
+            match (should_compress, self.encoding, buffered_encoding) {
+                // if compression is _not_ supported or the client doesn't accept it
+                (false, _, _) | (_, Encoding::Identity, _) => {
+                    return Poll::Ready(Ok(Response::from_parts(
+                        parts,
+                        CompressionBody::new(BodyInner::identity(body)),
+                    )))
+                }
+
+                (_, _, Some(encoding)) => CompressionBody::new(BodyInner::buffered(
+                    BufferedBody::new(body, encoding, encoding.level(self.quality)),
+                )),
+
+                #[cfg(feature = "compression-gzip")]
+                (_, Encoding::Gzip, _) => {
+                    CompressionBody::new(BodyInner::gzip(WrapBody::new(body, self.quality.gzip())))
+                }
+                #[cfg(feature = "compression-deflate")]
+                (_, Encoding::Deflate, _) => CompressionBody::new(BodyInner::deflate(
+                    WrapBody::new(body, self.quality.deflate()),
+                )),
+                #[cfg(feature = "compression-br")]
+                (_, Encoding::Brotli, _) => {
+                    CompressionBody::new(BodyInner::brotli(WrapBody::new(body, self.quality.br())))
+                }
+                #[cfg(feature = "compression-zstd")]
+                (_, Encoding::Zstd, _) => {
+                    CompressionBody::new(BodyInner::zstd(WrapBody::new(body, self.quality.zstd())))
+                }
+                #[cfg(feature = "fs")]
+                #[allow(unreachable_patterns)]
+                (true, _, _) => {
+                    // This should never happen because the `AcceptEncoding` struct which is used to determine
+                    // `self.encoding` will only enable the different compression algorithms if the
+                    // corresponding crate feature has been enabled. This means
+                    // Encoding::[Gzip|Brotli|Deflate] should be impossible at this point without the
+                    // features enabled.
+                    //
+                    // The match arm is still required though because the `fs` feature uses the
+                    // Encoding struct independently and requires no compression logic to be enabled.
+                    // This means a combination of an individual compression feature and `fs` will fail
+                    // to compile without this branch even though it will never be reached.
+                    //
+                    // To safeguard against refactors that changes this relationship or other bugs the
+                    // server will return an uncompressed response instead of panicking since that could
+                    // become a ddos attack vector.
+                    return Poll::Ready(Ok(Response::from_parts(
+                        parts,
+                        CompressionBody::new(BodyInner::identity(body)),
+                    )));
+                }
+            };
+
         parts.headers.remove(header::ACCEPT_RANGES);
         parts.headers.remove(header::CONTENT_LENGTH);
 
@@ -1,7 +1,9 @@
+use super::quality::Quality;
 use super::{Compression, Predicate};
 use crate::compression::predicate::DefaultPredicate;
 use crate::compression::CompressionLevel;
 use crate::compression_utils::AcceptEncoding;
+use crate::content_encoding::Encoding;
 use tower_layer::Layer;
 
 /// Compress response bodies of the underlying service.
@@ -14,7 +16,11 @@ use tower_layer::Layer;
 pub struct CompressionLayer<P = DefaultPredicate> {
     accept: AcceptEncoding,
     predicate: P,
-    quality: CompressionLevel,
+    quality: Quality,
+    disable_header: Option<&'static str>,
+    force: Option<Encoding>,
+    debug_header: bool,
+    buffer_small_bodies: bool,
 }
 
 impl<S, P> Layer<S> for CompressionLayer<P>
@@ -29,6 +35,10 @@ where
             accept: self.accept,
             predicate: self.predicate.clone(),
             quality: self.quality,
+            disable_header: self.disable_header,
+            force: self.force,
+            debug_header: self.debug_header,
+            buffer_small_bodies: self.buffer_small_bodies,
         }
     }
 }
@@ -69,7 +79,73 @@ impl CompressionLayer {
 
     /// Sets the compression quality.
     pub fn quality(mut self, quality: CompressionLevel) -> Self {
-        self.quality = quality;
+        self.quality.default = quality;
+        self
+    }
+
+    /// Sets the compression quality of the gzip encoding, overriding the one set with
+    /// [`quality`](Self::quality).
+    #[cfg(feature = "compression-gzip")]
+    pub fn gzip_level(mut self, quality: CompressionLevel) -> Self {
+        self.quality.gzip = Some(quality);
+        self
+    }
+
+    /// Sets the compression quality of the Deflate encoding, overriding the one set with
+    /// [`quality`](Self::quality).
+    #[cfg(feature = "compression-deflate")]
+    pub fn deflate_level(mut self, quality: CompressionLevel) -> Self {
+        self.quality.deflate = Some(quality);
+        self
+    }
+
+    /// Sets the compression quality of the Brotli encoding, overriding the one set with
+    /// [`quality`](Self::quality).
+    #[cfg(feature = "compression-br")]
+    pub fn br_level(mut self, quality: CompressionLevel) -> Self {
+        self.quality.br = Some(quality);
+        self
+    }
+
+    /// Sets the compression quality of the Zstd encoding, overriding the one set with
+    /// [`quality`](Self::quality).
+    #[cfg(feature = "compression-zstd")]
+    pub fn zstd_level(mut self, quality: CompressionLevel) -> Self {
+        self.quality.zstd = Some(quality);
+        self
+    }
+
+    /// Don't compress responses to requests that have the given header.
+    ///
+    /// See [`Compression::disable_for_request_header`] for more details.
+    pub fn disable_for_request_header(mut self, name: &'static str) -> Self {
+        self.disable_header = Some(name);
+        self
+    }
+
+    /// Always compress responses with the given encoding, regardless of the `Accept-Encoding`
+    /// header of the request.
+    ///
+    /// See [`Compression::force`] for more details.
+    pub fn force(mut self, encoding: super::Encoding) -> Self {
+        self.force = Some(encoding.into());
+        self
+    }
+
+    /// Sets whether to add a debug header to responses recording whether they were compressed.
+    ///
+    /// See [`Compression::debug_header`] for more details.
+    pub fn debug_header(mut self, enable: bool) -> Self {
+        self.debug_header = enable;
+        self
+    }
+
+    /// Sets whether to read response bodies whose size is known to be at most 64 KiB to the end,
+    /// and compress them at once.
+    ///
+    /// See [`Compression::buffer_small_bodies`] for more details.
+    pub fn buffer_small_bodies(mut self, enable: bool) -> Self {
+        self.buffer_small_bodies = enable;
         self
     }
 
@@ -116,6 +192,10 @@ impl CompressionLayer {
             accept: self.accept,
             predicate,
             quality: self.quality,
+            disable_header: self.disable_header,
+            force: self.force,
+            debug_header: self.debug_header,
+            buffer_small_bodies: self.buffer_small_bodies,
         }
     }
 }
@@ -72,14 +72,17 @@
 pub mod predicate;
 
 mod body;
+mod encode;
 mod future;
 mod layer;
 mod pin_project_cfg;
+mod quality;
 mod service;
 
 #[doc(inline)]
 pub use self::{
     body::CompressionBody,
+    encode::{compress_bytes, Encoding},
     future::ResponseFuture,
     layer::CompressionLayer,
     predicate::{DefaultPredicate, Predicate},
@@ -94,6 +97,7 @@ mod tests {
     use super::*;
     use crate::test_helpers::{Body, WithTrailers};
     use async_compression::tokio::write::{BrotliDecoder, BrotliEncoder};
+    use bytes::Bytes;
     use flate2::read::GzDecoder;
     use http::header::{
         ACCEPT_ENCODING, ACCEPT_RANGES, CONTENT_ENCODING, CONTENT_RANGE, CONTENT_TYPE, RANGE,
@@ -105,7 +109,7 @@ mod tests {
     use std::sync::{Arc, RwLock};
     use tokio::io::{AsyncReadExt, AsyncWriteExt};
     use tokio_util::io::StreamReader;
-    use tower::{service_fn, Service, ServiceExt};
+    use tower::{service_fn, Layer, Service, ServiceExt};
 
     // Compression filter allows every other request to be compressed
     #[derive(Clone)]
@@ -273,6 +277,64 @@ mod tests {
         assert_eq!(data, DATA.as_bytes());
     }
 
+    #[tokio::test]
+    async fn passes_through_already_encoded_response_the_client_accepts() {
+        const DATA: &[u8] = b"pretend this is gzip compressed data from upstream";
+
+        let svc = service_fn(|_| async {
+            let resp = Response::builder()
+                .header("content-encoding", "gzip")
+                .body(Body::from(DATA))
+                .unwrap();
+            Ok::<_, std::io::Error>(resp)
+        });
+        let mut svc = Compression::new(svc).compress_when(Always);
+
+        let req = Request::builder()
+            .header("accept-encoding", "gzip")
+            .body(Body::empty())
+            .unwrap();
+        let res = svc.ready().await.unwrap().call(req).await.unwrap();
+
+        assert_eq!(
+            res.headers()
+                .get_all("content-encoding")
+                .iter()
+                .collect::<Vec<&HeaderValue>>(),
+            vec!(HeaderValue::from_static("gzip"))
+        );
+        assert!(res.headers().get("vary").is_none());
+
+        let data = res.into_body().collect().await.unwrap().to_bytes();
+        assert_eq!(data, DATA);
+    }
+
+    #[tokio::test]
+    async fn compresses_response_with_identity_content_encoding() {
+        let svc = service_fn(|_| async {
+            let resp = Response::builder()
+                .header("content-encoding", "identity")
+                .body(Body::from("Hello, World!"))
+                .unwrap();
+            Ok::<_, std::io::Error>(resp)
+        });
+        let mut svc = Compression::new(svc).compress_when(Always);
+
+        let req = Request::builder()
+            .header("accept-encoding", "gzip")
+            .body(Body::empty())
+            .unwrap();
+        let res = svc.ready().await.unwrap().call(req).await.unwrap();
+
+        assert_eq!(res.headers()["content-encoding"], "gzip");
+
+        let compressed_data = res.into_body().collect().await.unwrap().to_bytes();
+        let mut decoder = GzDecoder::new(&compressed_data[..]);
+        let mut decompressed = String::new();
+        decoder.read_to_string(&mut decompressed).unwrap();
+        assert_eq!(decompressed, "Hello, World!");
+    }
+
     async fn handle(_req: Request<Body>) -> Result<Response<WithTrailers<Body>>, Infallible> {
         let mut trailers = HeaderMap::new();
         trailers.insert(HeaderName::from_static("foo"), "bar".parse().unwrap());
@@ -280,6 +342,70 @@ mod tests {
         Ok(Response::builder().body(body).unwrap())
     }
 
+    #[tokio::test]
+    async fn compresses_small_bodies_at_once() {
+        let svc = service_fn(|_req: Request<Body>| async {
+            Ok::<_, Infallible>(Response::new(Body::from("Hello, World!")))
+        });
+        let mut svc = Compression::new(svc)
+            .compress_when(Always)
+            .buffer_small_bodies(true);
+        let req = Request::builder()
+            .header(ACCEPT_ENCODING, "gzip")
+            .body(Body::empty())
+            .unwrap();
+        let res = svc.ready().await.unwrap().call(req).await.unwrap();
+        assert_eq!(res.headers()[CONTENT_ENCODING], "gzip");
+
+        let mut body = res.into_body();
+        let frame = body.frame().await.unwrap().unwrap().into_data().unwrap();
+        assert_eq!(
+            frame,
+            compress_bytes(
+                Encoding::Gzip,
+                CompressionLevel::Default,
+                &Bytes::from("Hello, World!")
+            )
+            .unwrap()
+        );
+        assert!(body.frame().await.is_none());
+    }
+
+    #[tokio::test]
+    async fn compress_bytes_matches_streaming_compression() {
+        let body = Bytes::from("Hello, World!");
+
+        for (encoding, name) in [
+            (Encoding::Gzip, "gzip"),
+            (Encoding::Deflate, "deflate"),
+            (Encoding::Brotli, "br"),
+            (Encoding::Zstd, "zstd"),
+        ] {
+            let compressed = compress_bytes(encoding, CompressionLevel::Default, &body).unwrap();
+            assert_eq!(
+                compressed,
+                compress_bytes(encoding, CompressionLevel::Default, &body).unwrap(),
+                "{} is not deterministic",
+                name
+            );
+
+            let mut svc = Compression::new(service_fn(handle)).compress_when(Always);
+            let req = Request::builder()
+                .header(ACCEPT_ENCODING, name)
+                .body(Body::empty())
+                .unwrap();
+            let res = svc.ready().await.unwrap().call(req).await.unwrap();
+            assert_eq!(res.headers()[CONTENT_ENCODING], name);
+
+            let streamed = res.into_body().collect().await.unwrap().to_bytes();
+            assert_eq!(
+                compressed, streamed,
+                "{} differs from streaming output",
+                name
+            );
+        }
+    }
+
     #[tokio::test]
     async fn will_not_compress_if_filtered_out() {
         use predicate::Predicate;
@@ -337,6 +463,182 @@ mod tests {
         assert!(String::from_utf8(data.to_vec()).is_err());
     }
 
+    #[tokio::test]
+    async fn disable_for_request_header() {
+        let svc = service_fn(handle);
+        let mut svc = Compression::new(svc)
+            .compress_when(Always)
+            .disable_for_request_header("x-no-compress");
+
+        let req = Request::builder()
+            .header(ACCEPT_ENCODING, "gzip")
+            .header("X-No-Compress", "1")
+            .body(Body::empty())
+            .unwrap();
+        let res = svc.ready().await.unwrap().call(req).await.unwrap();
+        assert!(res.headers().get(CONTENT_ENCODING).is_none());
+        let data = res.into_body().collect().await.unwrap().to_bytes();
+        assert_eq!(data, "Hello, World!");
+
+        let req = Request::builder()
+            .header(ACCEPT_ENCODING, "gzip")
+            .body(Body::empty())
+            .unwrap();
+        let res = svc.ready().await.unwrap().call(req).await.unwrap();
+        assert_eq!(res.headers()[CONTENT_ENCODING], "gzip");
+    }
+
+    #[tokio::test]
+    async fn force() {
+        let mut svc = CompressionLayer::new()
+            .force(Encoding::Gzip)
+            .compress_when(Always)
+            .layer(service_fn(handle));
+
+        let req = Request::builder().body(Body::empty()).unwrap();
+        let res = svc.ready().await.unwrap().call(req).await.unwrap();
+        assert_eq!(res.headers()[CONTENT_ENCODING], "gzip");
+
+        let body = res.into_body().collect().await.unwrap().to_bytes();
+        let mut decoder = GzDecoder::new(&body[..]);
+        let mut decompressed = String::new();
+        decoder.read_to_string(&mut decompressed).unwrap();
+        assert_eq!(decompressed, "Hello, World!");
+
+        // the `Accept-Encoding` header is ignored
+        let req = Request::builder()
+            .header(ACCEPT_ENCODING, "br")
+            .body(Body::empty())
+            .unwrap();
+        let res = svc.ready().await.unwrap().call(req).await.unwrap();
+        assert_eq!(res.headers()[CONTENT_ENCODING], "gzip");
+    }
+
+    #[tokio::test]
+    async fn debug_header() {
+        let mut svc = CompressionLayer::new()
+            .debug_header(true)
+            .disable_for_request_header("x-no-compress")
+            .compress_when(Always)
+            .layer(service_fn(handle));
+
+        let req = Request::builder()
+            .header(ACCEPT_ENCODING, "gzip")
+            .body(Body::empty())
+            .unwrap();
+        let res = svc.ready().await.unwrap().call(req).await.unwrap();
+        assert_eq!(res.headers()["x-tower-http-compression"], "gzip");
+
+        let req = Request::builder().body(Body::empty()).unwrap();
+        let res = svc.ready().await.unwrap().call(req).await.unwrap();
+        assert_eq!(
+            res.headers()["x-tower-http-compression"],
+            "skipped: not accepted"
+        );
+
+        let req = Request::builder()
+            .header(ACCEPT_ENCODING, "gzip")
+            .header("x-no-compress", "1")
+            .body(Body::empty())
+            .unwrap();
+        let res = svc.ready().await.unwrap().call(req).await.unwrap();
+        assert_eq!(
+            res.headers()["x-tower-http-compression"],
+            "skipped: disabled by request header"
+        );
+
+        // the default predicate doesn't compress images
+        let mut svc = CompressionLayer::new().debug_header(true).layer(service_fn(
+            |_: Request<Body>| async {
+                let mut res = Response::new(Body::from("a".repeat(64)));
+                res.headers_mut()
+                    .insert(CONTENT_TYPE, HeaderValue::from_static("image/png"));
+                Ok::<_, Infallible>(res)
+            },
+        ));
+        let req = Request::builder()
+            .header(ACCEPT_ENCODING, "gzip")
+            .body(Body::empty())
+            .unwrap();
+        let res = svc.ready().await.unwrap().call(req).await.unwrap();
+        assert_eq!(
+            res.headers()["x-tower-http-compression"],
+            "skipped: content-type excluded"
+        );
+
+        // nor small responses, and `NotForNoTransform` doesn't compress those that can't be
+        // transformed
+        let inner = service_fn(|req: Request<Body>| async move {
+            let mut res = if req.uri().path() == "/small" {
+                Response::new(Body::from("a"))
+            } else {
+                Response::new(Body::from("a".repeat(64)))
+            };
+            if req.uri().path() == "/no-transform" {
+                res.headers_mut().insert(
+                    http::header::CACHE_CONTROL,
+                    HeaderValue::from_static("public, No-Transform"),
+                );
+            }
+            Ok::<_, Infallible>(res)
+        });
+        let default = CompressionLayer::new().debug_header(true).layer(inner);
+        let no_transform = CompressionLayer::new()
+            .debug_header(true)
+            .compress_when(DefaultPredicate::new().and(predicate::NotForNoTransform))
+            .layer(inner);
+        for (path, expected, expected_no_transform) in [
+            (
+                "/small",
+                "skipped: below threshold",
+                "skipped: below threshold",
+            ),
+            ("/no-transform", "gzip", "skipped: no-transform"),
+            ("/", "gzip", "gzip"),
+        ] {
+            let req = || {
+                Request::builder()
+                    .uri(path)
+                    .header(ACCEPT_ENCODING, "gzip")
+                    .body(Body::empty())
+                    .unwrap()
+            };
+            let res = default.clone().oneshot(req()).await.unwrap();
+            assert_eq!(res.headers()["x-tower-http-compression"], expected);
+            let res = no_transform.clone().oneshot(req()).await.unwrap();
+            assert_eq!(
+                res.headers()["x-tower-http-compression"],
+                expected_no_transform
+            );
+        }
+
+        // other predicates are reported as a whole
+        let mut svc = CompressionLayer::new()
+            .debug_header(true)
+            .compress_when(|_, _, _: &HeaderMap, _: &http::Extensions| false)
+            .layer(service_fn(handle));
+        let req = Request::builder()
+            .header(ACCEPT_ENCODING, "gzip")
+            .body(Body::empty())
+            .unwrap();
+        let res = svc.ready().await.unwrap().call(req).await.unwrap();
+        assert_eq!(
+            res.headers()["x-tower-http-compression"],
+            "skipped: predicate"
+        );
+
+        // off by default
+        let mut svc = CompressionLayer::new()
+            .compress_when(Always)
+            .layer(service_fn(handle));
+        let req = Request::builder()
+            .header(ACCEPT_ENCODING, "gzip")
+            .body(Body::empty())
+            .unwrap();
+        let res = svc.ready().await.unwrap().call(req).await.unwrap();
+        assert!(!res.headers().contains_key("x-tower-http-compression"));
+    }
+
     #[tokio::test]
     async fn doesnt_compress_images() {
         async fn handle(_req: Request<Body>) -> Result<Response<Body>, Infallible> {
@@ -434,6 +736,47 @@ mod tests {
         );
     }
 
+    #[tokio::test]
+    async fn compress_with_per_encoding_quality() {
+        async fn compressed_len(
+            svc: Compression<
+                impl Service<Request<Body>, Response = Response<Body>, Error = std::io::Error>,
+            >,
+            encoding: &'static str,
+        ) -> usize {
+            let req = Request::builder()
+                .header("accept-encoding", encoding)
+                .body(Body::empty())
+                .unwrap();
+            let res = svc.oneshot(req).await.unwrap();
+            assert_eq!(res.headers()["content-encoding"], encoding);
+            res.into_body().collect().await.unwrap().to_bytes().len()
+        }
+
+        let svc = service_fn(|_| async {
+            let data = std::fs::read("Cargo.toml").unwrap();
+            Ok::<_, std::io::Error>(Response::new(Body::from(data)))
+        });
+
+        let best = Compression::new(svc).quality(CompressionLevel::Best);
+        let fastest = Compression::new(svc).quality(CompressionLevel::Fastest);
+        let mixed = Compression::new(svc)
+            .quality(CompressionLevel::Best)
+            .gzip_level(CompressionLevel::Fastest)
+            .zstd_level(CompressionLevel::Fastest);
+
+        for encoding in ["gzip", "zstd"] {
+            let fastest_len = compressed_len(fastest.clone(), encoding).await;
+            assert!(compressed_len(best.clone(), encoding).await < fastest_len);
+            assert_eq!(compressed_len(mixed.clone(), encoding).await, fastest_len);
+        }
+        for encoding in ["br", "deflate"] {
+            let best_len = compressed_len(best.clone(), encoding).await;
+            assert!(best_len < compressed_len(fastest.clone(), encoding).await);
+            assert_eq!(compressed_len(mixed.clone(), encoding).await, best_len);
+        }
+    }
+
     #[tokio::test]
     async fn should_not_compress_ranges() {
         let svc = service_fn(|_| async {
@@ -17,6 +17,22 @@ pub trait Predicate: Clone {
     where
         B: Body;
 
+    /// Why this response shouldn't be compressed, or `None` if it should.
+    ///
+    /// This is reported in the debug header of [`Compression`][super::Compression::debug_header].
+    /// The default implementation returns `predicate` if [`should_compress`](Self::should_compress)
+    /// returns `false`.
+    fn skip_reason<B>(&self, response: &http::Response<B>) -> Option<&'static str>
+    where
+        B: Body,
+    {
+        if self.should_compress(response) {
+            None
+        } else {
+            Some("predicate")
+        }
+    }
+
     /// Combine two predicates into one.
     ///
     /// The resulting predicate enables compression if both inner predicates do.
@@ -60,6 +76,13 @@ where
             .map(|inner| inner.should_compress(response))
             .unwrap_or(true)
     }
+
+    fn skip_reason<B>(&self, response: &http::Response<B>) -> Option<&'static str>
+    where
+        B: Body,
+    {
+        self.as_ref().and_then(|inner| inner.skip_reason(response))
+    }
 }
 
 /// Two predicates combined into one.
@@ -82,6 +105,15 @@ where
     {
         self.lhs.should_compress(response) && self.rhs.should_compress(response)
     }
+
+    fn skip_reason<B>(&self, response: &http::Response<B>) -> Option<&'static str>
+    where
+        B: Body,
+    {
+        self.lhs
+            .skip_reason(response)
+            .or_else(|| self.rhs.skip_reason(response))
+    }
 }
 
 /// The default predicate used by [`Compression`] and [`CompressionLayer`].
@@ -99,7 +131,9 @@ where
 /// by combining types in this module:
 ///
 /// ```rust
-/// use tower_http::compression::predicate::{SizeAbove, NotForContentType, Predicate};
+/// use tower_http::compression::predicate::{
+///     NotForContentType, NotForNoTransform, Predicate, SizeAbove,
+/// };
 ///
 /// // slightly large min size than the default 32
 /// let predicate = SizeAbove::new(256)
@@ -108,7 +142,9 @@ where
 ///     // still don't compress images
 ///     .and(NotForContentType::IMAGES)
 ///     // also don't compress JSON
-///     .and(NotForContentType::const_new("application/json"));
+///     .and(NotForContentType::const_new("application/json"))
+///     // also respect `cache-control: no-transform`
+///     .and(NotForNoTransform);
 /// ```
 ///
 /// [`Compression`]: super::Compression
@@ -142,6 +178,13 @@ impl Predicate for DefaultPredicate {
     {
         self.0.should_compress(response)
     }
+
+    fn skip_reason<B>(&self, response: &http::Response<B>) -> Option<&'static str>
+    where
+        B: Body,
+    {
+        self.0.skip_reason(response)
+    }
 }
 
 /// [`Predicate`] that will only allow compression of responses above a certain size.
@@ -185,6 +228,17 @@ impl Predicate for SizeAbove {
             _ => true,
         }
     }
+
+    fn skip_reason<B>(&self, response: &http::Response<B>) -> Option<&'static str>
+    where
+        B: Body,
+    {
+        if self.should_compress(response) {
+            None
+        } else {
+            Some("below threshold")
+        }
+    }
 }
 
 /// Predicate that wont allow responses with a specific `content-type` to be compressed.
@@ -237,6 +291,50 @@ impl Predicate for NotForContentType {
 
         !content_type(response).starts_with(self.content_type.as_str())
     }
+
+    fn skip_reason<B>(&self, response: &http::Response<B>) -> Option<&'static str>
+    where
+        B: Body,
+    {
+        if self.should_compress(response) {
+            None
+        } else {
+            Some("content-type excluded")
+        }
+    }
+}
+
+/// Predicate that wont compress responses whose `cache-control` header has the `no-transform`
+/// directive.
+///
+/// This isn't part of [`DefaultPredicate`], combine them with [`Predicate::and`] to use both.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct NotForNoTransform;
+
+impl Predicate for NotForNoTransform {
+    fn should_compress<B>(&self, response: &http::Response<B>) -> bool
+    where
+        B: Body,
+    {
+        !response
+            .headers()
+            .get_all(header::CACHE_CONTROL)
+            .iter()
+            .filter_map(|value| value.to_str().ok())
+            .flat_map(|value| value.split(','))
+            .any(|directive| directive.trim().eq_ignore_ascii_case("no-transform"))
+    }
+
+    fn skip_reason<B>(&self, response: &http::Response<B>) -> Option<&'static str>
+    where
+        B: Body,
+    {
+        if self.should_compress(response) {
+            None
+        } else {
+            Some("no-transform")
+        }
+    }
 }
 
 #[derive(Clone)]
@@ -0,0 +1,38 @@
+use crate::compression::CompressionLevel;
+
+// The compression quality of each encoding, which is `default` unless it is overridden for that
+// encoding.
+#[derive(Clone, Copy, Debug, Default)]
+pub(crate) struct Quality {
+    pub(crate) default: CompressionLevel,
+    #[cfg(feature = "compression-gzip")]
+    pub(crate) gzip: Option<CompressionLevel>,
+    #[cfg(feature = "compression-deflate")]
+    pub(crate) deflate: Option<CompressionLevel>,
+    #[cfg(feature = "compression-br")]
+    pub(crate) br: Option<CompressionLevel>,
+    #[cfg(feature = "compression-zstd")]
+    pub(crate) zstd: Option<CompressionLevel>,
+}
+
+impl Quality {
+    #[cfg(feature = "compression-gzip")]
+    pub(crate) fn gzip(self) -> CompressionLevel {
+        self.gzip.unwrap_or(self.default)
+    }
+
+    #[cfg(feature = "compression-deflate")]
+    pub(crate) fn deflate(self) -> CompressionLevel {
+        self.deflate.unwrap_or(self.default)
+    }
+
+    #[cfg(feature = "compression-br")]
+    pub(crate) fn br(self) -> CompressionLevel {
+        self.br.unwrap_or(self.default)
+    }
+
+    #[cfg(feature = "compression-zstd")]
+    pub(crate) fn zstd(self) -> CompressionLevel {
+        self.zstd.unwrap_or(self.default)
+    }
+}
@@ -1,3 +1,4 @@
+use super::quality::Quality;
 use super::{CompressionBody, CompressionLayer, ResponseFuture};
 use crate::compression::predicate::{DefaultPredicate, Predicate};
 use crate::compression::CompressionLevel;
@@ -18,7 +19,11 @@ pub struct Compression<S, P = DefaultPredicate> {
     pub(crate) inner: S,
     pub(crate) accept: AcceptEncoding,
     pub(crate) predicate: P,
-    pub(crate) quality: CompressionLevel,
+    pub(crate) quality: Quality,
+    pub(crate) disable_header: Option<&'static str>,
+    pub(crate) force: Option<Encoding>,
+    pub(crate) debug_header: bool,
+    pub(crate) buffer_small_bodies: bool,
 }
 
 impl<S> Compression<S, DefaultPredicate> {
@@ -28,7 +33,11 @@ impl<S> Compression<S, DefaultPredicate> {
             inner: service,
             accept: AcceptEncoding::default(),
             predicate: DefaultPredicate::default(),
-            quality: CompressionLevel::default(),
+            quality: Quality::default(),
+            disable_header: None,
+            force: None,
+            debug_header: false,
+            buffer_small_bodies: false,
         }
     }
 }
@@ -73,7 +82,124 @@ impl<S, P> Compression<S, P> {
 
     /// Sets the compression quality.
     pub fn quality(mut self, quality: CompressionLevel) -> Self {
-        self.quality = quality;
+        self.quality.default = quality;
+        self
+    }
+
+    /// Sets the compression quality of the gzip encoding, overriding the one set with
+    /// [`quality`](Self::quality).
+    #[cfg(feature = "compression-gzip")]
+    pub fn gzip_level(mut self, quality: CompressionLevel) -> Self {
+        self.quality.gzip = Some(quality);
+        self
+    }
+
+    /// Sets the compression quality of the Deflate encoding, overriding the one set with
+    /// [`quality`](Self::quality).
+    #[cfg(feature = "compression-deflate")]
+    pub fn deflate_level(mut self, quality: CompressionLevel) -> Self {
+        self.quality.deflate = Some(quality);
+        self
+    }
+
+    /// Sets the compression quality of the Brotli encoding, overriding the one set with
+    /// [`quality`](Self::quality).
+    #[cfg(feature = "compression-br")]
+    pub fn br_level(mut self, quality: CompressionLevel) -> Self {
+        self.quality.br = Some(quality);
+        self
+    }
+
+    /// Sets the compression quality of the Zstd encoding, overriding the one set with
+    /// [`quality`](Self::quality).
+    #[cfg(feature = "compression-zstd")]
+    pub fn zstd_level(mut self, quality: CompressionLevel) -> Self {
+        self.quality.zstd = Some(quality);
+        self
+    }
+
+    /// Don't compress responses to requests that have the given header.
+    ///
+    /// This lets clients opt out of compression, for example for debugging, even if their
+    /// `Accept-Encoding` header allows compressed responses. The value of the header is ignored.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use tower_http::compression::Compression;
+    /// use tower::util::service_fn;
+    ///
+    /// // Placeholder service_fn
+    /// let service = service_fn(|_: ()| async {
+    ///     Ok::<_, std::io::Error>(http::Response::new(()))
+    /// });
+    ///
+    /// // Requests with a `x-no-compress` header get uncompressed responses.
+    /// let service = Compression::new(service).disable_for_request_header("x-no-compress");
+    /// ```
+    pub fn disable_for_request_header(mut self, name: &'static str) -> Self {
+        self.disable_header = Some(name);
+        self
+    }
+
+    /// Always compress responses with the given encoding, regardless of the `Accept-Encoding`
+    /// header of the request.
+    ///
+    /// This is meant for known clients that always accept the encoding but don't send an
+    /// `Accept-Encoding` header. Other clients, such as browsers, proxies and caches, may not be
+    /// able to decode the response, so only use this when all clients are known to support the
+    /// encoding.
+    ///
+    /// Responses are still only compressed if the predicate allows it, and
+    /// [`disable_for_request_header`](Self::disable_for_request_header) still applies.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use tower_http::compression::{Compression, Encoding};
+    /// use tower::util::service_fn;
+    ///
+    /// // Placeholder service_fn
+    /// let service = service_fn(|_: ()| async {
+    ///     Ok::<_, std::io::Error>(http::Response::new(()))
+    /// });
+    ///
+    /// // All responses are compressed with gzip.
+    /// let service = Compression::new(service).force(Encoding::Gzip);
+    /// ```
+    pub fn force(mut self, encoding: super::Encoding) -> Self {
+        self.force = Some(encoding.into());
+        self
+    }
+
+    /// Sets whether to add a debug header to responses recording whether they were compressed.
+    ///
+    /// When enabled, responses get an `x-tower-http-compression` header with either the encoding
+    /// used, such as `gzip`, or the reason the response wasn't compressed:
+    ///
+    /// - `skipped: disabled by request header`, see
+    ///   [`disable_for_request_header`](Self::disable_for_request_header).
+    /// - `skipped: not accepted`, when the client doesn't accept any of the enabled encodings.
+    /// - `skipped: already encoded`, when the response has a `Content-Encoding` header.
+    /// - `skipped: content-range`, when the response is a range.
+    /// - `skipped: below threshold`, `skipped: content-type excluded` or `skipped: no-transform`,
+    ///   when that part of the [predicate](Self::compress_when) rejected the response, see
+    ///   [`Predicate::skip_reason`](super::Predicate::skip_reason).
+    /// - `skipped: predicate`, when another predicate rejected the response.
+    ///
+    /// This is meant for debugging. Defaults to `false`.
+    pub fn debug_header(mut self, enable: bool) -> Self {
+        self.debug_header = enable;
+        self
+    }
+
+    /// Sets whether to read response bodies whose size is known to be at most 64 KiB to the end,
+    /// and compress them at once with [`compress_bytes`](super::compress_bytes).
+    ///
+    /// Nothing is sent until the whole body has been read, so this is only meant for bodies that
+    /// are already in memory, such as cached responses. Defaults to `false`.
+    pub fn buffer_small_bodies(mut self, enable: bool) -> Self {
+        self.buffer_small_bodies = enable;
         self
     }
 
@@ -142,8 +268,8 @@ impl<S, P> Compression<S, P> {
     ///
     /// See [`predicate`](super::predicate) for more utilities for building compression predicates.
     ///
-    /// Responses that are already compressed (ie have a `content-encoding` header) will _never_ be
-    /// recompressed, regardless what they predicate says.
+    /// Responses that are already compressed (ie have a `content-encoding` header other than
+    /// `identity`) will _never_ be recompressed, regardless what they predicate says.
     pub fn compress_when<C>(self, predicate: C) -> Compression<S, C>
     where
         C: Predicate,
@@ -153,6 +279,10 @@ impl<S, P> Compression<S, P> {
             accept: self.accept,
             predicate,
             quality: self.quality,
+            disable_header: self.disable_header,
+            force: self.force,
+            debug_header: self.debug_header,
+            buffer_small_bodies: self.buffer_small_bodies,
         }
     }
 }
@@ -173,13 +303,33 @@ where
     }
 
     fn call(&mut self, req: Request<ReqBody>) -> Self::Future {
-        let encoding = Encoding::from_headers(req.headers(), self.accept);
+        let disabled = self
+            .disable_header
+            .map_or(false, |name| req.headers().contains_key(name));
+        let encoding = if disabled {
+            Encoding::Identity
+        } else if let Some(encoding) = self.force {
+            encoding
+        } else {
+            Encoding::from_headers(req.headers(), self.accept)
+        };
+        let debug_header = self.debug_header.then(|| {
+            if disabled {
+                Some("skipped: disabled by request header")
+            } else if encoding == Encoding::Identity {
+                Some("skipped: not accepted")
+            } else {
+                None
+            }
+        });
 
         ResponseFuture {
             inner: self.inner.call(req),
             encoding,
             predicate: self.predicate.clone(),
             quality: self.quality,
+            debug_header,
+            buffer_small_bodies: self.buffer_small_bodies,
         }
     }
 }
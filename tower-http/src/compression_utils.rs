@@ -238,6 +238,7 @@ pin_project! {
         body: B,
         yielded_all_data: bool,
         non_data_frame: Option<Frame<B::Data>>,
+        data_len: u64,
     }
 }
 
@@ -251,9 +252,15 @@ where
             body,
             yielded_all_data: false,
             non_data_frame: None,
+            data_len: 0,
         }
     }
 
+    /// Get the number of data bytes yielded so far
+    pub(crate) fn data_len(&self) -> u64 {
+        self.data_len
+    }
+
     /// Get a reference to the inner body
     pub(crate) fn get_ref(&self) -> &B {
         &self.body
@@ -291,7 +298,10 @@ where
 
             match std::task::ready!(this.body.poll_frame(cx)) {
                 Some(Ok(frame)) => match frame.into_data() {
-                    Ok(data) => return Poll::Ready(Some(Ok(data))),
+                    Ok(data) => {
+                        *this.data_len += data.remaining() as u64;
+                        return Poll::Ready(Some(Ok(data)));
+                    }
                     Err(frame) => {
                         *this.yielded_all_data = true;
                         *this.non_data_frame = Some(frame);
@@ -142,12 +142,18 @@ pub(crate) struct QValue(u16);
 ))]
 impl QValue {
     #[inline]
-    fn one() -> Self {
+    pub(crate) fn one() -> Self {
         Self(1000)
     }
 
+    #[cfg(feature = "fs")]
+    #[inline]
+    pub(crate) fn is_zero(self) -> bool {
+        self.0 == 0
+    }
+
     // Parse a q-value as specified in RFC 7231 section 5.3.1.
-    fn parse(s: &str) -> Option<Self> {
+    pub(crate) fn parse(s: &str) -> Option<Self> {
         let mut c = s.chars();
         // Parse "q=" (case-insensitively).
         match c.next() {
@@ -5,6 +5,8 @@ use http::{
     request::Parts as RequestParts,
 };
 
+use super::NULL;
+
 /// Holds configuration for how to set the [`Access-Control-Allow-Credentials`][mdn] header.
 ///
 /// See [`CorsLayer::allow_credentials`] for more details.
@@ -43,6 +45,7 @@ impl AllowCredentials {
         matches!(&self.0, AllowCredentialsInner::Yes)
     }
 
+    #[allow(clippy::borrow_interior_mutable_const)]
     pub(super) fn to_header(
         &self,
         origin: Option<&HeaderValue>,
@@ -51,6 +54,11 @@ impl AllowCredentials {
         #[allow(clippy::declare_interior_mutable_const)]
         const TRUE: HeaderValue = HeaderValue::from_static("true");
 
+        // any page can send the `null` origin, it must never be trusted with credentials
+        if origin == Some(&NULL) {
+            return None;
+        }
+
         let allow_creds = match &self.0 {
             AllowCredentialsInner::Yes => true,
             AllowCredentialsInner::No => false,
@@ -11,7 +11,7 @@ use std::{
     task::{Context, Poll},
 };
 
-use super::{Any, WILDCARD};
+use super::{Any, NULL, WILDCARD};
 
 /// Holds configuration for how to set the [`Access-Control-Allow-Origin`][mdn] header.
 ///
@@ -44,6 +44,9 @@ impl AllowOrigin {
 
     /// Set multiple allowed origins
     ///
+    /// The list may contain `null` to allow requests with an `Origin: null`
+    /// header.
+    ///
     /// See [`CorsLayer::allow_origin`] for more details.
     ///
     /// # Panics
@@ -69,9 +72,13 @@ impl AllowOrigin {
 
     /// Set the allowed origins from a predicate
     ///
+    /// The predicate isn't called for the `null` origin, see
+    /// [`CorsLayer::allow_null_origin`].
+    ///
     /// See [`CorsLayer::allow_origin`] for more details.
     ///
     /// [`CorsLayer::allow_origin`]: super::CorsLayer::allow_origin
+    /// [`CorsLayer::allow_null_origin`]: super::CorsLayer::allow_null_origin
     pub fn predicate<F>(f: F) -> Self
     where
         F: Fn(&HeaderValue, &RequestParts) -> bool + Send + Sync + 'static,
@@ -81,9 +88,13 @@ impl AllowOrigin {
 
     /// Set the allowed origins from an async predicate
     ///
+    /// The predicate isn't called for the `null` origin, see
+    /// [`CorsLayer::allow_null_origin`].
+    ///
     /// See [`CorsLayer::allow_origin`] for more details.
     ///
     /// [`CorsLayer::allow_origin`]: super::CorsLayer::allow_origin
+    /// [`CorsLayer::allow_null_origin`]: super::CorsLayer::allow_null_origin
     pub fn async_predicate<F, Fut>(f: F) -> Self
     where
         F: FnOnce(HeaderValue, &RequestParts) -> Fut + Send + Sync + 'static + Clone,
@@ -97,11 +108,13 @@ impl AllowOrigin {
     /// Allow any origin, by mirroring the request origin
     ///
     /// This is equivalent to
-    /// [`AllowOrigin::predicate(|_, _| true)`][Self::predicate].
+    /// [`AllowOrigin::predicate(|_, _| true)`][Self::predicate], so the `null`
+    /// origin isn't mirrored unless [`CorsLayer::allow_null_origin`] is enabled.
     ///
     /// See [`CorsLayer::allow_origin`] for more details.
     ///
     /// [`CorsLayer::allow_origin`]: super::CorsLayer::allow_origin
+    /// [`CorsLayer::allow_null_origin`]: super::CorsLayer::allow_null_origin
     pub fn mirror_request() -> Self {
         Self::predicate(|_, _| true)
     }
@@ -111,13 +124,25 @@ impl AllowOrigin {
         matches!(&self.0, OriginInner::Const(v) if v == WILDCARD)
     }
 
+    #[allow(clippy::borrow_interior_mutable_const)]
+    pub(super) fn is_null_allowed(&self) -> bool {
+        matches!(&self.0, OriginInner::List(l) if l.contains(&NULL))
+    }
+
+    #[allow(clippy::borrow_interior_mutable_const)]
     pub(super) fn to_future(
         &self,
         origin: Option<&HeaderValue>,
         parts: &RequestParts,
+        allow_null: bool,
     ) -> AllowOriginFuture {
         let name = header::ACCESS_CONTROL_ALLOW_ORIGIN;
 
+        if origin == Some(&NULL) && !self.is_wildcard() {
+            let allowed = allow_null || self.is_null_allowed();
+            return AllowOriginFuture::ok(allowed.then_some((name, NULL)));
+        }
+
         match &self.0 {
             OriginInner::Const(v) => AllowOriginFuture::ok(Some((name, v.clone()))),
             OriginInner::List(l) => {
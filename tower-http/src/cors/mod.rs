@@ -96,15 +96,20 @@ pub struct CorsLayer {
     allow_headers: AllowHeaders,
     allow_methods: AllowMethods,
     allow_origin: AllowOrigin,
+    allow_null_origin: bool,
     allow_private_network: AllowPrivateNetwork,
     expose_headers: ExposeHeaders,
     max_age: MaxAge,
     vary: Vary,
+    vary_request_dependent: bool,
 }
 
 #[allow(clippy::declare_interior_mutable_const)]
 const WILDCARD: HeaderValue = HeaderValue::from_static("*");
 
+#[allow(clippy::declare_interior_mutable_const)]
+const NULL: HeaderValue = HeaderValue::from_static("null");
+
 impl CorsLayer {
     /// Create a new `CorsLayer`.
     ///
@@ -119,10 +124,12 @@ impl CorsLayer {
             allow_headers: Default::default(),
             allow_methods: Default::default(),
             allow_origin: Default::default(),
+            allow_null_origin: false,
             allow_private_network: Default::default(),
             expose_headers: Default::default(),
             max_age: Default::default(),
             vary: Default::default(),
+            vary_request_dependent: false,
         }
     }
 
@@ -373,6 +380,10 @@ impl CorsLayer {
     /// ));
     /// ```
     ///
+    /// Requests with an `Origin: null` header are only allowed if `null` is
+    /// one of the origins passed to [`AllowOrigin::list`], or if
+    /// [`CorsLayer::allow_null_origin`] is enabled.
+    ///
     /// Note that multiple calls to this method will override any previous
     /// calls.
     ///
@@ -385,6 +396,33 @@ impl CorsLayer {
         self
     }
 
+    /// Allow requests with an `Origin: null` header.
+    ///
+    /// Browsers send the `null` origin from sandboxed iframes, `file://` pages
+    /// and after some cross-origin redirects, so any page can make requests
+    /// with it. Such requests are denied by default, even with
+    /// [`AllowOrigin::mirror_request`] or a predicate. When this is enabled,
+    /// `null` is sent back as the allowed origin.
+    ///
+    /// Allowing the `null` origin can't be combined with
+    /// [`CorsLayer::allow_credentials`], and the
+    /// `Access-Control-Allow-Credentials` header is never sent to requests
+    /// with the `null` origin.
+    ///
+    /// Defaults to `false`.
+    ///
+    /// ```
+    /// use tower_http::cors::CorsLayer;
+    ///
+    /// let layer = CorsLayer::new()
+    ///     .allow_origin(["https://example.com".parse().unwrap()])
+    ///     .allow_null_origin(true);
+    /// ```
+    pub fn allow_null_origin(mut self, allow_null_origin: bool) -> Self {
+        self.allow_null_origin = allow_null_origin;
+        self
+    }
+
     /// Set the value of the [`Access-Control-Expose-Headers`][mdn] header.
     ///
     /// ```
@@ -448,6 +486,53 @@ impl CorsLayer {
         self.vary = headers.into();
         self
     }
+
+    /// Add the request headers that the preflight response depends on to the
+    /// [`Vary`][mdn] header.
+    ///
+    /// When [`AllowHeaders::mirror_request`] or [`AllowMethods::mirror_request`]
+    /// is used, the preflight response depends on the
+    /// `Access-Control-Request-Headers` or `Access-Control-Request-Method`
+    /// request header, respectively. With this option enabled, those header
+    /// names are appended to the value(s) set with [`CorsLayer::vary`] on
+    /// preflight responses, unless they are already present.
+    ///
+    /// This is useful if you've customized the `Vary` header and still want
+    /// caches to store preflight responses correctly.
+    ///
+    /// Defaults to `false`.
+    ///
+    /// ```
+    /// use http::header;
+    /// use tower_http::cors::{AllowHeaders, CorsLayer};
+    ///
+    /// let layer = CorsLayer::new()
+    ///     .allow_headers(AllowHeaders::mirror_request())
+    ///     .vary([header::ORIGIN])
+    ///     .vary_request_dependent(true);
+    /// ```
+    ///
+    /// [mdn]: https://developer.mozilla.org/en-US/docs/Web/HTTP/Headers/Vary
+    pub fn vary_request_dependent(mut self, vary_request_dependent: bool) -> Self {
+        self.vary_request_dependent = vary_request_dependent;
+        self
+    }
+
+    fn preflight_vary(&self) -> Option<(HeaderName, HeaderValue)> {
+        if !self.vary_request_dependent {
+            return self.vary.to_header();
+        }
+
+        let mut request_dependent = Vec::new();
+        if self.allow_methods.is_mirror_request() {
+            request_dependent.push(header::ACCESS_CONTROL_REQUEST_METHOD);
+        }
+        if self.allow_headers.is_mirror_request() {
+            request_dependent.push(header::ACCESS_CONTROL_REQUEST_HEADERS);
+        }
+
+        self.vary.with(request_dependent).to_header()
+    }
 }
 
 /// Represents a wildcard value (`*`) used with some CORS headers such as
@@ -613,6 +698,13 @@ impl<S> Cors<S> {
         self.map_layer(|layer| layer.allow_origin(origin))
     }
 
+    /// Allow requests with an `Origin: null` header.
+    ///
+    /// See [`CorsLayer::allow_null_origin`] for more details.
+    pub fn allow_null_origin(self, allow_null_origin: bool) -> Self {
+        self.map_layer(|layer| layer.allow_null_origin(allow_null_origin))
+    }
+
     /// Set the value of the [`Access-Control-Expose-Headers`][mdn] header.
     ///
     /// See [`CorsLayer::expose_headers`] for more details.
@@ -637,6 +729,16 @@ impl<S> Cors<S> {
         self.map_layer(|layer| layer.allow_private_network(allow_private_network))
     }
 
+    /// Add the request headers that the preflight response depends on to the
+    /// [`Vary`][mdn] header.
+    ///
+    /// See [`CorsLayer::vary_request_dependent`] for more details.
+    ///
+    /// [mdn]: https://developer.mozilla.org/en-US/docs/Web/HTTP/Headers/Vary
+    pub fn vary_request_dependent(self, vary_request_dependent: bool) -> Self {
+        self.map_layer(|layer| layer.vary_request_dependent(vary_request_dependent))
+    }
+
     fn map_layer<F>(mut self, f: F) -> Self
     where
         F: FnOnce(CorsLayer) -> CorsLayer,
@@ -671,12 +773,16 @@ where
 
         headers.extend(self.layer.allow_credentials.to_header(origin, &parts));
         headers.extend(self.layer.allow_private_network.to_header(origin, &parts));
-        headers.extend(self.layer.vary.to_header());
 
-        let allow_origin_future = self.layer.allow_origin.to_future(origin, &parts);
+        let allow_origin_future =
+            self.layer
+                .allow_origin
+                .to_future(origin, &parts, self.layer.allow_null_origin);
 
         // Return results immediately upon preflight request
         if parts.method == Method::OPTIONS {
+            headers.extend(self.layer.preflight_vary());
+
             // These headers are applied only to preflight requests
             headers.extend(self.layer.allow_methods.to_header(&parts));
             headers.extend(self.layer.allow_headers.to_header(&parts));
@@ -689,6 +795,8 @@ where
                 },
             }
         } else {
+            headers.extend(self.layer.vary.to_header());
+
             // This header is applied only to non-preflight requests
             headers.extend(self.layer.expose_headers.to_header(&parts));
 
@@ -806,6 +914,12 @@ fn ensure_usable_cors_rules(layer: &CorsLayer) {
             "Invalid CORS configuration: Cannot combine `Access-Control-Allow-Credentials: true` \
              with `Access-Control-Expose-Headers: *`"
         );
+
+        assert!(
+            !layer.allow_null_origin && !layer.allow_origin.is_null_allowed(),
+            "Invalid CORS configuration: Cannot combine `Access-Control-Allow-Credentials: true` \
+             with `Access-Control-Allow-Origin: null`"
+        );
     }
 }
 
@@ -1,10 +1,10 @@
 use std::convert::Infallible;
 
 use crate::test_helpers::Body;
-use http::{header, HeaderValue, Request, Response};
+use http::{header, HeaderValue, Method, Request, Response};
 use tower::{service_fn, util::ServiceExt, Layer};
 
-use crate::cors::{AllowOrigin, CorsLayer};
+use crate::cors::{AllowCredentials, AllowHeaders, AllowMethods, AllowOrigin, CorsLayer};
 
 #[tokio::test]
 #[allow(
@@ -59,7 +59,7 @@ async fn test_allow_origin_async_predicate() {
     let parts = http::Request::new("hello world").into_parts().0;
 
     let header = allow_origin
-        .to_future(Some(&valid_origin), &parts)
+        .to_future(Some(&valid_origin), &parts, false)
         .await
         .unwrap();
     assert_eq!(header.0, header::ACCESS_CONTROL_ALLOW_ORIGIN);
@@ -68,6 +68,147 @@ async fn test_allow_origin_async_predicate() {
     let invalid_origin = HeaderValue::from_static("http://example.org");
     let parts = http::Request::new("hello world").into_parts().0;
 
-    let res = allow_origin.to_future(Some(&invalid_origin), &parts).await;
+    let res = allow_origin
+        .to_future(Some(&invalid_origin), &parts, false)
+        .await;
     assert!(res.is_none());
 }
+
+#[tokio::test]
+async fn vary_request_dependent_on_preflight() {
+    let svc = CorsLayer::new()
+        .allow_origin(AllowOrigin::exact(HeaderValue::from_static(
+            "http://example.com",
+        )))
+        .allow_headers(AllowHeaders::mirror_request())
+        .vary([header::ORIGIN])
+        .vary_request_dependent(true)
+        .layer(service_fn(|_: Request<Body>| async {
+            Ok::<_, Infallible>(Response::new(Body::empty()))
+        }));
+
+    let req = Request::builder()
+        .method(Method::OPTIONS)
+        .header(header::ORIGIN, "http://example.com")
+        .header(header::ACCESS_CONTROL_REQUEST_HEADERS, "x-custom")
+        .body(Body::empty())
+        .unwrap();
+    let res = svc.clone().oneshot(req).await.unwrap();
+
+    let mut vary_headers = res.headers().get_all(header::VARY).into_iter();
+    assert_eq!(
+        vary_headers.next().unwrap(),
+        "origin, access-control-request-headers"
+    );
+    assert_eq!(vary_headers.next(), None);
+    assert_eq!(
+        res.headers()[header::ACCESS_CONTROL_ALLOW_HEADERS],
+        "x-custom"
+    );
+
+    // non-preflight requests are left alone
+    let req = Request::builder()
+        .header(header::ORIGIN, "http://example.com")
+        .body(Body::empty())
+        .unwrap();
+    let res = svc.oneshot(req).await.unwrap();
+    assert_eq!(res.headers()[header::VARY], "origin");
+}
+
+#[tokio::test]
+async fn vary_request_dependent_deduplicates() {
+    let svc = CorsLayer::new()
+        .allow_headers(AllowHeaders::mirror_request())
+        .allow_methods(AllowMethods::mirror_request())
+        .vary_request_dependent(true)
+        .layer(service_fn(|_: Request<Body>| async {
+            Ok::<_, Infallible>(Response::new(Body::empty()))
+        }));
+
+    let req = Request::builder()
+        .method(Method::OPTIONS)
+        .header(header::ACCESS_CONTROL_REQUEST_METHOD, "PUT")
+        .header(header::ACCESS_CONTROL_REQUEST_HEADERS, "x-custom")
+        .body(Body::empty())
+        .unwrap();
+    let res = svc.oneshot(req).await.unwrap();
+
+    let mut vary_headers = res.headers().get_all(header::VARY).into_iter();
+    assert_eq!(
+        vary_headers.next().unwrap(),
+        "origin, access-control-request-method, access-control-request-headers"
+    );
+    assert_eq!(vary_headers.next(), None);
+}
+
+#[tokio::test]
+async fn allows_null_origin() {
+    async fn allowed_origin(layer: CorsLayer) -> Response<Body> {
+        let svc = layer.layer(service_fn(|_: Request<Body>| async {
+            Ok::<_, Infallible>(Response::new(Body::empty()))
+        }));
+        let req = Request::builder()
+            .header(header::ORIGIN, "null")
+            .body(Body::empty())
+            .unwrap();
+        svc.oneshot(req).await.unwrap()
+    }
+
+    let listed = CorsLayer::new().allow_origin([
+        HeaderValue::from_static("http://example.com"),
+        HeaderValue::from_static("null"),
+    ]);
+    let toggled = CorsLayer::new()
+        .allow_origin(AllowOrigin::mirror_request())
+        .allow_null_origin(true);
+
+    for layer in [listed, toggled] {
+        let res = allowed_origin(layer).await;
+        assert_eq!(res.headers()[header::ACCESS_CONTROL_ALLOW_ORIGIN], "null");
+        let vary = res.headers()[header::VARY].to_str().unwrap();
+        assert!(vary.split(", ").any(|name| name == "origin"));
+    }
+}
+
+#[tokio::test]
+async fn denies_null_origin() {
+    let svc = CorsLayer::new()
+        .allow_origin(AllowOrigin::mirror_request())
+        .allow_credentials(AllowCredentials::predicate(|_, _| true))
+        .layer(service_fn(|_: Request<Body>| async {
+            Ok::<_, Infallible>(Response::new(Body::empty()))
+        }));
+
+    let req = Request::builder()
+        .header(header::ORIGIN, "null")
+        .body(Body::empty())
+        .unwrap();
+    let res = svc.clone().oneshot(req).await.unwrap();
+    assert!(!res
+        .headers()
+        .contains_key(header::ACCESS_CONTROL_ALLOW_ORIGIN));
+    assert!(!res
+        .headers()
+        .contains_key(header::ACCESS_CONTROL_ALLOW_CREDENTIALS));
+
+    let req = Request::builder()
+        .header(header::ORIGIN, "http://example.com")
+        .body(Body::empty())
+        .unwrap();
+    let res = svc.oneshot(req).await.unwrap();
+    assert_eq!(
+        res.headers()[header::ACCESS_CONTROL_ALLOW_ORIGIN],
+        "http://example.com"
+    );
+}
+
+#[test]
+#[should_panic(expected = "Access-Control-Allow-Origin: null")]
+fn null_origin_with_credentials() {
+    let _ = CorsLayer::new()
+        .allow_origin([HeaderValue::from_static("null")])
+        .allow_credentials(true)
+        .layer(service_fn(|_: Request<Body>| async {
+            Ok::<_, Infallible>(Response::new(Body::empty()))
+        }));
+}
@@ -26,6 +26,27 @@ impl Vary {
         Self(headers.into_iter().map(Into::into).collect())
     }
 
+    /// Returns a copy with `headers` appended, skipping the ones already present.
+    pub(super) fn with<I>(&self, headers: I) -> Self
+    where
+        I: IntoIterator<Item = HeaderName>,
+    {
+        let mut values = self.0.clone();
+        for name in headers {
+            let present = values.iter().any(|val| {
+                val.to_str().map_or(false, |val| {
+                    val.split(',')
+                        .map(str::trim)
+                        .any(|v| v == "*" || v.eq_ignore_ascii_case(name.as_str()))
+                })
+            });
+            if !present {
+                values.push(name.into());
+            }
+        }
+        Self(values)
+    }
+
     pub(super) fn to_header(&self) -> Option<(HeaderName, HeaderValue)> {
         let values = &self.0;
         let mut res = values.first()?.as_bytes().to_owned();
@@ -19,9 +19,14 @@ use http_body::Body;
 use pin_project_lite::pin_project;
 use std::task::Context;
 use std::{
+    collections::VecDeque,
     io,
     marker::PhantomData,
     pin::Pin,
+    sync::{
+        atomic::{AtomicBool, AtomicU64, Ordering},
+        Arc,
+    },
     task::{ready, Poll},
 };
 use tokio_util::io::StreamReader;
@@ -37,6 +42,88 @@ pin_project! {
     {
         #[pin]
         pub(crate) inner: BodyInner<B>,
+        pub(crate) decoded_len: Option<DecodedContentLength>,
+        pub(crate) compressed_len: Option<CompressedContentLength>,
+        pub(crate) buffered: Option<Buffered>,
+    }
+}
+
+/// Frames that were read from a body ahead of time, see
+/// [`RequestDecompression::set_decoded_content_length`].
+///
+/// [`RequestDecompression::set_decoded_content_length`]: super::RequestDecompression::set_decoded_content_length
+pub(crate) struct Buffered {
+    pub(crate) frames: VecDeque<Result<http_body::Frame<Bytes>, BoxError>>,
+    /// Whether the inner body ended after the buffered frames.
+    pub(crate) end: bool,
+}
+
+/// Response extension that provides the decoded length of a decompressed body.
+///
+/// This is inserted by [`Decompression`] when [`Decompression::decoded_content_length`] is
+/// enabled. Since the body is decompressed as it is read, the length is only known once the body
+/// has been read to the end.
+///
+/// [`Decompression`]: super::Decompression
+/// [`Decompression::decoded_content_length`]: super::Decompression::decoded_content_length
+#[derive(Clone, Debug, Default)]
+pub struct DecodedContentLength {
+    state: Arc<LenState>,
+}
+
+#[derive(Debug, Default)]
+struct LenState {
+    len: AtomicU64,
+    done: AtomicBool,
+}
+
+impl DecodedContentLength {
+    /// Returns the decoded length of the body, or `None` if the body hasn't been read to the end
+    /// yet.
+    pub fn get(&self) -> Option<u64> {
+        if self.state.done.load(Ordering::Acquire) {
+            Some(self.state.len.load(Ordering::Relaxed))
+        } else {
+            None
+        }
+    }
+
+    fn add(&self, len: usize) {
+        self.state.len.fetch_add(len as u64, Ordering::Relaxed);
+    }
+
+    fn finish(&self) {
+        self.state.done.store(true, Ordering::Release);
+    }
+}
+
+/// Response extension that provides the compressed length of a decompressed body.
+///
+/// This is inserted by [`Decompression`] when [`Decompression::compressed_content_length`] is
+/// enabled. Like [`DecodedContentLength`], the length is only known once the body has been read
+/// to the end. Together they give the achieved compression ratio.
+///
+/// [`Decompression`]: super::Decompression
+/// [`Decompression::compressed_content_length`]: super::Decompression::compressed_content_length
+#[derive(Clone, Debug, Default)]
+pub struct CompressedContentLength {
+    state: Arc<LenState>,
+}
+
+impl CompressedContentLength {
+    /// Returns the compressed length of the body, or `None` if the body hasn't been read to the
+    /// end yet.
+    pub fn get(&self) -> Option<u64> {
+        if self.state.done.load(Ordering::Acquire) {
+            Some(self.state.len.load(Ordering::Relaxed))
+        } else {
+            None
+        }
+    }
+
+    fn finish(&self, len: u64) {
+        self.state.len.store(len, Ordering::Relaxed);
+        self.state.done.store(true, Ordering::Release);
     }
 }
 
@@ -49,6 +136,9 @@ where
             inner: BodyInner::Identity {
                 inner: B::default(),
             },
+            decoded_len: None,
+            compressed_len: None,
+            buffered: None,
         }
     }
 }
@@ -58,7 +148,22 @@ where
     B: Body,
 {
     pub(crate) fn new(inner: BodyInner<B>) -> Self {
-        Self { inner }
+        Self {
+            inner,
+            decoded_len: None,
+            compressed_len: None,
+            buffered: None,
+        }
+    }
+
+    pub(crate) fn with_decoded_len(mut self, decoded_len: DecodedContentLength) -> Self {
+        self.decoded_len = Some(decoded_len);
+        self
+    }
+
+    pub(crate) fn with_compressed_len(mut self, compressed_len: CompressedContentLength) -> Self {
+        self.compressed_len = Some(compressed_len);
+        self
     }
 
     /// Get a reference to the inner body
@@ -284,6 +389,35 @@ impl<B: Body> BodyInner<B> {
     pub(crate) fn identity(inner: B) -> Self {
         Self::Identity { inner }
     }
+
+    /// The number of compressed bytes read from the inner body so far, or `None` for identity
+    /// bodies.
+    fn compressed_len(&self) -> Option<u64> {
+        match self {
+            #[cfg(feature = "decompression-gzip")]
+            BodyInner::Gzip { inner } => Some(inner.read.get_ref().get_ref().get_ref().data_len()),
+            #[cfg(feature = "decompression-deflate")]
+            BodyInner::Deflate { inner } => {
+                Some(inner.read.get_ref().get_ref().get_ref().data_len())
+            }
+            #[cfg(feature = "decompression-br")]
+            BodyInner::Brotli { inner } => {
+                Some(inner.read.get_ref().get_ref().get_ref().data_len())
+            }
+            #[cfg(feature = "decompression-zstd")]
+            BodyInner::Zstd { inner } => Some(inner.read.get_ref().get_ref().get_ref().data_len()),
+            BodyInner::Identity { .. } => None,
+
+            #[cfg(not(feature = "decompression-gzip"))]
+            BodyInner::Gzip { inner } => match inner.0 {},
+            #[cfg(not(feature = "decompression-deflate"))]
+            BodyInner::Deflate { inner } => match inner.0 {},
+            #[cfg(not(feature = "decompression-br"))]
+            BodyInner::Brotli { inner } => match inner.0 {},
+            #[cfg(not(feature = "decompression-zstd"))]
+            BodyInner::Zstd { inner } => match inner.0 {},
+        }
+    }
 }
 
 impl<B> Body for DecompressionBody<B>
@@ -298,7 +432,18 @@ where
         self: Pin<&mut Self>,
         cx: &mut Context<'_>,
     ) -> Poll<Option<Result<http_body::Frame<Self::Data>, Self::Error>>> {
-        match self.project().inner.project() {
+        let mut this = self.project();
+
+        if let Some(buffered) = this.buffered {
+            if let Some(frame) = buffered.frames.pop_front() {
+                return Poll::Ready(Some(frame));
+            }
+            if buffered.end {
+                return Poll::Ready(None);
+            }
+        }
+
+        let result = ready!(match this.inner.as_mut().project() {
             #[cfg(feature = "decompression-gzip")]
             BodyInnerProj::Gzip { inner } => inner.poll_frame(cx),
             #[cfg(feature = "decompression-deflate")]
@@ -324,7 +469,29 @@ where
             BodyInnerProj::Brotli { inner } => match inner.0 {},
             #[cfg(not(feature = "decompression-zstd"))]
             BodyInnerProj::Zstd { inner } => match inner.0 {},
+        });
+
+        if let Some(decoded_len) = this.decoded_len {
+            match &result {
+                Some(Ok(frame)) => {
+                    if let Some(data) = frame.data_ref() {
+                        decoded_len.add(data.len());
+                    }
+                }
+                Some(Err(_)) => {}
+                None => decoded_len.finish(),
+            }
+        }
+
+        if let Some(compressed_len) = this.compressed_len {
+            if result.is_none() {
+                if let Some(len) = this.inner.compressed_len() {
+                    compressed_len.finish(len);
+                }
+            }
         }
+
+        Poll::Ready(result)
     }
 }
 
@@ -1,6 +1,6 @@
 #![allow(unused_imports)]
 
-use super::{body::BodyInner, DecompressionBody};
+use super::{body::BodyInner, CompressedContentLength, DecodedContentLength, DecompressionBody};
 use crate::compression_utils::{AcceptEncoding, CompressionLevel, WrapBody};
 use crate::content_encoding::SupportedEncodings;
 use http::{header, Response};
@@ -21,6 +21,8 @@ pin_project! {
         #[pin]
         pub(crate) inner: F,
         pub(crate) accept: AcceptEncoding,
+        pub(crate) decoded_content_length: bool,
+        pub(crate) compressed_content_length: bool,
     }
 }
 
@@ -70,6 +72,22 @@ where
                 entry.remove();
                 parts.headers.remove(header::CONTENT_LENGTH);
 
+                let body = if self.decoded_content_length {
+                    let decoded_len = DecodedContentLength::default();
+                    parts.extensions.insert(decoded_len.clone());
+                    body.with_decoded_len(decoded_len)
+                } else {
+                    body
+                };
+
+                let body = if self.compressed_content_length {
+                    let compressed_len = CompressedContentLength::default();
+                    parts.extensions.insert(compressed_len.clone());
+                    body.with_compressed_len(compressed_len)
+                } else {
+                    body
+                };
+
                 Response::from_parts(parts, body)
             } else {
                 Response::from_parts(parts, DecompressionBody::new(BodyInner::identity(body)))
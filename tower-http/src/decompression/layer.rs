@@ -11,6 +11,8 @@ use tower_layer::Layer;
 #[derive(Debug, Default, Clone)]
 pub struct DecompressionLayer {
     accept: AcceptEncoding,
+    decoded_content_length: bool,
+    compressed_content_length: bool,
 }
 
 impl<S> Layer<S> for DecompressionLayer {
@@ -20,6 +22,8 @@ impl<S> Layer<S> for DecompressionLayer {
         Decompression {
             inner: service,
             accept: self.accept,
+            decoded_content_length: self.decoded_content_length,
+            compressed_content_length: self.compressed_content_length,
         }
     }
 }
@@ -30,6 +34,28 @@ impl DecompressionLayer {
         Default::default()
     }
 
+    /// Sets whether to insert a [`DecodedContentLength`](super::DecodedContentLength) extension into decompressed responses.
+    ///
+    /// The extension provides the length of the decompressed body once it has been read to the
+    /// end, without having to buffer the body again.
+    ///
+    /// Defaults to `false`.
+    pub fn decoded_content_length(mut self, enable: bool) -> Self {
+        self.decoded_content_length = enable;
+        self
+    }
+
+    /// Sets whether to insert a [`CompressedContentLength`](super::CompressedContentLength) extension into decompressed responses.
+    ///
+    /// The extension provides the number of compressed bytes read from the inner body once the
+    /// decompressed body has been read to the end.
+    ///
+    /// Defaults to `false`.
+    pub fn compressed_content_length(mut self, enable: bool) -> Self {
+        self.compressed_content_length = enable;
+        self
+    }
+
     /// Sets whether to request the gzip encoding.
     #[cfg(feature = "decompression-gzip")]
     pub fn gzip(mut self, enable: bool) -> Self {
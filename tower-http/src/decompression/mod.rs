@@ -99,10 +99,13 @@ mod layer;
 mod service;
 
 pub use self::{
-    body::DecompressionBody, future::ResponseFuture, layer::DecompressionLayer,
+    body::{CompressedContentLength, DecodedContentLength, DecompressionBody},
+    future::ResponseFuture,
+    layer::DecompressionLayer,
     service::Decompression,
 };
 
+pub use self::request::content_length::{SetDecodedContentLength, SetDecodedContentLengthFuture};
 pub use self::request::future::RequestDecompressionFuture;
 pub use self::request::layer::RequestDecompressionLayer;
 pub use self::request::service::RequestDecompression;
@@ -184,6 +187,83 @@ mod tests {
         Ok(res)
     }
 
+    #[tokio::test]
+    async fn decoded_content_length() {
+        let mut client =
+            Decompression::new(Compression::new(service_fn(handle))).decoded_content_length(true);
+
+        let req = Request::builder()
+            .header("accept-encoding", "gzip")
+            .body(Body::empty())
+            .unwrap();
+        let res = client.ready().await.unwrap().call(req).await.unwrap();
+
+        let (parts, body) = res.into_parts();
+        let decoded_len = parts
+            .extensions
+            .get::<DecodedContentLength>()
+            .cloned()
+            .unwrap();
+        assert_eq!(decoded_len.get(), None);
+
+        let decompressed_data = body.collect().await.unwrap().to_bytes();
+        assert_eq!(decompressed_data, "Hello, World!");
+        assert_eq!(decoded_len.get(), Some(13));
+    }
+
+    #[tokio::test]
+    async fn compressed_content_length() {
+        let mut client = Decompression::new(service_fn(handle_multi_gz))
+            .decoded_content_length(true)
+            .compressed_content_length(true);
+
+        let req = Request::builder()
+            .header("accept-encoding", "gzip")
+            .body(Body::empty())
+            .unwrap();
+        let res = client.ready().await.unwrap().call(req).await.unwrap();
+
+        let (parts, body) = res.into_parts();
+        let decoded_len = parts
+            .extensions
+            .get::<DecodedContentLength>()
+            .cloned()
+            .unwrap();
+        let compressed_len = parts
+            .extensions
+            .get::<CompressedContentLength>()
+            .cloned()
+            .unwrap();
+        assert_eq!(compressed_len.get(), None);
+
+        let decompressed_data = body.collect().await.unwrap().to_bytes();
+        assert_eq!(decompressed_data, "Hello, World!");
+        assert_eq!(decoded_len.get(), Some(13));
+
+        let compressed = handle_multi_gz(Request::new(Body::empty()))
+            .await
+            .unwrap()
+            .into_body()
+            .collect()
+            .await
+            .unwrap()
+            .to_bytes();
+        assert_eq!(compressed_len.get(), Some(compressed.len() as u64));
+    }
+
+    #[tokio::test]
+    async fn no_decoded_content_length_by_default() {
+        let mut client = Decompression::new(Compression::new(service_fn(handle)));
+
+        let req = Request::builder()
+            .header("accept-encoding", "gzip")
+            .body(Body::empty())
+            .unwrap();
+        let res = client.ready().await.unwrap().call(req).await.unwrap();
+
+        assert!(res.extensions().get::<DecodedContentLength>().is_none());
+    }
+
     #[allow(dead_code)]
     async fn is_compatible_with_hyper() {
         let client =
@@ -0,0 +1,222 @@
+use super::MAX_BUFFERED_LEN;
+use crate::decompression::{
+    body::{BodyInner, Buffered},
+    DecompressionBody,
+};
+use crate::BoxError;
+use bytes::Bytes;
+use http::{header, request::Parts, Request};
+use http_body::{Body, Frame};
+use pin_project_lite::pin_project;
+use std::{
+    collections::VecDeque,
+    fmt,
+    future::Future,
+    mem,
+    pin::Pin,
+    task::{ready, Context, Poll},
+};
+use tower_service::Service;
+
+/// Sets the `Content-Length` header of small decompressed request bodies, and calls its
+/// underlying service.
+///
+/// [`RequestDecompression`] removes the `Content-Length` header of the requests it decompresses,
+/// since the decoded length isn't known while the body is streamed. This middleware goes inside
+/// of it, and buffers the decompressed bodies before calling the inner service, setting the
+/// `Content-Length` header to their decoded length. Once a body is longer than 64 KiB when
+/// decoded, or has trailers, the frames buffered so far are passed on followed by the rest of
+/// the body, without a `Content-Length` header. Requests that weren't decompressed are passed on
+/// as-is.
+///
+/// See [`RequestDecompression::set_decoded_content_length`] for wrapping a
+/// [`RequestDecompression`] service, or use [`ServiceBuilder::layer_fn`] with
+/// [`SetDecodedContentLength::new`] below a [`RequestDecompressionLayer`].
+///
+/// [`RequestDecompression`]: super::service::RequestDecompression
+/// [`RequestDecompression::set_decoded_content_length`]: super::service::RequestDecompression::set_decoded_content_length
+/// [`RequestDecompressionLayer`]: super::layer::RequestDecompressionLayer
+/// [`ServiceBuilder::layer_fn`]: tower::ServiceBuilder::layer_fn
+#[derive(Debug, Clone)]
+pub struct SetDecodedContentLength<S> {
+    inner: S,
+}
+
+impl<S> SetDecodedContentLength<S> {
+    /// Creates a new `SetDecodedContentLength` wrapping the `service`.
+    pub fn new(service: S) -> Self {
+        Self { inner: service }
+    }
+
+    define_inner_service_accessors!();
+}
+
+impl<S, ReqBody> Service<Request<DecompressionBody<ReqBody>>> for SetDecodedContentLength<S>
+where
+    S: Service<Request<DecompressionBody<ReqBody>>> + Clone,
+    ReqBody: Body + Unpin,
+    ReqBody::Error: Into<BoxError>,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = SetDecodedContentLengthFuture<S, ReqBody>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, req: Request<DecompressionBody<ReqBody>>) -> Self::Future {
+        if let BodyInner::Identity { .. } = req.body().inner {
+            return SetDecodedContentLengthFuture {
+                kind: Kind::Inner {
+                    fut: self.inner.call(req),
+                },
+            };
+        }
+
+        // the body is buffered before calling the service, which is ready now
+        let clone = self.inner.clone();
+        let service = mem::replace(&mut self.inner, clone);
+        let (parts, body) = req.into_parts();
+        SetDecodedContentLengthFuture {
+            kind: Kind::Buffering {
+                buffering: Some(Buffering {
+                    service,
+                    parts,
+                    body,
+                    frames: VecDeque::new(),
+                    len: 0,
+                }),
+            },
+        }
+    }
+}
+
+pin_project! {
+    /// Response future of [`SetDecodedContentLength`].
+    pub struct SetDecodedContentLengthFuture<S, ReqBody>
+    where
+        S: Service<Request<DecompressionBody<ReqBody>>>,
+        ReqBody: Body,
+    {
+        #[pin]
+        kind: Kind<S, ReqBody>,
+    }
+}
+
+pin_project! {
+    #[project = KindProj]
+    enum Kind<S, ReqBody>
+    where
+        S: Service<Request<DecompressionBody<ReqBody>>>,
+        ReqBody: Body,
+    {
+        Buffering {
+            buffering: Option<Buffering<S, ReqBody>>,
+        },
+        Inner {
+            #[pin]
+            fut: S::Future,
+        },
+    }
+}
+
+/// A request whose body is being buffered, before calling the inner service with it.
+struct Buffering<S, ReqBody>
+where
+    ReqBody: Body,
+{
+    service: S,
+    parts: Parts,
+    body: DecompressionBody<ReqBody>,
+    frames: VecDeque<Result<Frame<Bytes>, BoxError>>,
+    len: u64,
+}
+
+impl<S, ReqBody> Buffering<S, ReqBody>
+where
+    ReqBody: Body + Unpin,
+    ReqBody::Error: Into<BoxError>,
+{
+    /// Reads frames of the body until it ends, or until it is longer than `MAX_BUFFERED_LEN` once
+    /// decoded, and sets `Content-Length` to the decoded length if it ended.
+    ///
+    /// The frames that were read are kept in the body and returned again when it is polled.
+    fn poll_buffer(&mut self, cx: &mut Context<'_>) -> Poll<()> {
+        let end = loop {
+            match ready!(Pin::new(&mut self.body).poll_frame(cx)) {
+                Some(Ok(frame)) => match frame.data_ref() {
+                    Some(data) => {
+                        self.len += data.len() as u64;
+                        self.frames.push_back(Ok(frame));
+                        if self.len > MAX_BUFFERED_LEN {
+                            break false;
+                        }
+                    }
+                    // trailers can't be described by `Content-Length` alone, so stream the rest
+                    // of the body
+                    None => {
+                        self.frames.push_back(Ok(frame));
+                        break false;
+                    }
+                },
+                Some(Err(err)) => {
+                    self.frames.push_back(Err(err));
+                    break false;
+                }
+                None => break true,
+            }
+        };
+
+        if end {
+            self.parts
+                .headers
+                .insert(header::CONTENT_LENGTH, self.len.into());
+        }
+        self.body.buffered = Some(Buffered {
+            frames: mem::take(&mut self.frames),
+            end,
+        });
+        Poll::Ready(())
+    }
+}
+
+impl<S, ReqBody> fmt::Debug for SetDecodedContentLengthFuture<S, ReqBody>
+where
+    S: Service<Request<DecompressionBody<ReqBody>>>,
+    ReqBody: Body,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SetDecodedContentLengthFuture").finish()
+    }
+}
+
+impl<S, ReqBody> Future for SetDecodedContentLengthFuture<S, ReqBody>
+where
+    S: Service<Request<DecompressionBody<ReqBody>>>,
+    ReqBody: Body + Unpin,
+    ReqBody::Error: Into<BoxError>,
+{
+    type Output = Result<S::Response, S::Error>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let mut this = self.project();
+        loop {
+            match this.kind.as_mut().project() {
+                KindProj::Buffering { buffering } => {
+                    let state = buffering.as_mut().expect("future polled after completion");
+                    ready!(state.poll_buffer(cx));
+                    let Buffering {
+                        mut service,
+                        parts,
+                        body,
+                        ..
+                    } = buffering.take().unwrap();
+                    let fut = service.call(Request::from_parts(parts, body));
+                    this.kind.set(Kind::Inner { fut });
+                }
+                KindProj::Inner { fut } => return fut.poll(cx),
+            }
+        }
+    }
+}
@@ -1,14 +1,22 @@
+pub(super) mod content_length;
 pub(super) mod future;
 pub(super) mod layer;
 pub(super) mod service;
 
+/// The maximum decoded length of bodies buffered by
+/// [`SetDecodedContentLength`](content_length::SetDecodedContentLength).
+const MAX_BUFFERED_LEN: u64 = 64 * 1024;
+
 #[cfg(test)]
 mod tests {
     use super::service::RequestDecompression;
     use crate::decompression::DecompressionBody;
     use crate::test_helpers::Body;
+    use bytes::Bytes;
     use flate2::{write::GzEncoder, Compression};
+    use futures_util::StreamExt;
     use http::{header, Request, Response, StatusCode};
+    use http_body::Body as _;
     use http_body_util::BodyExt;
     use std::{convert::Infallible, io::Write};
     use tower::{service_fn, Service, ServiceExt};
@@ -44,6 +52,86 @@ mod tests {
         let _ = svc.ready().await.unwrap().call(req).await.unwrap();
     }
 
+    #[tokio::test]
+    async fn set_decoded_content_length() {
+        let mut req = request_gzip();
+        let len = req.body().size_hint().exact().unwrap();
+        req.headers_mut().insert(header::CONTENT_LENGTH, len.into());
+
+        let mut svc = RequestDecompression::new(service_fn(
+            |req: Request<DecompressionBody<Body>>| async move {
+                assert_eq!(req.headers()[header::CONTENT_LENGTH], "6");
+                assert_request_is_decompressed(req).await
+            },
+        ))
+        .set_decoded_content_length();
+        let _ = svc.ready().await.unwrap().call(req).await.unwrap();
+
+        // requests that aren't decompressed are passed on as-is
+        let req = Request::builder()
+            .header(header::CONTENT_LENGTH, "6")
+            .body(Body::from("Hello?"))
+            .unwrap();
+        let mut svc = RequestDecompression::new(service_fn(
+            |req: Request<DecompressionBody<Body>>| async move {
+                assert_eq!(req.headers()[header::CONTENT_LENGTH], "6");
+                assert_request_is_decompressed(req).await
+            },
+        ))
+        .set_decoded_content_length();
+        let _ = svc.ready().await.unwrap().call(req).await.unwrap();
+
+        // disabled by default
+        let mut req = request_gzip();
+        req.headers_mut().insert(header::CONTENT_LENGTH, len.into());
+        let mut svc = RequestDecompression::new(service_fn(
+            |req: Request<DecompressionBody<Body>>| async move {
+                assert!(!req.headers().contains_key(header::CONTENT_LENGTH));
+                assert_request_is_decompressed(req).await
+            },
+        ));
+        let _ = svc.ready().await.unwrap().call(req).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn set_decoded_content_length_of_streamed_body() {
+        let compressed = request_gzip().into_body();
+        let compressed = compressed.collect().await.unwrap().to_bytes();
+        // the second half of the body isn't available right away
+        let (first, second) = compressed.split_at(compressed.len() / 2);
+        let (first, second) = (
+            Bytes::copy_from_slice(first),
+            Bytes::copy_from_slice(second),
+        );
+        let stream = futures_util::stream::unfold(0, move |n| {
+            let (first, second) = (first.clone(), second.clone());
+            async move {
+                match n {
+                    0 => Some((Ok::<_, Infallible>(first), 1)),
+                    1 => {
+                        tokio::task::yield_now().await;
+                        Some((Ok(second), 2))
+                    }
+                    _ => None,
+                }
+            }
+        })
+        .fuse();
+        let req = Request::builder()
+            .header(header::CONTENT_ENCODING, "gzip")
+            .body(Body::from_stream(stream))
+            .unwrap();
+
+        let mut svc = RequestDecompression::new(service_fn(
+            |req: Request<DecompressionBody<Body>>| async move {
+                assert_eq!(req.headers()[header::CONTENT_LENGTH], "6");
+                assert_request_is_decompressed(req).await
+            },
+        ))
+        .set_decoded_content_length();
+        let _ = svc.ready().await.unwrap().call(req).await.unwrap();
+    }
+
     async fn assert_request_is_decompressed(
         req: Request<DecompressionBody<Body>>,
     ) -> Result<Response<Body>, Infallible> {
@@ -1,3 +1,4 @@
+use super::content_length::SetDecodedContentLength;
 use super::future::RequestDecompressionFuture as ResponseFuture;
 use super::layer::RequestDecompressionLayer;
 use crate::body::UnsyncBoxBody;
@@ -136,6 +137,24 @@ impl<S> RequestDecompression<S> {
         self
     }
 
+    /// Sets the `Content-Length` header of small decompressed request bodies.
+    ///
+    /// The `Content-Length` header of decompressed requests is removed, since the decoded length
+    /// isn't known while the body is streamed. This wraps the inner service in a
+    /// [`SetDecodedContentLength`] middleware, which buffers decompressed bodies of at most 64 KiB
+    /// before calling the inner service, and sets the `Content-Length` header to their decoded
+    /// length.
+    ///
+    /// Unlike `RequestDecompression`, the inner service needs to be `Clone` and the request body
+    /// `Unpin`.
+    pub fn set_decoded_content_length(self) -> RequestDecompression<SetDecodedContentLength<S>> {
+        RequestDecompression {
+            inner: SetDecodedContentLength::new(self.inner),
+            accept: self.accept,
+            pass_through_unaccepted: self.pass_through_unaccepted,
+        }
+    }
+
     /// Sets whether to support gzip encoding.
     #[cfg(feature = "decompression-gzip")]
     pub fn gzip(mut self, enable: bool) -> Self {
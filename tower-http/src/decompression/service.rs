@@ -18,6 +18,8 @@ use tower_service::Service;
 pub struct Decompression<S> {
     pub(crate) inner: S,
     pub(crate) accept: AcceptEncoding,
+    pub(crate) decoded_content_length: bool,
+    pub(crate) compressed_content_length: bool,
 }
 
 impl<S> Decompression<S> {
@@ -26,6 +28,8 @@ impl<S> Decompression<S> {
         Self {
             inner: service,
             accept: AcceptEncoding::default(),
+            decoded_content_length: false,
+            compressed_content_length: false,
         }
     }
 
@@ -38,6 +42,28 @@ impl<S> Decompression<S> {
         DecompressionLayer::new()
     }
 
+    /// Sets whether to insert a [`DecodedContentLength`](super::DecodedContentLength) extension into decompressed responses.
+    ///
+    /// The extension provides the length of the decompressed body once it has been read to the
+    /// end, without having to buffer the body again.
+    ///
+    /// Defaults to `false`.
+    pub fn decoded_content_length(mut self, enable: bool) -> Self {
+        self.decoded_content_length = enable;
+        self
+    }
+
+    /// Sets whether to insert a [`CompressedContentLength`](super::CompressedContentLength) extension into decompressed responses.
+    ///
+    /// The extension provides the number of compressed bytes read from the inner body once the
+    /// decompressed body has been read to the end.
+    ///
+    /// Defaults to `false`.
+    pub fn compressed_content_length(mut self, enable: bool) -> Self {
+        self.compressed_content_length = enable;
+        self
+    }
+
     /// Sets whether to request the gzip encoding.
     #[cfg(feature = "decompression-gzip")]
     pub fn gzip(mut self, enable: bool) -> Self {
@@ -122,6 +148,8 @@ where
         ResponseFuture {
             inner: self.inner.call(req),
             accept: self.accept,
+            decoded_content_length: self.decoded_content_length,
+            compressed_content_length: self.compressed_content_length,
         }
     }
 }
@@ -0,0 +1,284 @@
+//! Middleware that downgrades HTTP/2 and HTTP/3 requests to HTTP/1.1.
+//!
+//! A proxy that receives requests over HTTP/2 or HTTP/3 and forwards them to a backend over
+//! HTTP/1.1 can't send them as-is. [`DowngradeHttpVersion`] sets the version of these requests to
+//! HTTP/1.1 and adjusts their headers before calling the inner service:
+//!
+//! - The `Host` header is set to the authority of the request URI, which carries the
+//!   `:authority` pseudo-header, without its user information.
+//! - The `Connection`, `Keep-Alive`, `Proxy-Connection`, `Transfer-Encoding` and `Upgrade`
+//!   headers, and the headers named by `Connection`, are removed. So is `TE`, unless it is
+//!   `trailers`. These headers aren't allowed in HTTP/2 and HTTP/3, but a client or middleware
+//!   could still have added them.
+//! - Multiple `Cookie` headers are joined into one, as HTTP/2 and HTTP/3 allow splitting them but
+//!   HTTP/1.1 doesn't.
+//!
+//! Requests with an older version are left untouched, and so is the URI of requests.
+//!
+//! # Example
+//!
+//! ```
+//! use tower_http::downgrade_http_version::DowngradeHttpVersionLayer;
+//! use http::{header::HOST, Request, Response, Version};
+//! use http_body_util::Full;
+//! use bytes::Bytes;
+//! use std::convert::Infallible;
+//! use tower::{ServiceBuilder, Service, ServiceExt};
+//!
+//! async fn handle(req: Request<Full<Bytes>>) -> Result<Response<Full<Bytes>>, Infallible> {
+//!     assert_eq!(req.version(), Version::HTTP_11);
+//!     assert_eq!(req.headers()[HOST], "backend.example:8080");
+//!     Ok(Response::new(Full::default()))
+//! }
+//!
+//! # #[tokio::main]
+//! # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+//! let mut service = ServiceBuilder::new()
+//!     .layer(DowngradeHttpVersionLayer::new())
+//!     .service_fn(handle);
+//!
+//! let request = Request::builder()
+//!     .version(Version::HTTP_2)
+//!     .uri("http://backend.example:8080/api")
+//!     .body(Full::default())?;
+//! service.ready().await?.call(request).await?;
+//! #
+//! # Ok(())
+//! # }
+//! ```
+
+use http::{
+    header::{HeaderName, CONNECTION, COOKIE, HOST, TE, TRANSFER_ENCODING, UPGRADE},
+    HeaderMap, HeaderValue, Request, Response, Version,
+};
+use std::task::{Context, Poll};
+use tower_layer::Layer;
+use tower_service::Service;
+
+const KEEP_ALIVE: HeaderName = HeaderName::from_static("keep-alive");
+const PROXY_CONNECTION: HeaderName = HeaderName::from_static("proxy-connection");
+
+/// Layer that applies [`DowngradeHttpVersion`] which downgrades HTTP/2 and HTTP/3 requests to
+/// HTTP/1.1.
+///
+/// See the [module docs](self) for more details.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct DowngradeHttpVersionLayer {
+    _priv: (),
+}
+
+impl DowngradeHttpVersionLayer {
+    /// Create a new [`DowngradeHttpVersionLayer`].
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl<S> Layer<S> for DowngradeHttpVersionLayer {
+    type Service = DowngradeHttpVersion<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        DowngradeHttpVersion::new(inner)
+    }
+}
+
+/// Middleware that downgrades HTTP/2 and HTTP/3 requests to HTTP/1.1.
+///
+/// See the [module docs](self) for more details.
+#[derive(Debug, Clone, Copy)]
+pub struct DowngradeHttpVersion<S> {
+    inner: S,
+}
+
+impl<S> DowngradeHttpVersion<S> {
+    /// Create a new [`DowngradeHttpVersion`].
+    pub fn new(inner: S) -> Self {
+        Self { inner }
+    }
+
+    define_inner_service_accessors!();
+
+    /// Returns a new [`Layer`] that wraps services with a [`DowngradeHttpVersion`] middleware.
+    ///
+    /// [`Layer`]: tower_layer::Layer
+    pub fn layer() -> DowngradeHttpVersionLayer {
+        DowngradeHttpVersionLayer::new()
+    }
+}
+
+impl<S, ReqBody, ResBody> Service<Request<ReqBody>> for DowngradeHttpVersion<S>
+where
+    S: Service<Request<ReqBody>, Response = Response<ResBody>>,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = S::Future;
+
+    #[inline]
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, mut req: Request<ReqBody>) -> Self::Future {
+        if req.version() == Version::HTTP_2 || req.version() == Version::HTTP_3 {
+            *req.version_mut() = Version::HTTP_11;
+
+            let host = req.uri().authority().and_then(|authority| {
+                let host = match authority.as_str().rsplit_once('@') {
+                    Some((_, host)) => host,
+                    None => authority.as_str(),
+                };
+                HeaderValue::from_str(host).ok()
+            });
+            let headers = req.headers_mut();
+            if let Some(host) = host {
+                headers.insert(HOST, host);
+            }
+            remove_hop_by_hop_headers(headers);
+            join_cookies(headers);
+        }
+
+        self.inner.call(req)
+    }
+}
+
+fn remove_hop_by_hop_headers(headers: &mut HeaderMap) {
+    let named = headers
+        .get_all(CONNECTION)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(','))
+        .filter_map(|name| HeaderName::from_bytes(name.trim().as_bytes()).ok())
+        .collect::<Vec<_>>();
+    for name in named {
+        headers.remove(name);
+    }
+
+    for name in [
+        CONNECTION,
+        KEEP_ALIVE,
+        PROXY_CONNECTION,
+        TRANSFER_ENCODING,
+        UPGRADE,
+    ] {
+        headers.remove(name);
+    }
+
+    let trailers = headers.get_all(TE).iter().any(|value| {
+        value.to_str().map_or(false, |value| {
+            value
+                .split(',')
+                .any(|te| te.trim().eq_ignore_ascii_case("trailers"))
+        })
+    });
+    headers.remove(TE);
+    if trailers {
+        headers.insert(TE, HeaderValue::from_static("trailers"));
+    }
+}
+
+fn join_cookies(headers: &mut HeaderMap) {
+    if headers.get_all(COOKIE).iter().nth(1).is_none() {
+        return;
+    }
+
+    let mut joined = Vec::new();
+    for value in headers.get_all(COOKIE) {
+        if !joined.is_empty() {
+            joined.extend_from_slice(b"; ");
+        }
+        joined.extend_from_slice(value.as_bytes());
+    }
+    if let Ok(joined) = HeaderValue::from_bytes(&joined) {
+        headers.insert(COOKIE, joined);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_helpers::Body;
+    use std::convert::Infallible;
+    use tower::{ServiceBuilder, ServiceExt};
+
+    async fn downgraded(req: Request<Body>) -> Request<()> {
+        let svc = ServiceBuilder::new()
+            .layer(DowngradeHttpVersionLayer::new())
+            .service_fn(|req: Request<Body>| async move {
+                let (parts, _) = req.into_parts();
+                Ok::<_, Infallible>(Response::new(Request::from_parts(parts, ())))
+            });
+        svc.oneshot(req).await.unwrap().into_body()
+    }
+
+    #[tokio::test]
+    async fn downgrades_http2_requests() {
+        let req = Request::builder()
+            .version(Version::HTTP_2)
+            .uri("https://user@example.com:8443/path?query")
+            .header(CONNECTION, "keep-alive, x-hop")
+            .header(KEEP_ALIVE, "timeout=5")
+            .header("x-hop", "1")
+            .header(PROXY_CONNECTION, "keep-alive")
+            .header(TRANSFER_ENCODING, "chunked")
+            .header(UPGRADE, "websocket")
+            .header(TE, "gzip, trailers")
+            .header(COOKIE, "a=1")
+            .header(COOKIE, "b=2")
+            .header("x-end-to-end", "1")
+            .body(Body::empty())
+            .unwrap();
+        let req = downgraded(req).await;
+
+        assert_eq!(req.version(), Version::HTTP_11);
+        assert_eq!(req.uri(), "https://user@example.com:8443/path?query");
+        assert_eq!(req.headers()[HOST], "example.com:8443");
+        for name in [
+            CONNECTION,
+            KEEP_ALIVE,
+            PROXY_CONNECTION,
+            TRANSFER_ENCODING,
+            UPGRADE,
+        ] {
+            assert!(!req.headers().contains_key(&name), "{}", name);
+        }
+        assert!(!req.headers().contains_key("x-hop"));
+        assert_eq!(req.headers()[TE], "trailers");
+        assert_eq!(req.headers().get_all(COOKIE).iter().count(), 1);
+        assert_eq!(req.headers()[COOKIE], "a=1; b=2");
+        assert_eq!(req.headers()["x-end-to-end"], "1");
+    }
+
+    #[tokio::test]
+    async fn replaces_host() {
+        let req = Request::builder()
+            .version(Version::HTTP_3)
+            .uri("http://example.com/")
+            .header(HOST, "other.example")
+            .header(TE, "gzip")
+            .body(Body::empty())
+            .unwrap();
+        let req = downgraded(req).await;
+
+        assert_eq!(req.version(), Version::HTTP_11);
+        assert_eq!(req.headers()[HOST], "example.com");
+        assert!(!req.headers().contains_key(TE));
+    }
+
+    #[tokio::test]
+    async fn leaves_http1_requests() {
+        let req = Request::builder()
+            .uri("http://example.com/")
+            .header(CONNECTION, "keep-alive")
+            .header(COOKIE, "a=1")
+            .header(COOKIE, "b=2")
+            .body(Body::empty())
+            .unwrap();
+        let req = downgraded(req).await;
+
+        assert_eq!(req.version(), Version::HTTP_11);
+        assert!(!req.headers().contains_key(HOST));
+        assert_eq!(req.headers()[CONNECTION], "keep-alive");
+        assert_eq!(req.headers().get_all(COOKIE).iter().count(), 2);
+    }
+}
@@ -5,13 +5,24 @@
 //! The [`FollowRedirect`] middleware retries requests with the inner [`Service`] to follow HTTP
 //! redirections.
 //!
-//! The middleware tries to clone the original [`Request`] when making a redirected request.
-//! However, since [`Extensions`][http::Extensions] are `!Clone`, any extensions set by outer
-//! middleware will be discarded. Also, the request body cannot always be cloned. When the
+//! The middleware tries to clone the original [`Request`] when making a redirected request,
+//! including its [`Extensions`][http::Extensions], so that extensions set by outer middleware,
+//! such as request IDs, are also available to redirected requests. However, the request body
+//! cannot always be cloned. When the
 //! original body is known to be empty by [`Body::size_hint`], the middleware uses `Default`
 //! implementation of the body type to create a new request body. If you know that the body can be
 //! cloned in some way, you can tell the middleware to clone it by configuring a [`policy`].
 //!
+//! When a redirection changes the method to `GET` and drops the body, the `Content-Type`,
+//! `Content-Length` and `Transfer-Encoding` headers are removed from the redirected request too.
+//!
+//! Leading and trailing whitespace is trimmed from `Location` values before they are resolved
+//! against the request URI. Values that contain control characters, or that aren't valid URI
+//! references, aren't followed, and the redirection response is returned as-is. Since a [`Uri`]
+//! can't hold a fragment, fragments are tracked with the [`RequestFragment`] extension instead:
+//! a redirected request gets the fragment of the `Location` value, or inherits the fragment of
+//! the previous request if the `Location` value has none, as [RFC 7231 section 7.1.2] says.
+//!
 //! # Examples
 //!
 //! ## Basic usage
@@ -91,13 +102,17 @@
 //! # Ok(())
 //! # }
 //! ```
+//!
+//! [RFC 7231 section 7.1.2]: https://datatracker.ietf.org/doc/html/rfc7231#section-7.1.2
 
 pub mod policy;
 
-use self::policy::{Action, Attempt, Policy, Standard};
+use self::policy::{Action, AsyncPolicy, Attempt, Standard};
 use futures_util::future::Either;
 use http::{
-    header::LOCATION, HeaderMap, HeaderValue, Method, Request, Response, StatusCode, Uri, Version,
+    header::{HeaderName, CONTENT_LENGTH, CONTENT_TYPE, LOCATION, TRANSFER_ENCODING},
+    uri::{Authority, Scheme},
+    Extensions, HeaderMap, HeaderValue, Method, Request, Response, StatusCode, Uri, Version,
 };
 use http_body::Body;
 use iri_string::types::{UriAbsoluteString, UriReferenceStr};
@@ -113,13 +128,27 @@ use std::{
 use tower::util::Oneshot;
 use tower_layer::Layer;
 use tower_service::Service;
+#[cfg(feature = "follow-redirect-time")]
+use {
+    http::header::RETRY_AFTER,
+    std::time::Duration,
+    tokio::time::{Instant, Sleep},
+};
 
 /// [`Layer`] for retrying requests with a [`Service`] to follow redirection responses.
 ///
 /// See the [module docs](self) for more details.
-#[derive(Clone, Copy, Debug, Default)]
+#[derive(Clone, Copy, Debug)]
 pub struct FollowRedirectLayer<P = Standard> {
     policy: P,
+    intermediate_headers: bool,
+    preserve_header_case: bool,
+    redirect_history: bool,
+    #[cfg(feature = "follow-redirect-time")]
+    hop_timings: bool,
+    #[cfg(feature = "follow-redirect-time")]
+    rate_limited_retries: usize,
+    allow_scheme: fn(&Scheme) -> bool,
 }
 
 impl FollowRedirectLayer {
@@ -132,7 +161,73 @@ impl FollowRedirectLayer {
 impl<P> FollowRedirectLayer<P> {
     /// Create a new [`FollowRedirectLayer`] with the given redirection [`Policy`].
     pub fn with_policy(policy: P) -> Self {
-        FollowRedirectLayer { policy }
+        FollowRedirectLayer {
+            policy,
+            intermediate_headers: false,
+            preserve_header_case: false,
+            redirect_history: false,
+            #[cfg(feature = "follow-redirect-time")]
+            hop_timings: false,
+            #[cfg(feature = "follow-redirect-time")]
+            rate_limited_retries: 0,
+            allow_scheme: is_http,
+        }
+    }
+
+    /// Set whether to collect the headers of intermediate redirection responses.
+    ///
+    /// See [`FollowRedirect::intermediate_headers`] for more details.
+    pub fn intermediate_headers(mut self, enable: bool) -> Self {
+        self.intermediate_headers = enable;
+        self
+    }
+
+    /// Set whether to preserve the original casing of header names in redirected requests.
+    ///
+    /// See [`FollowRedirect::preserve_header_case`] for more details.
+    pub fn preserve_header_case(mut self, enable: bool) -> Self {
+        self.preserve_header_case = enable;
+        self
+    }
+
+    /// Set whether to record the URIs visited while following redirections.
+    ///
+    /// See [`FollowRedirect::redirect_history`] for more details.
+    pub fn redirect_history(mut self, enable: bool) -> Self {
+        self.redirect_history = enable;
+        self
+    }
+
+    /// Set whether to record how long each request took while following redirections.
+    ///
+    /// See [`FollowRedirect::hop_timings`] for more details.
+    #[cfg(feature = "follow-redirect-time")]
+    pub fn hop_timings(mut self, enable: bool) -> Self {
+        self.hop_timings = enable;
+        self
+    }
+
+    /// Set how many times to retry a request that was rejected with `429 Too Many Requests`.
+    ///
+    /// See [`FollowRedirect::retry_rate_limited`] for more details.
+    #[cfg(feature = "follow-redirect-time")]
+    pub fn retry_rate_limited(mut self, max_retries: usize) -> Self {
+        self.rate_limited_retries = max_retries;
+        self
+    }
+
+    /// Set which schemes redirections may lead to.
+    ///
+    /// See [`FollowRedirect::allow_scheme`] for more details.
+    pub fn allow_scheme(mut self, allow: fn(&Scheme) -> bool) -> Self {
+        self.allow_scheme = allow;
+        self
+    }
+}
+
+impl<P: Default> Default for FollowRedirectLayer<P> {
+    fn default() -> Self {
+        Self::with_policy(P::default())
     }
 }
 
@@ -144,7 +239,18 @@ where
     type Service = FollowRedirect<S, P>;
 
     fn layer(&self, inner: S) -> Self::Service {
-        FollowRedirect::with_policy(inner, self.policy.clone())
+        FollowRedirect {
+            inner,
+            policy: self.policy.clone(),
+            intermediate_headers: self.intermediate_headers,
+            preserve_header_case: self.preserve_header_case,
+            redirect_history: self.redirect_history,
+            #[cfg(feature = "follow-redirect-time")]
+            hop_timings: self.hop_timings,
+            #[cfg(feature = "follow-redirect-time")]
+            rate_limited_retries: self.rate_limited_retries,
+            allow_scheme: self.allow_scheme,
+        }
     }
 }
 
@@ -155,6 +261,14 @@ where
 pub struct FollowRedirect<S, P = Standard> {
     inner: S,
     policy: P,
+    intermediate_headers: bool,
+    preserve_header_case: bool,
+    redirect_history: bool,
+    #[cfg(feature = "follow-redirect-time")]
+    hop_timings: bool,
+    #[cfg(feature = "follow-redirect-time")]
+    rate_limited_retries: usize,
+    allow_scheme: fn(&Scheme) -> bool,
 }
 
 impl<S> FollowRedirect<S> {
@@ -177,7 +291,116 @@ where
 {
     /// Create a new [`FollowRedirect`] with the given redirection [`Policy`].
     pub fn with_policy(inner: S, policy: P) -> Self {
-        FollowRedirect { inner, policy }
+        FollowRedirect {
+            inner,
+            policy,
+            intermediate_headers: false,
+            preserve_header_case: false,
+            redirect_history: false,
+            #[cfg(feature = "follow-redirect-time")]
+            hop_timings: false,
+            #[cfg(feature = "follow-redirect-time")]
+            rate_limited_retries: 0,
+            allow_scheme: is_http,
+        }
+    }
+
+    /// Set whether to collect the headers of intermediate redirection responses.
+    ///
+    /// When enabled, the headers of every redirection response that was followed are inserted
+    /// into the final response as an [`IntermediateHeaders`] extension, in the order they were
+    /// received.
+    ///
+    /// Defaults to `false`.
+    pub fn intermediate_headers(mut self, enable: bool) -> Self {
+        self.intermediate_headers = enable;
+        self
+    }
+
+    /// Set whether to preserve the original casing of header names in redirected requests.
+    ///
+    /// [`HeaderMap`] normalizes header names to lowercase, which some legacy servers don't
+    /// accept. When enabled, the [`HeaderCase`] extension of the original request, if any, is
+    /// kept in every redirected request like other extensions. By default, it is removed, since
+    /// the casing isn't necessarily right for the server a redirection leads to.
+    ///
+    /// The middleware doesn't change how headers are written, the HTTP client has to apply the
+    /// casing from the extension. Note that HTTP/2 and later require header names to be
+    /// lowercase, so the casing only has an effect on HTTP/1 connections.
+    ///
+    /// Defaults to `false`.
+    pub fn preserve_header_case(mut self, enable: bool) -> Self {
+        self.preserve_header_case = enable;
+        self
+    }
+
+    /// Set whether to record the URIs visited while following redirections.
+    ///
+    /// When enabled, the final response gets a [`RedirectHistory`] extension with the original
+    /// request URI and every redirection target that was followed, each with the status of the
+    /// response received from it. The history is also inserted when the policy stops following
+    /// redirections.
+    ///
+    /// Defaults to `false`.
+    pub fn redirect_history(mut self, enable: bool) -> Self {
+        self.redirect_history = enable;
+        self
+    }
+
+    /// Set whether to record how long each request took while following redirections.
+    ///
+    /// When enabled, the final response gets a [`HopTimings`] extension with the time between
+    /// sending each request, starting with the original one, and receiving its response. The
+    /// timings are also inserted when the policy stops following redirections. Requests that
+    /// are retried after `429 Too Many Requests` get an entry for each attempt.
+    ///
+    /// Defaults to `false`.
+    #[cfg(feature = "follow-redirect-time")]
+    pub fn hop_timings(mut self, enable: bool) -> Self {
+        self.hop_timings = enable;
+        self
+    }
+
+    /// Set how many times to retry a request that was rejected with `429 Too Many Requests`.
+    ///
+    /// Some servers rate limit requests in the middle of a chain of redirections. When enabled,
+    /// a `429 Too Many Requests` response with a `Retry-After` header is not returned, but the
+    /// same request is sent again once the delay it specifies has elapsed, up to `max_retries`
+    /// times for each URI of the chain. The request is only retried if its body could be cloned
+    /// as described in [`Policy::clone_body`], and the redirection policy isn't consulted.
+    ///
+    /// Only delays in seconds are supported. Responses whose `Retry-After` header is missing or
+    /// holds a date are returned as-is, as is the last response once the retries are exhausted.
+    /// Since the middleware waits as long as the server asks for, consider combining it with a
+    /// [timeout](crate::timeout).
+    ///
+    /// Defaults to `0`.
+    #[cfg(feature = "follow-redirect-time")]
+    pub fn retry_rate_limited(mut self, max_retries: usize) -> Self {
+        self.rate_limited_retries = max_retries;
+        self
+    }
+
+    /// Set which schemes redirections may lead to.
+    ///
+    /// Redirections are only followed if `allow` returns `true` for the scheme of the location,
+    /// after it is resolved against the URI of the request. Redirections to other schemes, like
+    /// `file:` or `data:`, are returned as-is without consulting the policy, since most services
+    /// can't make requests to them. Use this if the inner service handles other schemes.
+    ///
+    /// Defaults to allowing only `http` and `https`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use tower_http::follow_redirect::FollowRedirectLayer;
+    ///
+    /// let layer = FollowRedirectLayer::new()
+    ///     .allow_scheme(|scheme| matches!(scheme.as_str(), "http" | "https" | "ftp"));
+    /// ```
+    pub fn allow_scheme(mut self, allow: fn(&Scheme) -> bool) -> Self {
+        self.allow_scheme = allow;
+        self
     }
 
     /// Returns a new [`Layer`] that wraps services with a `FollowRedirect` middleware
@@ -195,7 +418,7 @@ impl<ReqBody, ResBody, S, P> Service<Request<ReqBody>> for FollowRedirect<S, P>
 where
     S: Service<Request<ReqBody>, Response = Response<ResBody>> + Clone,
     ReqBody: Body + Default,
-    P: Policy<ReqBody, S::Error> + Clone,
+    P: AsyncPolicy<ReqBody, S::Error> + Clone,
 {
     type Response = Response<ResBody>;
     type Error = S::Error;
@@ -211,7 +434,19 @@ where
         let mut policy = self.policy.clone();
         let mut body = BodyRepr::None;
         body.try_clone_from(req.body(), &policy);
+        // captured before `on_request`, so that it sees the original extensions for every request
+        let mut extensions = req.extensions().clone();
+        if !self.preserve_header_case {
+            extensions.remove::<HeaderCase>();
+        }
         policy.on_request(&mut req);
+        #[cfg(feature = "follow-redirect-time")]
+        let (hop_timings, max_rate_limited_retries) = (
+            self.hop_timings.then(HopTimer::start),
+            self.rate_limited_retries,
+        );
+        #[cfg(not(feature = "follow-redirect-time"))]
+        let (hop_timings, max_rate_limited_retries) = (None, 0);
         ResponseFuture {
             method: req.method().clone(),
             uri: req.uri().clone(),
@@ -221,6 +456,25 @@ where
             future: Either::Left(service.call(req)),
             service,
             policy,
+            previous_origin: None,
+            intermediate_headers: if self.intermediate_headers {
+                Some(Vec::new())
+            } else {
+                None
+            },
+            extensions,
+            redirect_history: if self.redirect_history {
+                Some(Vec::new())
+            } else {
+                None
+            },
+            hop_timings,
+            redirects: 0,
+            delayed: None,
+            max_rate_limited_retries,
+            rate_limited_retries: 0,
+            allow_scheme: self.allow_scheme,
+            pending: None,
         }
     }
 }
@@ -228,6 +482,7 @@ where
 pin_project! {
     /// Response future for [`FollowRedirect`].
     #[derive(Debug)]
+    #[project = ResponseFutureProj]
     pub struct ResponseFuture<S, B, P>
     where
         S: Service<Request<B>>,
@@ -241,85 +496,379 @@ pin_project! {
         version: Version,
         headers: HeaderMap<HeaderValue>,
         body: BodyRepr<B>,
+        previous_origin: Option<Authority>,
+        intermediate_headers: Option<Vec<HeaderMap>>,
+        extensions: Extensions,
+        redirect_history: Option<Vec<(StatusCode, Uri)>>,
+        hop_timings: Option<HopTimer>,
+        redirects: usize,
+        delayed: Option<(Delay, Request<B>)>,
+        max_rate_limited_retries: usize,
+        rate_limited_retries: usize,
+        allow_scheme: fn(&Scheme) -> bool,
+        pending: Option<PendingRedirect<S::Response, B>>,
+    }
+}
+
+// Measures how long each request takes, for `HopTimings`.
+#[cfg(feature = "follow-redirect-time")]
+#[derive(Debug)]
+struct HopTimer {
+    timings: Vec<Duration>,
+    start: Instant,
+}
+
+#[cfg(feature = "follow-redirect-time")]
+impl HopTimer {
+    fn start() -> Self {
+        Self::resume(Vec::new())
     }
+
+    fn resume(timings: Vec<Duration>) -> Self {
+        HopTimer {
+            timings,
+            start: Instant::now(),
+        }
+    }
+}
+
+// Without the `follow-redirect-time` feature, requests are never timed or delayed.
+#[cfg(not(feature = "follow-redirect-time"))]
+#[derive(Debug)]
+enum HopTimer {}
+
+#[cfg(feature = "follow-redirect-time")]
+type Delay = Pin<Box<Sleep>>;
+
+#[cfg(not(feature = "follow-redirect-time"))]
+#[derive(Debug)]
+enum Delay {}
+
+// A redirection response the policy hasn't decided to follow or not yet.
+#[derive(Debug)]
+struct PendingRedirect<R, B> {
+    res: R,
+    method: Method,
+    location: Uri,
+    raw_location: HeaderValue,
+    body: B,
 }
 
 impl<S, ReqBody, ResBody, P> Future for ResponseFuture<S, ReqBody, P>
 where
     S: Service<Request<ReqBody>, Response = Response<ResBody>> + Clone,
     ReqBody: Body + Default,
-    P: Policy<ReqBody, S::Error>,
+    P: AsyncPolicy<ReqBody, S::Error>,
 {
     type Output = Result<Response<ResBody>, S::Error>;
 
     fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
         let mut this = self.project();
-        let mut res = ready!(this.future.as_mut().poll(cx)?);
-        res.extensions_mut().insert(RequestUri(this.uri.clone()));
-
-        match res.status() {
-            StatusCode::MOVED_PERMANENTLY | StatusCode::FOUND => {
-                // User agents MAY change the request method from POST to GET
-                // (RFC 7231 section 6.4.2. and 6.4.3.).
-                if *this.method == Method::POST {
-                    *this.method = Method::GET;
-                    *this.body = BodyRepr::Empty;
-                }
-            }
-            StatusCode::SEE_OTHER => {
-                // A user agent can perform a GET or HEAD request (RFC 7231 section 6.4.4.).
-                if *this.method != Method::HEAD {
-                    *this.method = Method::GET;
+        // a new request is polled right away, and readiness of the inner service decides when to
+        // return `Poll::Pending`
+        loop {
+            let PendingRedirect {
+                mut res,
+                method,
+                location,
+                raw_location,
+                body,
+            } = match this.pending.take() {
+                Some(pending) => pending,
+                None => {
+                    #[cfg(feature = "follow-redirect-time")]
+                    if let Some((sleep, _)) = this.delayed {
+                        ready!(sleep.as_mut().poll(cx));
+                        if let Some((_, req)) = this.delayed.take() {
+                            this.send(req);
+                        }
+                    }
+
+                    let mut res = match ready!(this.future.as_mut().poll(cx)) {
+                        Ok(res) => {
+                            #[cfg(feature = "follow-redirect-time")]
+                            if let Some(timer) = this.hop_timings {
+                                timer.timings.push(timer.start.elapsed());
+                            }
+                            res
+                        }
+                        Err(err) => {
+                            let redirected =
+                                matches!(this.future.as_ref().get_ref(), Either::Right(_));
+                            if !redirected {
+                                return Poll::Ready(Err(err));
+                            }
+                            this.policy.on_error(&err);
+                            if !this.policy.retry_on_error(&err) {
+                                return Poll::Ready(Err(err));
+                            }
+                            let body = match this.body.take() {
+                                Some(body) => body,
+                                None => return Poll::Ready(Err(err)),
+                            };
+                            this.body.try_clone_from(&body, &*this.policy);
+
+                            let req = redirected_request(
+                                body,
+                                this.method,
+                                this.uri,
+                                *this.version,
+                                this.headers,
+                                this.extensions,
+                                this.previous_origin,
+                            );
+                            this.send(req);
+                            continue;
+                        }
+                    };
+
+                    #[cfg(feature = "follow-redirect-time")]
+                    if res.status() == StatusCode::TOO_MANY_REQUESTS
+                        && *this.rate_limited_retries < *this.max_rate_limited_retries
+                    {
+                        if let Some(delay) = retry_after(res.headers()) {
+                            if let Some(body) = this.body.take() {
+                                *this.rate_limited_retries += 1;
+                                this.body.try_clone_from(&body, &*this.policy);
+
+                                let req = redirected_request(
+                                    body,
+                                    this.method,
+                                    this.uri,
+                                    *this.version,
+                                    this.headers,
+                                    this.extensions,
+                                    this.previous_origin,
+                                );
+                                *this.delayed = Some((Box::pin(tokio::time::sleep(delay)), req));
+                                continue;
+                            }
+                        }
+                    }
+
+                    res.extensions_mut().insert(RequestUri(this.uri.clone()));
+                    if let Some(fragment) = this.extensions.get::<RequestFragment>() {
+                        res.extensions_mut().insert(fragment.clone());
+                    }
+                    res.extensions_mut().insert(RedirectCount(*this.redirects));
+                    if let Some(intermediate_headers) = this.intermediate_headers.take() {
+                        res.extensions_mut()
+                            .insert(IntermediateHeaders(intermediate_headers));
+                    }
+                    if let Some(mut redirect_history) = this.redirect_history.take() {
+                        redirect_history.push((res.status(), this.uri.clone()));
+                        res.extensions_mut()
+                            .insert(RedirectHistory(redirect_history));
+                    }
+                    #[cfg(feature = "follow-redirect-time")]
+                    if let Some(timer) = this.hop_timings.take() {
+                        res.extensions_mut().insert(HopTimings(timer.timings));
+                    }
+
+                    let method = this.method.clone();
+                    let mut alternative_location = None;
+                    let status = res.status();
+                    match status {
+                        StatusCode::MOVED_PERMANENTLY
+                        | StatusCode::FOUND
+                        | StatusCode::SEE_OTHER
+                        | StatusCode::TEMPORARY_REDIRECT
+                        | StatusCode::PERMANENT_REDIRECT => {}
+                        status => match this.policy.alternative_location(status, res.headers()) {
+                            Some(location) => alternative_location = Some(location),
+                            None => return Poll::Ready(Ok(res)),
+                        },
+                    }
+
+                    match this.policy.rewrite_method(status, this.method) {
+                        Some(rewritten) => {
+                            if rewritten != *this.method {
+                                let drops_body =
+                                    rewritten == Method::GET || rewritten == Method::HEAD;
+                                *this.method = rewritten;
+                                if drops_body {
+                                    drop_body(this.body, this.headers);
+                                }
+                            }
+                        }
+                        None => match status {
+                            StatusCode::MOVED_PERMANENTLY | StatusCode::FOUND => {
+                                // User agents MAY change the request method from POST to GET
+                                // (RFC 7231 section 6.4.2. and 6.4.3.).
+                                if *this.method == Method::POST {
+                                    *this.method = Method::GET;
+                                    drop_body(this.body, this.headers);
+                                }
+                            }
+                            StatusCode::TEMPORARY_REDIRECT | StatusCode::PERMANENT_REDIRECT => {}
+                            // `303 See Other`, and alternative locations which are handled like it.
+                            _ => {
+                                // A user agent can perform a GET or HEAD request (RFC 7231 section 6.4.4.).
+                                if *this.method != Method::HEAD {
+                                    *this.method = Method::GET;
+                                }
+                                drop_body(this.body, this.headers);
+                            }
+                        },
+                    }
+
+                    let body = if let Some(body) = this.body.take() {
+                        body
+                    } else {
+                        return Poll::Ready(Ok(res));
+                    };
+
+                    let raw_location = match alternative_location {
+                        Some(loc) => loc,
+                        None => match res.headers().get(&LOCATION) {
+                            Some(loc) => loc.clone(),
+                            None => return Poll::Ready(Ok(res)),
+                        },
+                    };
+                    let location = str::from_utf8(raw_location.as_bytes())
+                        .ok()
+                        .and_then(|loc| resolve_location(loc, this.uri))
+                        .filter(|loc| loc.scheme().map_or(false, *this.allow_scheme));
+                    let location = if let Some(loc) = location {
+                        loc
+                    } else {
+                        return Poll::Ready(Ok(res));
+                    };
+
+                    PendingRedirect {
+                        res,
+                        method,
+                        location,
+                        raw_location,
+                        body,
+                    }
                 }
-                *this.body = BodyRepr::Empty;
-            }
-            StatusCode::TEMPORARY_REDIRECT | StatusCode::PERMANENT_REDIRECT => {}
-            _ => return Poll::Ready(Ok(res)),
-        };
+            };
 
-        let body = if let Some(body) = this.body.take() {
-            body
-        } else {
-            return Poll::Ready(Ok(res));
-        };
+            let attempt = Attempt {
+                status: res.status(),
+                method: &method,
+                location: &location,
+                raw_location: &raw_location,
+                headers: res.headers(),
+                extensions: res.extensions(),
+                previous: this.uri,
+            };
+            let action = match this.policy.poll_redirect(cx, &attempt) {
+                Poll::Ready(action) => action?,
+                Poll::Pending => {
+                    *this.pending = Some(PendingRedirect {
+                        res,
+                        method,
+                        location,
+                        raw_location,
+                        body,
+                    });
+                    return Poll::Pending;
+                }
+            };
+            match action {
+                Action::Follow => {
+                    if let Some(IntermediateHeaders(mut intermediate_headers)) =
+                        res.extensions_mut().remove()
+                    {
+                        intermediate_headers.push(mem::take(res.headers_mut()));
+                        *this.intermediate_headers = Some(intermediate_headers);
+                    }
+                    if let Some(RedirectHistory(redirect_history)) = res.extensions_mut().remove() {
+                        *this.redirect_history = Some(redirect_history);
+                    }
+                    #[cfg(feature = "follow-redirect-time")]
+                    if let Some(HopTimings(timings)) = res.extensions_mut().remove() {
+                        *this.hop_timings = Some(HopTimer::resume(timings));
+                    }
 
-        let location = res
-            .headers()
-            .get(&LOCATION)
-            .and_then(|loc| resolve_uri(str::from_utf8(loc.as_bytes()).ok()?, this.uri));
-        let location = if let Some(loc) = location {
-            loc
-        } else {
-            return Poll::Ready(Ok(res));
-        };
+                    let previous = mem::replace(this.uri, location);
+                    if let Some(fragment) = location_fragment(&raw_location) {
+                        this.extensions.insert(RequestFragment(fragment.to_owned()));
+                    }
+                    *this.redirects += 1;
+                    *this.rate_limited_retries = 0;
+                    *this.previous_origin = previous.authority().cloned();
+                    this.body.try_clone_from(&body, &*this.policy);
 
-        let attempt = Attempt {
-            status: res.status(),
-            location: &location,
-            previous: this.uri,
-        };
-        match this.policy.redirect(&attempt)? {
-            Action::Follow => {
-                *this.uri = location;
-                this.body.try_clone_from(&body, &this.policy);
-
-                let mut req = Request::new(body);
-                *req.uri_mut() = this.uri.clone();
-                *req.method_mut() = this.method.clone();
-                *req.version_mut() = *this.version;
-                *req.headers_mut() = this.headers.clone();
-                this.policy.on_request(&mut req);
-                this.future
-                    .set(Either::Right(Oneshot::new(this.service.clone(), req)));
-
-                cx.waker().wake_by_ref();
-                Poll::Pending
+                    let req = redirected_request(
+                        body,
+                        this.method,
+                        this.uri,
+                        *this.version,
+                        this.headers,
+                        this.extensions,
+                        this.previous_origin,
+                    );
+                    this.send(req);
+                }
+                Action::Stop => return Poll::Ready(Ok(res)),
             }
-            Action::Stop => Poll::Ready(Ok(res)),
         }
     }
 }
 
+impl<S, ReqBody, P> ResponseFutureProj<'_, S, ReqBody, P>
+where
+    S: Service<Request<ReqBody>> + Clone,
+    P: AsyncPolicy<ReqBody, S::Error>,
+{
+    fn send(&mut self, mut req: Request<ReqBody>) {
+        self.policy.on_request(&mut req);
+        #[cfg(feature = "follow-redirect-time")]
+        if let Some(timer) = self.hop_timings {
+            timer.start = Instant::now();
+        }
+        self.future
+            .set(Either::Right(Oneshot::new(self.service.clone(), req)));
+    }
+}
+
+// Removes the body of the redirected request, along with the headers describing it, so that the
+// request doesn't advertise a body that isn't sent.
+fn drop_body<B>(body: &mut BodyRepr<B>, headers: &mut HeaderMap) {
+    *body = BodyRepr::Empty;
+    headers.remove(CONTENT_TYPE);
+    headers.remove(CONTENT_LENGTH);
+    headers.remove(TRANSFER_ENCODING);
+}
+
+// Returns the delay of a `Retry-After` header given in seconds.
+#[cfg(feature = "follow-redirect-time")]
+fn retry_after(headers: &HeaderMap) -> Option<Duration> {
+    let seconds = headers
+        .get(RETRY_AFTER)?
+        .to_str()
+        .ok()?
+        .trim()
+        .parse()
+        .ok()?;
+    Some(Duration::from_secs(seconds))
+}
+
+fn redirected_request<B>(
+    body: B,
+    method: &Method,
+    uri: &Uri,
+    version: Version,
+    headers: &HeaderMap,
+    extensions: &Extensions,
+    previous_origin: &Option<Authority>,
+) -> Request<B> {
+    let mut req = Request::new(body);
+    *req.uri_mut() = uri.clone();
+    *req.method_mut() = method.clone();
+    *req.version_mut() = version;
+    *req.headers_mut() = headers.clone();
+    *req.extensions_mut() = extensions.clone();
+    if let Some(authority) = previous_origin {
+        req.extensions_mut()
+            .insert(PreviousOrigin(authority.clone()));
+    }
+    req
+}
+
 /// Response [`Extensions`][http::Extensions] value that represents the effective request URI of
 /// a response returned by a [`FollowRedirect`] middleware.
 ///
@@ -328,6 +877,114 @@ where
 #[derive(Clone)]
 pub struct RequestUri(pub Uri);
 
+/// Request and response [`Extensions`][http::Extensions] value that holds the fragment of a
+/// request URI, without the leading `#`.
+///
+/// A [`Uri`] can't hold a fragment, so set this on the original request to give its fragment to
+/// a [`FollowRedirect`] middleware. The fragment is replaced by the one of a `Location` value
+/// when there is one, and inherited by the redirected request otherwise. The fragment of the
+/// final request is set on the response, if there is one.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RequestFragment(pub String);
+
+/// Response [`Extensions`][http::Extensions] value that holds the number of redirections
+/// followed by a [`FollowRedirect`] middleware before producing the response.
+///
+/// When the `trace` feature is enabled, [`DefaultOnResponse`] records this value as the
+/// `http.redirects` field of the request span.
+///
+/// [`DefaultOnResponse`]: crate::trace::DefaultOnResponse
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RedirectCount(pub usize);
+
+/// Request [`Extensions`][http::Extensions] value that holds the authority of the previous
+/// request in a chain of redirections.
+///
+/// [`FollowRedirect`] inserts this value into every redirected request, so that layers between
+/// it and the HTTP client, such as a connection pool, can for example prefer reusing a connection
+/// to the same origin.
+#[derive(Clone, Debug)]
+pub struct PreviousOrigin(pub Authority);
+
+/// Response [`Extensions`][http::Extensions] value that holds the headers of the intermediate
+/// redirection responses followed by a [`FollowRedirect`] middleware.
+///
+/// This is only inserted when enabled with [`FollowRedirect::intermediate_headers`], since the
+/// headers may contain sensitive values such as cookies.
+#[derive(Clone, Debug)]
+pub struct IntermediateHeaders(pub Vec<HeaderMap>);
+
+/// Response [`Extensions`][http::Extensions] value that holds the URIs visited by a
+/// [`FollowRedirect`] middleware.
+///
+/// This is only inserted when enabled with [`FollowRedirect::redirect_history`].
+#[derive(Clone, Debug)]
+pub struct RedirectHistory(Vec<(StatusCode, Uri)>);
+
+impl RedirectHistory {
+    /// Returns the visited URIs in order, starting with the original request URI, each with the
+    /// status of the response received from it.
+    ///
+    /// The last entry is the URI of the final response, see [`RequestUri`].
+    pub fn hops(&self) -> &[(StatusCode, Uri)] {
+        &self.0
+    }
+}
+
+/// Response [`Extensions`][http::Extensions] value that holds how long each request made by a
+/// [`FollowRedirect`] middleware took, in order, starting with the original request.
+///
+/// Each duration is measured from sending the request to the inner service to receiving its
+/// response. This is only inserted when enabled with [`FollowRedirect::hop_timings`].
+#[cfg(feature = "follow-redirect-time")]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct HopTimings(pub Vec<Duration>);
+
+/// Request [`Extensions`][http::Extensions] value that holds the original casing of header names.
+///
+/// Since [`HeaderMap`] normalizes header names to lowercase, the casing has to be captured from
+/// the raw header names, for example as received by a server. [`FollowRedirect`] carries this
+/// value over to redirected requests when enabled with [`FollowRedirect::preserve_header_case`].
+///
+/// # Example
+///
+/// ```
+/// use http::header::HeaderName;
+/// use tower_http::follow_redirect::HeaderCase;
+///
+/// let header_case = HeaderCase::from_raw_names(["X-Custom-Header", "Content-Type"]);
+///
+/// let name = HeaderName::from_static("x-custom-header");
+/// assert_eq!(header_case.get(&name), Some("X-Custom-Header"));
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct HeaderCase(HeaderMap<Box<str>>);
+
+impl HeaderCase {
+    /// Capture the casing of the given raw header names.
+    ///
+    /// Names that aren't valid header names are ignored.
+    pub fn from_raw_names<I, N>(names: I) -> Self
+    where
+        I: IntoIterator<Item = N>,
+        N: AsRef<str>,
+    {
+        let mut map = HeaderMap::<Box<str>>::default();
+        for raw in names {
+            let raw = raw.as_ref();
+            if let Ok(name) = HeaderName::from_bytes(raw.as_bytes()) {
+                map.insert(name, raw.into());
+            }
+        }
+        HeaderCase(map)
+    }
+
+    /// Returns the original casing of the header name `name`, if it was captured.
+    pub fn get(&self, name: &HeaderName) -> Option<&str> {
+        self.0.get(name).map(|raw| &**raw)
+    }
+}
+
 #[derive(Debug)]
 enum BodyRepr<B> {
     Some(B),
@@ -352,7 +1009,7 @@ where
 
     fn try_clone_from<P, E>(&mut self, body: &B, policy: &P)
     where
-        P: Policy<B, E>,
+        P: AsyncPolicy<B, E>,
     {
         match self {
             BodyRepr::Some(_) | BodyRepr::Empty => {}
@@ -367,7 +1024,7 @@ where
 
 fn clone_body<P, B, E>(policy: &P, body: &B) -> Option<B>
 where
-    P: Policy<B, E>,
+    P: AsyncPolicy<B, E>,
     B: Body + Default,
 {
     if body.size_hint().exact() == Some(0) {
@@ -377,6 +1034,27 @@ where
     }
 }
 
+fn is_http(scheme: &Scheme) -> bool {
+    *scheme == Scheme::HTTP || *scheme == Scheme::HTTPS
+}
+
+/// Try to resolve the value of a `Location` header against a base URI `base`.
+fn resolve_location(location: &str, base: &Uri) -> Option<Uri> {
+    let location = location.trim_matches(|c: char| c.is_ascii_whitespace());
+    if location.chars().any(char::is_control) {
+        return None;
+    }
+    resolve_uri(location, base)
+}
+
+/// Returns the fragment of the value of a `Location` header, if it has one.
+fn location_fragment(location: &HeaderValue) -> Option<&str> {
+    let location = str::from_utf8(location.as_bytes()).ok()?;
+    let location = location.trim_matches(|c: char| c.is_ascii_whitespace());
+    let fragment = UriReferenceStr::new(location).ok()?.fragment()?;
+    Some(fragment.as_str())
+}
+
 /// Try to resolve a URI reference `relative` against a base URI `base`.
 fn resolve_uri(relative: &str, base: &Uri) -> Option<Uri> {
     let relative = UriReferenceStr::new(relative).ok()?;
@@ -389,8 +1067,12 @@ fn resolve_uri(relative: &str, base: &Uri) -> Option<Uri> {
 mod tests {
     use super::{policy::*, *};
     use crate::test_helpers::Body;
-    use http::header::LOCATION;
-    use std::convert::Infallible;
+    use http::header::{LOCATION, REFRESH, RETRY_AFTER};
+    use std::{
+        convert::Infallible,
+        sync::{Arc, Mutex},
+        time::Duration,
+    };
     use tower::{ServiceBuilder, ServiceExt};
 
     #[tokio::test]
@@ -409,6 +1091,7 @@ mod tests {
             res.extensions().get::<RequestUri>().unwrap().0,
             "http://example.com/0"
         );
+        assert_eq!(res.extensions().get(), Some(&RedirectCount(42)));
     }
 
     #[tokio::test]
@@ -427,6 +1110,7 @@ mod tests {
             res.extensions().get::<RequestUri>().unwrap().0,
             "http://example.com/42"
         );
+        assert_eq!(res.extensions().get(), Some(&RedirectCount(0)));
     }
 
     #[tokio::test]
@@ -447,6 +1131,1491 @@ mod tests {
         );
     }
 
+    #[tokio::test]
+    async fn error_on_stop() {
+        let svc = ServiceBuilder::new()
+            .layer(FollowRedirectLayer::with_policy(ErrorOnStop::new(
+                Limited::new(10),
+            )))
+            .buffer(1)
+            .service_fn(handle);
+        let req = Request::builder()
+            .uri("http://example.com/42")
+            .body(Body::empty())
+            .unwrap();
+        let err = svc.clone().oneshot(req).await.unwrap_err();
+        assert!(matches!(
+            err.downcast_ref::<RedirectError>(),
+            Some(RedirectError::LimitReachedWhileRedirecting)
+        ));
+
+        // below the limit
+        let req = Request::builder()
+            .uri("http://example.com/10")
+            .body(Body::empty())
+            .unwrap();
+        let res = svc.oneshot(req).await.unwrap();
+        assert_eq!(*res.body(), 0);
+    }
+
+    #[tokio::test]
+    async fn inserts_previous_origin() {
+        let origins = Arc::new(Mutex::new(Vec::new()));
+        let svc = ServiceBuilder::new()
+            .layer(FollowRedirectLayer::with_policy(Action::Follow))
+            .buffer(1)
+            .service_fn({
+                let origins = origins.clone();
+                move |req: Request<Body>| {
+                    origins.lock().unwrap().push(
+                        req.extensions()
+                            .get::<PreviousOrigin>()
+                            .map(|origin| origin.0.clone()),
+                    );
+                    handle(req)
+                }
+            });
+        let req = Request::builder()
+            .uri("http://example.com/2")
+            .body(Body::empty())
+            .unwrap();
+        let res = svc.oneshot(req).await.unwrap();
+        assert_eq!(*res.body(), 0);
+
+        let origins = origins.lock().unwrap();
+        assert_eq!(origins.len(), 3);
+        assert!(origins[0].is_none());
+        assert_eq!(origins[1].as_ref().unwrap(), "example.com");
+        assert_eq!(origins[2].as_ref().unwrap(), "example.com");
+    }
+
+    #[tokio::test]
+    async fn standard_removes_credentials_cross_host() {
+        use http::header::{AUTHORIZATION, COOKIE, PROXY_AUTHORIZATION};
+
+        // returns how many credentials were sent in the redirected request
+        async fn credentials_sent(policy: Standard, location: &'static str) -> usize {
+            let sent = Arc::new(Mutex::new(None));
+            let svc = ServiceBuilder::new()
+                .layer(FollowRedirectLayer::with_policy(policy))
+                .buffer(1)
+                .service_fn({
+                    let sent = sent.clone();
+                    move |req: Request<Body>| {
+                        let mut res = Response::builder();
+                        if req.uri() == "http://example.com/old" {
+                            res = res.status(StatusCode::FOUND).header(LOCATION, location);
+                        } else {
+                            let credentials = [AUTHORIZATION, COOKIE, PROXY_AUTHORIZATION]
+                                .iter()
+                                .filter(|name| req.headers().contains_key(*name))
+                                .count();
+                            *sent.lock().unwrap() = Some(credentials);
+                        }
+                        async move { Ok::<_, Infallible>(res.body(()).unwrap()) }
+                    }
+                });
+            let req = Request::builder()
+                .uri("http://example.com/old")
+                .header(AUTHORIZATION, "Bearer secret")
+                .header(COOKIE, "session=secret")
+                .header(PROXY_AUTHORIZATION, "Basic secret")
+                .body(Body::empty())
+                .unwrap();
+            let res = svc.oneshot(req).await.unwrap();
+            assert_eq!(res.status(), StatusCode::OK);
+            let sent = sent.lock().unwrap().unwrap();
+            sent
+        }
+
+        assert_eq!(credentials_sent(Standard::default(), "/new").await, 3);
+        assert_eq!(
+            credentials_sent(Standard::default(), "http://example.com:80/new").await,
+            3
+        );
+        assert_eq!(
+            credentials_sent(Standard::default(), "http://other.example/new").await,
+            0
+        );
+        assert_eq!(
+            credentials_sent(Standard::default(), "http://example.com:8080/new").await,
+            0
+        );
+        assert_eq!(
+            credentials_sent(
+                Standard::default().preserve_credentials_cross_host(true),
+                "http://other.example/new"
+            )
+            .await,
+            3
+        );
+    }
+
+    #[tokio::test]
+    async fn same_path_prefix_with_relative_locations() {
+        let svc = ServiceBuilder::new()
+            .layer(FollowRedirectLayer::with_policy(SamePathPrefix::new(
+                "/api",
+            )))
+            .buffer(1)
+            .service_fn(|req: Request<Body>| async move {
+                let location = match req.uri().path() {
+                    "/api/old" => Some("new"),
+                    "/api/new" => Some("/login"),
+                    _ => None,
+                };
+                let mut res = Response::builder();
+                if let Some(location) = location {
+                    res = res.status(StatusCode::FOUND).header(LOCATION, location);
+                }
+                Ok::<_, Infallible>(res.body(()).unwrap())
+            });
+        let req = Request::builder()
+            .uri("http://example.com/api/old")
+            .body(Body::empty())
+            .unwrap();
+        let res = svc.oneshot(req).await.unwrap();
+        assert_eq!(res.status(), StatusCode::FOUND);
+        assert_eq!(res.headers()[LOCATION], "/login");
+        assert_eq!(
+            res.extensions().get::<RequestUri>().unwrap().0,
+            "http://example.com/api/new"
+        );
+    }
+
+    #[tokio::test]
+    async fn max_location_length() {
+        let long = format!("/{}", "a".repeat(64));
+        let svc = ServiceBuilder::new()
+            .layer(FollowRedirectLayer::with_policy(MaxLocationLength::new(32)))
+            .buffer(1)
+            .service_fn(move |req: Request<Body>| {
+                let location = match req.uri().path() {
+                    "/old" => Some("/new".to_owned()),
+                    "/new" => Some(long.clone()),
+                    _ => None,
+                };
+                async move {
+                    let mut res = Response::builder();
+                    if let Some(location) = location {
+                        res = res.status(StatusCode::FOUND).header(LOCATION, location);
+                    }
+                    Ok::<_, Infallible>(res.body(()).unwrap())
+                }
+            });
+        let req = Request::builder()
+            .uri("http://example.com/old")
+            .body(Body::empty())
+            .unwrap();
+        let res = svc.oneshot(req).await.unwrap();
+        assert_eq!(res.status(), StatusCode::FOUND);
+        assert_eq!(res.headers()[LOCATION].len(), 65);
+        assert_eq!(
+            res.extensions().get::<RequestUri>().unwrap().0,
+            "http://example.com/new"
+        );
+    }
+
+    #[tokio::test]
+    async fn per_origin_byte_budget() {
+        let svc = ServiceBuilder::new()
+            .layer(FollowRedirectLayer::with_policy(PerOriginByteBudget::new(
+                1000,
+            )))
+            .buffer(1)
+            .service_fn(|req: Request<Body>| async move {
+                // every response redirects to the next page, with a large body
+                let next = req.uri().path()[1..].parse::<u32>().unwrap() + 1;
+                let res = Response::builder()
+                    .status(StatusCode::FOUND)
+                    .header(LOCATION, format!("/{}", next))
+                    .header(CONTENT_LENGTH, "400")
+                    .body(())
+                    .unwrap();
+                Ok::<_, Infallible>(res)
+            });
+        let req = Request::builder()
+            .uri("http://example.com/0")
+            .body(Body::empty())
+            .unwrap();
+        let res = svc.oneshot(req).await.unwrap();
+        assert_eq!(res.status(), StatusCode::FOUND);
+        // the third response brings the origin above its budget
+        assert_eq!(
+            res.extensions().get::<RequestUri>().unwrap().0,
+            "http://example.com/2"
+        );
+    }
+
+    #[tokio::test]
+    async fn per_origin_byte_budget_unknown_length() {
+        let svc = |policy| {
+            FollowRedirect::with_policy(
+                tower::service_fn(|req: Request<Body>| async move {
+                    let n: u64 = req.uri().path()[1..].parse().unwrap();
+                    let mut res = Response::builder();
+                    if n > 0 {
+                        res = res
+                            .status(StatusCode::FOUND)
+                            .header(LOCATION, format!("/{}", n - 1));
+                        // the length of odd redirections is unknown
+                        if n % 2 == 0 {
+                            res = res.header(CONTENT_LENGTH, "10");
+                        }
+                    }
+                    Ok::<_, Infallible>(res.body(n).unwrap())
+                }),
+                policy,
+            )
+        };
+        let request = |path| Request::builder().uri(path).body(Body::empty()).unwrap();
+
+        // the budget is exhausted by the first response of unknown length
+        let res = svc(PerOriginByteBudget::new(100))
+            .oneshot(request("http://example.com/4"))
+            .await
+            .unwrap();
+        assert_eq!(*res.body(), 3);
+
+        // 10 + 15 + 10 + 15 bytes
+        let res = svc(PerOriginByteBudget::new(40).unknown_length(15))
+            .oneshot(request("http://example.com/4"))
+            .await
+            .unwrap();
+        assert_eq!(*res.body(), 1);
+
+        let res = svc(PerOriginByteBudget::new(50).unknown_length(15))
+            .oneshot(request("http://example.com/4"))
+            .await
+            .unwrap();
+        assert_eq!(*res.body(), 0);
+    }
+
+    #[tokio::test]
+    async fn require_response_header() {
+        let policy = RequireResponseHeader::new(
+            HeaderName::from_static("x-follow"),
+            HeaderValue::from_static("ok"),
+        );
+        let svc = ServiceBuilder::new()
+            .layer(FollowRedirectLayer::with_policy(policy))
+            .buffer(1)
+            .service_fn(|req: Request<Body>| async move {
+                let mut res = Response::builder();
+                match req.uri().path() {
+                    "/real" => {
+                        res = res
+                            .status(StatusCode::FOUND)
+                            .header(LOCATION, "/error")
+                            .header("x-follow", "ok")
+                    }
+                    "/error" => res = res.status(StatusCode::FOUND).header(LOCATION, "/real"),
+                    _ => {}
+                }
+                Ok::<_, Infallible>(res.body(()).unwrap())
+            });
+
+        let req = Request::builder()
+            .uri("http://example.com/real")
+            .body(Body::empty())
+            .unwrap();
+        let res = svc.oneshot(req).await.unwrap();
+        assert_eq!(res.status(), StatusCode::FOUND);
+        assert_eq!(
+            res.extensions().get::<RequestUri>().unwrap().0,
+            "http://example.com/error"
+        );
+    }
+
+    #[tokio::test]
+    async fn method_status_table() {
+        let table = std::collections::HashMap::from([
+            ((Method::POST, StatusCode::FOUND), Action::Stop),
+            ((Method::GET, StatusCode::FOUND), Action::Follow),
+        ]);
+        let policy = MethodStatusTable::new(table, Action::Stop);
+        let svc = ServiceBuilder::new()
+            .layer(FollowRedirectLayer::with_policy(policy))
+            .buffer(1)
+            .service_fn(|req: Request<Body>| async move {
+                let mut res = Response::builder();
+                if req.uri().path() == "/old" {
+                    res = res.status(StatusCode::FOUND).header(LOCATION, "/new");
+                }
+                Ok::<_, Infallible>(res.body(()).unwrap())
+            });
+
+        // the table is consulted with the method before it is changed to `GET`
+        let req = Request::builder()
+            .method(Method::POST)
+            .uri("http://example.com/old")
+            .body(Body::empty())
+            .unwrap();
+        let res = svc.clone().oneshot(req).await.unwrap();
+        assert_eq!(res.status(), StatusCode::FOUND);
+
+        let req = Request::builder()
+            .uri("http://example.com/old")
+            .body(Body::empty())
+            .unwrap();
+        let res = svc.oneshot(req).await.unwrap();
+        assert_eq!(res.status(), StatusCode::OK);
+        assert_eq!(
+            res.extensions().get::<RequestUri>().unwrap().0,
+            "http://example.com/new"
+        );
+    }
+
+    #[tokio::test]
+    async fn follows_refresh() {
+        let policy =
+            Limited::default().and::<_, Body, Infallible>(FollowRefresh::new(Duration::ZERO));
+        let svc = ServiceBuilder::new()
+            .layer(FollowRedirectLayer::with_policy(policy))
+            .buffer(1)
+            .service_fn(|req: Request<Body>| async move {
+                let refresh = match req.uri().path() {
+                    "/now" => Some("0; url=/next"),
+                    "/later" => Some("30; url=/next"),
+                    _ => None,
+                };
+                let mut res = Response::builder();
+                if let Some(refresh) = refresh {
+                    res = res.header(REFRESH, refresh);
+                }
+                Ok::<_, Infallible>(res.body(req.method().clone()).unwrap())
+            });
+
+        let req = Request::builder()
+            .method(Method::POST)
+            .uri("http://example.com/now")
+            .body(Body::empty())
+            .unwrap();
+        let res = svc.clone().oneshot(req).await.unwrap();
+        assert_eq!(res.status(), StatusCode::OK);
+        assert_eq!(*res.body(), Method::GET);
+        assert_eq!(
+            res.extensions().get::<RequestUri>().unwrap().0,
+            "http://example.com/next"
+        );
+
+        let req = Request::builder()
+            .uri("http://example.com/later")
+            .body(Body::empty())
+            .unwrap();
+        let res = svc.oneshot(req).await.unwrap();
+        assert_eq!(res.headers()[REFRESH], "30; url=/next");
+        assert_eq!(
+            res.extensions().get::<RequestUri>().unwrap().0,
+            "http://example.com/later"
+        );
+    }
+
+    #[tokio::test]
+    async fn preserves_header_case() {
+        let casings = Arc::new(Mutex::new(Vec::new()));
+        let svc = ServiceBuilder::new()
+            .layer(FollowRedirectLayer::with_policy(Action::Follow).preserve_header_case(true))
+            .buffer(1)
+            .service_fn({
+                let casings = casings.clone();
+                move |req: Request<Body>| {
+                    let name = HeaderName::from_static("x-custom-header");
+                    casings.lock().unwrap().push(
+                        req.extensions()
+                            .get::<HeaderCase>()
+                            .and_then(|header_case| header_case.get(&name))
+                            .map(str::to_owned),
+                    );
+                    handle(req)
+                }
+            });
+        let mut req = Request::builder()
+            .uri("http://example.com/1")
+            .header("x-custom-header", "42")
+            .body(Body::empty())
+            .unwrap();
+        req.extensions_mut()
+            .insert(HeaderCase::from_raw_names(["X-Custom-Header"]));
+        let res = svc.oneshot(req).await.unwrap();
+        assert_eq!(*res.body(), 0);
+
+        let casings = casings.lock().unwrap();
+        assert_eq!(casings.len(), 2);
+        assert_eq!(casings[1].as_deref(), Some("X-Custom-Header"));
+    }
+
+    #[tokio::test]
+    async fn preserves_extensions() {
+        #[derive(Clone)]
+        struct RequestId(&'static str);
+
+        #[derive(Clone)]
+        struct Hop(usize);
+
+        // `on_request` sees the restored extensions of the original request and can change them
+        #[derive(Clone, Default)]
+        struct NumberHops(usize);
+
+        impl<B, E> Policy<B, E> for NumberHops {
+            fn redirect(&mut self, _: &Attempt<'_>) -> Result<Action, E> {
+                Ok(Action::Follow)
+            }
+
+            fn on_request(&mut self, req: &mut Request<B>) {
+                assert_eq!(req.extensions().get::<RequestId>().unwrap().0, "abc");
+                assert!(req.extensions().get::<Hop>().is_none());
+                req.extensions_mut().insert(Hop(self.0));
+                self.0 += 1;
+            }
+        }
+
+        let svc = ServiceBuilder::new()
+            .layer(FollowRedirectLayer::with_policy(NumberHops::default()))
+            .buffer(1)
+            .service_fn(|req: Request<Body>| {
+                assert_eq!(req.extensions().get::<RequestId>().unwrap().0, "abc");
+                let n: usize = req.uri().path()[1..].parse().unwrap();
+                assert_eq!(req.extensions().get::<Hop>().unwrap().0, 2 - n);
+                handle(req)
+            });
+        let mut req = Request::builder()
+            .uri("http://example.com/2")
+            .body(Body::empty())
+            .unwrap();
+        req.extensions_mut().insert(RequestId("abc"));
+        let res = svc.oneshot(req).await.unwrap();
+        assert_eq!(*res.body(), 0);
+    }
+
+    #[tokio::test]
+    async fn circuit_breaker_stops_redirects_to_failing_hosts() {
+        let hits = Arc::new(Mutex::new(0));
+        let policy = CircuitBreaker::new(2, Duration::from_secs(60), Duration::from_secs(60))
+            .and::<_, Body, &str>(Limited::default());
+        let svc = ServiceBuilder::new()
+            .layer(FollowRedirectLayer::with_policy(policy))
+            .service_fn({
+                let hits = hits.clone();
+                move |req: Request<Body>| {
+                    let hits = hits.clone();
+                    async move {
+                        if req.uri().host() == Some("down.example") {
+                            *hits.lock().unwrap() += 1;
+                            return Err("unavailable");
+                        }
+                        Ok(Response::builder()
+                            .status(StatusCode::FOUND)
+                            .header(LOCATION, "http://down.example/")
+                            .body(())
+                            .unwrap())
+                    }
+                }
+            });
+        let request = || {
+            Request::builder()
+                .uri("http://up.example/")
+                .body(Body::empty())
+                .unwrap()
+        };
+
+        for _ in 0..2 {
+            let err = svc.clone().oneshot(request()).await.unwrap_err();
+            assert_eq!(err, "unavailable");
+        }
+
+        // the circuit is open, so the redirection response is returned
+        let res = svc.oneshot(request()).await.unwrap();
+        assert_eq!(res.status(), StatusCode::FOUND);
+        assert_eq!(*hits.lock().unwrap(), 2);
+    }
+
+    #[tokio::test]
+    async fn circuit_breaker_sees_retried_errors() {
+        let hits = Arc::new(Mutex::new(0));
+        // the retry policy comes first, and decides to retry the first error
+        let policy =
+            RetryOnError::new(1, |_: &&str| true, Limited::default()).and::<_, Body, &str>(
+                CircuitBreaker::new(2, Duration::from_secs(60), Duration::from_secs(60)),
+            );
+        let svc = ServiceBuilder::new()
+            .layer(FollowRedirectLayer::with_policy(policy))
+            .service_fn({
+                let hits = hits.clone();
+                move |req: Request<Body>| {
+                    let hits = hits.clone();
+                    async move {
+                        if req.uri().host() == Some("down.example") {
+                            *hits.lock().unwrap() += 1;
+                            return Err("unavailable");
+                        }
+                        Ok(Response::builder()
+                            .status(StatusCode::FOUND)
+                            .header(LOCATION, "http://down.example/")
+                            .body(())
+                            .unwrap())
+                    }
+                }
+            });
+        let request = || {
+            Request::builder()
+                .uri("http://up.example/")
+                .body(Body::empty())
+                .unwrap()
+        };
+
+        let err = svc.clone().oneshot(request()).await.unwrap_err();
+        assert_eq!(err, "unavailable");
+        assert_eq!(*hits.lock().unwrap(), 2);
+
+        // both errors were counted, so the circuit is open
+        let res = svc.oneshot(request()).await.unwrap();
+        assert_eq!(res.status(), StatusCode::FOUND);
+        assert_eq!(*hits.lock().unwrap(), 2);
+    }
+
+    #[tokio::test]
+    async fn policies_see_redirection_response_headers() {
+        // don't follow redirections that ask to wait
+        let policy = redirect_fn(|attempt: &Attempt<'_>| {
+            if attempt.headers().contains_key(RETRY_AFTER) {
+                Ok::<_, crate::BoxError>(Action::Stop)
+            } else {
+                Ok(Action::Follow)
+            }
+        });
+        let svc = ServiceBuilder::new()
+            .layer(FollowRedirectLayer::with_policy(policy))
+            .buffer(1)
+            .service_fn(|req: Request<Body>| async move {
+                let mut res = Response::builder();
+                match req.uri().path() {
+                    "/now" => res = res.status(StatusCode::FOUND).header(LOCATION, "/new"),
+                    "/later" => {
+                        res = res
+                            .status(StatusCode::FOUND)
+                            .header(LOCATION, "/new")
+                            .header(RETRY_AFTER, "120")
+                    }
+                    _ => {}
+                }
+                Ok::<_, Infallible>(res.body(()).unwrap())
+            });
+
+        let req = Request::builder()
+            .uri("http://example.com/now")
+            .body(Body::empty())
+            .unwrap();
+        let res = svc.clone().oneshot(req).await.unwrap();
+        assert_eq!(res.status(), StatusCode::OK);
+
+        let req = Request::builder()
+            .uri("http://example.com/later")
+            .body(Body::empty())
+            .unwrap();
+        let res = svc.oneshot(req).await.unwrap();
+        assert_eq!(res.status(), StatusCode::FOUND);
+        assert_eq!(res.headers()[RETRY_AFTER], "120");
+    }
+
+    #[cfg(feature = "cookie")]
+    #[tokio::test]
+    async fn cookie_jar_sends_cookies_set_by_redirections() {
+        use http::header::{COOKIE, SET_COOKIE};
+
+        let received = Arc::new(Mutex::new(Vec::new()));
+        let policy = CookieJar::new().and::<_, Body, crate::BoxError>(Limited::default());
+        let svc = ServiceBuilder::new()
+            .layer(FollowRedirectLayer::with_policy(policy))
+            .buffer(1)
+            .service_fn({
+                let received = received.clone();
+                move |req: Request<Body>| {
+                    received
+                        .lock()
+                        .unwrap()
+                        .push((req.uri().to_string(), req.headers().get(COOKIE).cloned()));
+                    let mut res = Response::builder();
+                    match req.uri().to_string().as_str() {
+                        "http://example.com/login" => {
+                            res = res
+                                .status(StatusCode::FOUND)
+                                .header(SET_COOKIE, "session=abc; Path=/")
+                                .header(LOCATION, "/home")
+                        }
+                        "http://example.com/home" => {
+                            res = res
+                                .status(StatusCode::FOUND)
+                                .header(LOCATION, "http://other.example/")
+                        }
+                        _ => {}
+                    }
+                    async move { Ok::<_, Infallible>(res.body(()).unwrap()) }
+                }
+            });
+
+        let req = Request::builder()
+            .uri("http://example.com/login")
+            .body(Body::empty())
+            .unwrap();
+        let res = svc.oneshot(req).await.unwrap();
+        assert_eq!(res.status(), StatusCode::OK);
+
+        // the cookie is sent back to the host that set it, but not to another host
+        assert_eq!(
+            *received.lock().unwrap(),
+            [
+                ("http://example.com/login".to_owned(), None),
+                (
+                    "http://example.com/home".to_owned(),
+                    Some(HeaderValue::from_static("session=abc"))
+                ),
+                ("http://other.example/".to_owned(), None),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn stops_redirect_loops() {
+        let hits = Arc::new(Mutex::new(0));
+        let svc = ServiceBuilder::new()
+            .layer(FollowRedirectLayer::with_policy(
+                LoopDetect::new().and::<_, Body, Infallible>(Limited::new(100)),
+            ))
+            .buffer(1)
+            .service_fn({
+                let hits = hits.clone();
+                move |req: Request<Body>| {
+                    *hits.lock().unwrap() += 1;
+                    let location = if req.uri().path() == "/a" { "/b" } else { "/a" };
+                    let res = Response::builder()
+                        .status(StatusCode::FOUND)
+                        .header(LOCATION, location)
+                        .body(())
+                        .unwrap();
+                    async move { Ok::<_, Infallible>(res) }
+                }
+            });
+
+        let req = Request::builder()
+            .uri("http://example.com/a")
+            .body(Body::empty())
+            .unwrap();
+        let res = svc.clone().oneshot(req).await.unwrap();
+        assert_eq!(res.status(), StatusCode::FOUND);
+        assert_eq!(res.headers()[LOCATION], "/a");
+        assert_eq!(*hits.lock().unwrap(), 2);
+
+        // the loop can be turned into an error
+        let svc = ServiceBuilder::new()
+            .layer(FollowRedirectLayer::with_policy(
+                LoopDetect::new().or::<_, Body, _>(Err("redirect loop")),
+            ))
+            .service_fn(|req: Request<Body>| async move {
+                let location = if req.uri().path() == "/a" { "/b" } else { "/a" };
+                Ok::<_, &'static str>(
+                    Response::builder()
+                        .status(StatusCode::FOUND)
+                        .header(LOCATION, location)
+                        .body(())
+                        .unwrap(),
+                )
+            });
+        let req = Request::builder()
+            .uri("http://example.com/a")
+            .body(Body::empty())
+            .unwrap();
+        assert_eq!(svc.oneshot(req).await.unwrap_err(), "redirect loop");
+    }
+
+    #[tokio::test]
+    async fn strips_body_headers_when_dropping_body() {
+        let svc = ServiceBuilder::new()
+            .layer(FollowRedirectLayer::with_policy(Action::Follow))
+            .buffer(1)
+            .service_fn(|req: Request<Body>| async move {
+                let mut res = Response::builder();
+                match req.uri().path() {
+                    "/see-other" => {
+                        res = res.status(StatusCode::SEE_OTHER).header(LOCATION, "/new")
+                    }
+                    "/temporary" => {
+                        res = res
+                            .status(StatusCode::TEMPORARY_REDIRECT)
+                            .header(LOCATION, "/new")
+                    }
+                    _ => {}
+                }
+                let body_headers = [CONTENT_TYPE, CONTENT_LENGTH, TRANSFER_ENCODING]
+                    .iter()
+                    .filter(|name| req.headers().contains_key(*name))
+                    .count();
+                Ok::<_, Infallible>(res.body((req.method().clone(), body_headers)).unwrap())
+            });
+        let request = |path: &str, body: Body| {
+            Request::builder()
+                .method(Method::POST)
+                .uri(format!("http://example.com{}", path))
+                .header(CONTENT_TYPE, "text/plain")
+                .header(CONTENT_LENGTH, "5")
+                .header(TRANSFER_ENCODING, "chunked")
+                .body(body)
+                .unwrap()
+        };
+
+        let res = svc
+            .clone()
+            .oneshot(request("/see-other", Body::from("hello")))
+            .await
+            .unwrap();
+        assert_eq!(*res.body(), (Method::GET, 0));
+
+        // the body is replayed, so it is still described by the headers
+        let res = svc
+            .oneshot(request("/temporary", Body::empty()))
+            .await
+            .unwrap();
+        assert_eq!(*res.body(), (Method::POST, 3));
+    }
+
+    #[tokio::test]
+    async fn discards_header_case_by_default() {
+        let svc = ServiceBuilder::new()
+            .layer(FollowRedirectLayer::with_policy(Action::Follow))
+            .buffer(1)
+            .service_fn(|req: Request<Body>| {
+                if req.uri().path() == "/0" {
+                    assert!(req.extensions().get::<HeaderCase>().is_none());
+                }
+                handle(req)
+            });
+        let mut req = Request::builder()
+            .uri("http://example.com/1")
+            .body(Body::empty())
+            .unwrap();
+        req.extensions_mut()
+            .insert(HeaderCase::from_raw_names(["X-Custom-Header"]));
+        let res = svc.oneshot(req).await.unwrap();
+        assert_eq!(*res.body(), 0);
+    }
+
+    #[tokio::test]
+    async fn intermediate_headers() {
+        let svc = ServiceBuilder::new()
+            .layer(FollowRedirectLayer::with_policy(Action::Follow).intermediate_headers(true))
+            .buffer(1)
+            .service_fn(|req: Request<Body>| async move {
+                let mut res = handle(req).await?;
+                let n = *res.body();
+                res.headers_mut().insert("x-hop", n.into());
+                Ok::<_, Infallible>(res)
+            });
+        let req = Request::builder()
+            .uri("http://example.com/2")
+            .body(Body::empty())
+            .unwrap();
+        let res = svc.oneshot(req).await.unwrap();
+        assert_eq!(*res.body(), 0);
+        assert_eq!(res.headers()["x-hop"], "0");
+
+        let intermediate = &res.extensions().get::<IntermediateHeaders>().unwrap().0;
+        assert_eq!(intermediate.len(), 2);
+        assert_eq!(intermediate[0]["x-hop"], "2");
+        assert_eq!(intermediate[0][LOCATION], "/1");
+        assert_eq!(intermediate[1]["x-hop"], "1");
+        assert_eq!(intermediate[1][LOCATION], "/0");
+    }
+
+    #[tokio::test]
+    async fn no_intermediate_headers_by_default() {
+        let svc = ServiceBuilder::new()
+            .layer(FollowRedirectLayer::with_policy(Action::Follow))
+            .buffer(1)
+            .service_fn(handle);
+        let req = Request::builder()
+            .uri("http://example.com/2")
+            .body(Body::empty())
+            .unwrap();
+        let res = svc.oneshot(req).await.unwrap();
+        assert!(res.extensions().get::<IntermediateHeaders>().is_none());
+    }
+
+    #[tokio::test]
+    async fn redirect_history() {
+        let svc = ServiceBuilder::new()
+            .layer(FollowRedirectLayer::with_policy(Action::Follow).redirect_history(true))
+            .buffer(1)
+            .service_fn(handle);
+        let req = Request::builder()
+            .uri("http://example.com/2")
+            .body(Body::empty())
+            .unwrap();
+        let res = svc.oneshot(req).await.unwrap();
+        let history = res.extensions().get::<RedirectHistory>().unwrap();
+        assert_eq!(
+            history.hops(),
+            [
+                (
+                    StatusCode::MOVED_PERMANENTLY,
+                    Uri::from_static("http://example.com/2")
+                ),
+                (
+                    StatusCode::MOVED_PERMANENTLY,
+                    Uri::from_static("http://example.com/1")
+                ),
+                (StatusCode::OK, Uri::from_static("http://example.com/0")),
+            ]
+        );
+
+        // the history is also inserted when following stops early
+        let svc = ServiceBuilder::new()
+            .layer(FollowRedirectLayer::with_policy(Limited::new(1)).redirect_history(true))
+            .buffer(1)
+            .service_fn(handle);
+        let req = Request::builder()
+            .uri("http://example.com/2")
+            .body(Body::empty())
+            .unwrap();
+        let res = svc.oneshot(req).await.unwrap();
+        let history = res.extensions().get::<RedirectHistory>().unwrap();
+        assert_eq!(
+            history.hops(),
+            [
+                (
+                    StatusCode::MOVED_PERMANENTLY,
+                    Uri::from_static("http://example.com/2")
+                ),
+                (
+                    StatusCode::MOVED_PERMANENTLY,
+                    Uri::from_static("http://example.com/1")
+                ),
+            ]
+        );
+
+        // disabled by default
+        let svc = ServiceBuilder::new()
+            .layer(FollowRedirectLayer::with_policy(Action::Follow))
+            .buffer(1)
+            .service_fn(handle);
+        let req = Request::builder()
+            .uri("http://example.com/2")
+            .body(Body::empty())
+            .unwrap();
+        let res = svc.oneshot(req).await.unwrap();
+        assert!(res.extensions().get::<RedirectHistory>().is_none());
+    }
+
+    #[tokio::test]
+    async fn retries_redirected_request_on_error() {
+        let failures = Arc::new(Mutex::new(2));
+        let svc = ServiceBuilder::new()
+            .layer(FollowRedirectLayer::with_policy(RetryOnError::new(
+                3,
+                |err: &&str| *err == "connection reset",
+                Action::Follow,
+            )))
+            .service_fn(move |req: Request<Body>| {
+                let failures = failures.clone();
+                async move {
+                    if req.uri().path() == "/0" {
+                        let mut failures = failures.lock().unwrap();
+                        if *failures > 0 {
+                            *failures -= 1;
+                            return Err("connection reset");
+                        }
+                    }
+                    Ok(handle(req).await.unwrap())
+                }
+            });
+        let req = Request::builder()
+            .uri("http://example.com/1")
+            .body(Body::empty())
+            .unwrap();
+        let res = svc.oneshot(req).await.unwrap();
+        assert_eq!(*res.body(), 0);
+    }
+
+    #[cfg(feature = "follow-redirect-time")]
+    #[tokio::test(start_paused = true)]
+    async fn retries_rate_limited_request() {
+        let requests = Arc::new(Mutex::new(Vec::new()));
+        let svc = ServiceBuilder::new()
+            .layer(FollowRedirectLayer::with_policy(Action::Follow).retry_rate_limited(1))
+            .buffer(1)
+            .service_fn({
+                let requests = requests.clone();
+                move |req: Request<Body>| {
+                    let mut requests = requests.lock().unwrap();
+                    let retried = requests.contains(req.uri());
+                    requests.push(req.uri().clone());
+                    async move {
+                        let path = req.uri().path();
+                        if (path == "/0" && !retried) || path == "/429" {
+                            let res = Response::builder()
+                                .status(StatusCode::TOO_MANY_REQUESTS)
+                                .header(RETRY_AFTER, "10")
+                                .body(429)
+                                .unwrap();
+                            return Ok(res);
+                        }
+                        handle(req).await
+                    }
+                }
+            });
+
+        let start = tokio::time::Instant::now();
+        let req = Request::builder()
+            .uri("http://example.com/1")
+            .body(Body::empty())
+            .unwrap();
+        let res = svc.clone().oneshot(req).await.unwrap();
+        assert_eq!(res.status(), StatusCode::OK);
+        assert_eq!(*res.body(), 0);
+        assert_eq!(res.extensions().get(), Some(&RedirectCount(1)));
+        assert_eq!(start.elapsed(), Duration::from_secs(10));
+        assert_eq!(
+            *requests.lock().unwrap(),
+            [
+                "http://example.com/1",
+                "http://example.com/0",
+                "http://example.com/0"
+            ]
+        );
+
+        // the last response is returned once the retries are exhausted
+        let req = Request::builder()
+            .uri("http://example.com/429")
+            .body(Body::empty())
+            .unwrap();
+        let res = svc.oneshot(req).await.unwrap();
+        assert_eq!(res.status(), StatusCode::TOO_MANY_REQUESTS);
+    }
+
+    #[tokio::test]
+    async fn async_policy() {
+        // decides to follow every redirection after being polled once more
+        #[derive(Clone, Default)]
+        struct PendingOnce {
+            polled: Arc<Mutex<usize>>,
+            pending: bool,
+        }
+
+        impl AsyncPolicy<Body, crate::BoxError> for PendingOnce {
+            fn poll_redirect(
+                &mut self,
+                cx: &mut Context<'_>,
+                _: &Attempt<'_>,
+            ) -> Poll<Result<Action, crate::BoxError>> {
+                *self.polled.lock().unwrap() += 1;
+                self.pending = !self.pending;
+                if self.pending {
+                    cx.waker().wake_by_ref();
+                    Poll::Pending
+                } else {
+                    Poll::Ready(Ok(Action::Follow))
+                }
+            }
+        }
+
+        let policy = PendingOnce::default();
+        let svc = ServiceBuilder::new()
+            .layer(FollowRedirectLayer::with_policy(policy.clone()))
+            .buffer(1)
+            .service_fn(handle);
+        let req = Request::builder()
+            .uri("http://example.com/3")
+            .body(Body::empty())
+            .unwrap();
+        let res = svc.oneshot(req).await.unwrap();
+        assert_eq!(*res.body(), 0);
+        assert_eq!(res.extensions().get(), Some(&RedirectCount(3)));
+        assert_eq!(*policy.polled.lock().unwrap(), 6);
+    }
+
+    #[tokio::test]
+    async fn stops_on_self_redirect() {
+        let calls = Arc::new(Mutex::new(0));
+        let svc = {
+            let calls = calls.clone();
+            ServiceBuilder::new()
+                .layer(FollowRedirectLayer::with_policy(
+                    StopOnSelfRedirect::new().and::<_, Body, crate::BoxError>(Limited::new(10)),
+                ))
+                .buffer(1)
+                .service_fn(move |req: Request<Body>| {
+                    *calls.lock().unwrap() += 1;
+                    async move {
+                        if req.uri().path() == "/self" {
+                            let res = Response::builder()
+                                .status(StatusCode::FOUND)
+                                .header(LOCATION, "/self")
+                                .body(u64::MAX)
+                                .unwrap();
+                            return Ok(res);
+                        }
+                        handle(req).await
+                    }
+                })
+        };
+
+        let req = Request::builder()
+            .uri("http://example.com/self")
+            .body(Body::empty())
+            .unwrap();
+        let res = svc.clone().oneshot(req).await.unwrap();
+        assert_eq!(res.status(), StatusCode::FOUND);
+        assert_eq!(res.extensions().get(), Some(&RedirectCount(0)));
+        assert_eq!(*calls.lock().unwrap(), 1);
+
+        let req = Request::builder()
+            .uri("http://example.com/3")
+            .body(Body::empty())
+            .unwrap();
+        let res = svc.oneshot(req).await.unwrap();
+        assert_eq!(*res.body(), 0);
+        assert_eq!(res.extensions().get(), Some(&RedirectCount(3)));
+    }
+
+    #[cfg(feature = "follow-redirect-time")]
+    #[tokio::test(start_paused = true)]
+    async fn deadline() {
+        let svc = ServiceBuilder::new()
+            .layer(FollowRedirectLayer::with_policy(Deadline::new(
+                Duration::from_millis(2500),
+            )))
+            .buffer(1)
+            .service_fn(|req: Request<Body>| async move {
+                tokio::time::sleep(Duration::from_secs(1)).await;
+                handle(req).await
+            });
+        let req = Request::builder()
+            .uri("http://example.com/10")
+            .body(Body::empty())
+            .unwrap();
+        let res = svc.oneshot(req).await.unwrap();
+        // the third response arrives after 3 seconds
+        assert_eq!(*res.body(), 8);
+        assert_eq!(res.extensions().get(), Some(&RedirectCount(2)));
+    }
+
+    #[test]
+    fn follows_without_yielding() {
+        use futures_util::FutureExt;
+
+        let mut svc = FollowRedirect::with_policy(tower::service_fn(handle), Action::Follow);
+        let req = Request::builder()
+            .uri("http://example.com/10")
+            .body(Body::empty())
+            .unwrap();
+        // every redirection is ready right away, so the whole chain completes in a single poll
+        let res = svc.call(req).now_or_never().unwrap().unwrap();
+        assert_eq!(*res.body(), 0);
+        assert_eq!(res.extensions().get(), Some(&RedirectCount(10)));
+    }
+
+    #[tokio::test]
+    async fn does_not_follow_non_http_schemes() {
+        async fn redirect(
+            layer: FollowRedirectLayer<Action>,
+            location: &'static str,
+        ) -> Response<u64> {
+            let svc = ServiceBuilder::new().layer(layer).service_fn(
+                move |req: Request<Body>| async move {
+                    if req.uri().path() == "/start" {
+                        Ok::<_, Infallible>(
+                            Response::builder()
+                                .status(StatusCode::FOUND)
+                                .header(LOCATION, location)
+                                .body(1)
+                                .unwrap(),
+                        )
+                    } else {
+                        Ok(Response::new(0))
+                    }
+                },
+            );
+            let req = Request::builder()
+                .uri("http://example.com/start")
+                .body(Body::empty())
+                .unwrap();
+            svc.oneshot(req).await.unwrap()
+        }
+        let layer = FollowRedirectLayer::with_policy(Action::Follow);
+
+        for location in [
+            "file:///etc/passwd",
+            "data:text/plain,hello",
+            "ftp://example.com/file",
+        ] {
+            let res = redirect(layer, location).await;
+            assert_eq!(res.status(), StatusCode::FOUND, "{}", location);
+            assert_eq!(res.extensions().get(), Some(&RedirectCount(0)));
+        }
+
+        // scheme-relative locations get the scheme of the request
+        let res = redirect(layer, "//other.example/path").await;
+        assert_eq!(res.status(), StatusCode::OK);
+        assert_eq!(
+            res.extensions().get::<RequestUri>().unwrap().0,
+            "http://other.example/path"
+        );
+
+        let layer = layer.allow_scheme(|scheme| scheme.as_str() == "ftp");
+        let res = redirect(layer, "ftp://example.com/file").await;
+        assert_eq!(res.status(), StatusCode::OK);
+        let res = redirect(layer, "https://example.com/").await;
+        assert_eq!(res.status(), StatusCode::FOUND);
+    }
+
+    #[test]
+    fn resolves_scheme_relative_locations() {
+        let base = Uri::from_static("https://example.com/old/page?query");
+        let uri = resolve_uri("//cdn.example.com/asset", &base).unwrap();
+        assert_eq!(uri, "https://cdn.example.com/asset");
+        assert_eq!(uri.scheme(), Some(&Scheme::HTTPS));
+        assert_eq!(uri.authority().unwrap(), "cdn.example.com");
+
+        let base = Uri::from_static("http://example.com:8080/");
+        let uri = resolve_uri("//cdn.example.com:8443/a/../asset?v=1", &base).unwrap();
+        assert_eq!(uri, "http://cdn.example.com:8443/asset?v=1");
+        assert_eq!(uri.authority().unwrap(), "cdn.example.com:8443");
+    }
+
+    #[cfg(feature = "follow-redirect-time")]
+    #[tokio::test(start_paused = true)]
+    async fn hop_timings() {
+        let svc = ServiceBuilder::new()
+            .layer(FollowRedirectLayer::with_policy(Action::Follow).hop_timings(true))
+            .service_fn(|req: Request<Body>| async move {
+                // `/1` takes 2 seconds and `/0` takes 1 second
+                let n: u64 = req.uri().path()[1..].parse().unwrap();
+                tokio::time::sleep(Duration::from_secs(n + 1)).await;
+                handle(req).await
+            });
+        let req = Request::builder()
+            .uri("http://example.com/1")
+            .body(Body::empty())
+            .unwrap();
+        let res = svc.oneshot(req).await.unwrap();
+        let HopTimings(timings) = res.extensions().get::<HopTimings>().unwrap();
+        assert_eq!(timings.len(), 2);
+        for (hop, timing) in timings.iter().enumerate() {
+            let expected = Duration::from_secs(2 - hop as u64);
+            assert!(
+                *timing >= expected && *timing < expected + Duration::from_millis(100),
+                "{:?}",
+                timings
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn trims_location() {
+        let svc = ServiceBuilder::new()
+            .layer(FollowRedirectLayer::with_policy(Action::Follow))
+            .service_fn(|req: Request<Body>| async move {
+                let location = match req.uri().path() {
+                    "/spaces" => "  /next  ",
+                    "/tab" => "/next\tx",
+                    _ => return Ok::<_, Infallible>(Response::new(0)),
+                };
+                Ok(Response::builder()
+                    .status(StatusCode::FOUND)
+                    .header(LOCATION, location)
+                    .body(1)
+                    .unwrap())
+            });
+
+        let req = Request::builder()
+            .uri("http://example.com/spaces")
+            .body(Body::empty())
+            .unwrap();
+        let res = svc.oneshot(req).await.unwrap();
+        assert_eq!(*res.body(), 0);
+        assert_eq!(
+            res.extensions().get::<RequestUri>().unwrap().0,
+            "http://example.com/next"
+        );
+
+        let req = Request::builder()
+            .uri("http://example.com/tab")
+            .body(Body::empty())
+            .unwrap();
+        let res = svc.oneshot(req).await.unwrap();
+        assert_eq!(res.status(), StatusCode::FOUND);
+        assert_eq!(*res.body(), 1);
+    }
+
+    #[tokio::test]
+    async fn fragments() {
+        let svc = ServiceBuilder::new()
+            .layer(FollowRedirectLayer::with_policy(Action::Follow))
+            .service_fn(|req: Request<Body>| async move {
+                let location = match req.uri().path() {
+                    "/own" => "/next#own",
+                    "/none" => "/next",
+                    _ => {
+                        // the redirected request carries the fragment too
+                        let fragment = req.extensions().get::<RequestFragment>().cloned();
+                        let mut res = Response::new(0);
+                        res.extensions_mut().insert(fragment);
+                        return Ok::<_, Infallible>(res);
+                    }
+                };
+                Ok(Response::builder()
+                    .status(StatusCode::FOUND)
+                    .header(LOCATION, location)
+                    .body(1)
+                    .unwrap())
+            });
+
+        let cases = [
+            // the fragment of the `Location` value is kept
+            ("/own", Some("section"), Some("own")),
+            ("/own", None, Some("own")),
+            // the fragment of the original request is inherited
+            ("/none", Some("section"), Some("section")),
+            ("/none", None, None),
+        ];
+        for (path, fragment, expected) in cases {
+            let mut req = Request::builder()
+                .uri(format!("http://example.com{}", path))
+                .body(Body::empty())
+                .unwrap();
+            if let Some(fragment) = fragment {
+                req.extensions_mut()
+                    .insert(RequestFragment(fragment.to_owned()));
+            }
+            let res = svc.oneshot(req).await.unwrap();
+            assert_eq!(*res.body(), 0);
+            let expected = expected.map(|fragment| RequestFragment(fragment.to_owned()));
+            assert_eq!(res.extensions().get::<RequestFragment>(), expected.as_ref());
+            assert_eq!(
+                res.extensions().get::<Option<RequestFragment>>(),
+                Some(&expected)
+            );
+        }
+    }
+
+    #[test]
+    fn location_fragments() {
+        let fragment = |loc| location_fragment(&HeaderValue::from_static(loc)).map(str::to_owned);
+        assert_eq!(fragment(" /new#other ").as_deref(), Some("other"));
+        assert_eq!(fragment("#other").as_deref(), Some("other"));
+        assert_eq!(fragment("/new"), None);
+        // `Uri` never holds a fragment
+        let base = Uri::from_static("http://example.com/old#section");
+        assert_eq!(
+            resolve_uri("/new#other", &base).unwrap(),
+            "http://example.com/new"
+        );
+    }
+
+    #[test]
+    fn rejects_control_characters_in_location() {
+        let base = Uri::from_static("http://example.com/");
+        assert_eq!(
+            resolve_location(" \t/next\r\n", &base).unwrap(),
+            "http://example.com/next"
+        );
+        // `HeaderValue` already refuses line breaks, but `resolve_location` doesn't rely on it
+        assert!(resolve_location("/next\r\nSet-Cookie: x", &base).is_none());
+        assert!(resolve_location("/next\u{0}", &base).is_none());
+        assert!(resolve_location("/next\u{85}x", &base).is_none());
+    }
+
+    #[tokio::test]
+    async fn shared_allow_list() {
+        let allow_list = SharedAllowList::new(Vec::<String>::new());
+        let handle = allow_list.handle();
+        let svc = FollowRedirect::with_policy(
+            tower::service_fn(|req: Request<Body>| async move {
+                if req.uri().host() == Some("other.example") {
+                    return Ok::<_, Infallible>(Response::new(0));
+                }
+                Ok(Response::builder()
+                    .status(StatusCode::FOUND)
+                    .header(LOCATION, "http://other.example/")
+                    .body(1)
+                    .unwrap())
+            }),
+            allow_list,
+        );
+        let request = || {
+            Request::builder()
+                .uri("http://example.com/")
+                .body(Body::empty())
+                .unwrap()
+        };
+
+        let res = svc.clone().oneshot(request()).await.unwrap();
+        assert_eq!(res.status(), StatusCode::FOUND);
+
+        handle.insert("other.example");
+        let res = svc.oneshot(request()).await.unwrap();
+        assert_eq!(res.status(), StatusCode::OK);
+        assert_eq!(res.extensions().get(), Some(&RedirectCount(1)));
+    }
+
+    #[cfg(feature = "follow-redirect-time")]
+    #[tokio::test]
+    async fn remote_allow_list() {
+        let lookups = Arc::new(Mutex::new(Vec::new()));
+        let lookup = {
+            let lookups = lookups.clone();
+            tower::service_fn(move |host: String| {
+                lookups.lock().unwrap().push(host.clone());
+                async move {
+                    match &*host {
+                        "allowed.example" => Ok(true),
+                        "denied.example" => Ok(false),
+                        _ => Err("lookup failed"),
+                    }
+                }
+            })
+        };
+        let svc = FollowRedirect::with_policy(
+            tower::service_fn(|req: Request<Body>| async move {
+                match req.uri().path() {
+                    "/" => Ok::<_, Infallible>(Response::new(0)),
+                    path => Ok(Response::builder()
+                        .status(StatusCode::FOUND)
+                        .header(LOCATION, format!("http:/{}", path))
+                        .body(1)
+                        .unwrap()),
+                }
+            }),
+            RemoteAllowList::new(lookup, 16, Duration::from_secs(60)),
+        );
+        let request = |path| {
+            Request::builder()
+                .uri(format!("http://example.com{}", path))
+                .body(Body::empty())
+                .unwrap()
+        };
+
+        let res = svc
+            .clone()
+            .oneshot(request("/allowed.example/"))
+            .await
+            .unwrap();
+        assert_eq!(res.status(), StatusCode::OK);
+        let res = svc
+            .clone()
+            .oneshot(request("/denied.example/"))
+            .await
+            .unwrap();
+        assert_eq!(res.status(), StatusCode::FOUND);
+        let res = svc
+            .clone()
+            .oneshot(request("/other.example/"))
+            .await
+            .unwrap();
+        assert_eq!(res.status(), StatusCode::FOUND);
+        assert_eq!(
+            *lookups.lock().unwrap(),
+            ["allowed.example", "denied.example", "other.example"]
+        );
+
+        // served from the cache
+        let res = svc
+            .clone()
+            .oneshot(request("/allowed.example/"))
+            .await
+            .unwrap();
+        assert_eq!(res.status(), StatusCode::OK);
+        let res = svc
+            .clone()
+            .oneshot(request("/DENIED.example/"))
+            .await
+            .unwrap();
+        assert_eq!(res.status(), StatusCode::FOUND);
+        assert_eq!(lookups.lock().unwrap().len(), 3);
+
+        // errors aren't cached
+        let res = svc.oneshot(request("/other.example/")).await.unwrap();
+        assert_eq!(res.status(), StatusCode::FOUND);
+        assert_eq!(lookups.lock().unwrap().len(), 4);
+    }
+
+    #[tokio::test]
+    async fn rewrite_post_to_get() {
+        use bytes::Bytes;
+        use http_body_util::{BodyExt, Full};
+
+        async fn post<P>(policy: P) -> Response<String>
+        where
+            P: Policy<Full<Bytes>, Infallible> + Clone,
+        {
+            let svc = FollowRedirect::with_policy(
+                tower::service_fn(|req: Request<Full<Bytes>>| async move {
+                    if req.uri().path() == "/old" {
+                        return Ok::<_, Infallible>(
+                            Response::builder()
+                                .status(StatusCode::FOUND)
+                                .header(LOCATION, "/new")
+                                .body(String::new())
+                                .unwrap(),
+                        );
+                    }
+                    let content_length = req
+                        .headers()
+                        .get(CONTENT_LENGTH)
+                        .map_or("none", |value| value.to_str().unwrap())
+                        .to_owned();
+                    let method = req.method().clone();
+                    let body = req.into_body().collect().await.unwrap().to_bytes();
+                    Ok(Response::new(format!(
+                        "{} {} {:?}",
+                        method, content_length, body
+                    )))
+                }),
+                policy,
+            );
+            let req = Request::builder()
+                .method(Method::POST)
+                .uri("http://example.com/old")
+                .header(CONTENT_LENGTH, "4")
+                .body(Full::from("data"))
+                .unwrap();
+            svc.oneshot(req).await.unwrap()
+        }
+
+        let res = post(Standard::default()).await;
+        assert_eq!(res.status(), StatusCode::OK);
+        assert_eq!(res.body(), "GET none b\"\"");
+
+        let policy = Standard::default().rewrite_post_to_get(false);
+        // `Standard` doesn't clone the body, so the redirection is returned as-is
+        let res = post(policy.clone()).await;
+        assert_eq!(res.status(), StatusCode::FOUND);
+
+        let policy =
+            policy.and::<_, Full<Bytes>, Infallible>(clone_body_fn(|body: &Full<Bytes>| {
+                Some(body.clone())
+            }));
+        let res = post(policy).await;
+        assert_eq!(res.status(), StatusCode::OK);
+        assert_eq!(res.body(), "POST 4 b\"data\"");
+    }
+
+    #[tokio::test]
+    async fn does_not_retry_without_retry_policy() {
+        let svc = ServiceBuilder::new()
+            .layer(FollowRedirectLayer::with_policy(Action::Follow))
+            .service_fn(|req: Request<Body>| async move {
+                if req.uri().path() == "/0" {
+                    return Err("connection reset");
+                }
+                Ok(handle(req).await.unwrap())
+            });
+        let req = Request::builder()
+            .uri("http://example.com/1")
+            .body(Body::empty())
+            .unwrap();
+        assert_eq!(svc.oneshot(req).await.unwrap_err(), "connection reset");
+    }
+
     /// A server with an endpoint `GET /{n}` which redirects to `/{n-1}` unless `n` equals zero,
     /// returning `n` as the response body.
     async fn handle<B>(req: Request<B>) -> Result<Response<u64>, Infallible> {
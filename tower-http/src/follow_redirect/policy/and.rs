@@ -1,13 +1,14 @@
 use super::{Action, Attempt, Policy};
-use http::Request;
+use http::{HeaderMap, HeaderValue, Method, Request, StatusCode};
+use std::task::{ready, Context, Poll};
 
 /// A redirection [`Policy`] that combines the results of two `Policy`s.
 ///
 /// See [`PolicyExt::and`][super::PolicyExt::and] for more details.
 #[derive(Clone, Copy, Debug, Default)]
 pub struct And<A, B> {
-    a: A,
-    b: B,
+    pub(super) a: A,
+    pub(super) b: B,
 }
 
 impl<A, B> And<A, B> {
@@ -32,6 +33,11 @@ where
         }
     }
 
+    fn poll_ready(&mut self, cx: &mut Context<'_>, attempt: &Attempt<'_>) -> Poll<()> {
+        ready!(self.a.poll_ready(cx, attempt));
+        self.b.poll_ready(cx, attempt)
+    }
+
     fn on_request(&mut self, request: &mut Request<Bd>) {
         self.a.on_request(request);
         self.b.on_request(request);
@@ -40,12 +46,37 @@ where
     fn clone_body(&self, body: &Bd) -> Option<Bd> {
         self.a.clone_body(body).or_else(|| self.b.clone_body(body))
     }
+
+    fn on_error(&mut self, error: &E) {
+        self.a.on_error(error);
+        self.b.on_error(error);
+    }
+
+    fn retry_on_error(&mut self, error: &E) -> bool {
+        self.a.retry_on_error(error) || self.b.retry_on_error(error)
+    }
+
+    fn alternative_location(
+        &mut self,
+        status: StatusCode,
+        headers: &HeaderMap,
+    ) -> Option<HeaderValue> {
+        self.a
+            .alternative_location(status, headers)
+            .or_else(|| self.b.alternative_location(status, headers))
+    }
+
+    fn rewrite_method(&self, status: StatusCode, method: &Method) -> Option<Method> {
+        self.a
+            .rewrite_method(status, method)
+            .or_else(|| self.b.rewrite_method(status, method))
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use http::Uri;
+    use http::{Extensions, HeaderValue, Method, Uri};
 
     struct Taint<P> {
         policy: P,
@@ -75,7 +106,11 @@ mod tests {
     fn redirect() {
         let attempt = Attempt {
             status: Default::default(),
+            method: &Method::GET,
             location: &Uri::from_static("*"),
+            raw_location: &HeaderValue::from_static("*"),
+            headers: &HeaderMap::new(),
+            extensions: &Extensions::new(),
             previous: &Uri::from_static("*"),
         };
 
@@ -115,4 +150,37 @@ mod tests {
         assert!(a.used);
         assert!(!b.used);
     }
+
+    #[test]
+    fn redirect_error() {
+        let attempt = Attempt {
+            status: Default::default(),
+            method: &Method::GET,
+            location: &Uri::from_static("*"),
+            raw_location: &HeaderValue::from_static("*"),
+            headers: &HeaderMap::new(),
+            extensions: &Extensions::new(),
+            previous: &Uri::from_static("*"),
+        };
+
+        let mut a = Taint::new(Err::<Action, _>("error"));
+        let mut b = Taint::new(Action::Follow);
+        let mut policy = And::new::<(), &str>(&mut a, &mut b);
+        assert_eq!(
+            Policy::<(), &str>::redirect(&mut policy, &attempt).unwrap_err(),
+            "error"
+        );
+        assert!(a.used);
+        assert!(!b.used); // short-circuiting
+
+        let mut a = Taint::new(Action::Follow);
+        let mut b = Taint::new(Err::<Action, _>("error"));
+        let mut policy = And::new::<(), &str>(&mut a, &mut b);
+        assert_eq!(
+            Policy::<(), &str>::redirect(&mut policy, &attempt).unwrap_err(),
+            "error"
+        );
+        assert!(a.used);
+        assert!(b.used);
+    }
 }
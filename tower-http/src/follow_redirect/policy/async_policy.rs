@@ -0,0 +1,149 @@
+use super::{Action, Attempt, Policy};
+use http::{HeaderMap, HeaderValue, Method, Request, StatusCode};
+use std::task::{ready, Context, Poll};
+
+/// Trait for policies on handling redirection responses that need asynchronous work to decide
+/// whether to follow a redirection.
+///
+/// This is what a [`FollowRedirect`][super::super::FollowRedirect] middleware requires of its
+/// policy. Every [`Policy`] is an `AsyncPolicy` that decides immediately, so this trait only needs
+/// to be implemented by policies that for example consult a cache or a rate limiter. The
+/// combinators of [`PolicyExt`][super::PolicyExt] combine `Policy`s only.
+///
+/// Because of the implementation for every `Policy`, an implementation of this trait has to name
+/// the request body type `B` and the error type `E`, rather than being generic over them. A policy
+/// that is generic over them can implement [`Policy::poll_ready`] instead, which the
+/// implementation for every `Policy` waits for before deciding, like
+/// [`RemoteAllowList`][super::RemoteAllowList] does.
+///
+/// # Example
+///
+/// Asking another task whether to follow redirections:
+///
+/// ```
+/// use bytes::Bytes;
+/// use http_body_util::Full;
+/// use std::task::{ready, Context, Poll};
+/// use tokio::sync::mpsc;
+/// use tower_http::{
+///     follow_redirect::policy::{Action, AsyncPolicy, Attempt},
+///     BoxError,
+/// };
+///
+/// pub struct AskOtherTask {
+///     decisions: mpsc::Receiver<Action>,
+/// }
+///
+/// impl AsyncPolicy<Full<Bytes>, BoxError> for AskOtherTask {
+///     fn poll_redirect(
+///         &mut self,
+///         cx: &mut Context<'_>,
+///         _attempt: &Attempt<'_>,
+///     ) -> Poll<Result<Action, BoxError>> {
+///         match ready!(self.decisions.poll_recv(cx)) {
+///             Some(action) => Poll::Ready(Ok(action)),
+///             None => Poll::Ready(Err("the other task stopped".into())),
+///         }
+///     }
+/// }
+/// ```
+pub trait AsyncPolicy<B, E> {
+    /// Invoked when the service received a response with a redirection status code (`3xx`), or
+    /// a location from [`alternative_location`][AsyncPolicy::alternative_location].
+    ///
+    /// This method returns [`Poll::Pending`] until it has decided whether the service should follow
+    /// the redirection, and then the [`Action`] to take. As with futures, the policy must arrange
+    /// for `cx` to be woken up when it returns `Poll::Pending`. The `attempt` passed when polling
+    /// again is the same.
+    ///
+    /// See [`Policy::redirect`] for more details.
+    fn poll_redirect(
+        &mut self,
+        cx: &mut Context<'_>,
+        attempt: &Attempt<'_>,
+    ) -> Poll<Result<Action, E>>;
+
+    /// See [`Policy::on_request`].
+    ///
+    /// The default implementation does nothing.
+    fn on_request(&mut self, _request: &mut Request<B>) {}
+
+    /// See [`Policy::clone_body`].
+    ///
+    /// The default implementation returns `None`.
+    fn clone_body(&self, _body: &B) -> Option<B> {
+        None
+    }
+
+    /// See [`Policy::on_error`].
+    ///
+    /// The default implementation does nothing.
+    fn on_error(&mut self, _error: &E) {}
+
+    /// See [`Policy::retry_on_error`].
+    ///
+    /// The default implementation returns `false`.
+    fn retry_on_error(&mut self, _error: &E) -> bool {
+        false
+    }
+
+    /// See [`Policy::alternative_location`].
+    ///
+    /// The default implementation returns `None`.
+    fn alternative_location(
+        &mut self,
+        _status: StatusCode,
+        _headers: &HeaderMap,
+    ) -> Option<HeaderValue> {
+        None
+    }
+
+    /// See [`Policy::rewrite_method`].
+    ///
+    /// The default implementation returns `None`.
+    fn rewrite_method(&self, _status: StatusCode, _method: &Method) -> Option<Method> {
+        None
+    }
+}
+
+impl<B, E, P> AsyncPolicy<B, E> for P
+where
+    P: Policy<B, E>,
+{
+    fn poll_redirect(
+        &mut self,
+        cx: &mut Context<'_>,
+        attempt: &Attempt<'_>,
+    ) -> Poll<Result<Action, E>> {
+        ready!(Policy::poll_ready(self, cx, attempt));
+        Poll::Ready(self.redirect(attempt))
+    }
+
+    fn on_request(&mut self, request: &mut Request<B>) {
+        Policy::on_request(self, request)
+    }
+
+    fn clone_body(&self, body: &B) -> Option<B> {
+        Policy::clone_body(self, body)
+    }
+
+    fn on_error(&mut self, error: &E) {
+        Policy::on_error(self, error)
+    }
+
+    fn retry_on_error(&mut self, error: &E) -> bool {
+        Policy::retry_on_error(self, error)
+    }
+
+    fn alternative_location(
+        &mut self,
+        status: StatusCode,
+        headers: &HeaderMap,
+    ) -> Option<HeaderValue> {
+        Policy::alternative_location(self, status, headers)
+    }
+
+    fn rewrite_method(&self, status: StatusCode, method: &Method) -> Option<Method> {
+        Policy::rewrite_method(self, status, method)
+    }
+}
@@ -0,0 +1,85 @@
+use super::{Action, Attempt, Policy};
+use http::uri::Scheme;
+
+/// A redirection [`Policy`] that stops redirections from `https` to `http`.
+///
+/// Redirections that keep the scheme, and upgrades from `http` to `https`, are followed. Only the
+/// schemes of the previous request and of the location are compared, so this policy is cheap to
+/// combine with others. See [`SameScheme`][super::SameScheme] to also stop upgrades.
+///
+/// The policy stops following redirections with [`Action::Stop`], returning the redirection
+/// response. Combine it with an error using [`or`](super::PolicyExt::or) to fail instead.
+///
+/// # Example
+///
+/// ```
+/// use tower_http::follow_redirect::policy::{BlockDowngrade, Limited, PolicyExt};
+///
+/// let policy = BlockDowngrade::new().and::<_, (), ()>(Limited::default());
+/// ```
+///
+/// Returning an error for downgrades:
+///
+/// ```
+/// use tower_http::follow_redirect::policy::{BlockDowngrade, Limited, PolicyExt};
+///
+/// #[derive(Clone, Debug)]
+/// enum MyError {
+///     Downgrade,
+///     // ...
+/// }
+///
+/// let policy = BlockDowngrade::new()
+///     .or::<_, (), _>(Err(MyError::Downgrade))
+///     .and::<_, (), _>(Limited::default());
+/// ```
+#[derive(Clone, Copy, Debug, Default)]
+pub struct BlockDowngrade {
+    _priv: (),
+}
+
+impl BlockDowngrade {
+    /// Create a new [`BlockDowngrade`].
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl<B, E> Policy<B, E> for BlockDowngrade {
+    fn redirect(&mut self, attempt: &Attempt<'_>) -> Result<Action, E> {
+        if attempt.previous().scheme() == Some(&Scheme::HTTPS)
+            && attempt.location().scheme() == Some(&Scheme::HTTP)
+        {
+            Ok(Action::Stop)
+        } else {
+            Ok(Action::Follow)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use http::{Extensions, HeaderMap, HeaderValue, Method, StatusCode, Uri};
+
+    #[test]
+    fn works() {
+        let redirect = |previous: &'static str, location: &'static str| {
+            let attempt = Attempt {
+                status: StatusCode::FOUND,
+                method: &Method::GET,
+                location: &Uri::from_static(location),
+                raw_location: &HeaderValue::from_static(location),
+                headers: &HeaderMap::new(),
+                extensions: &Extensions::new(),
+                previous: &Uri::from_static(previous),
+            };
+            Policy::<(), ()>::redirect(&mut BlockDowngrade::new(), &attempt).unwrap()
+        };
+
+        assert!(redirect("https://example.com/", "http://example.com/").is_stop());
+        assert!(redirect("https://example.com/", "https://other.example/").is_follow());
+        assert!(redirect("http://example.com/", "http://other.example/").is_follow());
+        assert!(redirect("http://example.com/", "https://example.com/").is_follow());
+    }
+}
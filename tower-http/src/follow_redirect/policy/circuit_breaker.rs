@@ -0,0 +1,190 @@
+use super::{Action, Attempt, Policy};
+use http::{Request, Uri};
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+/// A redirection [`Policy`] that stops following redirections to hosts that keep failing.
+///
+/// Each host has a circuit breaker, shared by all clones of the policy, and thus by all requests
+/// made with a [`FollowRedirect`][crate::follow_redirect::FollowRedirect] middleware. The circuit
+/// of a host opens when `threshold` consecutive redirected requests to it fail with an error
+/// within `window`. While the circuit is open, redirections to the host are stopped with
+/// [`Action::Stop`], returning the redirection response.
+///
+/// Once `cooldown` has elapsed, the circuit is half-open: redirections to the host are followed
+/// again, but the next error opens the circuit again, while the next redirection response from
+/// the host closes it.
+///
+/// # Example
+///
+/// ```
+/// use std::time::Duration;
+/// use tower_http::follow_redirect::policy::{CircuitBreaker, Limited, PolicyExt};
+///
+/// // Stop following redirections to a host for 30 seconds after 5 errors within a minute.
+/// let policy = CircuitBreaker::new(5, Duration::from_secs(60), Duration::from_secs(30))
+///     .and::<_, (), ()>(Limited::default());
+/// ```
+#[derive(Clone, Debug)]
+pub struct CircuitBreaker {
+    threshold: u32,
+    window: Duration,
+    cooldown: Duration,
+    hosts: Arc<Mutex<HashMap<String, Circuit>>>,
+    // the host of the request in flight
+    current: Option<String>,
+}
+
+#[derive(Debug)]
+struct Circuit {
+    failures: u32,
+    window_start: Instant,
+    opened_at: Option<Instant>,
+}
+
+impl CircuitBreaker {
+    /// Create a new [`CircuitBreaker`] that opens the circuit of a host after `threshold`
+    /// consecutive errors within `window`, for `cooldown`.
+    pub fn new(threshold: u32, window: Duration, cooldown: Duration) -> Self {
+        CircuitBreaker {
+            threshold,
+            window,
+            cooldown,
+            hosts: Default::default(),
+            current: None,
+        }
+    }
+
+    fn is_open(&self, host: &str, now: Instant) -> bool {
+        let hosts = self.hosts.lock().unwrap();
+        hosts
+            .get(host)
+            .and_then(|circuit| circuit.opened_at)
+            .map_or(false, |opened_at| {
+                now.duration_since(opened_at) < self.cooldown
+            })
+    }
+
+    fn record_success(&self, host: &str) {
+        self.hosts.lock().unwrap().remove(host);
+    }
+
+    fn record_failure(&self, host: &str, now: Instant) {
+        let mut hosts = self.hosts.lock().unwrap();
+        let circuit = hosts.entry(host.to_owned()).or_insert(Circuit {
+            failures: 0,
+            window_start: now,
+            opened_at: None,
+        });
+
+        if circuit.opened_at.is_some() {
+            // failed while half-open
+            circuit.opened_at = Some(now);
+            return;
+        }
+
+        if now.duration_since(circuit.window_start) > self.window {
+            circuit.failures = 0;
+            circuit.window_start = now;
+        }
+        circuit.failures += 1;
+        if circuit.failures >= self.threshold {
+            circuit.opened_at = Some(now);
+        }
+    }
+}
+
+fn host(uri: &Uri) -> Option<String> {
+    uri.host().map(str::to_ascii_lowercase)
+}
+
+impl<B, E> Policy<B, E> for CircuitBreaker {
+    fn redirect(&mut self, attempt: &Attempt<'_>) -> Result<Action, E> {
+        if let Some(previous) = host(attempt.previous()) {
+            self.record_success(&previous);
+        }
+
+        match host(attempt.location()) {
+            Some(location) if self.is_open(&location, Instant::now()) => Ok(Action::Stop),
+            _ => Ok(Action::Follow),
+        }
+    }
+
+    fn on_request(&mut self, request: &mut Request<B>) {
+        self.current = host(request.uri());
+    }
+
+    fn on_error(&mut self, _error: &E) {
+        if let Some(current) = &self.current {
+            self.record_failure(current, Instant::now());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use http::{Extensions, HeaderMap, HeaderValue, Method, StatusCode};
+
+    fn redirect(policy: &mut CircuitBreaker, location: &str) -> Action {
+        let previous = Uri::from_static("http://up.example/");
+        let location = location.parse::<Uri>().unwrap();
+        let attempt = Attempt {
+            status: StatusCode::FOUND,
+            method: &Method::GET,
+            location: &location,
+            raw_location: &HeaderValue::from_static("/new"),
+            headers: &HeaderMap::new(),
+            extensions: &Extensions::new(),
+            previous: &previous,
+        };
+        Policy::<(), ()>::redirect(policy, &attempt).unwrap()
+    }
+
+    fn fail(policy: &CircuitBreaker, uri: &'static str) {
+        let mut policy = policy.clone();
+        let mut request = Request::builder().uri(uri).body(()).unwrap();
+        Policy::<(), ()>::on_request(&mut policy, &mut request);
+        Policy::<(), ()>::on_error(&mut policy, &());
+    }
+
+    #[test]
+    fn opens_after_threshold() {
+        let policy = CircuitBreaker::new(2, Duration::from_secs(60), Duration::from_secs(60));
+
+        fail(&policy, "http://down.example/");
+        assert!(redirect(&mut policy.clone(), "http://down.example/").is_follow());
+        fail(&policy, "http://DOWN.example/");
+        assert!(redirect(&mut policy.clone(), "http://down.example/a").is_stop());
+
+        // other hosts aren't affected
+        assert!(redirect(&mut policy.clone(), "http://up.example/").is_follow());
+    }
+
+    #[test]
+    fn half_opens_after_cooldown() {
+        let policy = CircuitBreaker::new(1, Duration::from_secs(60), Duration::ZERO);
+
+        fail(&policy, "http://down.example/");
+        assert!(redirect(&mut policy.clone(), "http://down.example/").is_follow());
+
+        // a redirection response from the host closes the circuit
+        let mut closing = policy.clone();
+        let attempt = Attempt {
+            status: StatusCode::FOUND,
+            method: &Method::GET,
+            location: &Uri::from_static("http://up.example/"),
+            raw_location: &HeaderValue::from_static("/new"),
+            headers: &HeaderMap::new(),
+            extensions: &Extensions::new(),
+            previous: &Uri::from_static("http://down.example/"),
+        };
+        assert!(Policy::<(), ()>::redirect(&mut closing, &attempt)
+            .unwrap()
+            .is_follow());
+        assert!(policy.hosts.lock().unwrap().is_empty());
+    }
+}
@@ -0,0 +1,414 @@
+use super::{Action, Attempt, Policy};
+use http::{
+    header::{COOKIE, SET_COOKIE},
+    HeaderValue, Request, Uri,
+};
+use std::time::{Duration, SystemTime};
+
+/// A redirection [`Policy`] that stores the cookies set by redirection responses and sends them
+/// with the following requests.
+///
+/// Cookies are scoped by their `Domain` and `Path` attributes as described in [RFC 6265], so they
+/// are only sent to the hosts and paths they are meant for, and cookies with the `Secure`
+/// attribute are only sent over `https`. Cookies for a domain that doesn't match the host of the
+/// response that set them are ignored. Public suffixes aren't known to the jar, so a host can set
+/// a cookie for its whole registrable domain and above, such as `Domain=co.uk` from
+/// `example.co.uk`.
+///
+/// The jar only lives as long as a single request and its redirections, so cookies are never
+/// shared between unrelated requests. The `Cookie` header of the original request is kept, and
+/// the cookies of the jar are appended to it.
+///
+/// To bound the memory used by a redirection chain that sets many cookies, the jar holds at most
+/// [`max_cookies`](Self::max_cookies) cookies whose names and values have a total length of at
+/// most [`max_total_bytes`](Self::max_total_bytes) bytes. The oldest cookies are evicted to make
+/// room for new ones, and cookies larger than the whole jar are ignored.
+///
+/// This policy always follows redirections, so combine it with another policy such as
+/// [`Limited`](super::Limited).
+///
+/// # Example
+///
+/// ```
+/// use tower_http::follow_redirect::policy::{CookieJar, Limited, PolicyExt};
+///
+/// let policy = CookieJar::new().and::<_, (), ()>(Limited::default());
+/// ```
+///
+/// [RFC 6265]: https://www.rfc-editor.org/rfc/rfc6265
+#[derive(Clone, Debug)]
+pub struct CookieJar {
+    // oldest first
+    cookies: Vec<Cookie>,
+    max_cookies: usize,
+    max_total_bytes: usize,
+}
+
+#[derive(Clone, Debug)]
+struct Cookie {
+    name: String,
+    value: String,
+    domain: String,
+    host_only: bool,
+    path: String,
+    secure: bool,
+    expires: Option<SystemTime>,
+}
+
+impl Cookie {
+    fn size(&self) -> usize {
+        self.name.len() + self.value.len()
+    }
+
+    fn is_expired(&self, now: SystemTime) -> bool {
+        self.expires.map_or(false, |expires| expires <= now)
+    }
+
+    fn matches(&self, uri: &Uri, now: SystemTime) -> bool {
+        let host = match uri.host() {
+            Some(host) => host.to_ascii_lowercase(),
+            None => return false,
+        };
+        let domain_matches = if self.host_only {
+            host == self.domain
+        } else {
+            domain_matches(&host, &self.domain)
+        };
+
+        domain_matches
+            && path_matches(uri.path(), &self.path)
+            && (!self.secure || uri.scheme_str() == Some("https"))
+            && !self.is_expired(now)
+    }
+}
+
+impl Default for CookieJar {
+    fn default() -> Self {
+        CookieJar {
+            cookies: Vec::new(),
+            max_cookies: 50,
+            max_total_bytes: 64 * 1024,
+        }
+    }
+}
+
+impl CookieJar {
+    /// Create a new, empty [`CookieJar`].
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the maximum number of cookies in the jar.
+    ///
+    /// Defaults to 50.
+    pub fn max_cookies(mut self, max_cookies: usize) -> Self {
+        self.max_cookies = max_cookies;
+        self
+    }
+
+    /// Set the maximum total length of the names and values of the cookies in the jar.
+    ///
+    /// Defaults to 64 KiB.
+    pub fn max_total_bytes(mut self, max_total_bytes: usize) -> Self {
+        self.max_total_bytes = max_total_bytes;
+        self
+    }
+
+    fn store(&mut self, cookie: Cookie, now: SystemTime) {
+        // a cookie replaces the one with the same name, domain and path
+        self.cookies.retain(|stored| {
+            let replaced = stored.name == cookie.name
+                && stored.domain == cookie.domain
+                && stored.path == cookie.path;
+            !replaced && !stored.is_expired(now)
+        });
+
+        if cookie.is_expired(now) || self.max_cookies == 0 || cookie.size() > self.max_total_bytes {
+            return;
+        }
+
+        let mut total: usize = self.cookies.iter().map(Cookie::size).sum();
+        while !self.cookies.is_empty()
+            && (self.cookies.len() >= self.max_cookies
+                || total + cookie.size() > self.max_total_bytes)
+        {
+            total -= self.cookies.remove(0).size();
+        }
+        self.cookies.push(cookie);
+    }
+
+    fn cookie_header(&self, uri: &Uri) -> Option<String> {
+        let now = SystemTime::now();
+        let mut cookies = self
+            .cookies
+            .iter()
+            .filter(|cookie| cookie.matches(uri, now))
+            .collect::<Vec<_>>();
+        // cookies with longer paths are listed first (RFC 6265 section 5.4)
+        cookies.sort_by_key(|cookie| std::cmp::Reverse(cookie.path.len()));
+
+        let header = cookies
+            .iter()
+            .map(|cookie| format!("{}={}", cookie.name, cookie.value))
+            .collect::<Vec<_>>()
+            .join("; ");
+        (!header.is_empty()).then_some(header)
+    }
+}
+
+impl<B, E> Policy<B, E> for CookieJar {
+    fn redirect(&mut self, attempt: &Attempt<'_>) -> Result<Action, E> {
+        let now = SystemTime::now();
+        for set_cookie in attempt.headers().get_all(SET_COOKIE) {
+            if let Some(cookie) = parse(set_cookie, attempt.previous(), now) {
+                self.store(cookie, now);
+            }
+        }
+        Ok(Action::Follow)
+    }
+
+    fn on_request(&mut self, request: &mut Request<B>) {
+        let cookies = match self.cookie_header(request.uri()) {
+            Some(cookies) => cookies,
+            None => return,
+        };
+        let header = match request
+            .headers()
+            .get(COOKIE)
+            .and_then(|existing| existing.to_str().ok())
+        {
+            Some(existing) => format!("{}; {}", existing, cookies),
+            None => cookies,
+        };
+        if let Ok(header) = HeaderValue::from_str(&header) {
+            request.headers_mut().insert(COOKIE, header);
+        }
+    }
+}
+
+// Parses a `Set-Cookie` header received in the response to a request to `uri`
+// (RFC 6265 section 5.2).
+fn parse(set_cookie: &HeaderValue, uri: &Uri, now: SystemTime) -> Option<Cookie> {
+    let host = uri.host()?.to_ascii_lowercase();
+    let mut parts = set_cookie.to_str().ok()?.split(';');
+
+    let (name, value) = parts.next()?.split_once('=')?;
+    let (name, value) = (name.trim(), value.trim());
+    if name.is_empty() {
+        return None;
+    }
+
+    let mut cookie = Cookie {
+        name: name.to_owned(),
+        value: value.to_owned(),
+        domain: host.clone(),
+        host_only: true,
+        path: default_path(uri.path()).to_owned(),
+        secure: false,
+        expires: None,
+    };
+    let mut max_age = None;
+    for attribute in parts {
+        let (key, value) = attribute.split_once('=').unwrap_or((attribute, ""));
+        let (key, value) = (key.trim(), value.trim());
+        if key.eq_ignore_ascii_case("domain") {
+            let domain = value.trim_start_matches('.').to_ascii_lowercase();
+            if !domain.is_empty() {
+                if !domain_matches(&host, &domain) {
+                    return None;
+                }
+                cookie.domain = domain;
+                cookie.host_only = false;
+            }
+        } else if key.eq_ignore_ascii_case("path") {
+            if value.starts_with('/') {
+                cookie.path = value.to_owned();
+            }
+        } else if key.eq_ignore_ascii_case("secure") {
+            cookie.secure = true;
+        } else if key.eq_ignore_ascii_case("max-age") {
+            if let Ok(seconds) = value.parse::<i64>() {
+                max_age = Some(seconds);
+            }
+        } else if key.eq_ignore_ascii_case("expires") {
+            if let Ok(expires) = httpdate::parse_http_date(value) {
+                cookie.expires = Some(expires);
+            }
+        }
+    }
+
+    // `Max-Age` takes precedence over `Expires`
+    if let Some(seconds) = max_age {
+        cookie.expires = if seconds > 0 {
+            now.checked_add(Duration::from_secs(seconds as u64))
+        } else {
+            Some(SystemTime::UNIX_EPOCH)
+        };
+    }
+
+    Some(cookie)
+}
+
+// RFC 6265 section 5.1.3
+fn domain_matches(host: &str, domain: &str) -> bool {
+    if host == domain {
+        return true;
+    }
+    host.len() > domain.len()
+        && host.ends_with(domain)
+        && host.as_bytes()[host.len() - domain.len() - 1] == b'.'
+        && host.parse::<std::net::IpAddr>().is_err()
+}
+
+// RFC 6265 section 5.1.4
+fn default_path(path: &str) -> &str {
+    match path.rfind('/') {
+        Some(0) | None => "/",
+        Some(i) => &path[..i],
+    }
+}
+
+// RFC 6265 section 5.1.4
+fn path_matches(path: &str, cookie_path: &str) -> bool {
+    path == cookie_path
+        || (path.starts_with(cookie_path)
+            && (cookie_path.ends_with('/') || path.as_bytes()[cookie_path.len()] == b'/'))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use http::{Extensions, HeaderMap, Method, StatusCode};
+
+    fn redirect(jar: &mut CookieJar, previous: &'static str, set_cookies: &[&'static str]) {
+        let mut headers = HeaderMap::new();
+        for set_cookie in set_cookies {
+            headers.append(SET_COOKIE, HeaderValue::from_static(set_cookie));
+        }
+        let attempt = Attempt {
+            status: StatusCode::FOUND,
+            method: &Method::GET,
+            location: &Uri::from_static("/"),
+            raw_location: &HeaderValue::from_static("/"),
+            headers: &headers,
+            extensions: &Extensions::new(),
+            previous: &Uri::from_static(previous),
+        };
+        Policy::<(), ()>::redirect(jar, &attempt).unwrap();
+    }
+
+    fn cookies(jar: &mut CookieJar, uri: &'static str) -> Option<String> {
+        let mut request = Request::builder().uri(uri).body(()).unwrap();
+        Policy::<(), ()>::on_request(jar, &mut request);
+        request
+            .headers()
+            .get(COOKIE)
+            .map(|cookie| cookie.to_str().unwrap().to_owned())
+    }
+
+    #[test]
+    fn scopes_host_only_cookies() {
+        let mut jar = CookieJar::new();
+        redirect(&mut jar, "http://example.com/login", &["session=abc"]);
+
+        assert_eq!(
+            cookies(&mut jar, "http://example.com/home").unwrap(),
+            "session=abc"
+        );
+        assert_eq!(cookies(&mut jar, "http://sub.example.com/"), None);
+        assert_eq!(cookies(&mut jar, "http://other.example/"), None);
+    }
+
+    #[test]
+    fn scopes_domain_cookies() {
+        let mut jar = CookieJar::new();
+        redirect(
+            &mut jar,
+            "http://a.example.com/",
+            &[
+                "wide=1; Domain=.example.com",
+                "evil=1; Domain=other.example",
+            ],
+        );
+
+        assert_eq!(
+            cookies(&mut jar, "http://b.example.com/").unwrap(),
+            "wide=1"
+        );
+        assert_eq!(cookies(&mut jar, "http://example.com/").unwrap(), "wide=1");
+        assert_eq!(cookies(&mut jar, "http://badexample.com/"), None);
+        assert_eq!(cookies(&mut jar, "http://other.example/"), None);
+    }
+
+    #[test]
+    fn scopes_paths_and_secure_cookies() {
+        let mut jar = CookieJar::new();
+        redirect(
+            &mut jar,
+            "https://example.com/app/login",
+            &["default=1", "root=1; Path=/", "secure=1; Path=/; Secure"],
+        );
+
+        assert_eq!(
+            cookies(&mut jar, "https://example.com/app/home").unwrap(),
+            "default=1; root=1; secure=1"
+        );
+        assert_eq!(
+            cookies(&mut jar, "http://example.com/application").unwrap(),
+            "root=1"
+        );
+    }
+
+    #[test]
+    fn replaces_and_expires_cookies() {
+        let mut jar = CookieJar::new();
+        redirect(&mut jar, "http://example.com/", &["a=1", "b=1"]);
+        redirect(&mut jar, "http://example.com/", &["a=2", "b=; Max-Age=0"]);
+        assert_eq!(cookies(&mut jar, "http://example.com/").unwrap(), "a=2");
+
+        redirect(
+            &mut jar,
+            "http://example.com/",
+            &["a=; Expires=Thu, 01 Jan 1970 00:00:00 GMT"],
+        );
+        assert_eq!(cookies(&mut jar, "http://example.com/"), None);
+    }
+
+    #[test]
+    fn keeps_existing_cookie_header() {
+        let mut jar = CookieJar::new();
+        redirect(&mut jar, "http://example.com/", &["a=1"]);
+
+        let mut request = Request::builder()
+            .uri("http://example.com/")
+            .header(COOKIE, "user=1")
+            .body(())
+            .unwrap();
+        Policy::<(), ()>::on_request(&mut jar, &mut request);
+        assert_eq!(request.headers()[COOKIE], "user=1; a=1");
+    }
+
+    #[test]
+    fn caps_number_of_cookies() {
+        let mut jar = CookieJar::new().max_cookies(2);
+        redirect(&mut jar, "http://example.com/", &["a=1", "b=1", "c=1"]);
+        redirect(&mut jar, "http://example.com/", &["d=1"]);
+
+        assert_eq!(jar.cookies.len(), 2);
+        assert_eq!(
+            cookies(&mut jar, "http://example.com/").unwrap(),
+            "c=1; d=1"
+        );
+    }
+
+    #[test]
+    fn caps_size_of_cookies() {
+        let mut jar = CookieJar::new().max_total_bytes(9);
+        redirect(&mut jar, "http://example.com/", &["a=1234", "b=1234"]);
+        assert_eq!(cookies(&mut jar, "http://example.com/").unwrap(), "b=1234");
+
+        // larger than the whole jar
+        redirect(&mut jar, "http://example.com/", &["c=1234567890"]);
+        assert_eq!(cookies(&mut jar, "http://example.com/").unwrap(), "b=1234");
+    }
+}
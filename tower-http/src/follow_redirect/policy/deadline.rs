@@ -0,0 +1,101 @@
+use super::{Action, Attempt, Or, Policy};
+use http::Request;
+use std::time::Duration;
+use tokio::time::Instant;
+
+/// A redirection [`Policy`] that stops following redirections once a chain of redirections has
+/// taken longer than a budget.
+///
+/// A timeout on the inner service bounds the time of each request, but a chain of slow
+/// redirections can still take many times as long. [`Deadline`] bounds the wall-clock time of the
+/// whole chain, from the initial request to the last redirection response, complementing the
+/// number of redirections bounded by [`Limited`](super::Limited).
+///
+/// Once the budget is exceeded, redirections are stopped with [`Action::Stop`], and the last
+/// redirection response is returned as-is. Use [`Deadline::or_error`] to fail instead. The request
+/// in flight is never cancelled.
+///
+/// The time is measured from the initial request of each chain of redirections.
+///
+/// # Example
+///
+/// ```
+/// use std::time::Duration;
+/// use tower_http::follow_redirect::policy::{Deadline, Limited, PolicyExt};
+///
+/// let policy = Deadline::new(Duration::from_secs(10)).and::<_, (), ()>(Limited::default());
+/// ```
+#[derive(Clone, Copy, Debug)]
+pub struct Deadline {
+    budget: Duration,
+    start: Option<Instant>,
+}
+
+impl Deadline {
+    /// Create a new [`Deadline`] that stops following redirections once `budget` has elapsed.
+    pub fn new(budget: Duration) -> Self {
+        Deadline {
+            budget,
+            start: None,
+        }
+    }
+
+    /// Returns a policy that fails with `error` instead of stopping once the budget is exceeded.
+    ///
+    /// See [`Limited::or_error`](super::Limited::or_error) for more details.
+    pub fn or_error<E>(self, error: E) -> Or<Self, Result<Action, E>>
+    where
+        E: Clone,
+    {
+        Or::new::<(), E>(self, Err(error))
+    }
+}
+
+impl<B, E> Policy<B, E> for Deadline {
+    fn redirect(&mut self, _: &Attempt<'_>) -> Result<Action, E> {
+        let start = *self.start.get_or_insert_with(Instant::now);
+        if start.elapsed() > self.budget {
+            Ok(Action::Stop)
+        } else {
+            Ok(Action::Follow)
+        }
+    }
+
+    fn on_request(&mut self, _: &mut Request<B>) {
+        self.start.get_or_insert_with(Instant::now);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use http::{Extensions, HeaderMap, HeaderValue, Method, StatusCode, Uri};
+
+    #[tokio::test(start_paused = true)]
+    async fn or_error() {
+        let uri = Uri::from_static("https://example.com/");
+        let attempt = Attempt {
+            status: StatusCode::FOUND,
+            method: &Method::GET,
+            location: &uri,
+            raw_location: &HeaderValue::from_static("https://example.com/"),
+            headers: &HeaderMap::new(),
+            extensions: &Extensions::new(),
+            previous: &uri,
+        };
+        let mut policy = Deadline::new(Duration::from_secs(5)).or_error("deadline exceeded");
+
+        let mut request = Request::builder().uri(uri.clone()).body(()).unwrap();
+        Policy::<(), _>::on_request(&mut policy, &mut request);
+        tokio::time::advance(Duration::from_secs(5)).await;
+        assert!(Policy::<(), _>::redirect(&mut policy, &attempt)
+            .unwrap()
+            .is_follow());
+
+        tokio::time::advance(Duration::from_millis(1)).await;
+        assert_eq!(
+            Policy::<(), _>::redirect(&mut policy, &attempt).unwrap_err(),
+            "deadline exceeded"
+        );
+    }
+}
@@ -0,0 +1,150 @@
+use super::{Action, Attempt, Policy};
+use http::{HeaderMap, HeaderValue, Method, Request, StatusCode};
+use std::{
+    fmt,
+    task::{Context, Poll},
+};
+
+/// A redirection [`Policy`] that fails with a [`RedirectError`] instead of returning a redirection
+/// response that the inner policy doesn't follow.
+///
+/// By default, a [`FollowRedirect`][super::super::FollowRedirect] middleware returns the last
+/// redirection response as-is when its policy stops following redirections, for example because
+/// the limit of a [`Limited`][super::Limited] policy was reached. Callers then have to check
+/// whether the final response is itself a redirection. With [`ErrorOnStop`], the middleware
+/// returns an error instead, converted from [`RedirectError::LimitReachedWhileRedirecting`].
+///
+/// Only responses with a `3xx` status code are turned into errors. Every [`Action::Stop`] of the
+/// inner policy is, so wrap only the policies that limit redirections, and combine the others
+/// outside of it.
+///
+/// # Example
+///
+/// ```
+/// use tower_http::follow_redirect::policy::{ErrorOnStop, Limited, PolicyExt, SameOrigin};
+///
+/// // Fail after 5 redirections, but return cross-origin redirection responses as-is.
+/// let policy = ErrorOnStop::new(Limited::new(5))
+///     .and::<_, (), tower_http::BoxError>(SameOrigin::new());
+/// ```
+#[derive(Clone, Copy, Debug)]
+pub struct ErrorOnStop<P> {
+    inner: P,
+}
+
+impl<P> ErrorOnStop<P> {
+    /// Create a new [`ErrorOnStop`] that fails when `inner` stops following redirections.
+    pub fn new(inner: P) -> Self {
+        ErrorOnStop { inner }
+    }
+}
+
+impl<P, B, E> Policy<B, E> for ErrorOnStop<P>
+where
+    P: Policy<B, E>,
+    E: From<RedirectError>,
+{
+    fn redirect(&mut self, attempt: &Attempt<'_>) -> Result<Action, E> {
+        match self.inner.redirect(attempt)? {
+            Action::Stop if attempt.status().is_redirection() => {
+                Err(RedirectError::LimitReachedWhileRedirecting.into())
+            }
+            action => Ok(action),
+        }
+    }
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>, attempt: &Attempt<'_>) -> Poll<()> {
+        self.inner.poll_ready(cx, attempt)
+    }
+
+    fn on_request(&mut self, request: &mut Request<B>) {
+        self.inner.on_request(request);
+    }
+
+    fn clone_body(&self, body: &B) -> Option<B> {
+        self.inner.clone_body(body)
+    }
+
+    fn on_error(&mut self, error: &E) {
+        self.inner.on_error(error);
+    }
+
+    fn retry_on_error(&mut self, error: &E) -> bool {
+        self.inner.retry_on_error(error)
+    }
+
+    fn alternative_location(
+        &mut self,
+        status: StatusCode,
+        headers: &HeaderMap,
+    ) -> Option<HeaderValue> {
+        self.inner.alternative_location(status, headers)
+    }
+
+    fn rewrite_method(&self, status: StatusCode, method: &Method) -> Option<Method> {
+        self.inner.rewrite_method(status, method)
+    }
+}
+
+/// Error returned by an [`ErrorOnStop`] policy.
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum RedirectError {
+    /// A redirection response wasn't followed because a limit of the policy was reached.
+    LimitReachedWhileRedirecting,
+}
+
+impl fmt::Display for RedirectError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RedirectError::LimitReachedWhileRedirecting => {
+                f.write_str("limit reached while following redirections")
+            }
+        }
+    }
+}
+
+impl std::error::Error for RedirectError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::follow_redirect::policy::Limited;
+    use http::{Extensions, Method, Uri};
+
+    #[test]
+    fn works() {
+        let mut policy = ErrorOnStop::new(Limited::new(1));
+        let uri = Uri::from_static("https://example.com/");
+
+        let mut attempt = Attempt {
+            status: StatusCode::FOUND,
+            method: &Method::GET,
+            location: &uri,
+            raw_location: &HeaderValue::from_static("https://example.com/"),
+            headers: &HeaderMap::new(),
+            extensions: &Extensions::new(),
+            previous: &uri,
+        };
+        assert!(
+            Policy::<(), crate::BoxError>::redirect(&mut policy, &attempt)
+                .unwrap()
+                .is_follow()
+        );
+
+        // responses that aren't redirections are still returned
+        attempt.status = StatusCode::OK;
+        assert!(
+            Policy::<(), crate::BoxError>::redirect(&mut policy, &attempt)
+                .unwrap()
+                .is_stop()
+        );
+
+        attempt.status = StatusCode::FOUND;
+        let err = Policy::<(), crate::BoxError>::redirect(&mut policy, &attempt).unwrap_err();
+        assert!(matches!(
+            err.downcast_ref::<RedirectError>(),
+            Some(RedirectError::LimitReachedWhileRedirecting)
+        ));
+    }
+}
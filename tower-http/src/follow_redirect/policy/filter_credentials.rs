@@ -106,7 +106,7 @@ impl<B, E> Policy<B, E> for FilterCredentials {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use http::Uri;
+    use http::{Extensions, HeaderMap, HeaderValue, Method, Uri};
 
     #[test]
     fn works() {
@@ -126,7 +126,11 @@ mod tests {
 
         let attempt = Attempt {
             status: Default::default(),
+            method: &Method::GET,
             location: &same_origin,
+            raw_location: &HeaderValue::from_static("http://example.com/new"),
+            headers: &HeaderMap::new(),
+            extensions: &Extensions::new(),
             previous: request.uri(),
         };
         assert!(Policy::<(), ()>::redirect(&mut policy, &attempt)
@@ -143,7 +147,11 @@ mod tests {
 
         let attempt = Attempt {
             status: Default::default(),
+            method: &Method::GET,
             location: &cross_origin,
+            raw_location: &HeaderValue::from_static("https://example.com/new"),
+            headers: &HeaderMap::new(),
+            extensions: &Extensions::new(),
             previous: request.uri(),
         };
         assert!(Policy::<(), ()>::redirect(&mut policy, &attempt)
@@ -0,0 +1,135 @@
+use super::{Action, Attempt, Policy};
+use http::{header::REFRESH, HeaderMap, HeaderValue, StatusCode};
+use std::time::Duration;
+
+/// A redirection [`Policy`] that follows `Refresh` headers of non-redirection responses.
+///
+/// Some servers redirect with a header like `Refresh: 0; url=/next` instead of a `3xx` status
+/// and a `Location` header. With this policy, a `Refresh` header with a delay of at most
+/// `max_delay` and a `url` is followed like a `303 See Other` redirection, even if the status of
+/// the response is `200 OK`. Refreshes without a `url` reload the same page and are ignored.
+///
+/// This policy follows every redirection, so it should be combined with other policies such as
+/// [`Limited`][super::Limited].
+///
+/// # Example
+///
+/// ```
+/// use std::time::Duration;
+/// use tower_http::follow_redirect::policy::{FollowRefresh, Limited, PolicyExt};
+///
+/// let policy = Limited::default().and::<_, (), ()>(FollowRefresh::new(Duration::ZERO));
+/// ```
+#[derive(Clone, Copy, Debug)]
+pub struct FollowRefresh {
+    max_delay: Duration,
+}
+
+impl FollowRefresh {
+    /// Create a new [`FollowRefresh`] that follows refreshes with a delay of at most
+    /// `max_delay`.
+    pub fn new(max_delay: Duration) -> Self {
+        FollowRefresh { max_delay }
+    }
+}
+
+impl<B, E> Policy<B, E> for FollowRefresh {
+    fn redirect(&mut self, _: &Attempt<'_>) -> Result<Action, E> {
+        Ok(Action::Follow)
+    }
+
+    fn alternative_location(
+        &mut self,
+        _status: StatusCode,
+        headers: &HeaderMap,
+    ) -> Option<HeaderValue> {
+        let (delay, url) = parse_refresh(headers.get(REFRESH)?.to_str().ok()?)?;
+        if delay > self.max_delay {
+            return None;
+        }
+        HeaderValue::from_str(url).ok()
+    }
+}
+
+// Parses a `Refresh` header value of the form `<delay>[; url=<url>]`, where the delay is in
+// seconds and may have a fractional part, and the url may be quoted.
+fn parse_refresh(value: &str) -> Option<(Duration, &str)> {
+    let value = value.trim();
+    let end = value.find([';', ',']).unwrap_or(value.len());
+    let (delay, rest) = value.split_at(end);
+
+    let delay = delay.trim();
+    let (seconds, fraction) = delay.split_once('.').unwrap_or((delay, ""));
+    let is_digits = |s: &str| s.bytes().all(|b| b.is_ascii_digit());
+    if seconds.is_empty() || !is_digits(seconds) || !is_digits(fraction) {
+        return None;
+    }
+    let delay = Duration::try_from_secs_f64(delay.parse().ok()?).ok()?;
+
+    let mut url = rest.get(1..).unwrap_or_default().trim();
+    if url
+        .get(..3)
+        .map_or(false, |prefix| prefix.eq_ignore_ascii_case("url"))
+    {
+        if let Some(after_eq) = url[3..].trim_start().strip_prefix('=') {
+            url = after_eq.trim_start();
+        }
+    }
+    for quote in ['"', '\''] {
+        if let Some(unquoted) = url.strip_prefix(quote) {
+            url = unquoted.split(quote).next().unwrap_or_default();
+            break;
+        }
+    }
+
+    if url.is_empty() {
+        None
+    } else {
+        Some((delay, url))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_refresh() {
+        assert_eq!(
+            parse_refresh("0; url=/next"),
+            Some((Duration::ZERO, "/next"))
+        );
+        assert_eq!(
+            parse_refresh("5;URL='https://example.com/'"),
+            Some((Duration::from_secs(5), "https://example.com/"))
+        );
+        assert_eq!(
+            parse_refresh(" 1.5 , url = \"/a b\" "),
+            Some((Duration::from_millis(1500), "/a b"))
+        );
+        assert_eq!(parse_refresh("0;/next"), Some((Duration::ZERO, "/next")));
+        assert_eq!(parse_refresh("0"), None);
+        assert_eq!(parse_refresh("0; url="), None);
+        assert_eq!(parse_refresh("soon; url=/next"), None);
+    }
+
+    #[test]
+    fn respects_max_delay() {
+        let mut policy = FollowRefresh::new(Duration::from_secs(1));
+        let location = |policy: &mut FollowRefresh, refresh: &'static str| {
+            let mut headers = HeaderMap::new();
+            headers.insert(REFRESH, HeaderValue::from_static(refresh));
+            Policy::<(), ()>::alternative_location(policy, StatusCode::OK, &headers)
+        };
+
+        assert_eq!(location(&mut policy, "1; url=/next").unwrap(), "/next");
+        assert!(location(&mut policy, "1.5; url=/next").is_none());
+        assert!(location(&mut policy, "30; url=/next").is_none());
+        assert!(Policy::<(), ()>::alternative_location(
+            &mut policy,
+            StatusCode::OK,
+            &HeaderMap::new()
+        )
+        .is_none());
+    }
+}
@@ -0,0 +1,227 @@
+use super::{Action, Attempt, Policy};
+use http::{
+    uri::{Authority, Scheme},
+    Uri,
+};
+use std::{fmt, sync::Arc};
+
+/// A redirection [`Policy`] that only follows redirections to some hosts.
+///
+/// Hosts are compared case-insensitively. An authority without a port matches the host on any
+/// port, and an authority with a port only matches the host on that port, where a location
+/// without a port has the default port of its scheme. That is, `example.com:443` matches
+/// `https://example.com/`, but not `http://example.com/`.
+///
+/// Redirections to other hosts are stopped with [`Action::Stop`].
+///
+/// # Example
+///
+/// ```
+/// use http::uri::Authority;
+/// use tower_http::follow_redirect::policy::{AllowHosts, Limited, PolicyExt};
+///
+/// let policy = AllowHosts::new([
+///     Authority::from_static("example.com"),
+///     Authority::from_static("api.example.com:8443"),
+/// ])
+/// .and::<_, (), ()>(Limited::default());
+/// ```
+#[derive(Clone, Debug)]
+pub struct AllowHosts {
+    hosts: Hosts,
+}
+
+impl AllowHosts {
+    /// Create a new [`AllowHosts`] that follows redirections to the `hosts`.
+    pub fn new<I>(hosts: I) -> Self
+    where
+        I: IntoIterator<Item = Authority>,
+    {
+        AllowHosts {
+            hosts: Hosts::List(hosts.into_iter().collect()),
+        }
+    }
+
+    /// Create a new [`AllowHosts`] that follows redirections to the authorities for which
+    /// `predicate` returns `true`.
+    pub fn predicate<F>(predicate: F) -> Self
+    where
+        F: Fn(&Authority) -> bool + Send + Sync + 'static,
+    {
+        AllowHosts {
+            hosts: Hosts::Predicate(Arc::new(predicate)),
+        }
+    }
+}
+
+impl<B, E> Policy<B, E> for AllowHosts {
+    fn redirect(&mut self, attempt: &Attempt<'_>) -> Result<Action, E> {
+        if self.hosts.matches(attempt.location()) {
+            Ok(Action::Follow)
+        } else {
+            Ok(Action::Stop)
+        }
+    }
+}
+
+/// A redirection [`Policy`] that stops redirections to some hosts.
+///
+/// Hosts are compared as with [`AllowHosts`]. Redirections to other hosts are followed, but not
+/// redirections to locations without a host.
+///
+/// # Example
+///
+/// ```
+/// use http::uri::Authority;
+/// use tower_http::follow_redirect::policy::{DenyHosts, Limited, PolicyExt};
+///
+/// let policy = DenyHosts::new([Authority::from_static("localhost")])
+///     .and::<_, (), ()>(Limited::default());
+/// ```
+#[derive(Clone, Debug)]
+pub struct DenyHosts {
+    hosts: Hosts,
+}
+
+impl DenyHosts {
+    /// Create a new [`DenyHosts`] that stops redirections to the `hosts`.
+    pub fn new<I>(hosts: I) -> Self
+    where
+        I: IntoIterator<Item = Authority>,
+    {
+        DenyHosts {
+            hosts: Hosts::List(hosts.into_iter().collect()),
+        }
+    }
+
+    /// Create a new [`DenyHosts`] that stops redirections to the authorities for which
+    /// `predicate` returns `true`.
+    pub fn predicate<F>(predicate: F) -> Self
+    where
+        F: Fn(&Authority) -> bool + Send + Sync + 'static,
+    {
+        DenyHosts {
+            hosts: Hosts::Predicate(Arc::new(predicate)),
+        }
+    }
+}
+
+impl<B, E> Policy<B, E> for DenyHosts {
+    fn redirect(&mut self, attempt: &Attempt<'_>) -> Result<Action, E> {
+        // locations without a host can't be checked
+        if attempt.location().authority().is_none() || self.hosts.matches(attempt.location()) {
+            Ok(Action::Stop)
+        } else {
+            Ok(Action::Follow)
+        }
+    }
+}
+
+#[derive(Clone)]
+enum Hosts {
+    List(Vec<Authority>),
+    Predicate(Arc<dyn Fn(&Authority) -> bool + Send + Sync>),
+}
+
+impl Hosts {
+    fn matches(&self, location: &Uri) -> bool {
+        let authority = match location.authority() {
+            Some(authority) => authority,
+            None => return false,
+        };
+
+        match self {
+            Hosts::List(hosts) => {
+                let port = authority.port_u16().or_else(|| match location.scheme() {
+                    Some(scheme) if *scheme == Scheme::HTTP => Some(80),
+                    Some(scheme) if *scheme == Scheme::HTTPS => Some(443),
+                    _ => None,
+                });
+                hosts.iter().any(|host| {
+                    host.host().eq_ignore_ascii_case(authority.host())
+                        && host
+                            .port_u16()
+                            .map_or(true, |host_port| Some(host_port) == port)
+                })
+            }
+            Hosts::Predicate(predicate) => predicate(authority),
+        }
+    }
+}
+
+impl fmt::Debug for Hosts {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Hosts::List(hosts) => f.debug_tuple("List").field(hosts).finish(),
+            Hosts::Predicate(_) => f.debug_tuple("Predicate").finish(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use http::{Extensions, HeaderMap, HeaderValue, Method, StatusCode};
+
+    fn redirect<P: Policy<(), ()>>(policy: &mut P, location: &str) -> Action {
+        let location = location.parse::<Uri>().unwrap();
+        let attempt = Attempt {
+            status: StatusCode::FOUND,
+            method: &Method::GET,
+            location: &location,
+            raw_location: &HeaderValue::from_static("/new"),
+            headers: &HeaderMap::new(),
+            extensions: &Extensions::new(),
+            previous: &Uri::from_static("http://example.com/"),
+        };
+        policy.redirect(&attempt).unwrap()
+    }
+
+    fn hosts() -> [Authority; 2] {
+        [
+            Authority::from_static("Example.com"),
+            Authority::from_static("api.example.com:8443"),
+        ]
+    }
+
+    #[test]
+    fn allows_listed_hosts() {
+        let mut policy = AllowHosts::new(hosts());
+        assert!(redirect(&mut policy, "http://example.com/").is_follow());
+        assert!(redirect(&mut policy, "https://EXAMPLE.COM:8080/").is_follow());
+        assert!(redirect(&mut policy, "https://api.example.com:8443/").is_follow());
+
+        assert!(redirect(&mut policy, "https://www.example.com/").is_stop());
+        assert!(redirect(&mut policy, "https://api.example.com/").is_stop());
+        assert!(redirect(&mut policy, "https://api.example.com:443/").is_stop());
+    }
+
+    #[test]
+    fn denies_listed_hosts() {
+        let mut policy = DenyHosts::new(hosts());
+        assert!(redirect(&mut policy, "http://example.com/").is_stop());
+        assert!(redirect(&mut policy, "https://user@example.com/").is_stop());
+        assert!(redirect(&mut policy, "https://api.example.com:8443/").is_stop());
+
+        assert!(redirect(&mut policy, "https://www.example.com/").is_follow());
+        assert!(redirect(&mut policy, "https://api.example.com/").is_follow());
+    }
+
+    #[test]
+    fn default_ports() {
+        let mut policy = AllowHosts::new([Authority::from_static("example.com:443")]);
+        assert!(redirect(&mut policy, "https://example.com/").is_follow());
+        assert!(redirect(&mut policy, "http://example.com/").is_stop());
+    }
+
+    #[test]
+    fn predicate() {
+        let mut policy = AllowHosts::predicate(|authority| authority.host().ends_with(".test"));
+        assert!(redirect(&mut policy, "http://a.test/").is_follow());
+        assert!(redirect(&mut policy, "http://a.example/").is_stop());
+
+        let mut policy = DenyHosts::predicate(|authority| authority.host() == "localhost");
+        assert!(redirect(&mut policy, "http://localhost:8080/").is_stop());
+        assert!(redirect(&mut policy, "http://a.example/").is_follow());
+    }
+}
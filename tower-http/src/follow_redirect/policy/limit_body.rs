@@ -0,0 +1,138 @@
+use super::{Action, Attempt, Policy};
+use http::{HeaderMap, HeaderValue, Method, Request, StatusCode};
+use http_body::Body;
+use std::{
+    sync::atomic::{AtomicU64, Ordering},
+    task::{Context, Poll},
+};
+
+/// A redirection [`Policy`] that limits the number of bytes of request bodies cloned by the
+/// inner policy.
+///
+/// [`Policy::clone_body`] is called once for the initial request and once more for each
+/// redirection that replays the body, such as `307 Temporary Redirect`, so a large body can be
+/// held in memory many times over a chain of redirections. [`LimitBody`] adds up the size of the
+/// cloned bodies, as given by the upper bound of [`Body::size_hint`], and refuses to clone a body
+/// once the total would exceed a budget. Bodies without an upper bound are never cloned.
+///
+/// The redirected request whose body couldn't be cloned is still made, but the next redirection
+/// that would replay the body isn't followed.
+///
+/// The budget covers a single chain of redirections, and is reset for every request made with a
+/// [`FollowRedirect`][crate::follow_redirect::FollowRedirect] middleware.
+///
+/// # Example
+///
+/// ```
+/// use bytes::Bytes;
+/// use http_body_util::Full;
+/// use tower_http::follow_redirect::policy::{clone_body_fn, LimitBody};
+///
+/// // Clone bodies of up to 1 MiB in total.
+/// let policy = LimitBody::new(
+///     clone_body_fn(|body: &Full<Bytes>| Some(body.clone())),
+///     1024 * 1024,
+/// );
+/// ```
+#[derive(Debug)]
+pub struct LimitBody<P> {
+    inner: P,
+    max_bytes: u64,
+    // `clone_body` takes `&self`
+    cloned: AtomicU64,
+}
+
+impl<P> LimitBody<P> {
+    /// Create a new [`LimitBody`] that stops cloning request bodies with `inner` once more than
+    /// `max_bytes` bytes were cloned in total.
+    pub fn new(inner: P, max_bytes: u64) -> Self {
+        LimitBody {
+            inner,
+            max_bytes,
+            cloned: AtomicU64::new(0),
+        }
+    }
+}
+
+impl<P: Clone> Clone for LimitBody<P> {
+    fn clone(&self) -> Self {
+        LimitBody {
+            inner: self.inner.clone(),
+            max_bytes: self.max_bytes,
+            cloned: AtomicU64::new(self.cloned.load(Ordering::Relaxed)),
+        }
+    }
+}
+
+impl<P, B, E> Policy<B, E> for LimitBody<P>
+where
+    P: Policy<B, E>,
+    B: Body,
+{
+    fn redirect(&mut self, attempt: &Attempt<'_>) -> Result<Action, E> {
+        self.inner.redirect(attempt)
+    }
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>, attempt: &Attempt<'_>) -> Poll<()> {
+        self.inner.poll_ready(cx, attempt)
+    }
+
+    fn on_request(&mut self, request: &mut Request<B>) {
+        self.inner.on_request(request);
+    }
+
+    fn clone_body(&self, body: &B) -> Option<B> {
+        let size = body.size_hint().upper()?;
+        let cloned = self.cloned.load(Ordering::Relaxed).checked_add(size)?;
+        if cloned > self.max_bytes {
+            return None;
+        }
+
+        let body = self.inner.clone_body(body)?;
+        self.cloned.store(cloned, Ordering::Relaxed);
+        Some(body)
+    }
+
+    fn on_error(&mut self, error: &E) {
+        self.inner.on_error(error);
+    }
+
+    fn retry_on_error(&mut self, error: &E) -> bool {
+        self.inner.retry_on_error(error)
+    }
+
+    fn alternative_location(
+        &mut self,
+        status: StatusCode,
+        headers: &HeaderMap,
+    ) -> Option<HeaderValue> {
+        self.inner.alternative_location(status, headers)
+    }
+
+    fn rewrite_method(&self, status: StatusCode, method: &Method) -> Option<Method> {
+        self.inner.rewrite_method(status, method)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{super::clone_body_fn, *};
+    use bytes::Bytes;
+    use http_body_util::Full;
+
+    #[test]
+    fn refuses_clones_over_budget() {
+        let policy = LimitBody::new(clone_body_fn(|body: &Full<Bytes>| Some(body.clone())), 8);
+        let body = Full::new(Bytes::from_static(b"hello"));
+
+        assert!(Policy::<_, ()>::clone_body(&policy, &body).is_some());
+        // 10 bytes would have been cloned
+        assert!(Policy::<_, ()>::clone_body(&policy, &body).is_none());
+
+        // clones keep the number of bytes cloned so far
+        let policy = policy.clone();
+        assert!(Policy::<_, ()>::clone_body(&policy, &body).is_none());
+        let small = Full::new(Bytes::from_static(b"abc"));
+        assert!(Policy::<_, ()>::clone_body(&policy, &small).is_some());
+    }
+}
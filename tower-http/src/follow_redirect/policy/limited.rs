@@ -1,6 +1,9 @@
-use super::{Action, Attempt, Policy};
+use super::{Action, Attempt, Or, Policy};
 
 /// A redirection [`Policy`] that limits the number of successive redirections.
+///
+/// Once the limit is reached, redirections are stopped with [`Action::Stop`], and the last
+/// redirection response is returned as-is. Use [`Limited::or_error`] to fail instead.
 #[derive(Clone, Copy, Debug)]
 pub struct Limited {
     remaining: usize,
@@ -11,6 +14,31 @@ impl Limited {
     pub fn new(max: usize) -> Self {
         Limited { remaining: max }
     }
+
+    /// Returns a policy that fails with `error` instead of stopping once the limit is reached.
+    ///
+    /// The error is returned by the [`FollowRedirect`][crate::follow_redirect::FollowRedirect]
+    /// middleware in place of the last redirection response, so it must be of the error type of
+    /// the inner service.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use tower_http::follow_redirect::policy::{Limited, Policy};
+    ///
+    /// #[derive(Clone, Debug)]
+    /// struct TooManyRedirects;
+    ///
+    /// fn policy<B>() -> impl Policy<B, TooManyRedirects> {
+    ///     Limited::new(5).or_error(TooManyRedirects)
+    /// }
+    /// ```
+    pub fn or_error<E>(self, error: E) -> Or<Self, Result<Action, E>>
+    where
+        E: Clone,
+    {
+        Or::new::<(), E>(self, Err(error))
+    }
 }
 
 impl Default for Limited {
@@ -36,7 +64,7 @@ impl<B, E> Policy<B, E> for Limited {
 
 #[cfg(test)]
 mod tests {
-    use http::{Request, Uri};
+    use http::{Extensions, HeaderMap, HeaderValue, Method, Request, Uri};
 
     use super::*;
 
@@ -51,7 +79,11 @@ mod tests {
 
             let attempt = Attempt {
                 status: Default::default(),
+                method: &Method::GET,
                 location: &uri,
+                raw_location: &HeaderValue::from_static("https://example.com/"),
+                headers: &HeaderMap::new(),
+                extensions: &Extensions::new(),
                 previous: &uri,
             };
             assert!(Policy::<(), ()>::redirect(&mut policy, &attempt)
@@ -64,11 +96,38 @@ mod tests {
 
         let attempt = Attempt {
             status: Default::default(),
+            method: &Method::GET,
             location: &uri,
+            raw_location: &HeaderValue::from_static("https://example.com/"),
+            headers: &HeaderMap::new(),
+            extensions: &Extensions::new(),
             previous: &uri,
         };
         assert!(Policy::<(), ()>::redirect(&mut policy, &attempt)
             .unwrap()
             .is_stop());
     }
+
+    #[test]
+    fn or_error() {
+        let uri = Uri::from_static("https://example.com/");
+        let mut policy = Limited::new(1).or_error("too many redirections");
+        let attempt = Attempt {
+            status: Default::default(),
+            method: &Method::GET,
+            location: &uri,
+            raw_location: &HeaderValue::from_static("https://example.com/"),
+            headers: &HeaderMap::new(),
+            extensions: &Extensions::new(),
+            previous: &uri,
+        };
+
+        assert!(Policy::<(), _>::redirect(&mut policy, &attempt)
+            .unwrap()
+            .is_follow());
+        assert_eq!(
+            Policy::<(), _>::redirect(&mut policy, &attempt).unwrap_err(),
+            "too many redirections"
+        );
+    }
 }
@@ -0,0 +1,117 @@
+use super::{Action, Attempt, Policy};
+use http::uri::Scheme;
+
+/// A redirection [`Policy`] that limits the number of redirections to each scheme.
+///
+/// Redirections are counted by the scheme of their target, so a redirection from `https` to
+/// `http` counts towards the `http` limit. Redirections to schemes without a limit aren't
+/// limited by this policy, so combine it with [`Limited`][super::Limited] to also limit the
+/// total number of redirections.
+///
+/// # Example
+///
+/// ```
+/// use http::uri::Scheme;
+/// use tower_http::follow_redirect::policy::{Limited, LimitedPerScheme, PolicyExt};
+///
+/// // Allow up to 10 redirections to `https` URIs, but only 1 to `http` URIs.
+/// let policy = LimitedPerScheme::new()
+///     .limit(Scheme::HTTPS, 10)
+///     .limit(Scheme::HTTP, 1)
+///     .and::<_, (), ()>(Limited::default());
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct LimitedPerScheme {
+    remaining: Vec<(Scheme, usize)>,
+}
+
+impl LimitedPerScheme {
+    /// Create a new [`LimitedPerScheme`] without any limits.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Limit the number of redirections to `scheme` to `max`.
+    ///
+    /// Replaces any previous limit for `scheme`.
+    pub fn limit(mut self, scheme: Scheme, max: usize) -> Self {
+        match self.remaining.iter_mut().find(|(s, _)| *s == scheme) {
+            Some((_, remaining)) => *remaining = max,
+            None => self.remaining.push((scheme, max)),
+        }
+        self
+    }
+}
+
+impl<B, E> Policy<B, E> for LimitedPerScheme {
+    fn redirect(&mut self, attempt: &Attempt<'_>) -> Result<Action, E> {
+        let scheme = match attempt.location().scheme() {
+            Some(scheme) => scheme,
+            None => return Ok(Action::Follow),
+        };
+
+        match self.remaining.iter_mut().find(|(s, _)| s == scheme) {
+            Some((_, 0)) => Ok(Action::Stop),
+            Some((_, remaining)) => {
+                *remaining -= 1;
+                Ok(Action::Follow)
+            }
+            None => Ok(Action::Follow),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use http::{Extensions, HeaderMap, HeaderValue, Method, StatusCode, Uri};
+
+    fn redirect(policy: &mut LimitedPerScheme, previous: &str, location: &str) -> Action {
+        let previous = previous.parse::<Uri>().unwrap();
+        let location = location.parse::<Uri>().unwrap();
+        let attempt = Attempt {
+            status: StatusCode::FOUND,
+            method: &Method::GET,
+            location: &location,
+            raw_location: &HeaderValue::from_static("/new"),
+            headers: &HeaderMap::new(),
+            extensions: &Extensions::new(),
+            previous: &previous,
+        };
+        Policy::<(), ()>::redirect(policy, &attempt).unwrap()
+    }
+
+    #[test]
+    fn counts_by_target_scheme() {
+        let mut policy = LimitedPerScheme::new()
+            .limit(Scheme::HTTP, 1)
+            .limit(Scheme::HTTPS, 10);
+
+        assert!(redirect(&mut policy, "https://a.example/", "http://b.example/").is_follow());
+        assert!(redirect(&mut policy, "http://b.example/", "https://c.example/").is_follow());
+        assert!(redirect(&mut policy, "https://c.example/", "https://d.example/").is_follow());
+        // the second redirection to `http` exceeds its limit, with `https` far below its limit
+        assert!(redirect(&mut policy, "https://d.example/", "http://e.example/").is_stop());
+        assert!(redirect(&mut policy, "https://d.example/", "https://e.example/").is_follow());
+    }
+
+    #[test]
+    fn doesnt_limit_other_schemes() {
+        let mut policy = LimitedPerScheme::new().limit(Scheme::HTTP, 0);
+
+        assert!(redirect(&mut policy, "http://a.example/", "http://b.example/").is_stop());
+        for _ in 0..100 {
+            assert!(redirect(&mut policy, "http://a.example/", "https://b.example/").is_follow());
+        }
+    }
+
+    #[test]
+    fn replaces_limits() {
+        let mut policy = LimitedPerScheme::new()
+            .limit(Scheme::HTTP, 0)
+            .limit(Scheme::HTTP, 1);
+
+        assert!(redirect(&mut policy, "http://a.example/", "http://b.example/").is_follow());
+        assert!(redirect(&mut policy, "http://b.example/", "http://c.example/").is_stop());
+    }
+}
@@ -0,0 +1,179 @@
+use super::{Action, Attempt, Policy};
+use http::Uri;
+use std::{collections::HashSet, fmt::Write};
+
+/// A redirection [`Policy`] that stops following redirections when they lead back to a URI that
+/// was already visited.
+///
+/// By default, URIs are compared exactly. With [`canonicalize`](Self::canonicalize), URIs that
+/// only differ in the case of the scheme and host, an explicit default port or a trailing slash
+/// are considered equal, so that a chain like `/a` → `/a/` → `/a` is detected as a loop.
+///
+/// The policy stops following redirections with [`Action::Stop`], returning the last redirection
+/// response. Combine it with an error using [`or`](super::PolicyExt::or) to fail instead.
+///
+/// # Example
+///
+/// ```
+/// use tower_http::follow_redirect::policy::{Limited, LoopDetect, PolicyExt};
+///
+/// let policy = LoopDetect::new().canonicalize(true).and::<_, (), ()>(Limited::default());
+/// ```
+///
+/// Returning an error when a loop is detected:
+///
+/// ```
+/// use tower_http::follow_redirect::policy::{Limited, LoopDetect, PolicyExt};
+///
+/// #[derive(Clone, Debug)]
+/// enum MyError {
+///     RedirectLoop,
+///     // ...
+/// }
+///
+/// let policy = LoopDetect::new()
+///     .or::<_, (), _>(Err(MyError::RedirectLoop))
+///     .and::<_, (), _>(Limited::default());
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct LoopDetect {
+    visited: HashSet<String>,
+    canonicalize: bool,
+}
+
+impl LoopDetect {
+    /// Create a new [`LoopDetect`] that compares URIs exactly.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets whether to canonicalize URIs before comparing them.
+    ///
+    /// Canonicalization lowercases the scheme and host, removes the port if it is the default
+    /// port of the scheme, and removes trailing slashes from the path. Defaults to `false`.
+    pub fn canonicalize(mut self, canonicalize: bool) -> Self {
+        self.canonicalize = canonicalize;
+        self
+    }
+
+    fn key(&self, uri: &Uri) -> String {
+        if self.canonicalize {
+            canonical(uri)
+        } else {
+            uri.to_string()
+        }
+    }
+}
+
+impl<B, E> Policy<B, E> for LoopDetect {
+    fn redirect(&mut self, attempt: &Attempt<'_>) -> Result<Action, E> {
+        let previous = self.key(attempt.previous());
+        self.visited.insert(previous);
+
+        if self.visited.contains(&self.key(attempt.location())) {
+            Ok(Action::Stop)
+        } else {
+            Ok(Action::Follow)
+        }
+    }
+}
+
+fn canonical(uri: &Uri) -> String {
+    let mut key = String::new();
+
+    let scheme = uri.scheme_str().map(str::to_ascii_lowercase);
+    if let Some(scheme) = &scheme {
+        key.push_str(scheme);
+        key.push_str("://");
+    }
+
+    if let Some(authority) = uri.authority() {
+        key.push_str(&authority.host().to_ascii_lowercase());
+        let default_port = match scheme.as_deref() {
+            Some("http") => Some(80),
+            Some("https") => Some(443),
+            _ => None,
+        };
+        if let Some(port) = authority.port_u16() {
+            if Some(port) != default_port {
+                let _ = write!(key, ":{}", port);
+            }
+        }
+    }
+
+    key.push_str(uri.path().trim_end_matches('/'));
+
+    if let Some(query) = uri.query() {
+        key.push('?');
+        key.push_str(query);
+    }
+
+    key
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use http::{Extensions, HeaderMap, HeaderValue, Method, StatusCode};
+
+    fn redirect(policy: &mut LoopDetect, previous: &str, location: &str) -> Action {
+        let previous = previous.parse::<Uri>().unwrap();
+        let location = location.parse::<Uri>().unwrap();
+        let attempt = Attempt {
+            status: StatusCode::FOUND,
+            method: &Method::GET,
+            location: &location,
+            raw_location: &HeaderValue::from_static("/new"),
+            headers: &HeaderMap::new(),
+            extensions: &Extensions::new(),
+            previous: &previous,
+        };
+        Policy::<(), ()>::redirect(policy, &attempt).unwrap()
+    }
+
+    #[test]
+    fn detects_exact_loops() {
+        let mut policy = LoopDetect::new();
+        let (a, a_slash) = ("http://example.com/a", "http://example.com/a/");
+        assert!(redirect(&mut policy, a, a_slash).is_follow());
+        assert!(redirect(&mut policy, a_slash, a).is_stop());
+    }
+
+    #[test]
+    fn canonicalizes_uris() {
+        let mut policy = LoopDetect::new().canonicalize(true);
+        assert!(redirect(&mut policy, "http://example.com/a", "http://example.com/a/").is_stop());
+
+        let mut policy = LoopDetect::new().canonicalize(true);
+        assert!(redirect(
+            &mut policy,
+            "https://example.com/a?x=1",
+            "https://EXAMPLE.com:443/a/?x=1"
+        )
+        .is_stop());
+    }
+
+    #[test]
+    fn follows_distinct_uris() {
+        let mut policy = LoopDetect::new().canonicalize(true);
+        assert!(redirect(&mut policy, "http://example.com/a", "http://example.com/b").is_follow());
+        assert!(redirect(
+            &mut policy,
+            "http://example.com/b",
+            "http://example.com/a/b"
+        )
+        .is_follow());
+        assert!(redirect(
+            &mut policy,
+            "http://example.com/a/b",
+            "http://example.com:8080/a"
+        )
+        .is_follow());
+        assert!(redirect(
+            &mut policy,
+            "http://example.com:8080/a",
+            "http://example.com/a?x=1"
+        )
+        .is_follow());
+    }
+}
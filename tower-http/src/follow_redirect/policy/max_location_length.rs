@@ -0,0 +1,76 @@
+use super::{Action, Attempt, Policy};
+
+/// A redirection [`Policy`] that stops at redirections with overly long `Location` headers.
+///
+/// The length is checked against the raw value of the `Location` header, in bytes, so relative
+/// locations are not penalized for the length of the URI they are resolved against.
+///
+/// # Example
+///
+/// ```
+/// use tower_http::follow_redirect::policy::{Limited, MaxLocationLength, PolicyExt};
+///
+/// let policy = MaxLocationLength::new(2048).and::<_, (), ()>(Limited::default());
+/// ```
+#[derive(Clone, Copy, Debug)]
+pub struct MaxLocationLength {
+    max: usize,
+}
+
+impl MaxLocationLength {
+    /// Create a new [`MaxLocationLength`] that follows redirections whose `Location` header is at
+    /// most `max` bytes long.
+    pub fn new(max: usize) -> Self {
+        MaxLocationLength { max }
+    }
+}
+
+impl<B, E> Policy<B, E> for MaxLocationLength {
+    fn redirect(&mut self, attempt: &Attempt<'_>) -> Result<Action, E> {
+        if attempt.raw_location().len() <= self.max {
+            Ok(Action::Follow)
+        } else {
+            Ok(Action::Stop)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use http::{Extensions, HeaderMap, HeaderValue, Method, Uri};
+
+    #[test]
+    fn works() {
+        let mut policy = MaxLocationLength::new(16);
+
+        let previous = Uri::from_static("http://example.com/old");
+        let location = Uri::from_static("http://example.com/new");
+        let attempt = Attempt {
+            status: Default::default(),
+            method: &Method::GET,
+            location: &location,
+            raw_location: &HeaderValue::from_static("/new"),
+            headers: &HeaderMap::new(),
+            extensions: &Extensions::new(),
+            previous: &previous,
+        };
+        assert!(Policy::<(), ()>::redirect(&mut policy, &attempt)
+            .unwrap()
+            .is_follow());
+
+        let location = Uri::from_static("http://example.com/0123456789abcdef");
+        let attempt = Attempt {
+            status: Default::default(),
+            method: &Method::GET,
+            location: &location,
+            raw_location: &HeaderValue::from_static("/0123456789abcdef"),
+            headers: &HeaderMap::new(),
+            extensions: &Extensions::new(),
+            previous: &previous,
+        };
+        assert!(Policy::<(), ()>::redirect(&mut policy, &attempt)
+            .unwrap()
+            .is_stop());
+    }
+}
@@ -0,0 +1,98 @@
+use super::{Action, Attempt, Policy};
+use http::{Method, StatusCode};
+use std::collections::HashMap;
+
+/// A redirection [`Policy`] that looks up the action for each redirection in a table of request
+/// methods and response statuses.
+///
+/// The method is the one of the request that received the redirection response, see
+/// [`Attempt::method`]. Combinations that aren't in the table get a default action.
+///
+/// # Example
+///
+/// ```
+/// use http::{Method, StatusCode};
+/// use std::collections::HashMap;
+/// use tower_http::follow_redirect::policy::{Action, Limited, MethodStatusTable, PolicyExt};
+///
+/// let table = HashMap::from([
+///     ((Method::POST, StatusCode::FOUND), Action::Stop),
+///     ((Method::GET, StatusCode::FOUND), Action::Follow),
+/// ]);
+/// let policy = MethodStatusTable::new(table, Action::Stop).and::<_, (), ()>(Limited::default());
+/// ```
+#[derive(Clone, Debug)]
+pub struct MethodStatusTable {
+    table: HashMap<(Method, StatusCode), Action>,
+    default: Action,
+}
+
+impl MethodStatusTable {
+    /// Create a new [`MethodStatusTable`] from a table of actions, and the action for
+    /// combinations that aren't in the table.
+    pub fn new(table: HashMap<(Method, StatusCode), Action>, default: Action) -> Self {
+        MethodStatusTable { table, default }
+    }
+}
+
+impl<B, E> Policy<B, E> for MethodStatusTable {
+    fn redirect(&mut self, attempt: &Attempt<'_>) -> Result<Action, E> {
+        let key = (attempt.method().clone(), attempt.status());
+        Ok(self.table.get(&key).copied().unwrap_or(self.default))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use http::{Extensions, HeaderMap, HeaderValue, Uri};
+
+    fn redirect(policy: &mut MethodStatusTable, method: Method, status: StatusCode) -> Action {
+        let previous = Uri::from_static("http://example.com/old");
+        let location = Uri::from_static("http://example.com/new");
+        let attempt = Attempt {
+            status,
+            method: &method,
+            location: &location,
+            raw_location: &HeaderValue::from_static("/new"),
+            headers: &HeaderMap::new(),
+            extensions: &Extensions::new(),
+            previous: &previous,
+        };
+        Policy::<(), ()>::redirect(policy, &attempt).unwrap()
+    }
+
+    #[test]
+    fn looks_up_table() {
+        let mut policy = MethodStatusTable::new(
+            HashMap::from([
+                ((Method::POST, StatusCode::FOUND), Action::Stop),
+                ((Method::GET, StatusCode::FOUND), Action::Follow),
+                (
+                    (Method::PUT, StatusCode::TEMPORARY_REDIRECT),
+                    Action::Follow,
+                ),
+                ((Method::GET, StatusCode::SEE_OTHER), Action::Stop),
+            ]),
+            Action::Follow,
+        );
+
+        assert!(redirect(&mut policy, Method::POST, StatusCode::FOUND).is_stop());
+        assert!(redirect(&mut policy, Method::GET, StatusCode::FOUND).is_follow());
+        assert!(redirect(&mut policy, Method::PUT, StatusCode::TEMPORARY_REDIRECT).is_follow());
+        assert!(redirect(&mut policy, Method::GET, StatusCode::SEE_OTHER).is_stop());
+    }
+
+    #[test]
+    fn falls_back_to_default() {
+        let table = HashMap::from([((Method::GET, StatusCode::FOUND), Action::Follow)]);
+
+        let mut policy = MethodStatusTable::new(table.clone(), Action::Stop);
+        assert!(redirect(&mut policy, Method::GET, StatusCode::FOUND).is_follow());
+        assert!(redirect(&mut policy, Method::POST, StatusCode::FOUND).is_stop());
+        assert!(redirect(&mut policy, Method::GET, StatusCode::MOVED_PERMANENTLY).is_stop());
+
+        let mut policy = MethodStatusTable::new(table, Action::Follow);
+        assert!(redirect(&mut policy, Method::DELETE, StatusCode::SEE_OTHER).is_follow());
+    }
+}
@@ -1,24 +1,94 @@
 //! Tools for customizing the behavior of a [`FollowRedirect`][super::FollowRedirect] middleware.
 
 mod and;
+mod async_policy;
+mod block_downgrade;
+mod circuit_breaker;
 mod clone_body_fn;
+#[cfg(feature = "cookie")]
+mod cookie_jar;
+#[cfg(feature = "follow-redirect-time")]
+mod deadline;
+mod error_on_stop;
 mod filter_credentials;
+mod follow_refresh;
+mod hosts;
+mod limit_body;
 mod limited;
+mod limited_per_scheme;
+mod loop_detect;
+mod max_location_length;
+mod method_status_table;
+mod not;
+mod observe;
 mod or;
+mod per_host_limit;
+mod per_origin_byte_budget;
+mod redirect_content_type;
 mod redirect_fn;
+mod referrer_policy;
+#[cfg(feature = "follow-redirect-time")]
+mod remote_allow_list;
+mod require_absolute_location;
+mod require_idempotency_key;
+mod require_response_header;
+mod require_tls_version;
+mod retry_on_error;
+mod rewrite_post_to_get;
 mod same_origin;
+mod same_path_prefix;
+mod same_scheme;
+mod sampled;
+mod set_referer;
+mod shared_allow_list;
+mod stop_on_self_redirect;
 
 pub use self::{
     and::And,
+    async_policy::AsyncPolicy,
+    block_downgrade::BlockDowngrade,
+    circuit_breaker::CircuitBreaker,
     clone_body_fn::{clone_body_fn, CloneBodyFn},
+    error_on_stop::{ErrorOnStop, RedirectError},
     filter_credentials::FilterCredentials,
+    follow_refresh::FollowRefresh,
+    hosts::{AllowHosts, DenyHosts},
+    limit_body::LimitBody,
     limited::Limited,
+    limited_per_scheme::LimitedPerScheme,
+    loop_detect::LoopDetect,
+    max_location_length::MaxLocationLength,
+    method_status_table::MethodStatusTable,
+    not::{not, Not},
+    observe::{observe, Observe, RedirectEvent},
     or::Or,
+    per_host_limit::PerHostLimit,
+    per_origin_byte_budget::PerOriginByteBudget,
+    redirect_content_type::RedirectContentType,
     redirect_fn::{redirect_fn, RedirectFn},
+    referrer_policy::ReferrerPolicy,
+    require_absolute_location::RequireAbsoluteLocation,
+    require_idempotency_key::RequireIdempotencyKey,
+    require_response_header::RequireResponseHeader,
+    require_tls_version::{RequireTlsVersion, TlsVersion},
+    retry_on_error::RetryOnError,
+    rewrite_post_to_get::RewritePostToGet,
     same_origin::SameOrigin,
+    same_path_prefix::SamePathPrefix,
+    same_scheme::SameScheme,
+    sampled::Sampled,
+    set_referer::SetReferer,
+    shared_allow_list::{AllowListHandle, SharedAllowList},
+    stop_on_self_redirect::StopOnSelfRedirect,
 };
 
-use http::{uri::Scheme, Request, StatusCode, Uri};
+#[cfg(feature = "cookie")]
+pub use self::cookie_jar::CookieJar;
+#[cfg(feature = "follow-redirect-time")]
+pub use self::{deadline::Deadline, remote_allow_list::RemoteAllowList};
+
+use http::{uri::Scheme, Extensions, HeaderMap, HeaderValue, Method, Request, StatusCode, Uri};
+use std::task::{Context, Poll};
 
 /// Trait for the policy on handling redirection responses.
 ///
@@ -48,12 +118,29 @@ use http::{uri::Scheme, Request, StatusCode, Uri};
 /// }
 /// ```
 pub trait Policy<B, E> {
-    /// Invoked when the service received a response with a redirection status code (`3xx`).
+    /// Invoked when the service received a response with a redirection status code (`3xx`), or
+    /// a location from [`alternative_location`][Policy::alternative_location].
     ///
     /// This method returns an [`Action`] which indicates whether the service should follow
     /// the redirection.
     fn redirect(&mut self, attempt: &Attempt<'_>) -> Result<Action, E>;
 
+    /// Invoked before [`redirect`][Policy::redirect] to wait until the policy is ready to decide
+    /// whether to follow the redirection, for example because it has to look something up.
+    ///
+    /// `redirect` is invoked with the same `attempt` once this returns [`Poll::Ready`]. As with
+    /// futures, the policy must arrange for `cx` to be woken up when it returns
+    /// [`Poll::Pending`]. This can be invoked again after returning `Poll::Ready`, when a policy
+    /// combined with this one isn't ready yet.
+    ///
+    /// This makes the policy an [`AsyncPolicy`] that can be combined with other policies, unlike
+    /// implementing `AsyncPolicy` directly.
+    ///
+    /// The default implementation returns `Poll::Ready` right away.
+    fn poll_ready(&mut self, _cx: &mut Context<'_>, _attempt: &Attempt<'_>) -> Poll<()> {
+        Poll::Ready(())
+    }
+
     /// Invoked right before the service makes a request, regardless of whether it is redirected
     /// or not.
     ///
@@ -74,6 +161,59 @@ pub trait Policy<B, E> {
     fn clone_body(&self, _body: &B) -> Option<B> {
         None
     }
+
+    /// Invoked when the service returns an error for a redirected request, before
+    /// [`retry_on_error`][Policy::retry_on_error].
+    ///
+    /// Unlike `retry_on_error`, this is invoked on every policy combined with
+    /// [`and`](PolicyExt::and) or [`or`](PolicyExt::or), so it can be used to observe errors.
+    ///
+    /// The default implementation does nothing.
+    fn on_error(&mut self, _error: &E) {}
+
+    /// Invoked when the service returns an error for a redirected request.
+    ///
+    /// If this returns `true`, the redirected request is made again to the same location, as long
+    /// as the request body can be cloned with [`clone_body`][Policy::clone_body]. Errors of the
+    /// initial request are always returned as-is.
+    ///
+    /// The default implementation returns `false`.
+    fn retry_on_error(&mut self, _error: &E) -> bool {
+        false
+    }
+
+    /// Invoked when the service received a response that isn't a redirection, to get the target
+    /// of a redirection signalled in another way, such as with a `Refresh` header.
+    ///
+    /// If this returns a location, the response is handled like a `303 See Other` redirection to
+    /// it, and [`redirect`][Policy::redirect] is invoked to decide whether to follow it.
+    ///
+    /// The default implementation returns `None`.
+    fn alternative_location(
+        &mut self,
+        _status: StatusCode,
+        _headers: &HeaderMap,
+    ) -> Option<HeaderValue> {
+        None
+    }
+
+    /// Invoked when the service received a redirection, to choose the method of the redirected
+    /// request.
+    ///
+    /// If this returns a method, the redirected request is made with it. The request body is
+    /// kept, unless the method is changed to `GET` or `HEAD`, in which case it is dropped along
+    /// with the headers describing it. For example, returning the method of the request keeps it
+    /// in `301 Moved Permanently` and `302 Found` redirections, like in `307 Temporary Redirect`
+    /// ones.
+    ///
+    /// If this returns `None`, the method is changed like user agents do: from `POST` to `GET` for
+    /// `301 Moved Permanently` and `302 Found`, and to `GET` unless it is `HEAD` for `303 See
+    /// Other` and [alternative locations][Policy::alternative_location].
+    ///
+    /// The default implementation returns `None`.
+    fn rewrite_method(&self, _status: StatusCode, _method: &Method) -> Option<Method> {
+        None
+    }
 }
 
 impl<B, E, P> Policy<B, E> for &mut P
@@ -84,6 +224,10 @@ where
         (**self).redirect(attempt)
     }
 
+    fn poll_ready(&mut self, cx: &mut Context<'_>, attempt: &Attempt<'_>) -> Poll<()> {
+        (**self).poll_ready(cx, attempt)
+    }
+
     fn on_request(&mut self, request: &mut Request<B>) {
         (**self).on_request(request)
     }
@@ -91,6 +235,26 @@ where
     fn clone_body(&self, body: &B) -> Option<B> {
         (**self).clone_body(body)
     }
+
+    fn on_error(&mut self, error: &E) {
+        (**self).on_error(error)
+    }
+
+    fn retry_on_error(&mut self, error: &E) -> bool {
+        (**self).retry_on_error(error)
+    }
+
+    fn alternative_location(
+        &mut self,
+        status: StatusCode,
+        headers: &HeaderMap,
+    ) -> Option<HeaderValue> {
+        (**self).alternative_location(status, headers)
+    }
+
+    fn rewrite_method(&self, status: StatusCode, method: &Method) -> Option<Method> {
+        (**self).rewrite_method(status, method)
+    }
 }
 
 impl<B, E, P> Policy<B, E> for Box<P>
@@ -101,6 +265,10 @@ where
         (**self).redirect(attempt)
     }
 
+    fn poll_ready(&mut self, cx: &mut Context<'_>, attempt: &Attempt<'_>) -> Poll<()> {
+        (**self).poll_ready(cx, attempt)
+    }
+
     fn on_request(&mut self, request: &mut Request<B>) {
         (**self).on_request(request)
     }
@@ -108,6 +276,26 @@ where
     fn clone_body(&self, body: &B) -> Option<B> {
         (**self).clone_body(body)
     }
+
+    fn on_error(&mut self, error: &E) {
+        (**self).on_error(error)
+    }
+
+    fn retry_on_error(&mut self, error: &E) -> bool {
+        (**self).retry_on_error(error)
+    }
+
+    fn alternative_location(
+        &mut self,
+        status: StatusCode,
+        headers: &HeaderMap,
+    ) -> Option<HeaderValue> {
+        (**self).alternative_location(status, headers)
+    }
+
+    fn rewrite_method(&self, status: StatusCode, method: &Method) -> Option<Method> {
+        (**self).rewrite_method(status, method)
+    }
 }
 
 /// An extension trait for `Policy` that provides additional adapters.
@@ -166,6 +354,29 @@ pub trait PolicyExt {
     where
         Self: Policy<B, E> + Sized,
         P: Policy<B, E>;
+
+    /// Create a new `Policy` that returns [`Action::Follow`] if `self` returns [`Action::Stop`],
+    /// and the other way around.
+    ///
+    /// See [`not`] for more details.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use tower_http::follow_redirect::{
+    ///     policy::{Limited, PolicyExt, SameOrigin},
+    ///     FollowRedirectLayer,
+    /// };
+    ///
+    /// // Follow only cross-origin redirections, up to 10 of them.
+    /// let policy = SameOrigin::new()
+    ///     .not::<(), ()>()
+    ///     .and::<_, (), ()>(Limited::new(10));
+    /// let layer = FollowRedirectLayer::with_policy(policy);
+    /// ```
+    fn not<B, E>(self) -> Not<Self>
+    where
+        Self: Policy<B, E> + Sized;
 }
 
 impl<T> PolicyExt for T
@@ -187,18 +398,59 @@ where
     {
         Or::new(self, other)
     }
+
+    fn not<B, E>(self) -> Not<Self>
+    where
+        Self: Policy<B, E> + Sized,
+    {
+        not(self)
+    }
 }
 
 /// A redirection [`Policy`] with a reasonable set of standard behavior.
 ///
-/// This policy limits the number of successive redirections ([`Limited`])
-/// and removes credentials from requests in cross-origin redirections ([`FilterCredentials`]).
+/// This policy limits the number of successive redirections ([`Limited`]) and removes
+/// credentials from requests in cross-origin redirections ([`FilterCredentials`]).
+///
+/// The credentials are the `Authorization`, `Cookie` and `Proxy-Authorization` headers, which are
+/// removed when the redirection changes the scheme, host or port of the request.
 pub type Standard = And<Limited, FilterCredentials>;
 
+impl Standard {
+    /// Configure `self` to keep credentials in cross-origin redirections, instead of removing
+    /// them.
+    ///
+    /// Only enable this if every origin the requests may be redirected to is trusted with the
+    /// credentials.
+    pub fn preserve_credentials_cross_host(mut self, enable: bool) -> Self {
+        self.b = self.b.block_cross_origin(!enable);
+        self
+    }
+
+    /// Returns a policy that changes the method of `POST` requests to `GET` in
+    /// `301 Moved Permanently` and `302 Found` redirections if `enable` is `true`, like user
+    /// agents and [`Standard`] itself do.
+    ///
+    /// When disabled, the method and the body of requests are kept, as in `307 Temporary
+    /// Redirect` redirections. Since [`Standard`] doesn't clone request bodies, the redirection
+    /// is then only followed if the request body is empty, unless the returned policy is
+    /// combined with one that clones them. See [`RewritePostToGet`] for more details.
+    pub fn rewrite_post_to_get(self, enable: bool) -> And<Self, RewritePostToGet> {
+        And {
+            a: self,
+            b: RewritePostToGet::new(enable),
+        }
+    }
+}
+
 /// A type that holds information on a redirection attempt.
 pub struct Attempt<'a> {
     pub(crate) status: StatusCode,
+    pub(crate) method: &'a Method,
     pub(crate) location: &'a Uri,
+    pub(crate) raw_location: &'a HeaderValue,
+    pub(crate) headers: &'a HeaderMap,
+    pub(crate) extensions: &'a Extensions,
     pub(crate) previous: &'a Uri,
 }
 
@@ -208,11 +460,40 @@ impl<'a> Attempt<'a> {
         self.status
     }
 
+    /// Returns the method of the request that received the redirection response.
+    ///
+    /// This is the method before any change made for the redirection, such as from `POST` to
+    /// `GET` for `303 See Other`.
+    pub fn method(&self) -> &'a Method {
+        self.method
+    }
+
     /// Returns the destination URI of the redirection.
     pub fn location(&self) -> &'a Uri {
         self.location
     }
 
+    /// Returns the value of the `Location` header of the redirection response, as received.
+    ///
+    /// Unlike [`location`](Self::location), this has not been resolved against the URI of the
+    /// previous request.
+    pub fn raw_location(&self) -> &'a HeaderValue {
+        self.raw_location
+    }
+
+    /// Returns the headers of the redirection response.
+    pub fn headers(&self) -> &'a HeaderMap {
+        self.headers
+    }
+
+    /// Returns the extensions of the redirection response.
+    ///
+    /// Inner services, such as a TLS connector, can use them to pass information about the
+    /// response to policies.
+    pub fn extensions(&self) -> &'a Extensions {
+        self.extensions
+    }
+
     /// Returns the URI of the original request.
     pub fn previous(&self) -> &'a Uri {
         self.previous
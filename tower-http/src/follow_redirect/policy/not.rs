@@ -0,0 +1,142 @@
+use super::{Action, Attempt, Policy};
+use http::{HeaderMap, HeaderValue, Method, Request, StatusCode};
+use std::task::{Context, Poll};
+
+/// A redirection [`Policy`] that inverts the decision of another `Policy`.
+///
+/// See [`not`] for more details.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Not<P> {
+    inner: P,
+}
+
+/// Create a new redirection [`Policy`] that follows the redirections `inner` stops, and stops the
+/// redirections `inner` follows.
+///
+/// Errors of `inner` are returned unchanged, and the other methods of the returned `Policy` are
+/// delegated to `inner`.
+///
+/// Since a policy that stops is inverted into one that keeps following, combine limits such as
+/// [`Limited`][super::Limited] outside of `not` with [`and`][super::PolicyExt::and], rather than
+/// inverting them along with the condition. A `not` inside an [`or`][super::PolicyExt::or] is
+/// only consulted when the first policy stops.
+///
+/// # Example
+///
+/// ```
+/// use tower_http::follow_redirect::policy::{self, Limited, PolicyExt, SameOrigin};
+///
+/// // Follow only cross-origin redirections, up to the default limit.
+/// let policy = policy::not(SameOrigin::new()).and::<_, (), ()>(Limited::default());
+/// ```
+pub fn not<P>(inner: P) -> Not<P> {
+    Not { inner }
+}
+
+impl<P, B, E> Policy<B, E> for Not<P>
+where
+    P: Policy<B, E>,
+{
+    fn redirect(&mut self, attempt: &Attempt<'_>) -> Result<Action, E> {
+        match self.inner.redirect(attempt)? {
+            Action::Follow => Ok(Action::Stop),
+            Action::Stop => Ok(Action::Follow),
+        }
+    }
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>, attempt: &Attempt<'_>) -> Poll<()> {
+        self.inner.poll_ready(cx, attempt)
+    }
+
+    fn on_request(&mut self, request: &mut Request<B>) {
+        self.inner.on_request(request);
+    }
+
+    fn clone_body(&self, body: &B) -> Option<B> {
+        self.inner.clone_body(body)
+    }
+
+    fn on_error(&mut self, error: &E) {
+        self.inner.on_error(error);
+    }
+
+    fn retry_on_error(&mut self, error: &E) -> bool {
+        self.inner.retry_on_error(error)
+    }
+
+    fn alternative_location(
+        &mut self,
+        status: StatusCode,
+        headers: &HeaderMap,
+    ) -> Option<HeaderValue> {
+        self.inner.alternative_location(status, headers)
+    }
+
+    fn rewrite_method(&self, status: StatusCode, method: &Method) -> Option<Method> {
+        self.inner.rewrite_method(status, method)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::follow_redirect::policy::{Limited, PolicyExt, SameOrigin};
+    use http::{Extensions, Method, Uri};
+
+    fn redirect<P>(policy: &mut P, location: &'static str) -> Result<Action, &'static str>
+    where
+        P: Policy<(), &'static str>,
+    {
+        let attempt = Attempt {
+            status: StatusCode::FOUND,
+            method: &Method::GET,
+            location: &Uri::from_static(location),
+            raw_location: &HeaderValue::from_static(location),
+            headers: &HeaderMap::new(),
+            extensions: &Extensions::new(),
+            previous: &Uri::from_static("http://example.com/old"),
+        };
+        policy.redirect(&attempt)
+    }
+
+    #[test]
+    fn inverts_same_origin() {
+        let mut policy = not(SameOrigin::new());
+        assert!(redirect(&mut policy, "http://example.com/new")
+            .unwrap()
+            .is_stop());
+        assert!(redirect(&mut policy, "http://example.net/new")
+            .unwrap()
+            .is_follow());
+    }
+
+    #[test]
+    fn policy_ext() {
+        let mut policy = SameOrigin::new()
+            .not::<(), &str>()
+            .and::<_, (), &str>(Limited::new(1))
+            .or::<_, (), &str>(Err("stopped"));
+        assert_eq!(
+            redirect(&mut policy, "http://example.com/new").unwrap_err(),
+            "stopped"
+        );
+        assert!(redirect(&mut policy, "http://example.net/new")
+            .unwrap()
+            .is_follow());
+
+        // the limit is reached
+        assert_eq!(
+            redirect(&mut policy, "http://example.net/new").unwrap_err(),
+            "stopped"
+        );
+    }
+
+    #[test]
+    fn forwards_errors() {
+        let mut policy = not(Err::<Action, _>("error"));
+        assert_eq!(
+            redirect(&mut policy, "http://example.com/new").unwrap_err(),
+            "error"
+        );
+    }
+}
@@ -0,0 +1,213 @@
+use super::{eq_origin, Action, Attempt, Policy};
+use http::{HeaderMap, HeaderValue, Method, Request, StatusCode};
+use std::{
+    fmt,
+    task::{Context, Poll},
+};
+
+/// A redirection [`Policy`] that reports the decisions of another policy to a callback.
+///
+/// See [`observe`] for more details.
+#[derive(Clone, Copy)]
+pub struct Observe<P, F> {
+    inner: P,
+    f: F,
+}
+
+impl<P, F> fmt::Debug for Observe<P, F>
+where
+    P: fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Observe")
+            .field("inner", &self.inner)
+            .field("f", &std::any::type_name::<F>())
+            .finish()
+    }
+}
+
+impl<P, F, B, E> Policy<B, E> for Observe<P, F>
+where
+    P: Policy<B, E>,
+    F: Fn(RedirectEvent<'_>),
+{
+    fn redirect(&mut self, attempt: &Attempt<'_>) -> Result<Action, E> {
+        let action = self.inner.redirect(attempt)?;
+        (self.f)(RedirectEvent {
+            status: attempt.status(),
+            from_host: attempt.previous().host(),
+            to_host: attempt.location().host(),
+            cross_origin: !eq_origin(attempt.previous(), attempt.location()),
+            action,
+        });
+        Ok(action)
+    }
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>, attempt: &Attempt<'_>) -> Poll<()> {
+        self.inner.poll_ready(cx, attempt)
+    }
+
+    fn on_request(&mut self, request: &mut Request<B>) {
+        self.inner.on_request(request);
+    }
+
+    fn clone_body(&self, body: &B) -> Option<B> {
+        self.inner.clone_body(body)
+    }
+
+    fn on_error(&mut self, error: &E) {
+        self.inner.on_error(error);
+    }
+
+    fn retry_on_error(&mut self, error: &E) -> bool {
+        self.inner.retry_on_error(error)
+    }
+
+    fn alternative_location(
+        &mut self,
+        status: StatusCode,
+        headers: &HeaderMap,
+    ) -> Option<HeaderValue> {
+        self.inner.alternative_location(status, headers)
+    }
+
+    fn rewrite_method(&self, status: StatusCode, method: &Method) -> Option<Method> {
+        self.inner.rewrite_method(status, method)
+    }
+}
+
+/// Wrap a redirection [`Policy`] to report each of its decisions to the closure
+/// `F: Fn(RedirectEvent<'_>)`.
+///
+/// The closure is called after every successful [`redirect`][Policy::redirect] call of `inner`,
+/// with attributes suitable for recording metrics, for example with OpenTelemetry. Decisions that
+/// resulted in an error are not reported.
+///
+/// # Example
+///
+/// ```
+/// use std::sync::{
+///     atomic::{AtomicUsize, Ordering},
+///     Arc,
+/// };
+/// use tower_http::follow_redirect::policy::{self, Limited};
+///
+/// let cross_origin = Arc::new(AtomicUsize::new(0));
+/// let policy = policy::observe(Limited::default(), {
+///     let cross_origin = cross_origin.clone();
+///     move |event| {
+///         if event.cross_origin() && event.action().is_follow() {
+///             cross_origin.fetch_add(1, Ordering::Relaxed);
+///         }
+///     }
+/// });
+/// ```
+pub fn observe<P, F>(inner: P, f: F) -> Observe<P, F>
+where
+    F: Fn(RedirectEvent<'_>),
+{
+    Observe { inner, f }
+}
+
+/// A decision made by a redirection [`Policy`], reported by [`observe`].
+#[derive(Clone, Copy, Debug)]
+pub struct RedirectEvent<'a> {
+    status: StatusCode,
+    from_host: Option<&'a str>,
+    to_host: Option<&'a str>,
+    cross_origin: bool,
+    action: Action,
+}
+
+impl<'a> RedirectEvent<'a> {
+    /// Returns the status code of the redirection response.
+    pub fn status(&self) -> StatusCode {
+        self.status
+    }
+
+    /// Returns the host of the request that was redirected.
+    pub fn from_host(&self) -> Option<&'a str> {
+        self.from_host
+    }
+
+    /// Returns the host of the destination of the redirection.
+    pub fn to_host(&self) -> Option<&'a str> {
+        self.to_host
+    }
+
+    /// Returns whether the redirection crosses origins.
+    pub fn cross_origin(&self) -> bool {
+        self.cross_origin
+    }
+
+    /// Returns the decision of the policy.
+    pub fn action(&self) -> Action {
+        self.action
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::follow_redirect::policy::Limited;
+    use http::{Extensions, HeaderValue, Method, Uri};
+    use std::sync::{Arc, Mutex};
+
+    #[test]
+    fn reports_decisions() {
+        let events = Arc::new(Mutex::new(Vec::new()));
+        let mut policy = observe(Limited::new(1), {
+            let events = events.clone();
+            move |event: RedirectEvent<'_>| {
+                events.lock().unwrap().push((
+                    event.status(),
+                    event.from_host().map(str::to_owned),
+                    event.to_host().map(str::to_owned),
+                    event.cross_origin(),
+                    event.action().is_follow(),
+                ));
+            }
+        });
+
+        let hops = [
+            ("http://example.com/1", "http://example.com/2"),
+            ("http://example.com/2", "http://www.example.com/3"),
+        ];
+        for (previous, location) in hops {
+            let previous = Uri::from_static(previous);
+            let raw_location = HeaderValue::from_static(location);
+            let location = Uri::from_static(location);
+            let attempt = Attempt {
+                status: StatusCode::FOUND,
+                method: &Method::GET,
+                location: &location,
+                raw_location: &raw_location,
+                headers: &HeaderMap::new(),
+                extensions: &Extensions::new(),
+                previous: &previous,
+            };
+            Policy::<(), ()>::redirect(&mut policy, &attempt).unwrap();
+        }
+
+        let events = events.lock().unwrap();
+        assert_eq!(
+            *events,
+            [
+                (
+                    StatusCode::FOUND,
+                    Some("example.com".to_owned()),
+                    Some("example.com".to_owned()),
+                    false,
+                    true,
+                ),
+                (
+                    StatusCode::FOUND,
+                    Some("example.com".to_owned()),
+                    Some("www.example.com".to_owned()),
+                    true,
+                    false,
+                ),
+            ]
+        );
+    }
+}
@@ -1,5 +1,6 @@
 use super::{Action, Attempt, Policy};
-use http::Request;
+use http::{HeaderMap, HeaderValue, Method, Request, StatusCode};
+use std::task::{ready, Context, Poll};
 
 /// A redirection [`Policy`] that combines the results of two `Policy`s.
 ///
@@ -32,6 +33,11 @@ where
         }
     }
 
+    fn poll_ready(&mut self, cx: &mut Context<'_>, attempt: &Attempt<'_>) -> Poll<()> {
+        ready!(self.a.poll_ready(cx, attempt));
+        self.b.poll_ready(cx, attempt)
+    }
+
     fn on_request(&mut self, request: &mut Request<Bd>) {
         self.a.on_request(request);
         self.b.on_request(request);
@@ -40,12 +46,37 @@ where
     fn clone_body(&self, body: &Bd) -> Option<Bd> {
         self.a.clone_body(body).or_else(|| self.b.clone_body(body))
     }
+
+    fn on_error(&mut self, error: &E) {
+        self.a.on_error(error);
+        self.b.on_error(error);
+    }
+
+    fn retry_on_error(&mut self, error: &E) -> bool {
+        self.a.retry_on_error(error) || self.b.retry_on_error(error)
+    }
+
+    fn alternative_location(
+        &mut self,
+        status: StatusCode,
+        headers: &HeaderMap,
+    ) -> Option<HeaderValue> {
+        self.a
+            .alternative_location(status, headers)
+            .or_else(|| self.b.alternative_location(status, headers))
+    }
+
+    fn rewrite_method(&self, status: StatusCode, method: &Method) -> Option<Method> {
+        self.a
+            .rewrite_method(status, method)
+            .or_else(|| self.b.rewrite_method(status, method))
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use http::Uri;
+    use http::{Extensions, HeaderValue, Method, Uri};
 
     struct Taint<P> {
         policy: P,
@@ -75,7 +106,11 @@ mod tests {
     fn redirect() {
         let attempt = Attempt {
             status: Default::default(),
+            method: &Method::GET,
             location: &Uri::from_static("*"),
+            raw_location: &HeaderValue::from_static("*"),
+            headers: &HeaderMap::new(),
+            extensions: &Extensions::new(),
             previous: &Uri::from_static("*"),
         };
 
@@ -0,0 +1,95 @@
+use super::{Action, Attempt, Policy};
+use http::uri::Authority;
+use std::collections::HashMap;
+
+/// A redirection [`Policy`] that limits the number of redirections to each host.
+///
+/// [`Limited`](super::Limited) bounds the length of the whole chain of redirections, and
+/// policies like [`SameOrigin`](super::SameOrigin) restrict where it may lead, but a host can
+/// still bounce a client between many distinct URIs of its own. [`PerHostLimit`] counts the
+/// redirections to each target authority, compared case-insensitively and including the port,
+/// and stops following redirections with [`Action::Stop`] once one of them exceeds the limit.
+///
+/// The counts cover a single chain of redirections, and are reset for every request made with a
+/// [`FollowRedirect`][crate::follow_redirect::FollowRedirect] middleware.
+///
+/// # Example
+///
+/// ```
+/// use tower_http::follow_redirect::policy::{Limited, PerHostLimit, PolicyExt};
+///
+/// let policy = PerHostLimit::new(3).and::<_, (), ()>(Limited::default());
+/// ```
+#[derive(Clone, Debug)]
+pub struct PerHostLimit {
+    max: usize,
+    redirects: HashMap<Authority, usize>,
+}
+
+impl PerHostLimit {
+    /// Create a new [`PerHostLimit`] with a limit of `max` redirections to each host.
+    pub fn new(max: usize) -> Self {
+        PerHostLimit {
+            max,
+            redirects: HashMap::new(),
+        }
+    }
+}
+
+impl<B, E> Policy<B, E> for PerHostLimit {
+    fn redirect(&mut self, attempt: &Attempt<'_>) -> Result<Action, E> {
+        let authority = match attempt.location().authority() {
+            Some(authority) => authority,
+            None => return Ok(Action::Follow),
+        };
+
+        let redirects = self.redirects.entry(authority.clone()).or_insert(0);
+        if *redirects < self.max {
+            *redirects += 1;
+            Ok(Action::Follow)
+        } else {
+            Ok(Action::Stop)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use http::{Extensions, HeaderMap, HeaderValue, Method, StatusCode, Uri};
+
+    fn redirect(policy: &mut PerHostLimit, previous: &str, location: &str) -> Action {
+        let previous = previous.parse::<Uri>().unwrap();
+        let location = location.parse::<Uri>().unwrap();
+        let attempt = Attempt {
+            status: StatusCode::FOUND,
+            method: &Method::GET,
+            location: &location,
+            raw_location: &HeaderValue::from_static("/new"),
+            headers: &HeaderMap::new(),
+            extensions: &Extensions::new(),
+            previous: &previous,
+        };
+        Policy::<(), ()>::redirect(policy, &attempt).unwrap()
+    }
+
+    #[test]
+    fn stops_after_limit_per_host() {
+        let mut policy = PerHostLimit::new(3);
+        let chain = [
+            "http://example.com/loop",
+            "http://example.com/a",
+            "http://example.com/b",
+            "http://EXAMPLE.com/c",
+            "http://example.com/a",
+        ];
+
+        for hop in chain.windows(2).take(3) {
+            assert!(redirect(&mut policy, hop[0], hop[1]).is_follow());
+        }
+        // other hosts, or other ports, have their own count
+        assert!(redirect(&mut policy, chain[3], "http://example.org/").is_follow());
+        assert!(redirect(&mut policy, chain[3], "http://example.com:8080/").is_follow());
+        assert!(redirect(&mut policy, chain[3], chain[4]).is_stop());
+    }
+}
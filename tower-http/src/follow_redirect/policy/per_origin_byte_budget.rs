@@ -0,0 +1,133 @@
+use super::{eq_origin, Action, Attempt, Policy};
+use http::{header, Uri};
+
+/// A redirection [`Policy`] that stops following redirections to an origin once the responses
+/// received from it exceed a budget of bytes.
+///
+/// The size of a redirection response is its `Content-Length`, as the body of a redirection
+/// response is dropped without being read. Responses without a valid `Content-Length` could be of
+/// any size, so by default they exhaust the budget of their origin. Use
+/// [`unknown_length`](Self::unknown_length) to count them as a fixed number of bytes instead.
+///
+/// The budget of each origin covers a single chain of redirections, and is reset for every
+/// request made with a [`FollowRedirect`][crate::follow_redirect::FollowRedirect] middleware.
+#[derive(Clone, Debug)]
+pub struct PerOriginByteBudget {
+    max_bytes: u64,
+    unknown_length: u64,
+    received: Vec<(Uri, u64)>,
+}
+
+impl PerOriginByteBudget {
+    /// Create a new [`PerOriginByteBudget`] that stops following redirections to an origin once
+    /// more than `max_bytes` bytes were received from it.
+    pub fn new(max_bytes: u64) -> Self {
+        PerOriginByteBudget {
+            max_bytes,
+            unknown_length: u64::MAX,
+            received: Vec::new(),
+        }
+    }
+
+    /// Count redirection responses without a valid `Content-Length` as `bytes` bytes.
+    ///
+    /// By default, they are counted as `u64::MAX` bytes, which exceeds any budget.
+    pub fn unknown_length(mut self, bytes: u64) -> Self {
+        self.unknown_length = bytes;
+        self
+    }
+
+    fn received(&self, uri: &Uri) -> u64 {
+        self.received
+            .iter()
+            .find(|(origin, _)| eq_origin(origin, uri))
+            .map_or(0, |(_, bytes)| *bytes)
+    }
+}
+
+impl<B, E> Policy<B, E> for PerOriginByteBudget {
+    fn redirect(&mut self, attempt: &Attempt<'_>) -> Result<Action, E> {
+        let size = attempt
+            .headers()
+            .get(header::CONTENT_LENGTH)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.parse::<u64>().ok())
+            .unwrap_or(self.unknown_length);
+
+        let previous = attempt.previous();
+        match self
+            .received
+            .iter_mut()
+            .find(|(origin, _)| eq_origin(origin, previous))
+        {
+            Some((_, bytes)) => *bytes = bytes.saturating_add(size),
+            None => self.received.push((previous.clone(), size)),
+        }
+
+        if self.received(attempt.location()) > self.max_bytes {
+            Ok(Action::Stop)
+        } else {
+            Ok(Action::Follow)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use http::{Extensions, HeaderMap, HeaderValue, Method, StatusCode};
+
+    #[test]
+    fn works() {
+        let mut policy = PerOriginByteBudget::new(100);
+
+        let mut headers = HeaderMap::new();
+        headers.insert(header::CONTENT_LENGTH, HeaderValue::from_static("60"));
+        let mut redirect = |previous: &'static str, location: &'static str| {
+            let attempt = Attempt {
+                status: StatusCode::FOUND,
+                method: &Method::GET,
+                location: &Uri::from_static(location),
+                raw_location: &HeaderValue::from_static(location),
+                headers: &headers,
+                extensions: &Extensions::new(),
+                previous: &Uri::from_static(previous),
+            };
+            Policy::<(), ()>::redirect(&mut policy, &attempt).unwrap()
+        };
+
+        assert!(redirect("http://a.example/1", "http://a.example/2").is_follow());
+        assert!(redirect("http://a.example:80/2", "http://b.example/").is_follow());
+        // 120 bytes were received from `http://a.example`
+        assert!(redirect("http://b.example/", "http://a.example/3").is_stop());
+        assert!(redirect("http://b.example/", "https://a.example/3").is_follow());
+    }
+
+    #[test]
+    fn unknown_length() {
+        let redirect = |policy: &mut PerOriginByteBudget, content_length: &'static str| {
+            let mut headers = HeaderMap::new();
+            headers.insert(
+                header::CONTENT_LENGTH,
+                HeaderValue::from_static(content_length),
+            );
+            let attempt = Attempt {
+                status: StatusCode::FOUND,
+                method: &Method::GET,
+                location: &Uri::from_static("http://a.example/2"),
+                raw_location: &HeaderValue::from_static("http://a.example/2"),
+                headers: &headers,
+                extensions: &Extensions::new(),
+                previous: &Uri::from_static("http://a.example/1"),
+            };
+            Policy::<(), ()>::redirect(policy, &attempt).unwrap()
+        };
+
+        assert!(redirect(&mut PerOriginByteBudget::new(100), "invalid").is_stop());
+
+        let mut policy = PerOriginByteBudget::new(100).unknown_length(40);
+        assert!(redirect(&mut policy, "invalid").is_follow());
+        assert!(redirect(&mut policy, "50").is_follow());
+        assert!(redirect(&mut policy, "invalid").is_stop());
+    }
+}
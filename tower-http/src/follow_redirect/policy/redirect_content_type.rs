@@ -0,0 +1,106 @@
+use super::{Action, Attempt, Policy};
+use http::header::CONTENT_TYPE;
+use std::sync::Arc;
+
+/// A redirection [`Policy`] that only follows redirections whose response has no `Content-Type`
+/// or one of the given content types.
+///
+/// Redirection responses usually have no body, or a small HTML page. A redirection with another
+/// content type, such as `application/octet-stream`, may be a resource itself, so this policy
+/// stops there instead of following it.
+///
+/// Content types are matched on their type and subtype, case-insensitively and ignoring any
+/// parameters, so `text/html` matches `Text/HTML; charset=utf-8`.
+///
+/// # Example
+///
+/// ```
+/// use tower_http::follow_redirect::policy::{Limited, PolicyExt, RedirectContentType};
+///
+/// let policy = RedirectContentType::new(&["text/html"]).and::<_, (), ()>(Limited::default());
+/// ```
+#[derive(Clone, Debug)]
+pub struct RedirectContentType {
+    allowed: Arc<[Box<str>]>,
+}
+
+impl RedirectContentType {
+    /// Create a new [`RedirectContentType`] that follows redirections whose response has no
+    /// `Content-Type` or one of the `allowed` content types.
+    pub fn new(allowed: &[&str]) -> Self {
+        let allowed = allowed
+            .iter()
+            .map(|&content_type| Box::from(essence(content_type)))
+            .collect::<Vec<_>>();
+        RedirectContentType {
+            allowed: allowed.into(),
+        }
+    }
+}
+
+impl<B, E> Policy<B, E> for RedirectContentType {
+    fn redirect(&mut self, attempt: &Attempt<'_>) -> Result<Action, E> {
+        let content_type = match attempt.headers().get(CONTENT_TYPE) {
+            Some(content_type) => content_type,
+            None => return Ok(Action::Follow),
+        };
+
+        let allowed = content_type.to_str().map_or(false, |content_type| {
+            let content_type = essence(content_type);
+            self.allowed
+                .iter()
+                .any(|allowed| allowed.eq_ignore_ascii_case(content_type))
+        });
+        if allowed {
+            Ok(Action::Follow)
+        } else {
+            Ok(Action::Stop)
+        }
+    }
+}
+
+// The type and subtype of a content type, without parameters.
+fn essence(content_type: &str) -> &str {
+    content_type.split(';').next().unwrap_or_default().trim()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use http::{Extensions, HeaderMap, HeaderValue, Method, StatusCode, Uri};
+
+    fn redirect(content_type: Option<&'static str>) -> Action {
+        let mut policy = RedirectContentType::new(&["text/html", "text/plain"]);
+        let mut headers = HeaderMap::new();
+        if let Some(content_type) = content_type {
+            headers.insert(CONTENT_TYPE, HeaderValue::from_static(content_type));
+        }
+        let previous = Uri::from_static("http://example.com/old");
+        let location = Uri::from_static("http://example.com/new");
+        let attempt = Attempt {
+            status: StatusCode::FOUND,
+            method: &Method::GET,
+            location: &location,
+            raw_location: &HeaderValue::from_static("/new"),
+            headers: &headers,
+            extensions: &Extensions::new(),
+            previous: &previous,
+        };
+        Policy::<(), ()>::redirect(&mut policy, &attempt).unwrap()
+    }
+
+    #[test]
+    fn follows_allowed_content_types() {
+        assert!(redirect(Some("text/html")).is_follow());
+        assert!(redirect(Some("Text/HTML; charset=utf-8")).is_follow());
+        assert!(redirect(Some("text/plain")).is_follow());
+        assert!(redirect(None).is_follow());
+    }
+
+    #[test]
+    fn stops_other_content_types() {
+        assert!(redirect(Some("application/octet-stream")).is_stop());
+        assert!(redirect(Some("text/htmlx")).is_stop());
+        assert!(redirect(Some("")).is_stop());
+    }
+}
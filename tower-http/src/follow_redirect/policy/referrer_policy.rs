@@ -0,0 +1,206 @@
+use super::{Action, Attempt, Policy};
+use http::{header, uri::Scheme, HeaderValue, Request, Uri};
+
+/// A redirection [`Policy`] that trims or removes the `Referer` header of requests in
+/// redirections, like the [`Referrer-Policy`][mdn] of browsers.
+///
+/// The `Referer` header of a request is kept in the requests made for its redirections, which
+/// can leak a sensitive path or query to another origin. [`ReferrerPolicy`] rewrites it in each
+/// redirected request, depending on the URI of the request and the URI that was redirected from:
+///
+/// - [`no_referrer`](Self::no_referrer) removes it.
+/// - [`no_referrer_when_downgrade`](Self::no_referrer_when_downgrade) removes it when the
+///   request goes from `https` to `http`, and keeps it otherwise.
+/// - [`strict_origin`](Self::strict_origin) removes it when the request goes from `https` to
+///   `http`, and keeps only the origin of the referrer otherwise, such as
+///   `https://example.com/`.
+///
+/// A request goes from `https` to `http` if its scheme is `http`, and the previous URI or the
+/// `Referer` itself is an `https` URI. The initial request is left alone, and so are requests
+/// without a `Referer`. Use [`SetReferer`](super::SetReferer) to set the `Referer` of redirected
+/// requests to the previous URI instead.
+///
+/// # Example
+///
+/// ```
+/// use tower_http::follow_redirect::policy::{Limited, PolicyExt, ReferrerPolicy};
+///
+/// let policy = ReferrerPolicy::strict_origin().and::<_, (), ()>(Limited::default());
+/// ```
+///
+/// [mdn]: https://developer.mozilla.org/en-US/docs/Web/HTTP/Headers/Referrer-Policy
+#[derive(Clone, Debug)]
+pub struct ReferrerPolicy {
+    mode: Mode,
+    previous: Option<Uri>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Mode {
+    NoReferrer,
+    NoReferrerWhenDowngrade,
+    StrictOrigin,
+}
+
+impl ReferrerPolicy {
+    /// Create a new [`ReferrerPolicy`] that removes the `Referer` from every request in
+    /// redirections.
+    pub fn no_referrer() -> Self {
+        Self::with_mode(Mode::NoReferrer)
+    }
+
+    /// Create a new [`ReferrerPolicy`] that removes the `Referer` from requests in redirections
+    /// from `https` to `http`.
+    pub fn no_referrer_when_downgrade() -> Self {
+        Self::with_mode(Mode::NoReferrerWhenDowngrade)
+    }
+
+    /// Create a new [`ReferrerPolicy`] that removes the `Referer` from requests in redirections
+    /// from `https` to `http`, and trims it to its origin in the other ones.
+    pub fn strict_origin() -> Self {
+        Self::with_mode(Mode::StrictOrigin)
+    }
+
+    fn with_mode(mode: Mode) -> Self {
+        ReferrerPolicy {
+            mode,
+            previous: None,
+        }
+    }
+}
+
+impl<B, E> Policy<B, E> for ReferrerPolicy {
+    fn redirect(&mut self, attempt: &Attempt<'_>) -> Result<Action, E> {
+        self.previous = Some(attempt.previous().clone());
+        Ok(Action::Follow)
+    }
+
+    fn on_request(&mut self, request: &mut Request<B>) {
+        // leave the initial request alone
+        let previous = match self.previous.take() {
+            Some(previous) => previous,
+            None => return,
+        };
+        let referer = match request.headers().get(header::REFERER) {
+            Some(referer) => std::str::from_utf8(referer.as_bytes())
+                .ok()
+                .and_then(|referer| referer.parse::<Uri>().ok()),
+            None => return,
+        };
+
+        let downgrade = request.uri().scheme() == Some(&Scheme::HTTP)
+            && (previous.scheme() == Some(&Scheme::HTTPS)
+                || referer.as_ref().and_then(Uri::scheme) == Some(&Scheme::HTTPS));
+        let rewritten = match self.mode {
+            Mode::NoReferrer => None,
+            Mode::NoReferrerWhenDowngrade if downgrade => None,
+            Mode::NoReferrerWhenDowngrade => return,
+            Mode::StrictOrigin if downgrade => None,
+            Mode::StrictOrigin => referer.as_ref().and_then(origin),
+        };
+
+        match rewritten {
+            Some(referer) => {
+                request.headers_mut().insert(header::REFERER, referer);
+            }
+            None => {
+                request.headers_mut().remove(header::REFERER);
+            }
+        }
+    }
+}
+
+fn origin(uri: &Uri) -> Option<HeaderValue> {
+    let value = match (uri.scheme(), uri.host(), uri.port()) {
+        (Some(scheme), Some(host), Some(port)) => format!("{}://{}:{}/", scheme, host, port),
+        (Some(scheme), Some(host), None) => format!("{}://{}/", scheme, host),
+        _ => return None,
+    };
+    HeaderValue::from_str(&value).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use http::{Extensions, HeaderMap, Method, StatusCode};
+
+    const REFERER: &str = "http://user@example.com:8080/account?token=secret";
+
+    fn follow(
+        mut policy: ReferrerPolicy,
+        previous: &'static str,
+        location: &'static str,
+    ) -> Option<HeaderValue> {
+        let mut initial = Request::builder()
+            .uri(previous)
+            .header(header::REFERER, REFERER)
+            .body(())
+            .unwrap();
+        Policy::<(), ()>::on_request(&mut policy, &mut initial);
+        assert_eq!(initial.headers()[header::REFERER], REFERER);
+
+        let attempt = Attempt {
+            status: StatusCode::FOUND,
+            method: &Method::GET,
+            location: &Uri::from_static(location),
+            raw_location: &HeaderValue::from_static(location),
+            headers: &HeaderMap::new(),
+            extensions: &Extensions::new(),
+            previous: &Uri::from_static(previous),
+        };
+        assert!(Policy::<(), ()>::redirect(&mut policy, &attempt)
+            .unwrap()
+            .is_follow());
+
+        let mut request = Request::builder()
+            .uri(location)
+            .header(header::REFERER, REFERER)
+            .body(())
+            .unwrap();
+        Policy::<(), ()>::on_request(&mut policy, &mut request);
+        request.headers().get(header::REFERER).cloned()
+    }
+
+    #[test]
+    fn cross_origin_upgrade() {
+        let (previous, location) = ("http://example.com/old", "https://other.example/new");
+        assert_eq!(
+            follow(ReferrerPolicy::strict_origin(), previous, location).unwrap(),
+            "http://example.com:8080/"
+        );
+        assert_eq!(
+            follow(
+                ReferrerPolicy::no_referrer_when_downgrade(),
+                previous,
+                location
+            )
+            .unwrap(),
+            REFERER
+        );
+        assert_eq!(
+            follow(ReferrerPolicy::no_referrer(), previous, location),
+            None
+        );
+    }
+
+    #[test]
+    fn downgrade() {
+        let (previous, location) = ("https://example.com/old", "http://other.example/new");
+        assert_eq!(
+            follow(ReferrerPolicy::strict_origin(), previous, location),
+            None
+        );
+        assert_eq!(
+            follow(
+                ReferrerPolicy::no_referrer_when_downgrade(),
+                previous,
+                location
+            ),
+            None
+        );
+        assert_eq!(
+            follow(ReferrerPolicy::no_referrer(), previous, location),
+            None
+        );
+    }
+}
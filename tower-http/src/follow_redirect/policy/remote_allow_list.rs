@@ -0,0 +1,228 @@
+use super::{Action, Attempt, Policy};
+use std::{
+    collections::HashMap,
+    fmt,
+    future::Future,
+    pin::Pin,
+    sync::{Arc, Mutex},
+    task::{ready, Context, Poll},
+    time::Duration,
+};
+use tokio::time::Instant;
+use tower_service::Service;
+
+/// A redirection [`Policy`] that asks a lookup service whether to follow redirections to a host.
+///
+/// The lookup service is called with the lowercased host of the location, and responds with
+/// whether redirections to it are allowed. It can for example be an HTTP client for an allow-list
+/// endpoint, adapted with [`map_request`] and [`map_response`].
+///
+/// Responses are cached for `ttl`, for up to `capacity` hosts shared by all clones of the policy,
+/// so that following redirections to the same host doesn't call the lookup service every time.
+/// When the cache is full, expired hosts are evicted first, then the host that expires the
+/// soonest. Errors of the lookup service aren't cached, and stop the redirection with
+/// [`Action::Stop`], like redirections to locations without a host.
+///
+/// This policy decides asynchronously with [`Policy::poll_ready`], so it can be combined with
+/// other policies, and used with a [`FollowRedirect`][crate::follow_redirect::FollowRedirect]
+/// middleware as an [`AsyncPolicy`][super::AsyncPolicy].
+///
+/// # Example
+///
+/// ```
+/// use std::{convert::Infallible, time::Duration};
+/// use tower_http::follow_redirect::policy::{Limited, PolicyExt, RemoteAllowList};
+///
+/// let lookup = tower::service_fn(|host: String| async move {
+///     // ask the allow-list endpoint
+///     Ok::<_, Infallible>(host.ends_with(".example.com"))
+/// });
+/// let policy = RemoteAllowList::new(lookup, 1024, Duration::from_secs(60))
+///     .and::<_, (), ()>(Limited::default());
+/// ```
+///
+/// [`map_request`]: https://docs.rs/tower/latest/tower/trait.ServiceExt.html#method.map_request
+/// [`map_response`]: https://docs.rs/tower/latest/tower/trait.ServiceExt.html#method.map_response
+pub struct RemoteAllowList<L>
+where
+    L: Service<String>,
+{
+    lookup: L,
+    cache: Arc<Mutex<Cache>>,
+    ttl: Duration,
+    capacity: usize,
+    pending: Option<(String, Pin<Box<L::Future>>)>,
+    decision: Option<(String, bool)>,
+}
+
+impl<L> RemoteAllowList<L>
+where
+    L: Service<String, Response = bool>,
+{
+    /// Create a new [`RemoteAllowList`] that calls `lookup` for hosts that aren't cached, and
+    /// caches its responses for `ttl`, for up to `capacity` hosts.
+    pub fn new(lookup: L, capacity: usize, ttl: Duration) -> Self {
+        RemoteAllowList {
+            lookup,
+            cache: Arc::new(Mutex::new(Cache::default())),
+            ttl,
+            capacity,
+            pending: None,
+            decision: None,
+        }
+    }
+}
+
+impl<L> Clone for RemoteAllowList<L>
+where
+    L: Service<String> + Clone,
+{
+    fn clone(&self) -> Self {
+        // a lookup in progress belongs to the attempt of this policy
+        RemoteAllowList {
+            lookup: self.lookup.clone(),
+            cache: self.cache.clone(),
+            ttl: self.ttl,
+            capacity: self.capacity,
+            pending: None,
+            decision: None,
+        }
+    }
+}
+
+impl<L> fmt::Debug for RemoteAllowList<L>
+where
+    L: Service<String> + fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RemoteAllowList")
+            .field("lookup", &self.lookup)
+            .field("ttl", &self.ttl)
+            .field("capacity", &self.capacity)
+            .finish()
+    }
+}
+
+impl<L, B, E> Policy<B, E> for RemoteAllowList<L>
+where
+    L: Service<String, Response = bool>,
+{
+    fn redirect(&mut self, attempt: &Attempt<'_>) -> Result<Action, E> {
+        let allowed = match (attempt.location().host(), self.decision.take()) {
+            (Some(host), Some((decided, allowed))) if host.eq_ignore_ascii_case(&decided) => {
+                allowed
+            }
+            (Some(host), _) => self
+                .cache
+                .lock()
+                .unwrap()
+                .get(&host.to_ascii_lowercase())
+                .unwrap_or(false),
+            (None, _) => false,
+        };
+        if allowed {
+            Ok(Action::Follow)
+        } else {
+            Ok(Action::Stop)
+        }
+    }
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>, attempt: &Attempt<'_>) -> Poll<()> {
+        let host = match attempt.location().host() {
+            Some(host) => host.to_ascii_lowercase(),
+            None => return Poll::Ready(()),
+        };
+        if matches!(&self.decision, Some((decided, _)) if *decided == host) {
+            return Poll::Ready(());
+        }
+        if let Some(allowed) = self.cache.lock().unwrap().get(&host) {
+            self.decision = Some((host, allowed));
+            return Poll::Ready(());
+        }
+
+        if !matches!(&self.pending, Some((pending, _)) if *pending == host) {
+            if ready!(self.lookup.poll_ready(cx)).is_err() {
+                self.decision = Some((host, false));
+                return Poll::Ready(());
+            }
+            let future = self.lookup.call(host.clone());
+            self.pending = Some((host.clone(), Box::pin(future)));
+        }
+        let (_, future) = self.pending.as_mut().unwrap();
+        let allowed = match ready!(future.as_mut().poll(cx)) {
+            Ok(allowed) => {
+                self.cache
+                    .lock()
+                    .unwrap()
+                    .insert(host.clone(), allowed, self.ttl, self.capacity);
+                allowed
+            }
+            Err(_) => false,
+        };
+        self.pending = None;
+        self.decision = Some((host, allowed));
+        Poll::Ready(())
+    }
+}
+
+#[derive(Default)]
+struct Cache {
+    // whether a host is allowed, and when that expires
+    hosts: HashMap<String, (bool, Instant)>,
+}
+
+impl Cache {
+    fn get(&self, host: &str) -> Option<bool> {
+        match self.hosts.get(host) {
+            Some(&(allowed, expires)) if Instant::now() < expires => Some(allowed),
+            _ => None,
+        }
+    }
+
+    fn insert(&mut self, host: String, allowed: bool, ttl: Duration, capacity: usize) {
+        if capacity == 0 {
+            return;
+        }
+        let now = Instant::now();
+        if self.hosts.len() >= capacity && !self.hosts.contains_key(&host) {
+            self.hosts.retain(|_, &mut (_, expires)| now < expires);
+        }
+        if self.hosts.len() >= capacity && !self.hosts.contains_key(&host) {
+            let soonest = self
+                .hosts
+                .iter()
+                .min_by_key(|(_, &(_, expires))| expires)
+                .map(|(host, _)| host.clone());
+            if let Some(soonest) = soonest {
+                self.hosts.remove(&soonest);
+            }
+        }
+        self.hosts.insert(host, (allowed, now + ttl));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test(start_paused = true)]
+    async fn cache_expires_and_evicts() {
+        let ttl = Duration::from_secs(10);
+        let mut cache = Cache::default();
+        cache.insert("a.example".to_owned(), true, ttl, 2);
+        tokio::time::advance(Duration::from_secs(1)).await;
+        cache.insert("b.example".to_owned(), false, ttl, 2);
+        assert_eq!(cache.get("a.example"), Some(true));
+        assert_eq!(cache.get("b.example"), Some(false));
+
+        // evicts the host that expires the soonest
+        cache.insert("c.example".to_owned(), true, ttl, 2);
+        assert_eq!(cache.get("a.example"), None);
+        assert_eq!(cache.get("b.example"), Some(false));
+        assert_eq!(cache.get("c.example"), Some(true));
+
+        tokio::time::advance(ttl).await;
+        assert_eq!(cache.get("b.example"), None);
+        assert_eq!(cache.get("c.example"), None);
+    }
+}
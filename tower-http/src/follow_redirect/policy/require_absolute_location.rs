@@ -0,0 +1,80 @@
+use super::{Action, Attempt, Policy};
+use iri_string::types::UriReferenceStr;
+use std::str;
+
+/// A redirection [`Policy`] that stops at redirections to relative locations.
+///
+/// Redirections are followed only if the raw value of the `Location` header is an absolute URI,
+/// with both a scheme and an authority, such as `https://example.com/new`. Relative references,
+/// such as `/new` or `//example.com/new`, are not resolved against the URI of the previous
+/// request, but stop the chain of redirections.
+///
+/// # Example
+///
+/// ```
+/// use tower_http::follow_redirect::policy::{Limited, PolicyExt, RequireAbsoluteLocation};
+///
+/// let policy = RequireAbsoluteLocation::new().and::<_, (), ()>(Limited::default());
+/// ```
+#[derive(Clone, Copy, Debug, Default)]
+pub struct RequireAbsoluteLocation {
+    _priv: (),
+}
+
+impl RequireAbsoluteLocation {
+    /// Create a new [`RequireAbsoluteLocation`].
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl<B, E> Policy<B, E> for RequireAbsoluteLocation {
+    fn redirect(&mut self, attempt: &Attempt<'_>) -> Result<Action, E> {
+        let absolute = str::from_utf8(attempt.raw_location().as_bytes())
+            .ok()
+            .and_then(|location| UriReferenceStr::new(location).ok())
+            .map_or(false, |location| {
+                location.scheme_str().is_some() && location.authority_str().is_some()
+            });
+        if absolute {
+            Ok(Action::Follow)
+        } else {
+            Ok(Action::Stop)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use http::{Extensions, HeaderMap, HeaderValue, Method, Uri};
+
+    fn redirect(raw_location: &'static str) -> Action {
+        let previous = Uri::from_static("http://example.com/old");
+        let location = Uri::from_static("http://example.com/new");
+        let attempt = Attempt {
+            status: Default::default(),
+            method: &Method::GET,
+            location: &location,
+            raw_location: &HeaderValue::from_static(raw_location),
+            headers: &HeaderMap::new(),
+            extensions: &Extensions::new(),
+            previous: &previous,
+        };
+        Policy::<(), ()>::redirect(&mut RequireAbsoluteLocation::new(), &attempt).unwrap()
+    }
+
+    #[test]
+    fn follows_absolute_location() {
+        assert!(redirect("http://example.com/new").is_follow());
+        assert!(redirect("https://user@example.com:8443/new?query").is_follow());
+    }
+
+    #[test]
+    fn stops_at_relative_location() {
+        assert!(redirect("/new").is_stop());
+        assert!(redirect("new").is_stop());
+        assert!(redirect("//example.com/new").is_stop());
+        assert!(redirect("?query").is_stop());
+    }
+}
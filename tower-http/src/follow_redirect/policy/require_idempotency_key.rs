@@ -0,0 +1,115 @@
+use super::{Action, Attempt, Policy};
+use http::{header::HeaderName, Method, Request, StatusCode};
+
+/// A redirection [`Policy`] that stops redirections that would resend a non-idempotent request
+/// without an idempotency key.
+///
+/// `307 Temporary Redirect` and `308 Permanent Redirect` responses are followed with the same
+/// method and body, so following them for a method such as `POST` may cause the side effects of
+/// the request to happen twice. This policy stops such redirections unless the request carries an
+/// idempotency key header, `Idempotency-Key` by default, which lets the server detect duplicates.
+///
+/// Other redirections are followed.
+///
+/// # Example
+///
+/// ```
+/// use tower_http::follow_redirect::policy::{Limited, PolicyExt, RequireIdempotencyKey};
+///
+/// let policy = RequireIdempotencyKey::default().and::<_, (), ()>(Limited::default());
+/// ```
+#[derive(Clone, Debug)]
+pub struct RequireIdempotencyKey {
+    header: HeaderName,
+    unsafe_request: bool,
+}
+
+impl RequireIdempotencyKey {
+    /// Create a new [`RequireIdempotencyKey`] that looks for the idempotency key in the `header`
+    /// request header.
+    pub fn new(header: HeaderName) -> Self {
+        RequireIdempotencyKey {
+            header,
+            unsafe_request: false,
+        }
+    }
+}
+
+impl Default for RequireIdempotencyKey {
+    /// Returns the default [`RequireIdempotencyKey`], which looks for the idempotency key in the
+    /// `Idempotency-Key` request header.
+    fn default() -> Self {
+        RequireIdempotencyKey::new(HeaderName::from_static("idempotency-key"))
+    }
+}
+
+impl<B, E> Policy<B, E> for RequireIdempotencyKey {
+    fn redirect(&mut self, attempt: &Attempt<'_>) -> Result<Action, E> {
+        let preserves_body = matches!(
+            attempt.status(),
+            StatusCode::TEMPORARY_REDIRECT | StatusCode::PERMANENT_REDIRECT
+        );
+        if preserves_body && self.unsafe_request {
+            Ok(Action::Stop)
+        } else {
+            Ok(Action::Follow)
+        }
+    }
+
+    fn on_request(&mut self, request: &mut Request<B>) {
+        self.unsafe_request =
+            !is_idempotent(request.method()) && !request.headers().contains_key(&self.header);
+    }
+}
+
+fn is_idempotent(method: &Method) -> bool {
+    matches!(
+        *method,
+        Method::GET | Method::HEAD | Method::PUT | Method::DELETE | Method::OPTIONS | Method::TRACE
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use http::{Extensions, HeaderMap, HeaderValue, Uri};
+
+    fn redirect(mut request: Request<()>, status: StatusCode) -> Action {
+        let mut policy = RequireIdempotencyKey::default();
+        Policy::<(), ()>::on_request(&mut policy, &mut request);
+
+        let location = Uri::from_static("http://example.com/new");
+        let attempt = Attempt {
+            status,
+            method: request.method(),
+            location: &location,
+            raw_location: &HeaderValue::from_static("/new"),
+            headers: &HeaderMap::new(),
+            extensions: &Extensions::new(),
+            previous: request.uri(),
+        };
+        Policy::<(), ()>::redirect(&mut policy, &attempt).unwrap()
+    }
+
+    #[test]
+    fn follows_with_idempotency_key() {
+        let request = Request::post("http://example.com/old")
+            .header("idempotency-key", "8e03978e")
+            .body(())
+            .unwrap();
+        assert!(redirect(request, StatusCode::PERMANENT_REDIRECT).is_follow());
+    }
+
+    #[test]
+    fn stops_without_idempotency_key() {
+        let request = Request::post("http://example.com/old").body(()).unwrap();
+        assert!(redirect(request, StatusCode::PERMANENT_REDIRECT).is_stop());
+
+        // The request is resent as a `GET` without a body.
+        let request = Request::post("http://example.com/old").body(()).unwrap();
+        assert!(redirect(request, StatusCode::SEE_OTHER).is_follow());
+
+        let request = Request::put("http://example.com/old").body(()).unwrap();
+        assert!(redirect(request, StatusCode::PERMANENT_REDIRECT).is_follow());
+    }
+}
@@ -0,0 +1,96 @@
+use super::{Action, Attempt, Policy};
+use http::{header::HeaderName, HeaderValue};
+
+/// A redirection [`Policy`] that only follows redirections whose response carries a header with
+/// a given value.
+///
+/// Some APIs return error pages with a redirection status, and mark their actual redirections
+/// with a header such as `X-Follow: ok`. This policy follows redirections with the header and
+/// stops redirections without it, or with another value.
+///
+/// # Example
+///
+/// ```
+/// use http::{header::HeaderName, HeaderValue};
+/// use tower_http::follow_redirect::policy::{Limited, PolicyExt, RequireResponseHeader};
+///
+/// let policy = RequireResponseHeader::new(
+///     HeaderName::from_static("x-follow"),
+///     HeaderValue::from_static("ok"),
+/// )
+/// .and::<_, (), ()>(Limited::default());
+/// ```
+#[derive(Clone, Debug)]
+pub struct RequireResponseHeader {
+    name: HeaderName,
+    value: HeaderValue,
+}
+
+impl RequireResponseHeader {
+    /// Create a new [`RequireResponseHeader`] that only follows redirections whose response has
+    /// a `name` header equal to `value`.
+    pub fn new(name: HeaderName, value: HeaderValue) -> Self {
+        RequireResponseHeader { name, value }
+    }
+}
+
+impl<B, E> Policy<B, E> for RequireResponseHeader {
+    fn redirect(&mut self, attempt: &Attempt<'_>) -> Result<Action, E> {
+        let matches = attempt
+            .headers()
+            .get_all(&self.name)
+            .iter()
+            .any(|value| *value == self.value);
+        if matches {
+            Ok(Action::Follow)
+        } else {
+            Ok(Action::Stop)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use http::{Extensions, HeaderMap, Method, StatusCode, Uri};
+
+    fn redirect(headers: &HeaderMap) -> Action {
+        let mut policy = RequireResponseHeader::new(
+            HeaderName::from_static("x-follow"),
+            HeaderValue::from_static("ok"),
+        );
+        let previous = Uri::from_static("http://example.com/old");
+        let location = Uri::from_static("http://example.com/new");
+        let attempt = Attempt {
+            status: StatusCode::FOUND,
+            method: &Method::GET,
+            location: &location,
+            raw_location: &HeaderValue::from_static("/new"),
+            headers,
+            extensions: &Extensions::new(),
+            previous: &previous,
+        };
+        Policy::<(), ()>::redirect(&mut policy, &attempt).unwrap()
+    }
+
+    #[test]
+    fn follows_with_matching_header() {
+        let mut headers = HeaderMap::new();
+        headers.insert("x-follow", HeaderValue::from_static("ok"));
+        assert!(redirect(&headers).is_follow());
+
+        let mut headers = HeaderMap::new();
+        headers.append("x-follow", HeaderValue::from_static("maybe"));
+        headers.append("x-follow", HeaderValue::from_static("ok"));
+        assert!(redirect(&headers).is_follow());
+    }
+
+    #[test]
+    fn stops_without_matching_header() {
+        assert!(redirect(&HeaderMap::new()).is_stop());
+
+        let mut headers = HeaderMap::new();
+        headers.insert("x-follow", HeaderValue::from_static("no"));
+        assert!(redirect(&headers).is_stop());
+    }
+}
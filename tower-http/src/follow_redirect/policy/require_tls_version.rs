@@ -0,0 +1,96 @@
+use super::{Action, Attempt, Policy};
+
+/// A redirection [`Policy`] that stops following redirections received over a connection with
+/// a TLS version below a minimum.
+///
+/// The TLS version is read from the [`TlsVersion`] extension of the redirection response.
+/// `FollowRedirect` doesn't know about the connection, so this policy depends on the TLS layer
+/// of the client inserting the negotiated version into each response. Responses without the
+/// extension, such as those received over plain HTTP, are followed. Combine this policy with
+/// [`BlockDowngrade`][super::BlockDowngrade] to also stop at downgrades to plain HTTP.
+///
+/// # Example
+///
+/// ```
+/// use tower_http::follow_redirect::policy::{Limited, PolicyExt, RequireTlsVersion, TlsVersion};
+///
+/// let policy = RequireTlsVersion::new(TlsVersion::Tls1_2).and::<_, (), ()>(Limited::default());
+/// ```
+#[derive(Clone, Copy, Debug)]
+pub struct RequireTlsVersion {
+    min: TlsVersion,
+}
+
+impl RequireTlsVersion {
+    /// Create a new [`RequireTlsVersion`] that only follows redirections received over TLS
+    /// version `min` or higher.
+    pub fn new(min: TlsVersion) -> Self {
+        RequireTlsVersion { min }
+    }
+}
+
+impl<B, E> Policy<B, E> for RequireTlsVersion {
+    fn redirect(&mut self, attempt: &Attempt<'_>) -> Result<Action, E> {
+        match attempt.extensions().get::<TlsVersion>() {
+            Some(version) if *version < self.min => Ok(Action::Stop),
+            _ => Ok(Action::Follow),
+        }
+    }
+}
+
+/// Response [`Extensions`][http::Extensions] value for the TLS version negotiated for the
+/// connection a response was received over.
+///
+/// Used by [`RequireTlsVersion`]. It should be inserted into responses by the TLS layer of the
+/// client.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[non_exhaustive]
+pub enum TlsVersion {
+    /// TLS 1.0.
+    Tls1_0,
+    /// TLS 1.1.
+    Tls1_1,
+    /// TLS 1.2.
+    Tls1_2,
+    /// TLS 1.3.
+    Tls1_3,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use http::{Extensions, HeaderMap, HeaderValue, Method, StatusCode, Uri};
+
+    fn redirect(version: Option<TlsVersion>) -> Action {
+        let mut policy = RequireTlsVersion::new(TlsVersion::Tls1_2);
+        let mut extensions = Extensions::new();
+        if let Some(version) = version {
+            extensions.insert(version);
+        }
+        let previous = Uri::from_static("https://example.com/old");
+        let location = Uri::from_static("https://example.com/new");
+        let attempt = Attempt {
+            status: StatusCode::FOUND,
+            method: &Method::GET,
+            location: &location,
+            raw_location: &HeaderValue::from_static("/new"),
+            headers: &HeaderMap::new(),
+            extensions: &extensions,
+            previous: &previous,
+        };
+        Policy::<(), ()>::redirect(&mut policy, &attempt).unwrap()
+    }
+
+    #[test]
+    fn stops_below_min() {
+        assert!(redirect(Some(TlsVersion::Tls1_0)).is_stop());
+        assert!(redirect(Some(TlsVersion::Tls1_1)).is_stop());
+    }
+
+    #[test]
+    fn follows_at_or_above_min() {
+        assert!(redirect(Some(TlsVersion::Tls1_2)).is_follow());
+        assert!(redirect(Some(TlsVersion::Tls1_3)).is_follow());
+        assert!(redirect(None).is_follow());
+    }
+}
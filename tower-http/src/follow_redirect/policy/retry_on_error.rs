@@ -0,0 +1,127 @@
+use super::{Action, Attempt, Policy};
+use http::{HeaderMap, HeaderValue, Method, Request, StatusCode};
+use std::{
+    fmt,
+    task::{Context, Poll},
+};
+
+/// A redirection [`Policy`] that retries redirected requests that failed with an error.
+///
+/// When the service returns an error for a redirected request and `predicate` returns `true`
+/// for it, the request is made again to the same location, up to `max_retries` times per
+/// redirection. Redirections themselves are handled by the inner policy.
+///
+/// Retrying requires the request body to be cloned, so the inner policy has to be able to
+/// [clone the body][Policy::clone_body] of requests that have one.
+///
+/// # Example
+///
+/// ```
+/// use std::io;
+/// use tower_http::follow_redirect::policy::{Limited, RetryOnError};
+///
+/// // Retry each redirection up to 2 times on connection errors.
+/// let policy = RetryOnError::new(
+///     2,
+///     |err: &io::Error| err.kind() == io::ErrorKind::ConnectionReset,
+///     Limited::default(),
+/// );
+/// ```
+#[derive(Clone, Copy)]
+pub struct RetryOnError<P, F> {
+    inner: P,
+    predicate: F,
+    max_retries: usize,
+    retries: usize,
+}
+
+impl<P, F> RetryOnError<P, F> {
+    /// Create a new [`RetryOnError`] that retries redirected requests up to `max_retries` times
+    /// when `predicate` returns `true` for the error.
+    pub fn new(max_retries: usize, predicate: F, inner: P) -> Self {
+        RetryOnError {
+            inner,
+            predicate,
+            max_retries,
+            retries: 0,
+        }
+    }
+}
+
+impl<P, F> fmt::Debug for RetryOnError<P, F>
+where
+    P: fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RetryOnError")
+            .field("inner", &self.inner)
+            .field("predicate", &std::any::type_name::<F>())
+            .field("max_retries", &self.max_retries)
+            .field("retries", &self.retries)
+            .finish()
+    }
+}
+
+impl<P, F, B, E> Policy<B, E> for RetryOnError<P, F>
+where
+    P: Policy<B, E>,
+    F: Fn(&E) -> bool,
+{
+    fn redirect(&mut self, attempt: &Attempt<'_>) -> Result<Action, E> {
+        // A redirection response starts a new hop with its own retries.
+        self.retries = 0;
+        self.inner.redirect(attempt)
+    }
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>, attempt: &Attempt<'_>) -> Poll<()> {
+        self.inner.poll_ready(cx, attempt)
+    }
+
+    fn on_request(&mut self, request: &mut Request<B>) {
+        self.inner.on_request(request);
+    }
+
+    fn clone_body(&self, body: &B) -> Option<B> {
+        self.inner.clone_body(body)
+    }
+
+    fn on_error(&mut self, error: &E) {
+        self.inner.on_error(error);
+    }
+
+    fn retry_on_error(&mut self, error: &E) -> bool {
+        if self.retries < self.max_retries && (self.predicate)(error) {
+            self.retries += 1;
+            true
+        } else {
+            self.inner.retry_on_error(error)
+        }
+    }
+
+    fn alternative_location(
+        &mut self,
+        status: StatusCode,
+        headers: &HeaderMap,
+    ) -> Option<HeaderValue> {
+        self.inner.alternative_location(status, headers)
+    }
+
+    fn rewrite_method(&self, status: StatusCode, method: &Method) -> Option<Method> {
+        self.inner.rewrite_method(status, method)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn retries_up_to_max_retries() {
+        let mut policy = RetryOnError::new(2, |err: &&str| *err == "retry", Action::Follow);
+
+        assert!(!Policy::<(), _>::retry_on_error(&mut policy, &"fatal"));
+        assert!(Policy::<(), _>::retry_on_error(&mut policy, &"retry"));
+        assert!(Policy::<(), _>::retry_on_error(&mut policy, &"retry"));
+        assert!(!Policy::<(), _>::retry_on_error(&mut policy, &"retry"));
+    }
+}
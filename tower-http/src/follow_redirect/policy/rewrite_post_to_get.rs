@@ -0,0 +1,56 @@
+use super::{Action, Attempt, Policy};
+use http::{Method, StatusCode};
+
+/// A redirection [`Policy`] that decides whether `POST` requests are changed to `GET` in
+/// `301 Moved Permanently` and `302 Found` redirections.
+///
+/// User agents make the redirected request of a `POST` request with `GET` and without a body in
+/// these redirections, and so does a [`FollowRedirect`][crate::follow_redirect::FollowRedirect]
+/// middleware by default. Some APIs expect the request to be replayed instead, as in
+/// `307 Temporary Redirect` redirections. When disabled, this policy keeps the method and the
+/// body of requests, which are then only redirected if the body can be cloned, as described in
+/// [`Policy::clone_body`].
+///
+/// It can be added to the [`Standard`](super::Standard) policy with
+/// [`Standard::rewrite_post_to_get`](super::Standard::rewrite_post_to_get).
+///
+/// # Example
+///
+/// ```
+/// use tower_http::follow_redirect::policy::{Limited, PolicyExt, RewritePostToGet};
+///
+/// let policy = RewritePostToGet::new(false).and::<_, (), ()>(Limited::default());
+/// ```
+#[derive(Clone, Copy, Debug)]
+pub struct RewritePostToGet {
+    enable: bool,
+}
+
+impl RewritePostToGet {
+    /// Create a new [`RewritePostToGet`] that changes `POST` requests to `GET` if `enable` is
+    /// `true`, and keeps their method otherwise.
+    pub fn new(enable: bool) -> Self {
+        RewritePostToGet { enable }
+    }
+}
+
+impl Default for RewritePostToGet {
+    fn default() -> Self {
+        Self::new(true)
+    }
+}
+
+impl<B, E> Policy<B, E> for RewritePostToGet {
+    fn redirect(&mut self, _: &Attempt<'_>) -> Result<Action, E> {
+        Ok(Action::Follow)
+    }
+
+    fn rewrite_method(&self, status: StatusCode, method: &Method) -> Option<Method> {
+        match status {
+            StatusCode::MOVED_PERMANENTLY | StatusCode::FOUND if !self.enable => {
+                Some(method.clone())
+            }
+            _ => None,
+        }
+    }
+}
@@ -2,6 +2,11 @@ use super::{eq_origin, Action, Attempt, Policy};
 use std::fmt;
 
 /// A redirection [`Policy`] that stops cross-origin redirections.
+///
+/// Redirections are followed only if the scheme, host and port of the location are the same as
+/// those of the previous request, where a missing port is the default port of the scheme. That is,
+/// `https://example.com:443/` has the same origin as `https://example.com/`, but not as
+/// `http://example.com:80/`.
 #[derive(Clone, Copy, Default)]
 pub struct SameOrigin {
     _priv: (),
@@ -33,7 +38,7 @@ impl<B, E> Policy<B, E> for SameOrigin {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use http::{Request, Uri};
+    use http::{Extensions, HeaderMap, HeaderValue, Method, Request, Uri};
 
     #[test]
     fn works() {
@@ -48,7 +53,11 @@ mod tests {
 
         let attempt = Attempt {
             status: Default::default(),
+            method: &Method::GET,
             location: &same_origin,
+            raw_location: &HeaderValue::from_static("http://example.com/new"),
+            headers: &HeaderMap::new(),
+            extensions: &Extensions::new(),
             previous: request.uri(),
         };
         assert!(Policy::<(), ()>::redirect(&mut policy, &attempt)
@@ -60,11 +69,41 @@ mod tests {
 
         let attempt = Attempt {
             status: Default::default(),
+            method: &Method::GET,
             location: &cross_origin,
+            raw_location: &HeaderValue::from_static("https://example.com/new"),
+            headers: &HeaderMap::new(),
+            extensions: &Extensions::new(),
             previous: request.uri(),
         };
         assert!(Policy::<(), ()>::redirect(&mut policy, &attempt)
             .unwrap()
             .is_stop());
     }
+
+    #[test]
+    fn compares_scheme_host_and_port() {
+        let redirect = |previous: &'static str, location: &'static str| {
+            let attempt = Attempt {
+                status: Default::default(),
+                method: &Method::GET,
+                location: &Uri::from_static(location),
+                raw_location: &HeaderValue::from_static(location),
+                headers: &HeaderMap::new(),
+                extensions: &Extensions::new(),
+                previous: &Uri::from_static(previous),
+            };
+            Policy::<(), ()>::redirect(&mut SameOrigin::new(), &attempt).unwrap()
+        };
+
+        // scheme downgrade, even with the default port of each scheme
+        assert!(redirect("https://example.com/", "http://example.com/").is_stop());
+        assert!(redirect("https://example.com:443/", "http://example.com:80/").is_stop());
+        // explicit default ports are the same as implicit ones
+        assert!(redirect("https://example.com/", "https://example.com:443/new").is_follow());
+        assert!(redirect("http://example.com:80/", "http://example.com/new").is_follow());
+        assert!(redirect("https://example.com/", "https://example.com:8443/").is_stop());
+        // cross-host
+        assert!(redirect("https://example.com/", "https://www.example.com/").is_stop());
+    }
 }
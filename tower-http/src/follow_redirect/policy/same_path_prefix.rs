@@ -0,0 +1,85 @@
+use super::{Action, Attempt, Policy};
+use std::sync::Arc;
+
+/// A redirection [`Policy`] that only follows redirections within a path prefix.
+///
+/// The prefix is matched against whole path segments, so a prefix of `/api` matches the paths
+/// `/api` and `/api/users`, but not `/apis`. The location of the redirection is resolved against
+/// the previous URI, so relative locations are supported as well.
+///
+/// # Example
+///
+/// ```
+/// use tower_http::follow_redirect::policy::{Limited, PolicyExt, SamePathPrefix};
+///
+/// // Only follow redirections that stay under `/api`.
+/// let policy = SamePathPrefix::new("/api").and::<_, (), ()>(Limited::default());
+/// ```
+#[derive(Clone, Debug)]
+pub struct SamePathPrefix {
+    prefix: Arc<str>,
+}
+
+impl SamePathPrefix {
+    /// Create a new [`SamePathPrefix`] that follows redirections to paths starting with `prefix`.
+    pub fn new(prefix: &str) -> Self {
+        SamePathPrefix {
+            prefix: prefix.trim_end_matches('/').into(),
+        }
+    }
+
+    fn matches(&self, path: &str) -> bool {
+        path.strip_prefix(&*self.prefix)
+            .map_or(false, |rest| rest.is_empty() || rest.starts_with('/'))
+    }
+}
+
+impl<B, E> Policy<B, E> for SamePathPrefix {
+    fn redirect(&mut self, attempt: &Attempt<'_>) -> Result<Action, E> {
+        if self.matches(attempt.location().path()) {
+            Ok(Action::Follow)
+        } else {
+            Ok(Action::Stop)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use http::{Extensions, HeaderMap, HeaderValue, Method, Uri};
+
+    fn redirect(policy: &mut SamePathPrefix, location: &'static str) -> Action {
+        let previous = Uri::from_static("http://example.com/api/old");
+        let raw_location = HeaderValue::from_static(location);
+        let location = Uri::from_static(location);
+        let attempt = Attempt {
+            status: Default::default(),
+            method: &Method::GET,
+            location: &location,
+            raw_location: &raw_location,
+            headers: &HeaderMap::new(),
+            extensions: &Extensions::new(),
+            previous: &previous,
+        };
+        Policy::<(), ()>::redirect(policy, &attempt).unwrap()
+    }
+
+    #[test]
+    fn follows_within_prefix() {
+        let mut policy = SamePathPrefix::new("/api");
+
+        assert!(redirect(&mut policy, "http://example.com/api").is_follow());
+        assert!(redirect(&mut policy, "http://example.com/api/").is_follow());
+        assert!(redirect(&mut policy, "http://example.com/api/new?a=b").is_follow());
+    }
+
+    #[test]
+    fn stops_outside_prefix() {
+        let mut policy = SamePathPrefix::new("/api/");
+
+        assert!(redirect(&mut policy, "http://example.com/").is_stop());
+        assert!(redirect(&mut policy, "http://example.com/apis").is_stop());
+        assert!(redirect(&mut policy, "http://example.com/login?next=/api").is_stop());
+    }
+}
@@ -0,0 +1,119 @@
+use super::{Action, Attempt, Policy};
+use http::{uri::Scheme, Request};
+
+/// A redirection [`Policy`] that stops redirections changing the scheme of the original request.
+///
+/// Unlike a downgrade protection, this stops both `http` to `https` and `https` to `http`
+/// redirections. Relative locations inherit the scheme of the previous request, so they are
+/// always followed.
+///
+/// # Example
+///
+/// ```
+/// use tower_http::follow_redirect::policy::{Limited, PolicyExt, SameScheme};
+///
+/// let policy = SameScheme::new().and::<_, (), ()>(Limited::default());
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct SameScheme {
+    scheme: Option<Scheme>,
+}
+
+impl SameScheme {
+    /// Create a new [`SameScheme`].
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl<B, E> Policy<B, E> for SameScheme {
+    fn redirect(&mut self, attempt: &Attempt<'_>) -> Result<Action, E> {
+        let original = self.scheme.as_ref().or_else(|| attempt.previous().scheme());
+        if attempt.location().scheme() == original {
+            Ok(Action::Follow)
+        } else {
+            Ok(Action::Stop)
+        }
+    }
+
+    fn on_request(&mut self, request: &mut Request<B>) {
+        if self.scheme.is_none() {
+            self.scheme = request.uri().scheme().cloned();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use http::{Extensions, HeaderMap, HeaderValue, Method, Uri};
+
+    #[test]
+    fn works() {
+        let mut policy = SameScheme::default();
+
+        let initial = Uri::from_static("http://example.com/old");
+        let same_scheme = Uri::from_static("http://www.example.com/new");
+        let other_scheme = Uri::from_static("https://example.com/new");
+
+        let mut request = Request::builder().uri(initial).body(()).unwrap();
+        Policy::<(), ()>::on_request(&mut policy, &mut request);
+
+        let attempt = Attempt {
+            status: Default::default(),
+            method: &Method::GET,
+            location: &same_scheme,
+            raw_location: &HeaderValue::from_static("http://www.example.com/new"),
+            headers: &HeaderMap::new(),
+            extensions: &Extensions::new(),
+            previous: request.uri(),
+        };
+        assert!(Policy::<(), ()>::redirect(&mut policy, &attempt)
+            .unwrap()
+            .is_follow());
+
+        let mut request = Request::builder().uri(same_scheme).body(()).unwrap();
+        Policy::<(), ()>::on_request(&mut policy, &mut request);
+
+        let attempt = Attempt {
+            status: Default::default(),
+            method: &Method::GET,
+            location: &other_scheme,
+            raw_location: &HeaderValue::from_static("https://example.com/new"),
+            headers: &HeaderMap::new(),
+            extensions: &Extensions::new(),
+            previous: request.uri(),
+        };
+        assert!(Policy::<(), ()>::redirect(&mut policy, &attempt)
+            .unwrap()
+            .is_stop());
+    }
+
+    #[test]
+    fn compares_against_original_scheme() {
+        let mut policy = SameScheme::default();
+
+        let initial = Uri::from_static("https://example.com/old");
+        let mut request = Request::builder().uri(initial).body(()).unwrap();
+        Policy::<(), ()>::on_request(&mut policy, &mut request);
+
+        // A later hop with a different scheme doesn't change the scheme to keep.
+        let previous = Uri::from_static("http://example.com/new");
+        let mut request = Request::builder().uri(previous).body(()).unwrap();
+        Policy::<(), ()>::on_request(&mut policy, &mut request);
+
+        let location = Uri::from_static("http://example.com/newer");
+        let attempt = Attempt {
+            status: Default::default(),
+            method: &Method::GET,
+            location: &location,
+            raw_location: &HeaderValue::from_static("/newer"),
+            headers: &HeaderMap::new(),
+            extensions: &Extensions::new(),
+            previous: request.uri(),
+        };
+        assert!(Policy::<(), ()>::redirect(&mut policy, &attempt)
+            .unwrap()
+            .is_stop());
+    }
+}
@@ -0,0 +1,214 @@
+use super::{Action, Attempt, Policy};
+use http::{HeaderMap, HeaderValue, Method, Request, StatusCode};
+use std::{
+    collections::hash_map::RandomState,
+    hash::{BuildHasher, Hasher},
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
+    task::{Context, Poll},
+};
+
+/// A redirection [`Policy`] that only follows redirections for a sample of requests.
+///
+/// For each request made through a [`FollowRedirect`][super::super::FollowRedirect] middleware,
+/// `Sampled` decides once whether the request is part of the sample. Redirections of sampled
+/// requests are handled by the inner policy, while redirection responses of other requests are
+/// returned as-is.
+///
+/// This can for example be used for A/B testing how clients handle redirection responses.
+///
+/// # Example
+///
+/// ```
+/// use tower_http::follow_redirect::policy::{Limited, Sampled};
+///
+/// // Follow redirections for roughly 10% of the requests.
+/// let policy = Sampled::new(0.1, Limited::default());
+///
+/// // Use a fixed seed to make the sampling reproducible.
+/// let policy = Sampled::new(0.1, Limited::default()).seed(42);
+/// ```
+#[derive(Clone, Debug)]
+pub struct Sampled<P> {
+    inner: P,
+    rate: f64,
+    state: Arc<AtomicU64>,
+    follow: Option<bool>,
+}
+
+impl<P> Sampled<P> {
+    /// Create a new [`Sampled`] that delegates to `inner` for a `rate` fraction of the requests.
+    ///
+    /// A `rate` of `1.0` (or more) follows redirections for every request, and a `rate` of `0.0`
+    /// (or less) never follows redirections.
+    pub fn new(rate: f64, inner: P) -> Self {
+        let seed = RandomState::new().build_hasher().finish();
+        Sampled {
+            inner,
+            rate,
+            state: Arc::new(AtomicU64::new(seed)),
+            follow: None,
+        }
+    }
+
+    /// Seed the random number generator used for sampling.
+    ///
+    /// Requests are then sampled in a deterministic sequence, which is shared by all clones of
+    /// the returned policy.
+    pub fn seed(mut self, seed: u64) -> Self {
+        self.state = Arc::new(AtomicU64::new(seed));
+        self
+    }
+
+    fn follow(&mut self) -> bool {
+        if let Some(follow) = self.follow {
+            return follow;
+        }
+        let follow = next_f64(&self.state) < self.rate;
+        self.follow = Some(follow);
+        follow
+    }
+}
+
+impl<P, B, E> Policy<B, E> for Sampled<P>
+where
+    P: Policy<B, E>,
+{
+    fn redirect(&mut self, attempt: &Attempt<'_>) -> Result<Action, E> {
+        if self.follow() {
+            self.inner.redirect(attempt)
+        } else {
+            Ok(Action::Stop)
+        }
+    }
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>, attempt: &Attempt<'_>) -> Poll<()> {
+        self.inner.poll_ready(cx, attempt)
+    }
+
+    fn on_request(&mut self, request: &mut Request<B>) {
+        // The first call is made for the initial request, which decides the sample for the
+        // entire chain of redirections.
+        self.follow();
+        self.inner.on_request(request);
+    }
+
+    fn clone_body(&self, body: &B) -> Option<B> {
+        self.inner.clone_body(body)
+    }
+
+    fn on_error(&mut self, error: &E) {
+        self.inner.on_error(error);
+    }
+
+    fn retry_on_error(&mut self, error: &E) -> bool {
+        self.inner.retry_on_error(error)
+    }
+
+    fn alternative_location(
+        &mut self,
+        status: StatusCode,
+        headers: &HeaderMap,
+    ) -> Option<HeaderValue> {
+        self.inner.alternative_location(status, headers)
+    }
+
+    fn rewrite_method(&self, status: StatusCode, method: &Method) -> Option<Method> {
+        self.inner.rewrite_method(status, method)
+    }
+}
+
+/// Returns a pseudo-random number in `[0, 1)` using the SplitMix64 generator.
+fn next_f64(state: &AtomicU64) -> f64 {
+    const GAMMA: u64 = 0x9e37_79b9_7f4a_7c15;
+
+    let mut z = state
+        .fetch_add(GAMMA, Ordering::Relaxed)
+        .wrapping_add(GAMMA);
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    z ^= z >> 31;
+
+    (z >> 11) as f64 / (1u64 << 53) as f64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use http::{Extensions, HeaderValue, Method, Uri};
+
+    fn sample(policy: &Sampled<Action>) -> Action {
+        let uri = Uri::from_static("https://example.com/");
+        let mut policy = policy.clone();
+
+        let mut request = Request::builder().uri(uri.clone()).body(()).unwrap();
+        Policy::<(), ()>::on_request(&mut policy, &mut request);
+
+        let attempt = Attempt {
+            status: Default::default(),
+            method: &Method::GET,
+            location: &uri,
+            raw_location: &HeaderValue::from_static("https://example.com/"),
+            headers: &HeaderMap::new(),
+            extensions: &Extensions::new(),
+            previous: &uri,
+        };
+        Policy::<(), ()>::redirect(&mut policy, &attempt).unwrap()
+    }
+
+    #[test]
+    fn always_follows() {
+        let policy = Sampled::new(1.0, Action::Follow);
+        for _ in 0..100 {
+            assert!(sample(&policy).is_follow());
+        }
+    }
+
+    #[test]
+    fn always_stops() {
+        let policy = Sampled::new(0.0, Action::Follow);
+        for _ in 0..100 {
+            assert!(sample(&policy).is_stop());
+        }
+    }
+
+    #[test]
+    fn seeded_is_deterministic() {
+        let a = Sampled::new(0.5, Action::Follow).seed(7);
+        let b = Sampled::new(0.5, Action::Follow).seed(7);
+        let a = (0..100).map(|_| sample(&a).is_follow()).collect::<Vec<_>>();
+        let b = (0..100).map(|_| sample(&b).is_follow()).collect::<Vec<_>>();
+        assert_eq!(a, b);
+        assert!(a.contains(&true));
+        assert!(a.contains(&false));
+    }
+
+    #[test]
+    fn decides_once_per_request() {
+        let uri = Uri::from_static("https://example.com/");
+        let mut policy = Sampled::new(0.5, Action::Follow).seed(3);
+
+        let mut request = Request::builder().uri(uri.clone()).body(()).unwrap();
+        Policy::<(), ()>::on_request(&mut policy, &mut request);
+
+        let attempt = Attempt {
+            status: Default::default(),
+            method: &Method::GET,
+            location: &uri,
+            raw_location: &HeaderValue::from_static("https://example.com/"),
+            headers: &HeaderMap::new(),
+            extensions: &Extensions::new(),
+            previous: &uri,
+        };
+        let first = Policy::<(), ()>::redirect(&mut policy, &attempt)
+            .unwrap()
+            .is_follow();
+        for _ in 0..10 {
+            Policy::<(), ()>::on_request(&mut policy, &mut request);
+            let action = Policy::<(), ()>::redirect(&mut policy, &attempt).unwrap();
+            assert_eq!(action.is_follow(), first);
+        }
+    }
+}
@@ -0,0 +1,169 @@
+use super::{Action, Attempt, Policy};
+use std::{
+    collections::HashSet,
+    sync::{Arc, RwLock},
+};
+
+/// A redirection [`Policy`] that only follows redirections to the hosts of a list that can be
+/// updated at runtime.
+///
+/// The list is shared by all clones of the policy, and thus by all requests made with a
+/// [`FollowRedirect`][crate::follow_redirect::FollowRedirect] middleware. It is updated through
+/// an [`AllowListHandle`], and every redirection is checked against the current list, so updates
+/// apply to requests that are already in flight too. Hosts are compared case-insensitively,
+/// regardless of the port.
+///
+/// Redirections to other hosts, or to locations without a host, are stopped with
+/// [`Action::Stop`].
+///
+/// # Example
+///
+/// ```
+/// use tower_http::follow_redirect::policy::{Limited, PolicyExt, SharedAllowList};
+///
+/// let allow_list = SharedAllowList::new(["example.com"]);
+/// let handle = allow_list.handle();
+/// let policy = allow_list.and::<_, (), ()>(Limited::default());
+///
+/// // later, for example when the configuration is reloaded
+/// handle.replace(["example.com", "cdn.example.com"]);
+/// ```
+#[derive(Clone, Debug)]
+pub struct SharedAllowList {
+    hosts: Arc<RwLock<HashSet<String>>>,
+}
+
+impl SharedAllowList {
+    /// Create a new [`SharedAllowList`] that follows redirections to the `hosts`.
+    pub fn new<I, T>(hosts: I) -> Self
+    where
+        I: IntoIterator<Item = T>,
+        T: AsRef<str>,
+    {
+        SharedAllowList {
+            hosts: Arc::new(RwLock::new(normalize(hosts))),
+        }
+    }
+
+    /// Returns an [`AllowListHandle`] to update the list of hosts.
+    pub fn handle(&self) -> AllowListHandle {
+        AllowListHandle {
+            hosts: self.hosts.clone(),
+        }
+    }
+}
+
+impl<B, E> Policy<B, E> for SharedAllowList {
+    fn redirect(&mut self, attempt: &Attempt<'_>) -> Result<Action, E> {
+        let allowed = attempt.location().host().map_or(false, |host| {
+            let hosts = self.hosts.read().unwrap();
+            hosts.contains(&host.to_ascii_lowercase())
+        });
+        if allowed {
+            Ok(Action::Follow)
+        } else {
+            Ok(Action::Stop)
+        }
+    }
+}
+
+/// A handle to update the hosts of a [`SharedAllowList`].
+///
+/// Created with [`SharedAllowList::handle`].
+#[derive(Clone, Debug)]
+pub struct AllowListHandle {
+    hosts: Arc<RwLock<HashSet<String>>>,
+}
+
+impl AllowListHandle {
+    /// Replace the whole list of hosts.
+    pub fn replace<I, T>(&self, hosts: I)
+    where
+        I: IntoIterator<Item = T>,
+        T: AsRef<str>,
+    {
+        let hosts = normalize(hosts);
+        *self.hosts.write().unwrap() = hosts;
+    }
+
+    /// Add a host to the list.
+    pub fn insert(&self, host: &str) {
+        self.hosts
+            .write()
+            .unwrap()
+            .insert(host.to_ascii_lowercase());
+    }
+
+    /// Remove a host from the list.
+    pub fn remove(&self, host: &str) {
+        self.hosts
+            .write()
+            .unwrap()
+            .remove(&host.to_ascii_lowercase());
+    }
+
+    /// Returns whether the host is in the list.
+    pub fn contains(&self, host: &str) -> bool {
+        self.hosts
+            .read()
+            .unwrap()
+            .contains(&host.to_ascii_lowercase())
+    }
+}
+
+fn normalize<I, T>(hosts: I) -> HashSet<String>
+where
+    I: IntoIterator<Item = T>,
+    T: AsRef<str>,
+{
+    hosts
+        .into_iter()
+        .map(|host| host.as_ref().to_ascii_lowercase())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use http::{Extensions, HeaderMap, HeaderValue, Method, StatusCode, Uri};
+
+    fn redirect(policy: &mut SharedAllowList, location: &'static str) -> Action {
+        let attempt = Attempt {
+            status: StatusCode::FOUND,
+            method: &Method::GET,
+            location: &Uri::from_static(location),
+            raw_location: &HeaderValue::from_static(location),
+            headers: &HeaderMap::new(),
+            extensions: &Extensions::new(),
+            previous: &Uri::from_static("http://example.com/"),
+        };
+        Policy::<(), ()>::redirect(policy, &attempt).unwrap()
+    }
+
+    #[test]
+    fn follows_listed_hosts() {
+        let mut policy = SharedAllowList::new(["Example.com"]);
+        assert!(redirect(&mut policy, "http://example.com/").is_follow());
+        assert!(redirect(&mut policy, "https://EXAMPLE.com:8443/").is_follow());
+        assert!(redirect(&mut policy, "http://www.example.com/").is_stop());
+        assert!(redirect(&mut policy, "/relative").is_stop());
+    }
+
+    #[test]
+    fn updates_every_clone() {
+        let mut policy = SharedAllowList::new(["example.com"]);
+        let handle = policy.handle();
+        // as made for another request
+        let mut clone = policy.clone();
+
+        handle.insert("cdn.example.com");
+        assert!(handle.contains("CDN.example.com"));
+        assert!(redirect(&mut policy, "http://cdn.example.com/").is_follow());
+        assert!(redirect(&mut clone, "http://cdn.example.com/").is_follow());
+
+        handle.replace(["cdn.example.com"]);
+        assert!(redirect(&mut clone, "http://example.com/").is_stop());
+        handle.remove("cdn.example.com");
+        assert!(redirect(&mut clone, "http://cdn.example.com/").is_stop());
+    }
+}
@@ -0,0 +1,103 @@
+use super::{Action, Attempt, Policy};
+use http::{Request, Uri};
+
+/// A redirection [`Policy`] that stops following redirections that lead to the URI they came
+/// from.
+///
+/// Following a redirection from `/x` to `/x` makes the same request again, and usually gets the
+/// same response until another policy, such as [`Limited`](super::Limited), gives up.
+/// [`StopOnSelfRedirect`] stops right away when the location of a redirection is exactly the URI
+/// of the request that received it, or the URI of the original request.
+///
+/// Longer loops, such as `/a` → `/b` → `/c` → `/b`, are detected by [`LoopDetect`], which
+/// remembers every visited URI.
+///
+/// # Example
+///
+/// ```
+/// use tower_http::follow_redirect::policy::{Limited, PolicyExt, StopOnSelfRedirect};
+///
+/// let policy = StopOnSelfRedirect::new().and::<_, (), ()>(Limited::default());
+/// ```
+///
+/// [`LoopDetect`]: super::LoopDetect
+#[derive(Clone, Debug, Default)]
+pub struct StopOnSelfRedirect {
+    original: Option<Uri>,
+}
+
+impl StopOnSelfRedirect {
+    /// Create a new [`StopOnSelfRedirect`].
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl<B, E> Policy<B, E> for StopOnSelfRedirect {
+    fn redirect(&mut self, attempt: &Attempt<'_>) -> Result<Action, E> {
+        let location = attempt.location();
+        if location == attempt.previous() || self.original.as_ref() == Some(location) {
+            Ok(Action::Stop)
+        } else {
+            Ok(Action::Follow)
+        }
+    }
+
+    fn on_request(&mut self, request: &mut Request<B>) {
+        if self.original.is_none() {
+            self.original = Some(request.uri().clone());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use http::{Extensions, HeaderMap, HeaderValue, Method, StatusCode};
+
+    fn redirect(policy: &mut StopOnSelfRedirect, previous: &str, location: &str) -> Action {
+        let mut request = Request::builder().uri(previous).body(()).unwrap();
+        Policy::<(), ()>::on_request(policy, &mut request);
+
+        let location = location.parse::<Uri>().unwrap();
+        let attempt = Attempt {
+            status: StatusCode::FOUND,
+            method: &Method::GET,
+            location: &location,
+            raw_location: &HeaderValue::from_static("/new"),
+            headers: &HeaderMap::new(),
+            extensions: &Extensions::new(),
+            previous: request.uri(),
+        };
+        Policy::<(), ()>::redirect(policy, &attempt).unwrap()
+    }
+
+    #[test]
+    fn stops_on_self_redirect() {
+        let (a, b) = ("http://example.com/a", "http://example.com/b");
+
+        assert!(redirect(&mut StopOnSelfRedirect::new(), a, a).is_stop());
+
+        let mut policy = StopOnSelfRedirect::new();
+        assert!(redirect(&mut policy, a, b).is_follow());
+        assert!(redirect(&mut policy, b, b).is_stop());
+
+        // back to the original request
+        let mut policy = StopOnSelfRedirect::new();
+        assert!(redirect(&mut policy, a, b).is_follow());
+        assert!(redirect(&mut policy, b, a).is_stop());
+    }
+
+    #[test]
+    fn follows_other_redirects() {
+        let mut policy = StopOnSelfRedirect::new();
+        assert!(redirect(&mut policy, "http://example.com/a", "http://example.com/b").is_follow());
+        assert!(redirect(&mut policy, "http://example.com/b", "https://example.com/b").is_follow());
+        assert!(redirect(
+            &mut policy,
+            "https://example.com/b",
+            "https://example.com/b?x"
+        )
+        .is_follow());
+    }
+}
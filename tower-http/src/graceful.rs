@@ -0,0 +1,384 @@
+//! Middleware that rejects new requests during graceful shutdown.
+//!
+//! [`Graceful`] counts the requests it is processing, the same way as
+//! [`InFlightRequests`][crate::metrics::InFlightRequests]: a request is in-flight from when it is
+//! received until its response body is consumed or dropped, or an error happens.
+//!
+//! Once [`GracefulShutdown::shutdown`] is called, new requests are rejected with `503 Service
+//! Unavailable` without calling the inner service, while in-flight requests are allowed to
+//! finish. [`GracefulShutdown::wait_idle`] resolves once there are no more in-flight requests.
+//!
+//! # Example
+//!
+//! ```
+//! use tower_http::graceful::GracefulLayer;
+//! use http::{Request, Response, StatusCode};
+//! use http_body_util::Full;
+//! use bytes::Bytes;
+//! use std::convert::Infallible;
+//! use tower::{ServiceBuilder, Service, ServiceExt};
+//!
+//! async fn handle(req: Request<Full<Bytes>>) -> Result<Response<Full<Bytes>>, Infallible> {
+//!     // ...
+//!     # Ok(Response::new(Full::default()))
+//! }
+//!
+//! # #[tokio::main]
+//! # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+//! let (graceful_layer, shutdown) = GracefulLayer::pair();
+//!
+//! let mut service = ServiceBuilder::new()
+//!     .layer(graceful_layer)
+//!     .service_fn(handle);
+//!
+//! // when the server begins shutting down
+//! shutdown.shutdown();
+//!
+//! // new requests are rejected
+//! let response = service
+//!     .ready()
+//!     .await?
+//!     .call(Request::new(Full::default()))
+//!     .await?;
+//! assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+//!
+//! // wait for in-flight requests to finish
+//! shutdown.wait_idle().await;
+//! # Ok(())
+//! # }
+//! ```
+
+use http::{Request, Response, StatusCode};
+use http_body::Body;
+use pin_project_lite::pin_project;
+use std::{
+    future::Future,
+    pin::Pin,
+    sync::{
+        atomic::{AtomicBool, AtomicUsize, Ordering},
+        Arc,
+    },
+    task::{ready, Context, Poll},
+};
+use tokio::sync::Notify;
+use tower_layer::Layer;
+use tower_service::Service;
+
+/// Layer that applies [`Graceful`] which rejects new requests during graceful shutdown.
+///
+/// See the [module docs](self) for more details.
+#[derive(Clone, Debug)]
+pub struct GracefulLayer {
+    shutdown: GracefulShutdown,
+}
+
+impl GracefulLayer {
+    /// Create a new `GracefulLayer` and its associated shutdown handle.
+    pub fn pair() -> (Self, GracefulShutdown) {
+        let shutdown = GracefulShutdown::new();
+        let layer = Self::new(shutdown.clone());
+        (layer, shutdown)
+    }
+
+    /// Create a new `GracefulLayer` controlled by the given shutdown handle.
+    pub fn new(shutdown: GracefulShutdown) -> Self {
+        Self { shutdown }
+    }
+}
+
+impl<S> Layer<S> for GracefulLayer {
+    type Service = Graceful<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        Graceful {
+            inner,
+            shutdown: self.shutdown.clone(),
+        }
+    }
+}
+
+/// Middleware that rejects new requests during graceful shutdown.
+///
+/// See the [module docs](self) for more details.
+#[derive(Clone, Debug)]
+pub struct Graceful<S> {
+    inner: S,
+    shutdown: GracefulShutdown,
+}
+
+impl<S> Graceful<S> {
+    /// Create a new `Graceful` and its associated shutdown handle.
+    pub fn pair(inner: S) -> (Self, GracefulShutdown) {
+        let shutdown = GracefulShutdown::new();
+        let service = Self::new(inner, shutdown.clone());
+        (service, shutdown)
+    }
+
+    /// Create a new `Graceful` controlled by the given shutdown handle.
+    pub fn new(inner: S, shutdown: GracefulShutdown) -> Self {
+        Self { inner, shutdown }
+    }
+
+    define_inner_service_accessors!();
+}
+
+/// Handle for starting graceful shutdown and waiting for in-flight requests to finish.
+///
+/// This will normally be combined with [`GracefulLayer`] or [`Graceful`]. Cloning the handle is
+/// cheap and clones control the same services.
+#[derive(Debug, Clone, Default)]
+pub struct GracefulShutdown {
+    shared: Arc<Shared>,
+}
+
+#[derive(Debug, Default)]
+struct Shared {
+    shutting_down: AtomicBool,
+    in_flight: AtomicUsize,
+    idle: Notify,
+}
+
+impl GracefulShutdown {
+    /// Create a new `GracefulShutdown`.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Start graceful shutdown.
+    ///
+    /// From now on, new requests are rejected with `503 Service Unavailable`.
+    pub fn shutdown(&self) {
+        self.shared.shutting_down.store(true, Ordering::SeqCst);
+    }
+
+    /// Returns `true` if graceful shutdown has started.
+    pub fn is_shutting_down(&self) -> bool {
+        self.shared.shutting_down.load(Ordering::SeqCst)
+    }
+
+    /// Get the current number of in-flight requests.
+    pub fn in_flight(&self) -> usize {
+        self.shared.in_flight.load(Ordering::SeqCst)
+    }
+
+    /// Wait until there are no in-flight requests.
+    ///
+    /// This resolves immediately if there are none. It is normally awaited after calling
+    /// [`shutdown`](Self::shutdown), otherwise new requests may arrive afterwards.
+    pub async fn wait_idle(&self) {
+        loop {
+            // `notified` is created before checking the count so a notification sent in between
+            // isn't missed
+            let notified = self.shared.idle.notified();
+            if self.in_flight() == 0 {
+                return;
+            }
+            notified.await;
+        }
+    }
+
+    fn increment(&self) -> InFlightGuard {
+        self.shared.in_flight.fetch_add(1, Ordering::SeqCst);
+        InFlightGuard {
+            shared: self.shared.clone(),
+        }
+    }
+}
+
+struct InFlightGuard {
+    shared: Arc<Shared>,
+}
+
+impl Drop for InFlightGuard {
+    fn drop(&mut self) {
+        if self.shared.in_flight.fetch_sub(1, Ordering::SeqCst) == 1 {
+            self.shared.idle.notify_waiters();
+        }
+    }
+}
+
+impl<S, ReqBody, ResBody> Service<Request<ReqBody>> for Graceful<S>
+where
+    S: Service<Request<ReqBody>, Response = Response<ResBody>>,
+    ResBody: Default,
+{
+    type Response = Response<ResponseBody<ResBody>>;
+    type Error = S::Error;
+    type Future = ResponseFuture<S::Future>;
+
+    #[inline]
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, req: Request<ReqBody>) -> Self::Future {
+        // the count is incremented before checking the flag, so `wait_idle` can't resolve between
+        // the check and the increment
+        let guard = self.shutdown.increment();
+        if self.shutdown.is_shutting_down() {
+            drop(guard);
+            return ResponseFuture {
+                kind: Kind::Rejected,
+            };
+        }
+
+        ResponseFuture {
+            kind: Kind::Future {
+                future: self.inner.call(req),
+                guard: Some(guard),
+            },
+        }
+    }
+}
+
+pin_project! {
+    /// Response future for [`Graceful`].
+    pub struct ResponseFuture<F> {
+        #[pin]
+        kind: Kind<F>,
+    }
+}
+
+pin_project! {
+    #[project = KindProj]
+    enum Kind<F> {
+        Future {
+            #[pin]
+            future: F,
+            guard: Option<InFlightGuard>,
+        },
+        Rejected,
+    }
+}
+
+impl<F, B, E> Future for ResponseFuture<F>
+where
+    F: Future<Output = Result<Response<B>, E>>,
+    B: Default,
+{
+    type Output = Result<Response<ResponseBody<B>>, E>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        match self.project().kind.project() {
+            KindProj::Future { future, guard } => {
+                let response = ready!(future.poll(cx))?;
+                let guard = guard.take();
+                let response = response.map(move |body| ResponseBody { inner: body, guard });
+                Poll::Ready(Ok(response))
+            }
+            KindProj::Rejected => {
+                let mut response = Response::new(ResponseBody {
+                    inner: B::default(),
+                    guard: None,
+                });
+                *response.status_mut() = StatusCode::SERVICE_UNAVAILABLE;
+                Poll::Ready(Ok(response))
+            }
+        }
+    }
+}
+
+pin_project! {
+    /// Response body for [`Graceful`].
+    pub struct ResponseBody<B> {
+        #[pin]
+        inner: B,
+        guard: Option<InFlightGuard>,
+    }
+}
+
+impl<B> Body for ResponseBody<B>
+where
+    B: Body,
+{
+    type Data = B::Data;
+    type Error = B::Error;
+
+    #[inline]
+    fn poll_frame(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Result<http_body::Frame<Self::Data>, Self::Error>>> {
+        self.project().inner.poll_frame(cx)
+    }
+
+    #[inline]
+    fn is_end_stream(&self) -> bool {
+        self.inner.is_end_stream()
+    }
+
+    #[inline]
+    fn size_hint(&self) -> http_body::SizeHint {
+        self.inner.size_hint()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_helpers::Body;
+    use std::{convert::Infallible, time::Duration};
+    use tokio::sync::oneshot;
+    use tower::{ServiceBuilder, ServiceExt};
+
+    #[tokio::test]
+    async fn rejects_new_requests_and_waits_for_in_flight() {
+        let (tx, rx) = oneshot::channel::<()>();
+        let rx = Arc::new(tokio::sync::Mutex::new(Some(rx)));
+        let (layer, shutdown) = GracefulLayer::pair();
+        let mut svc = ServiceBuilder::new()
+            .layer(layer)
+            .service_fn(move |_: Request<Body>| {
+                let rx = rx.clone();
+                async move {
+                    // the first request waits until it is released
+                    if let Some(rx) = rx.lock().await.take() {
+                        rx.await.unwrap();
+                    }
+                    Ok::<_, Infallible>(Response::new(Body::from("done")))
+                }
+            });
+
+        let outstanding = svc.ready().await.unwrap().call(Request::new(Body::empty()));
+        let outstanding = tokio::spawn(async move {
+            let res = outstanding.await.unwrap();
+            crate::test_helpers::to_bytes(res.into_body())
+                .await
+                .unwrap()
+        });
+        assert_eq!(shutdown.in_flight(), 1);
+
+        shutdown.shutdown();
+        let res = svc
+            .ready()
+            .await
+            .unwrap()
+            .call(Request::new(Body::empty()))
+            .await
+            .unwrap();
+        assert_eq!(res.status(), StatusCode::SERVICE_UNAVAILABLE);
+        assert_eq!(shutdown.in_flight(), 1);
+
+        let idle = tokio::spawn({
+            let shutdown = shutdown.clone();
+            async move { shutdown.wait_idle().await }
+        });
+        tokio::time::sleep(Duration::from_millis(10)).await;
+        assert!(!idle.is_finished());
+
+        tx.send(()).unwrap();
+        assert_eq!(outstanding.await.unwrap(), "done");
+        tokio::time::timeout(Duration::from_secs(1), idle)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(shutdown.in_flight(), 0);
+    }
+
+    #[tokio::test]
+    async fn wait_idle_without_requests() {
+        let shutdown = GracefulShutdown::new();
+        assert!(!shutdown.is_shutting_down());
+        shutdown.wait_idle().await;
+    }
+}
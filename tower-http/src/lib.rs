@@ -330,6 +330,45 @@ pub mod timeout;
 #[cfg(feature = "normalize-path")]
 pub mod normalize_path;
 
+#[cfg(feature = "rewrite-location")]
+pub mod rewrite_location;
+
+#[cfg(feature = "redact-query")]
+pub mod redact_query;
+
+#[cfg(feature = "server-timing")]
+pub mod server_timing;
+
+#[cfg(feature = "graceful")]
+pub mod graceful;
+
+#[cfg(feature = "validate-host")]
+pub mod validate_host;
+
+#[cfg(feature = "reject-ambiguous-length")]
+pub mod reject_ambiguous_length;
+
+#[cfg(feature = "rate-limit")]
+pub mod rate_limit;
+
+#[cfg(feature = "base64-decode")]
+pub mod base64_decode;
+
+#[cfg(feature = "normalize-host")]
+pub mod normalize_host;
+
+#[cfg(feature = "buffer-body")]
+pub mod buffer_body;
+
+#[cfg(feature = "normalize-forwarded")]
+pub mod normalize_forwarded;
+
+#[cfg(feature = "cache")]
+pub mod cache;
+
+#[cfg(feature = "downgrade-http-version")]
+pub mod downgrade_http_version;
+
 pub mod classify;
 pub mod services;
 
@@ -344,6 +383,7 @@ pub use self::builder::ServiceBuilderExt;
 pub mod validate_request;
 
 #[cfg(any(
+    feature = "base64-decode",
     feature = "catch-panic",
     feature = "decompression-br",
     feature = "decompression-deflate",
@@ -351,6 +391,7 @@ pub mod validate_request;
     feature = "decompression-zstd",
     feature = "fs",
     feature = "limit",
+    feature = "sse",
 ))]
 pub mod body;
 
@@ -1,5 +1,7 @@
 //! Middleware for limiting request bodies.
 //!
+//! See [`uri_length`] for limiting the length of request URIs instead.
+//!
 //! This layer will also intercept requests with a `Content-Length` header
 //! larger than the allowable limit and return an immediate error response
 //! before reading any of the body.
@@ -135,8 +137,10 @@ mod body;
 mod future;
 mod layer;
 mod service;
+pub mod uri_length;
 
 pub use body::ResponseBody;
 pub use future::ResponseFuture;
 pub use layer::RequestBodyLimitLayer;
 pub use service::RequestBodyLimit;
+pub use uri_length::{LimitUriLength, LimitUriLengthLayer};
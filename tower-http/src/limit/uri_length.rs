@@ -0,0 +1,217 @@
+//! Middleware for limiting the length of request URIs.
+//!
+//! Requests whose path and query are longer than the limit are rejected with `414 URI Too
+//! Long`, without calling the inner service. The scheme and authority of requests in absolute
+//! form are not counted, so the limit applies the same way to all request forms.
+//!
+//! # Example
+//!
+//! ```rust
+//! use bytes::Bytes;
+//! use std::convert::Infallible;
+//! use http::{Request, Response, StatusCode};
+//! use tower::{Service, ServiceExt, ServiceBuilder};
+//! use tower_http::limit::LimitUriLengthLayer;
+//! use http_body_util::Full;
+//!
+//! # #[tokio::main]
+//! # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+//! async fn handle(req: Request<Full<Bytes>>) -> Result<Response<Full<Bytes>>, Infallible> {
+//!     // ...
+//!     # Ok(Response::new(Full::default()))
+//! }
+//!
+//! let mut svc = ServiceBuilder::new()
+//!     // Limit the path and query of requests to 2048 bytes.
+//!     .layer(LimitUriLengthLayer::new(2048))
+//!     .service_fn(handle);
+//!
+//! let request = Request::builder()
+//!     .uri(format!("/search?q={}", "a".repeat(2048)))
+//!     .body(Full::default())?;
+//!
+//! let response = svc.ready().await?.call(request).await?;
+//!
+//! assert_eq!(response.status(), StatusCode::URI_TOO_LONG);
+//! # Ok(())
+//! # }
+//! ```
+
+use http::{Request, Response, StatusCode, Uri};
+use pin_project_lite::pin_project;
+use std::{
+    future::Future,
+    pin::Pin,
+    task::{Context, Poll},
+};
+use tower_layer::Layer;
+use tower_service::Service;
+
+/// Layer that applies the [`LimitUriLength`] middleware that rejects requests with URIs longer
+/// than the configured limit with `414 URI Too Long` responses.
+///
+/// See the [module docs](crate::limit::uri_length) for an example.
+#[derive(Clone, Copy, Debug)]
+pub struct LimitUriLengthLayer {
+    limit: usize,
+}
+
+impl LimitUriLengthLayer {
+    /// Create a new `LimitUriLengthLayer` with the given limit, in bytes, for the path and query
+    /// of request URIs.
+    pub fn new(limit: usize) -> Self {
+        Self { limit }
+    }
+}
+
+impl<S> Layer<S> for LimitUriLengthLayer {
+    type Service = LimitUriLength<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        LimitUriLength {
+            inner,
+            limit: self.limit,
+        }
+    }
+}
+
+/// Middleware that rejects requests with URIs longer than the configured limit with `414 URI Too
+/// Long` responses.
+///
+/// See the [module docs](crate::limit::uri_length) for an example.
+#[derive(Clone, Copy, Debug)]
+pub struct LimitUriLength<S> {
+    inner: S,
+    limit: usize,
+}
+
+impl<S> LimitUriLength<S> {
+    /// Create a new `LimitUriLength` with the given limit, in bytes, for the path and query of
+    /// request URIs.
+    pub fn new(inner: S, limit: usize) -> Self {
+        Self { inner, limit }
+    }
+
+    define_inner_service_accessors!();
+
+    /// Returns a new [`Layer`] that wraps services with a `LimitUriLength` middleware.
+    ///
+    /// [`Layer`]: tower_layer::Layer
+    pub fn layer(limit: usize) -> LimitUriLengthLayer {
+        LimitUriLengthLayer::new(limit)
+    }
+}
+
+impl<ReqBody, ResBody, S> Service<Request<ReqBody>> for LimitUriLength<S>
+where
+    S: Service<Request<ReqBody>, Response = Response<ResBody>>,
+    ResBody: Default,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = ResponseFuture<S::Future>;
+
+    #[inline]
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, req: Request<ReqBody>) -> Self::Future {
+        if uri_length(req.uri()) > self.limit {
+            return ResponseFuture {
+                inner: ResponseFutureInner::UriTooLong,
+            };
+        }
+
+        ResponseFuture {
+            inner: ResponseFutureInner::Future {
+                future: self.inner.call(req),
+            },
+        }
+    }
+}
+
+fn uri_length(uri: &Uri) -> usize {
+    uri.path_and_query()
+        .map_or(0, |path_and_query| path_and_query.as_str().len())
+}
+
+pin_project! {
+    /// Response future for [`LimitUriLength`].
+    pub struct ResponseFuture<F> {
+        #[pin]
+        inner: ResponseFutureInner<F>,
+    }
+}
+
+pin_project! {
+    #[project = ResFutProj]
+    enum ResponseFutureInner<F> {
+        UriTooLong,
+        Future {
+            #[pin]
+            future: F,
+        }
+    }
+}
+
+impl<ResBody, F, E> Future for ResponseFuture<F>
+where
+    ResBody: Default,
+    F: Future<Output = Result<Response<ResBody>, E>>,
+{
+    type Output = Result<Response<ResBody>, E>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        match self.project().inner.project() {
+            ResFutProj::UriTooLong => {
+                let mut res = Response::new(ResBody::default());
+                *res.status_mut() = StatusCode::URI_TOO_LONG;
+                Poll::Ready(Ok(res))
+            }
+            ResFutProj::Future { future } => future.poll(cx),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_helpers::Body;
+    use std::convert::Infallible;
+    use tower::{ServiceBuilder, ServiceExt};
+
+    async fn status(uri: &str) -> StatusCode {
+        let svc = ServiceBuilder::new()
+            .layer(LimitUriLengthLayer::new(16))
+            .service_fn(|_: Request<Body>| async {
+                Ok::<_, Infallible>(Response::new(Body::empty()))
+            });
+
+        let req = Request::builder().uri(uri).body(Body::empty()).unwrap();
+        svc.oneshot(req).await.unwrap().status()
+    }
+
+    #[tokio::test]
+    async fn allows_uris_up_to_limit() {
+        // 15 bytes
+        assert_eq!(status("/search?q=abcde").await, StatusCode::OK);
+        // 16 bytes
+        assert_eq!(status("/search?q=abcdef").await, StatusCode::OK);
+        // scheme and authority aren't counted
+        assert_eq!(
+            status("http://example.com/search?q=abcdef").await,
+            StatusCode::OK
+        );
+    }
+
+    #[tokio::test]
+    async fn rejects_uris_over_limit() {
+        // 17 bytes
+        assert_eq!(status("/search?q=abcdefg").await, StatusCode::URI_TOO_LONG);
+        assert_eq!(
+            status("http://example.com/search?q=abcdefg").await,
+            StatusCode::URI_TOO_LONG
+        );
+    }
+}
@@ -0,0 +1,345 @@
+//! Middleware that sanitizes the `Forwarded` and `X-Forwarded-*` headers of requests.
+//!
+//! Proxies record the addresses of the clients they forward requests for in the
+//! [`Forwarded`][mdn] and `X-Forwarded-For` headers, and the original host and scheme in
+//! `Forwarded` and `X-Forwarded-Host` and `X-Forwarded-Proto`. Clients can send these headers
+//! too, so a service that trusts them can be fooled into logging or rate limiting the wrong
+//! address. [`NormalizeForwarded`] rewrites them before calling the inner service, according
+//! to a [`ForwardedMode`]:
+//!
+//! - [`ForwardedMode::Strip`] removes them, for services exposed directly to clients.
+//! - [`ForwardedMode::AppendClient`] adds an address at the end of the chain.
+//! - [`ForwardedMode::TrustHops`] keeps only the entries added by a number of trusted proxies,
+//!   which are the last ones.
+//!
+//! # Example
+//!
+//! ```
+//! use tower_http::normalize_forwarded::{ForwardedMode, NormalizeForwardedLayer};
+//! use http::{Request, Response};
+//! use http_body_util::Full;
+//! use bytes::Bytes;
+//! use std::convert::Infallible;
+//! use tower::{ServiceBuilder, Service, ServiceExt};
+//!
+//! async fn handle(req: Request<Full<Bytes>>) -> Result<Response<Full<Bytes>>, Infallible> {
+//!     // only the entry added by the load balancer in front of the service is left
+//!     assert_eq!(req.headers()["x-forwarded-for"], "203.0.113.7");
+//!     Ok(Response::new(Full::default()))
+//! }
+//!
+//! # #[tokio::main]
+//! # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+//! let mut service = ServiceBuilder::new()
+//!     .layer(NormalizeForwardedLayer::new(ForwardedMode::TrustHops(1)))
+//!     .service_fn(handle);
+//!
+//! let request = Request::builder()
+//!     .header("x-forwarded-for", "127.0.0.1, 203.0.113.7")
+//!     .body(Full::default())?;
+//! service.ready().await?.call(request).await?;
+//! #
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! [mdn]: https://developer.mozilla.org/en-US/docs/Web/HTTP/Headers/Forwarded
+
+use http::{
+    header::{Entry, HeaderName, FORWARDED},
+    HeaderMap, HeaderValue, Request, Response,
+};
+use std::{
+    net::IpAddr,
+    task::{Context, Poll},
+};
+use tower_layer::Layer;
+use tower_service::Service;
+
+const X_FORWARDED_FOR: HeaderName = HeaderName::from_static("x-forwarded-for");
+
+/// How [`NormalizeForwarded`] rewrites the forwarded headers of requests.
+///
+/// See the [module docs](self) for more details.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ForwardedMode {
+    /// Remove the `Forwarded` header and every `X-Forwarded-*` header.
+    Strip,
+    /// Append an address to the `X-Forwarded-For` header, and to the `Forwarded` header if the
+    /// request has one.
+    ///
+    /// The headers added by clients are left in place before it.
+    AppendClient(IpAddr),
+    /// Keep only the last `n` entries of the `Forwarded` and `X-Forwarded-*` headers, which are
+    /// the ones added by the `n` trusted proxies in front of the service.
+    ///
+    /// Headers whose value isn't valid UTF-8 are removed.
+    TrustHops(usize),
+}
+
+/// Layer that applies [`NormalizeForwarded`] which sanitizes the forwarded headers of requests.
+///
+/// See the [module docs](self) for more details.
+#[derive(Debug, Clone, Copy)]
+pub struct NormalizeForwardedLayer {
+    mode: ForwardedMode,
+}
+
+impl NormalizeForwardedLayer {
+    /// Create a new [`NormalizeForwardedLayer`].
+    pub fn new(mode: ForwardedMode) -> Self {
+        Self { mode }
+    }
+}
+
+impl<S> Layer<S> for NormalizeForwardedLayer {
+    type Service = NormalizeForwarded<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        NormalizeForwarded::new(inner, self.mode)
+    }
+}
+
+/// Middleware that sanitizes the forwarded headers of requests.
+///
+/// See the [module docs](self) for more details.
+#[derive(Debug, Clone, Copy)]
+pub struct NormalizeForwarded<S> {
+    inner: S,
+    mode: ForwardedMode,
+}
+
+impl<S> NormalizeForwarded<S> {
+    /// Create a new [`NormalizeForwarded`].
+    pub fn new(inner: S, mode: ForwardedMode) -> Self {
+        Self { inner, mode }
+    }
+
+    define_inner_service_accessors!();
+
+    /// Returns a new [`Layer`] that wraps services with a [`NormalizeForwarded`] middleware.
+    ///
+    /// [`Layer`]: tower_layer::Layer
+    pub fn layer(mode: ForwardedMode) -> NormalizeForwardedLayer {
+        NormalizeForwardedLayer::new(mode)
+    }
+}
+
+impl<S, ReqBody, ResBody> Service<Request<ReqBody>> for NormalizeForwarded<S>
+where
+    S: Service<Request<ReqBody>, Response = Response<ResBody>>,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = S::Future;
+
+    #[inline]
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, mut req: Request<ReqBody>) -> Self::Future {
+        let headers = req.headers_mut();
+        match self.mode {
+            ForwardedMode::Strip => {
+                for name in forwarded_header_names(headers) {
+                    headers.remove(name);
+                }
+            }
+            ForwardedMode::AppendClient(ip) => {
+                append(headers, X_FORWARDED_FOR, ip.to_string());
+                if headers.contains_key(FORWARDED) {
+                    let node = match ip {
+                        IpAddr::V4(ip) => format!("for={}", ip),
+                        IpAddr::V6(ip) => format!("for=\"[{}]\"", ip),
+                    };
+                    append(headers, FORWARDED, node);
+                }
+            }
+            ForwardedMode::TrustHops(hops) => {
+                for name in forwarded_header_names(headers) {
+                    keep_last(headers, name, hops);
+                }
+            }
+        }
+
+        self.inner.call(req)
+    }
+}
+
+fn forwarded_header_names(headers: &HeaderMap) -> Vec<HeaderName> {
+    headers
+        .keys()
+        .filter(|name| *name == FORWARDED || name.as_str().starts_with("x-forwarded-"))
+        .cloned()
+        .collect()
+}
+
+// Appends `entry` to the list of the last `name` header, or adds the header.
+fn append(headers: &mut HeaderMap, name: HeaderName, entry: String) {
+    match headers.entry(name) {
+        Entry::Occupied(mut values) => {
+            if let Some(last) = values.iter_mut().last() {
+                let mut value = last.as_bytes().to_vec();
+                value.extend_from_slice(b", ");
+                value.extend_from_slice(entry.as_bytes());
+                if let Ok(value) = HeaderValue::from_bytes(&value) {
+                    *last = value;
+                }
+            }
+        }
+        Entry::Vacant(values) => {
+            if let Ok(value) = HeaderValue::from_str(&entry) {
+                values.insert(value);
+            }
+        }
+    }
+}
+
+// Keeps the last `n` entries of the lists of all the `name` headers, joined in a single header.
+fn keep_last(headers: &mut HeaderMap, name: HeaderName, n: usize) {
+    let entries = headers
+        .get_all(&name)
+        .iter()
+        .map(|value| value.to_str().map(split_list))
+        .collect::<Result<Vec<_>, _>>();
+    let kept = entries.ok().and_then(|entries| {
+        let entries = entries.concat();
+        let kept = &entries[entries.len().saturating_sub(n)..];
+        if kept.is_empty() {
+            None
+        } else {
+            HeaderValue::from_str(&kept.join(", ")).ok()
+        }
+    });
+
+    headers.remove(&name);
+    if let Some(kept) = kept {
+        headers.insert(name, kept);
+    }
+}
+
+// Splits a comma separated list, ignoring the commas in quoted strings.
+fn split_list(value: &str) -> Vec<String> {
+    let mut entries = Vec::new();
+    let mut entry = String::new();
+    let mut quoted = false;
+    let mut escaped = false;
+    for c in value.chars() {
+        match c {
+            _ if escaped => escaped = false,
+            '\\' if quoted => escaped = true,
+            '"' => quoted = !quoted,
+            ',' if !quoted => {
+                entries.push(std::mem::take(&mut entry));
+                continue;
+            }
+            _ => {}
+        }
+        entry.push(c);
+    }
+    entries.push(entry);
+
+    entries
+        .into_iter()
+        .map(|entry| entry.trim().to_owned())
+        .filter(|entry| !entry.is_empty())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_helpers::Body;
+    use std::convert::Infallible;
+    use tower::{ServiceBuilder, ServiceExt};
+
+    // A request with headers forged by the client, followed by the entries of a trusted proxy.
+    async fn normalized(mode: ForwardedMode) -> HeaderMap {
+        let svc = ServiceBuilder::new()
+            .layer(NormalizeForwardedLayer::new(mode))
+            .service_fn(|req: Request<Body>| async move {
+                let mut res = Response::new(Body::empty());
+                *res.headers_mut() = req.headers().clone();
+                Ok::<_, Infallible>(res)
+            });
+        let req = Request::builder()
+            .header("x-forwarded-for", "127.0.0.1, 10.0.0.1")
+            .header("x-forwarded-for", "198.51.100.2")
+            .header("x-forwarded-proto", "http, https")
+            .header(FORWARDED, "for=127.0.0.1;proto=http, for=\"[2001:db8::1]\"")
+            .header("x-real-ip", "127.0.0.1")
+            .body(Body::empty())
+            .unwrap();
+        let res = svc.oneshot(req).await.unwrap();
+        res.headers().clone()
+    }
+
+    fn values(headers: &HeaderMap, name: &str) -> Vec<String> {
+        headers
+            .get_all(name)
+            .iter()
+            .map(|value| value.to_str().unwrap().to_owned())
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn strip() {
+        let headers = normalized(ForwardedMode::Strip).await;
+        assert!(!headers.contains_key("x-forwarded-for"));
+        assert!(!headers.contains_key("x-forwarded-proto"));
+        assert!(!headers.contains_key(FORWARDED));
+        assert_eq!(headers["x-real-ip"], "127.0.0.1");
+    }
+
+    #[tokio::test]
+    async fn append_client() {
+        let headers = normalized(ForwardedMode::AppendClient([203, 0, 113, 7].into())).await;
+        assert_eq!(
+            values(&headers, "x-forwarded-for"),
+            ["127.0.0.1, 10.0.0.1", "198.51.100.2, 203.0.113.7"]
+        );
+        assert_eq!(
+            headers[FORWARDED],
+            "for=127.0.0.1;proto=http, for=\"[2001:db8::1]\", for=203.0.113.7"
+        );
+        assert_eq!(headers["x-forwarded-proto"], "http, https");
+
+        let ip = "2001:db8::7".parse().unwrap();
+        let headers = normalized(ForwardedMode::AppendClient(ip)).await;
+        assert!(headers[FORWARDED]
+            .to_str()
+            .unwrap()
+            .ends_with(", for=\"[2001:db8::7]\""));
+    }
+
+    #[tokio::test]
+    async fn trust_hops() {
+        let headers = normalized(ForwardedMode::TrustHops(2)).await;
+        assert_eq!(
+            values(&headers, "x-forwarded-for"),
+            ["10.0.0.1, 198.51.100.2"]
+        );
+        assert_eq!(headers["x-forwarded-proto"], "http, https");
+        assert_eq!(
+            headers[FORWARDED],
+            "for=127.0.0.1;proto=http, for=\"[2001:db8::1]\""
+        );
+
+        let headers = normalized(ForwardedMode::TrustHops(1)).await;
+        assert_eq!(values(&headers, "x-forwarded-for"), ["198.51.100.2"]);
+        assert_eq!(headers[FORWARDED], "for=\"[2001:db8::1]\"");
+
+        let headers = normalized(ForwardedMode::TrustHops(0)).await;
+        assert!(!headers.contains_key("x-forwarded-for"));
+        assert!(!headers.contains_key(FORWARDED));
+    }
+
+    #[test]
+    fn splits_quoted_lists() {
+        assert_eq!(
+            split_list("for=a, for=\"b,c\";by=\"\\\"\" ,, for=d"),
+            ["for=a", "for=\"b,c\";by=\"\\\"\"", "for=d"]
+        );
+    }
+}
@@ -0,0 +1,283 @@
+//! Middleware that normalizes the host of requests.
+//!
+//! Requests for the same host can spell it in different ways, which splits caches keyed on the
+//! host and breaks routing on exact hosts. [`NormalizeHost`] rewrites the `Host` header, and the
+//! authority of the request URI when it has one, before calling the inner service:
+//!
+//! - The host is lowercased.
+//! - Internationalized domain names are converted to their ASCII (punycode) form.
+//! - The default port is removed. That is `:80` for `http` and `:443` for `https` URIs, and either
+//!   of them for the `Host` header of requests whose URI has no scheme.
+//!
+//! Hosts that aren't valid domain names are left untouched, and so is the rest of the URI.
+//!
+//! # Example
+//!
+//! ```
+//! use tower_http::normalize_host::NormalizeHostLayer;
+//! use http::{header::HOST, Request, Response};
+//! use http_body_util::Full;
+//! use bytes::Bytes;
+//! use std::convert::Infallible;
+//! use tower::{ServiceBuilder, Service, ServiceExt};
+//!
+//! async fn handle(req: Request<Full<Bytes>>) -> Result<Response<Full<Bytes>>, Infallible> {
+//!     assert_eq!(req.headers()[HOST], "example.com");
+//!     Ok(Response::new(Full::default()))
+//! }
+//!
+//! # #[tokio::main]
+//! # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+//! let mut service = ServiceBuilder::new()
+//!     .layer(NormalizeHostLayer::new())
+//!     .service_fn(handle);
+//!
+//! let request = Request::builder()
+//!     .header(HOST, "Example.COM:80")
+//!     .body(Full::default())?;
+//! service.ready().await?.call(request).await?;
+//! #
+//! # Ok(())
+//! # }
+//! ```
+
+use http::{header::HOST, uri::Scheme, HeaderValue, Request, Response, Uri};
+use std::task::{Context, Poll};
+use tower_layer::Layer;
+use tower_service::Service;
+
+/// Layer that applies [`NormalizeHost`] which normalizes the host of requests.
+///
+/// See the [module docs](self) for more details.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct NormalizeHostLayer {
+    _priv: (),
+}
+
+impl NormalizeHostLayer {
+    /// Create a new [`NormalizeHostLayer`].
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl<S> Layer<S> for NormalizeHostLayer {
+    type Service = NormalizeHost<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        NormalizeHost::new(inner)
+    }
+}
+
+/// Middleware that normalizes the host of requests.
+///
+/// See the [module docs](self) for more details.
+#[derive(Debug, Clone, Copy)]
+pub struct NormalizeHost<S> {
+    inner: S,
+}
+
+impl<S> NormalizeHost<S> {
+    /// Create a new [`NormalizeHost`].
+    pub fn new(inner: S) -> Self {
+        Self { inner }
+    }
+
+    define_inner_service_accessors!();
+
+    /// Returns a new [`Layer`] that wraps services with a [`NormalizeHost`] middleware.
+    ///
+    /// [`Layer`]: tower_layer::Layer
+    pub fn layer() -> NormalizeHostLayer {
+        NormalizeHostLayer::new()
+    }
+}
+
+impl<S, ReqBody, ResBody> Service<Request<ReqBody>> for NormalizeHost<S>
+where
+    S: Service<Request<ReqBody>, Response = Response<ResBody>>,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = S::Future;
+
+    #[inline]
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, mut req: Request<ReqBody>) -> Self::Future {
+        let default_ports: &[u16] = match req.uri().scheme() {
+            Some(scheme) if *scheme == Scheme::HTTP => &[80],
+            Some(scheme) if *scheme == Scheme::HTTPS => &[443],
+            Some(_) => &[],
+            None => &[80, 443],
+        };
+
+        for (name, value) in req.headers_mut().iter_mut() {
+            if name != HOST {
+                continue;
+            }
+            let normalized = std::str::from_utf8(value.as_bytes())
+                .ok()
+                .and_then(|host| normalize(host, default_ports))
+                .and_then(|host| HeaderValue::from_str(&host).ok());
+            if let Some(normalized) = normalized {
+                *value = normalized;
+            }
+        }
+
+        normalize_uri(req.uri_mut(), default_ports);
+
+        self.inner.call(req)
+    }
+}
+
+fn normalize_uri(uri: &mut Uri, default_ports: &[u16]) {
+    let authority = match uri
+        .authority()
+        .and_then(|authority| normalize(authority.as_str(), default_ports))
+        .and_then(|authority| authority.parse().ok())
+    {
+        Some(authority) => authority,
+        None => return,
+    };
+
+    let mut parts = uri.clone().into_parts();
+    parts.authority = Some(authority);
+    if let Ok(normalized) = Uri::from_parts(parts) {
+        *uri = normalized;
+    }
+}
+
+// Returns the normalized authority, or `None` if it is already normalized or the host isn't valid.
+fn normalize(authority: &str, default_ports: &[u16]) -> Option<String> {
+    let (userinfo, host_port) = match authority.rfind('@') {
+        Some(at) => authority.split_at(at + 1),
+        None => ("", authority),
+    };
+
+    // the port is whatever follows the last `:`, unless it is part of an IPv6 address
+    let (host, port) = match host_port.rfind(':') {
+        Some(colon) if !host_port[colon..].contains(']') => {
+            (&host_port[..colon], Some(&host_port[colon + 1..]))
+        }
+        _ => (host_port, None),
+    };
+
+    let host = if host.starts_with('[') {
+        host.to_ascii_lowercase()
+    } else {
+        idna::domain_to_ascii(host).ok()?
+    };
+    if host.is_empty() {
+        return None;
+    }
+
+    let normalized = match port {
+        Some("") => format!("{}{}", userinfo, host),
+        Some(port) => {
+            let number = port.parse::<u16>().ok()?;
+            if default_ports.contains(&number) {
+                format!("{}{}", userinfo, host)
+            } else {
+                format!("{}{}:{}", userinfo, host, port)
+            }
+        }
+        None => format!("{}{}", userinfo, host),
+    };
+
+    (normalized != authority).then_some(normalized)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_helpers::Body;
+    use std::convert::Infallible;
+    use tower::{ServiceBuilder, ServiceExt};
+
+    async fn normalized(uri: &str, host: &'static str) -> (String, String) {
+        let svc = ServiceBuilder::new()
+            .layer(NormalizeHostLayer::new())
+            .service_fn(|req: Request<Body>| async move {
+                let mut res = Response::new(Body::empty());
+                res.headers_mut().insert(HOST, req.headers()[HOST].clone());
+                res.extensions_mut().insert(req.uri().clone());
+                Ok::<_, Infallible>(res)
+            });
+        let req = Request::builder()
+            .uri(uri)
+            .header(HOST, host)
+            .body(Body::empty())
+            .unwrap();
+        let res = svc.oneshot(req).await.unwrap();
+        (
+            res.extensions().get::<Uri>().unwrap().to_string(),
+            res.headers()[HOST].to_str().unwrap().to_owned(),
+        )
+    }
+
+    #[tokio::test]
+    async fn lowercases_host() {
+        assert_eq!(
+            normalized("/path?Query", "Example.COM").await,
+            ("/path?Query".to_owned(), "example.com".to_owned())
+        );
+        assert_eq!(
+            normalized("http://User@EXAMPLE.com:8080/Path", "EXAMPLE.com:8080").await,
+            (
+                "http://User@example.com:8080/Path".to_owned(),
+                "example.com:8080".to_owned()
+            )
+        );
+    }
+
+    #[tokio::test]
+    async fn removes_default_port() {
+        assert_eq!(
+            normalized("/", "example.com:443").await,
+            ("/".to_owned(), "example.com".to_owned())
+        );
+        assert_eq!(
+            normalized("https://example.com:443/", "example.com:443").await,
+            ("https://example.com/".to_owned(), "example.com".to_owned())
+        );
+        // only the default port of the scheme of the URI
+        assert_eq!(
+            normalized("http://example.com:443/", "example.com:443").await,
+            (
+                "http://example.com:443/".to_owned(),
+                "example.com:443".to_owned()
+            )
+        );
+        assert_eq!(
+            normalized("/", "[::1]:80").await,
+            ("/".to_owned(), "[::1]".to_owned())
+        );
+    }
+
+    #[tokio::test]
+    async fn keeps_canonical_host() {
+        assert_eq!(
+            normalized("http://example.com:8080/", "example.com:8080").await,
+            (
+                "http://example.com:8080/".to_owned(),
+                "example.com:8080".to_owned()
+            )
+        );
+        assert_eq!(
+            normalized("/", "xn--bcher-kva.example").await,
+            ("/".to_owned(), "xn--bcher-kva.example".to_owned())
+        );
+    }
+
+    #[test]
+    fn converts_idn_to_ascii() {
+        assert_eq!(
+            normalize("Bücher.example", &[80]).as_deref(),
+            Some("xn--bcher-kva.example")
+        );
+        assert_eq!(normalize("exa mple.com", &[80]), None);
+    }
+}
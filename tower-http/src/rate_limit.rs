@@ -0,0 +1,494 @@
+//! Middleware that limits the rate of requests per client.
+//!
+//! [`RateLimit`] keeps a [token bucket] for each client, identified by a key extracted from
+//! requests, such as the IP address of the client or an API key header. Each request takes a token
+//! from the bucket of its client, and buckets are refilled by one token per refill interval, up to
+//! their capacity. Requests from clients whose bucket is empty are rejected with `429 Too Many
+//! Requests` and a `Retry-After` header, without calling the inner service.
+//!
+//! Services created from the same [`RateLimitLayer`], and clones of a [`RateLimit`], share their
+//! buckets, so the limits apply across connections.
+//!
+//! To bound the memory used by the middleware, buckets are evicted once the number of keys reaches
+//! a maximum: first the buckets that were refilled to their capacity, which are equivalent to new
+//! buckets, and then the least recently used ones if that's not enough.
+//!
+//! # Example
+//!
+//! ```
+//! use tower_http::rate_limit::RateLimitLayer;
+//! use http::{Request, Response, StatusCode};
+//! use http_body_util::Full;
+//! use bytes::Bytes;
+//! use std::{convert::Infallible, time::Duration};
+//! use tower::{ServiceBuilder, Service, ServiceExt};
+//!
+//! async fn handle(req: Request<Full<Bytes>>) -> Result<Response<Full<Bytes>>, Infallible> {
+//!     Ok(Response::new(Full::default()))
+//! }
+//!
+//! # #[tokio::main]
+//! # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+//! // Allow bursts of 2 requests per API key, and 1 more request every 10 seconds.
+//! let layer = RateLimitLayer::new(
+//!     |req: &Request<Full<Bytes>>| req.headers().get("x-api-key").cloned(),
+//!     2,
+//!     Duration::from_secs(10),
+//! );
+//! let mut service = ServiceBuilder::new().layer(layer).service_fn(handle);
+//!
+//! for _ in 0..2 {
+//!     let request = Request::builder()
+//!         .header("x-api-key", "abc")
+//!         .body(Full::default())?;
+//!     let response = service.ready().await?.call(request).await?;
+//!     assert_eq!(response.status(), StatusCode::OK);
+//! }
+//!
+//! let request = Request::builder()
+//!     .header("x-api-key", "abc")
+//!     .body(Full::default())?;
+//! let response = service.ready().await?.call(request).await?;
+//! assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+//! assert_eq!(response.headers()["retry-after"], "10");
+//! #
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! [token bucket]: https://en.wikipedia.org/wiki/Token_bucket
+
+use http::{header, HeaderValue, Request, Response, StatusCode};
+use pin_project_lite::pin_project;
+use std::{
+    collections::HashMap,
+    fmt,
+    future::Future,
+    hash::Hash,
+    pin::Pin,
+    sync::{Arc, Mutex},
+    task::{Context, Poll},
+    time::Duration,
+};
+use tokio::time::Instant;
+use tower_layer::Layer;
+use tower_service::Service;
+
+const DEFAULT_MAX_KEYS: usize = 10_000;
+
+/// Layer that applies [`RateLimit`] which limits the rate of requests per client.
+///
+/// See the [module docs](self) for more details.
+pub struct RateLimitLayer<F, K> {
+    key: F,
+    config: Config,
+    buckets: Arc<Mutex<HashMap<K, Bucket>>>,
+}
+
+impl<F, K> RateLimitLayer<F, K> {
+    /// Create a new [`RateLimitLayer`].
+    ///
+    /// `key` extracts the key identifying the client from requests. Each client can make
+    /// `capacity` requests in a burst, after which one more request is allowed every
+    /// `refill_interval`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `refill_interval` is zero.
+    pub fn new(key: F, capacity: u32, refill_interval: Duration) -> Self {
+        assert!(
+            !refill_interval.is_zero(),
+            "refill interval must be greater than zero"
+        );
+        Self {
+            key,
+            config: Config {
+                capacity,
+                refill_interval,
+                max_keys: DEFAULT_MAX_KEYS,
+            },
+            buckets: Default::default(),
+        }
+    }
+
+    /// Set the maximum number of keys to keep buckets for.
+    ///
+    /// Defaults to 10,000.
+    pub fn max_keys(mut self, max_keys: usize) -> Self {
+        self.config.max_keys = max_keys;
+        self
+    }
+}
+
+impl<F, K> Clone for RateLimitLayer<F, K>
+where
+    F: Clone,
+{
+    fn clone(&self) -> Self {
+        Self {
+            key: self.key.clone(),
+            config: self.config,
+            buckets: self.buckets.clone(),
+        }
+    }
+}
+
+impl<F, K> fmt::Debug for RateLimitLayer<F, K> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RateLimitLayer")
+            .field("key", &std::any::type_name::<F>())
+            .field("config", &self.config)
+            .finish()
+    }
+}
+
+impl<S, F, K> Layer<S> for RateLimitLayer<F, K>
+where
+    F: Clone,
+{
+    type Service = RateLimit<S, F, K>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        RateLimit {
+            inner,
+            key: self.key.clone(),
+            config: self.config,
+            buckets: self.buckets.clone(),
+        }
+    }
+}
+
+/// Middleware that limits the rate of requests per client.
+///
+/// See the [module docs](self) for more details.
+pub struct RateLimit<S, F, K> {
+    inner: S,
+    key: F,
+    config: Config,
+    buckets: Arc<Mutex<HashMap<K, Bucket>>>,
+}
+
+impl<S, F, K> RateLimit<S, F, K> {
+    /// Create a new [`RateLimit`].
+    ///
+    /// See [`RateLimitLayer::new`] for the meaning of the arguments.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `refill_interval` is zero.
+    pub fn new(inner: S, key: F, capacity: u32, refill_interval: Duration) -> Self
+    where
+        F: Clone,
+    {
+        RateLimitLayer::new(key, capacity, refill_interval).layer(inner)
+    }
+
+    /// Set the maximum number of keys to keep buckets for.
+    ///
+    /// Defaults to 10,000.
+    pub fn max_keys(mut self, max_keys: usize) -> Self {
+        self.config.max_keys = max_keys;
+        self
+    }
+
+    define_inner_service_accessors!();
+
+    /// Returns a new [`Layer`] that wraps services with a `RateLimit` middleware.
+    ///
+    /// [`Layer`]: tower_layer::Layer
+    pub fn layer(key: F, capacity: u32, refill_interval: Duration) -> RateLimitLayer<F, K> {
+        RateLimitLayer::new(key, capacity, refill_interval)
+    }
+}
+
+impl<S, F, K> Clone for RateLimit<S, F, K>
+where
+    S: Clone,
+    F: Clone,
+{
+    fn clone(&self) -> Self {
+        Self {
+            inner: self.inner.clone(),
+            key: self.key.clone(),
+            config: self.config,
+            buckets: self.buckets.clone(),
+        }
+    }
+}
+
+impl<S, F, K> fmt::Debug for RateLimit<S, F, K>
+where
+    S: fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RateLimit")
+            .field("inner", &self.inner)
+            .field("key", &std::any::type_name::<F>())
+            .field("config", &self.config)
+            .finish()
+    }
+}
+
+impl<ReqBody, ResBody, S, F, K> Service<Request<ReqBody>> for RateLimit<S, F, K>
+where
+    S: Service<Request<ReqBody>, Response = Response<ResBody>>,
+    F: Fn(&Request<ReqBody>) -> K,
+    K: Hash + Eq,
+    ResBody: Default,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = ResponseFuture<S::Future>;
+
+    #[inline]
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, req: Request<ReqBody>) -> Self::Future {
+        let key = (self.key)(&req);
+        let acquired = {
+            let mut buckets = self.buckets.lock().unwrap();
+            acquire(&mut buckets, key, &self.config, Instant::now())
+        };
+
+        match acquired {
+            Ok(()) => ResponseFuture {
+                inner: ResponseFutureInner::Future {
+                    future: self.inner.call(req),
+                },
+            },
+            Err(retry_after) => ResponseFuture {
+                inner: ResponseFutureInner::TooManyRequests { retry_after },
+            },
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Config {
+    capacity: u32,
+    refill_interval: Duration,
+    max_keys: usize,
+}
+
+#[derive(Debug)]
+struct Bucket {
+    tokens: u32,
+    // when the bucket had `tokens` tokens, so the next token is added `refill_interval` later
+    updated: Instant,
+}
+
+impl Bucket {
+    // Adds the tokens for the refill intervals elapsed since `updated`. Full buckets don't
+    // accumulate time, so the next token is added `refill_interval` after one is taken.
+    fn refill(&mut self, config: &Config, now: Instant) {
+        let elapsed = now.saturating_duration_since(self.updated);
+        let refills = elapsed.as_nanos() / config.refill_interval.as_nanos();
+        let missing = config.capacity - self.tokens;
+        if refills >= u128::from(missing) {
+            self.tokens = config.capacity;
+            self.updated = now;
+        } else {
+            // `refills` is less than `missing`, so it fits in a `u32`
+            let refills = refills as u32;
+            self.tokens += refills;
+            self.updated += config.refill_interval * refills;
+        }
+    }
+
+    fn is_full(&self, config: &Config, now: Instant) -> bool {
+        let missing = config.capacity - self.tokens;
+        now.saturating_duration_since(self.updated) >= config.refill_interval * missing
+    }
+}
+
+// Takes a token from the bucket of `key`, or returns the number of seconds until a token is
+// available.
+fn acquire<K>(
+    buckets: &mut HashMap<K, Bucket>,
+    key: K,
+    config: &Config,
+    now: Instant,
+) -> Result<(), u64>
+where
+    K: Hash + Eq,
+{
+    if !buckets.contains_key(&key) && buckets.len() >= config.max_keys {
+        evict(buckets, config, now);
+    }
+
+    let bucket = buckets.entry(key).or_insert(Bucket {
+        tokens: config.capacity,
+        updated: now,
+    });
+    bucket.refill(config, now);
+
+    if bucket.tokens > 0 {
+        bucket.tokens -= 1;
+        Ok(())
+    } else {
+        let wait = (bucket.updated + config.refill_interval).saturating_duration_since(now);
+        let secs = wait.as_secs() + u64::from(wait.subsec_nanos() > 0);
+        Err(secs.max(1))
+    }
+}
+
+fn evict<K>(buckets: &mut HashMap<K, Bucket>, config: &Config, now: Instant)
+where
+    K: Hash + Eq,
+{
+    buckets.retain(|_, bucket| !bucket.is_full(config, now));
+
+    while buckets.len() >= config.max_keys {
+        let oldest = match buckets.values().map(|bucket| bucket.updated).min() {
+            Some(oldest) => oldest,
+            None => break,
+        };
+        buckets.retain(|_, bucket| bucket.updated != oldest);
+    }
+}
+
+pin_project! {
+    /// Response future for [`RateLimit`].
+    pub struct ResponseFuture<F> {
+        #[pin]
+        inner: ResponseFutureInner<F>,
+    }
+}
+
+pin_project! {
+    #[project = ResFutProj]
+    enum ResponseFutureInner<F> {
+        TooManyRequests {
+            retry_after: u64,
+        },
+        Future {
+            #[pin]
+            future: F,
+        }
+    }
+}
+
+impl<ResBody, F, E> Future for ResponseFuture<F>
+where
+    ResBody: Default,
+    F: Future<Output = Result<Response<ResBody>, E>>,
+{
+    type Output = Result<Response<ResBody>, E>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        match self.project().inner.project() {
+            ResFutProj::TooManyRequests { retry_after } => {
+                let mut res = Response::new(ResBody::default());
+                *res.status_mut() = StatusCode::TOO_MANY_REQUESTS;
+                res.headers_mut()
+                    .insert(header::RETRY_AFTER, HeaderValue::from(*retry_after));
+                Poll::Ready(Ok(res))
+            }
+            ResFutProj::Future { future } => future.poll(cx),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_helpers::Body;
+    use std::convert::Infallible;
+    use tower::{ServiceBuilder, ServiceExt};
+
+    fn service(
+        layer: RateLimitLayer<fn(&Request<Body>) -> Option<HeaderValue>, Option<HeaderValue>>,
+    ) -> impl Service<Request<Body>, Response = Response<Body>, Error = Infallible> + Clone {
+        ServiceBuilder::new()
+            .layer(layer)
+            .service_fn(|_: Request<Body>| async {
+                Ok::<_, Infallible>(Response::new(Body::empty()))
+            })
+    }
+
+    fn layer(
+        capacity: u32,
+        refill_interval: Duration,
+    ) -> RateLimitLayer<fn(&Request<Body>) -> Option<HeaderValue>, Option<HeaderValue>> {
+        RateLimitLayer::new(
+            |req| req.headers().get("x-api-key").cloned(),
+            capacity,
+            refill_interval,
+        )
+    }
+
+    async fn call<S>(svc: &S, key: &'static str) -> Response<Body>
+    where
+        S: Service<Request<Body>, Response = Response<Body>, Error = Infallible> + Clone,
+    {
+        let req = Request::builder()
+            .header("x-api-key", key)
+            .body(Body::empty())
+            .unwrap();
+        svc.clone().oneshot(req).await.unwrap()
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn limits_bursts_and_refills() {
+        let svc = service(layer(2, Duration::from_secs(10)));
+
+        assert_eq!(call(&svc, "a").await.status(), StatusCode::OK);
+        assert_eq!(call(&svc, "a").await.status(), StatusCode::OK);
+        let res = call(&svc, "a").await;
+        assert_eq!(res.status(), StatusCode::TOO_MANY_REQUESTS);
+        assert_eq!(res.headers()[header::RETRY_AFTER], "10");
+
+        // other keys have their own bucket
+        assert_eq!(call(&svc, "b").await.status(), StatusCode::OK);
+
+        tokio::time::advance(Duration::from_millis(4500)).await;
+        let res = call(&svc, "a").await;
+        assert_eq!(res.status(), StatusCode::TOO_MANY_REQUESTS);
+        assert_eq!(res.headers()[header::RETRY_AFTER], "6");
+
+        // one token is added per refill interval
+        tokio::time::advance(Duration::from_millis(5500)).await;
+        assert_eq!(call(&svc, "a").await.status(), StatusCode::OK);
+        assert_eq!(
+            call(&svc, "a").await.status(),
+            StatusCode::TOO_MANY_REQUESTS
+        );
+
+        // up to the capacity
+        tokio::time::advance(Duration::from_secs(60)).await;
+        assert_eq!(call(&svc, "a").await.status(), StatusCode::OK);
+        assert_eq!(call(&svc, "a").await.status(), StatusCode::OK);
+        assert_eq!(
+            call(&svc, "a").await.status(),
+            StatusCode::TOO_MANY_REQUESTS
+        );
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn evicts_keys() {
+        let layer = layer(1, Duration::from_secs(10)).max_keys(2);
+        let buckets = layer.buckets.clone();
+        let svc = service(layer);
+
+        assert_eq!(call(&svc, "a").await.status(), StatusCode::OK);
+        tokio::time::advance(Duration::from_secs(1)).await;
+        assert_eq!(call(&svc, "b").await.status(), StatusCode::OK);
+        tokio::time::advance(Duration::from_secs(1)).await;
+
+        // the least recently used key is evicted
+        assert_eq!(call(&svc, "c").await.status(), StatusCode::OK);
+        assert_eq!(buckets.lock().unwrap().len(), 2);
+        assert_eq!(
+            call(&svc, "c").await.status(),
+            StatusCode::TOO_MANY_REQUESTS
+        );
+        assert_eq!(call(&svc, "a").await.status(), StatusCode::OK);
+
+        // refilled buckets are evicted first
+        tokio::time::advance(Duration::from_secs(10)).await;
+        assert_eq!(call(&svc, "d").await.status(), StatusCode::OK);
+        assert_eq!(buckets.lock().unwrap().len(), 1);
+    }
+}
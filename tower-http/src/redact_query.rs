@@ -0,0 +1,226 @@
+//! Middleware that exposes a redacted request target for logging.
+//!
+//! Query strings often carry secrets, such as `?access_token=...`, that shouldn't end up in logs.
+//! [`RedactQuery`] inserts a [`RedactedTarget`] extension into requests, containing the path and
+//! query of the request URI with the values of the given parameters replaced by `REDACTED`. The
+//! request URI itself is left untouched, so the inner service still sees the real values.
+//!
+//! When the `trace` feature is enabled, [`DefaultMakeSpan`] records the [`RedactedTarget`] instead
+//! of the request URI if the extension is present. `RedactQueryLayer` must then be applied
+//! before [`TraceLayer`].
+//!
+//! # Example
+//!
+//! ```
+//! use tower_http::redact_query::{RedactQueryLayer, RedactedTarget};
+//! use http::{Request, Response};
+//! use http_body_util::Full;
+//! use bytes::Bytes;
+//! use std::convert::Infallible;
+//! use tower::{ServiceBuilder, Service, ServiceExt};
+//!
+//! async fn handle(req: Request<Full<Bytes>>) -> Result<Response<Full<Bytes>>, Infallible> {
+//!     let target = req.extensions().get::<RedactedTarget>().unwrap();
+//!     assert_eq!(target.as_str(), "/users?access_token=REDACTED&page=2");
+//!
+//!     // the request URI is unchanged
+//!     assert_eq!(req.uri(), "/users?access_token=hunter2&page=2");
+//!
+//!     Ok(Response::new(Full::default()))
+//! }
+//!
+//! # #[tokio::main]
+//! # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+//! let mut service = ServiceBuilder::new()
+//!     .layer(RedactQueryLayer::new(&["access_token", "sig"]))
+//!     .service_fn(handle);
+//!
+//! let request = Request::builder()
+//!     .uri("/users?access_token=hunter2&page=2")
+//!     .body(Full::default())?;
+//!
+//! service.ready().await?.call(request).await?;
+//! #
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! [`DefaultMakeSpan`]: crate::trace::DefaultMakeSpan
+//! [`TraceLayer`]: crate::trace::TraceLayer
+
+use http::{Request, Uri};
+use std::{
+    fmt,
+    sync::Arc,
+    task::{Context, Poll},
+};
+use tower_layer::Layer;
+use tower_service::Service;
+
+const REDACTED: &str = "REDACTED";
+
+/// Layer that applies [`RedactQuery`] which exposes a redacted request target for logging.
+///
+/// See the [module docs](self) for more details.
+#[derive(Debug, Clone)]
+pub struct RedactQueryLayer {
+    params: Arc<[Box<str>]>,
+}
+
+impl RedactQueryLayer {
+    /// Create a new [`RedactQueryLayer`].
+    ///
+    /// The values of the query parameters named `params` will be redacted.
+    pub fn new(params: &[&str]) -> Self {
+        let params = params
+            .iter()
+            .map(|&param| Box::from(param))
+            .collect::<Vec<_>>();
+        RedactQueryLayer {
+            params: params.into(),
+        }
+    }
+}
+
+impl<S> Layer<S> for RedactQueryLayer {
+    type Service = RedactQuery<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        RedactQuery {
+            inner,
+            params: self.params.clone(),
+        }
+    }
+}
+
+/// Middleware that exposes a redacted request target for logging.
+///
+/// See the [module docs](self) for more details.
+#[derive(Debug, Clone)]
+pub struct RedactQuery<S> {
+    inner: S,
+    params: Arc<[Box<str>]>,
+}
+
+impl<S> RedactQuery<S> {
+    /// Create a new [`RedactQuery`].
+    ///
+    /// The values of the query parameters named `params` will be redacted.
+    pub fn new(inner: S, params: &[&str]) -> Self {
+        RedactQueryLayer::new(params).layer(inner)
+    }
+
+    define_inner_service_accessors!();
+
+    /// Returns a new [`Layer`] that wraps services with a `RedactQuery` middleware.
+    ///
+    /// [`Layer`]: tower_layer::Layer
+    pub fn layer(params: &[&str]) -> RedactQueryLayer {
+        RedactQueryLayer::new(params)
+    }
+}
+
+impl<S, ReqBody> Service<Request<ReqBody>> for RedactQuery<S>
+where
+    S: Service<Request<ReqBody>>,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = S::Future;
+
+    #[inline]
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, mut req: Request<ReqBody>) -> Self::Future {
+        let target = RedactedTarget(redact(req.uri(), &self.params));
+        req.extensions_mut().insert(target);
+        self.inner.call(req)
+    }
+}
+
+/// The path and query of the request URI, with sensitive query parameters redacted.
+///
+/// Inserted into requests by [`RedactQuery`]. See the [module docs](self) for more details.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RedactedTarget(String);
+
+impl RedactedTarget {
+    /// Get the redacted path and query as a `&str`.
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl fmt::Display for RedactedTarget {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+fn redact(uri: &Uri, params: &[Box<str>]) -> String {
+    let mut target = uri.path().to_owned();
+
+    let query = match uri.query() {
+        Some(query) => query,
+        None => return target,
+    };
+
+    target.push('?');
+    for (i, pair) in query.split('&').enumerate() {
+        if i > 0 {
+            target.push('&');
+        }
+
+        match pair.split_once('=') {
+            Some((name, _)) if params.iter().any(|param| **param == *name) => {
+                target.push_str(name);
+                target.push('=');
+                target.push_str(REDACTED);
+            }
+            _ => target.push_str(pair),
+        }
+    }
+
+    target
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::convert::Infallible;
+    use tower::{ServiceBuilder, ServiceExt};
+
+    async fn redacted(uri: &'static str) -> RedactedTarget {
+        let svc = ServiceBuilder::new()
+            .layer(RedactQueryLayer::new(&["access_token", "sig"]))
+            .service_fn(move |req: Request<()>| async move {
+                assert_eq!(req.uri(), uri);
+                let target = req.extensions().get::<RedactedTarget>().unwrap().clone();
+                Ok::<_, Infallible>(target)
+            });
+
+        svc.oneshot(Request::builder().uri(uri).body(()).unwrap())
+            .await
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn redacts_matching_params() {
+        let target = redacted("/files?page=2&access_token=hunter2&sort=asc&sig=abc%3D").await;
+        assert_eq!(
+            target.as_str(),
+            "/files?page=2&access_token=REDACTED&sort=asc&sig=REDACTED"
+        );
+    }
+
+    #[tokio::test]
+    async fn leaves_other_params() {
+        let target = redacted("http://example.com/files?page=2&token&x_sig=1").await;
+        assert_eq!(target.as_str(), "/files?page=2&token&x_sig=1");
+
+        let target = redacted("/files").await;
+        assert_eq!(target.as_str(), "/files");
+    }
+}
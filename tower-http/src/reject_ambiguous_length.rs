@@ -0,0 +1,277 @@
+//! Middleware that rejects requests with an ambiguous body length.
+//!
+//! If a proxy and a server disagree about where the body of a request ends, an attacker can
+//! smuggle a second request in the body of the first. [RFC 9112] allows servers to reject
+//! requests whose length is ambiguous, which [`RejectAmbiguousLength`] does with `400 Bad
+//! Request`, without calling the inner service, when:
+//!
+//! - Both `Content-Length` and `Transfer-Encoding` headers are present.
+//! - `Content-Length` headers, or the values of a comma-separated list in one, are invalid or
+//!   differ. Repeated identical values are accepted.
+//!
+//! The HTTP implementation may already reject some of these requests, but applying this
+//! middleware makes sure they never reach services, whatever the implementation does.
+//!
+//! # Example
+//!
+//! ```
+//! use tower_http::reject_ambiguous_length::RejectAmbiguousLengthLayer;
+//! use http::{header, Request, Response, StatusCode};
+//! use http_body_util::Full;
+//! use bytes::Bytes;
+//! use std::convert::Infallible;
+//! use tower::{ServiceBuilder, Service, ServiceExt};
+//!
+//! async fn handle(req: Request<Full<Bytes>>) -> Result<Response<Full<Bytes>>, Infallible> {
+//!     Ok(Response::new(Full::default()))
+//! }
+//!
+//! # #[tokio::main]
+//! # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+//! let mut service = ServiceBuilder::new()
+//!     .layer(RejectAmbiguousLengthLayer::new())
+//!     .service_fn(handle);
+//!
+//! let request = Request::builder()
+//!     .header(header::CONTENT_LENGTH, "5")
+//!     .header(header::TRANSFER_ENCODING, "chunked")
+//!     .body(Full::from("hello"))?;
+//! let response = service.ready().await?.call(request).await?;
+//! assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+//! #
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! [RFC 9112]: https://www.rfc-editor.org/rfc/rfc9112#section-6.3
+
+use http::{header, HeaderMap, Request, Response, StatusCode};
+use pin_project_lite::pin_project;
+use std::{
+    future::Future,
+    pin::Pin,
+    task::{Context, Poll},
+};
+use tower_layer::Layer;
+use tower_service::Service;
+
+/// Layer that applies [`RejectAmbiguousLength`] which rejects requests with an ambiguous body
+/// length.
+///
+/// See the [module docs](self) for more details.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct RejectAmbiguousLengthLayer {
+    _priv: (),
+}
+
+impl RejectAmbiguousLengthLayer {
+    /// Create a new [`RejectAmbiguousLengthLayer`].
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl<S> Layer<S> for RejectAmbiguousLengthLayer {
+    type Service = RejectAmbiguousLength<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        RejectAmbiguousLength { inner }
+    }
+}
+
+/// Middleware that rejects requests with an ambiguous body length.
+///
+/// See the [module docs](self) for more details.
+#[derive(Debug, Clone, Copy)]
+pub struct RejectAmbiguousLength<S> {
+    inner: S,
+}
+
+impl<S> RejectAmbiguousLength<S> {
+    /// Create a new [`RejectAmbiguousLength`].
+    pub fn new(inner: S) -> Self {
+        Self { inner }
+    }
+
+    define_inner_service_accessors!();
+
+    /// Returns a new [`Layer`] that wraps services with a `RejectAmbiguousLength` middleware.
+    ///
+    /// [`Layer`]: tower_layer::Layer
+    pub fn layer() -> RejectAmbiguousLengthLayer {
+        RejectAmbiguousLengthLayer::new()
+    }
+}
+
+impl<ReqBody, ResBody, S> Service<Request<ReqBody>> for RejectAmbiguousLength<S>
+where
+    S: Service<Request<ReqBody>, Response = Response<ResBody>>,
+    ResBody: Default,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = ResponseFuture<S::Future>;
+
+    #[inline]
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, req: Request<ReqBody>) -> Self::Future {
+        if is_ambiguous(req.headers()) {
+            return ResponseFuture {
+                inner: ResponseFutureInner::BadRequest,
+            };
+        }
+
+        ResponseFuture {
+            inner: ResponseFutureInner::Future {
+                future: self.inner.call(req),
+            },
+        }
+    }
+}
+
+fn is_ambiguous(headers: &HeaderMap) -> bool {
+    let mut lengths = headers.get_all(header::CONTENT_LENGTH).iter().peekable();
+    if lengths.peek().is_none() {
+        return false;
+    }
+    if headers.contains_key(header::TRANSFER_ENCODING) {
+        return true;
+    }
+
+    let mut len = None;
+    for value in lengths {
+        let value = match value.to_str() {
+            Ok(value) => value,
+            Err(_) => return true,
+        };
+        for value in value.split(',') {
+            let value = value.trim();
+            if value.is_empty() || !value.bytes().all(|b| b.is_ascii_digit()) {
+                return true;
+            }
+            let value = match value.parse::<u64>() {
+                Ok(value) => value,
+                Err(_) => return true,
+            };
+            match len {
+                Some(len) if len != value => return true,
+                _ => len = Some(value),
+            }
+        }
+    }
+    false
+}
+
+pin_project! {
+    /// Response future for [`RejectAmbiguousLength`].
+    pub struct ResponseFuture<F> {
+        #[pin]
+        inner: ResponseFutureInner<F>,
+    }
+}
+
+pin_project! {
+    #[project = ResFutProj]
+    enum ResponseFutureInner<F> {
+        BadRequest,
+        Future {
+            #[pin]
+            future: F,
+        }
+    }
+}
+
+impl<ResBody, F, E> Future for ResponseFuture<F>
+where
+    ResBody: Default,
+    F: Future<Output = Result<Response<ResBody>, E>>,
+{
+    type Output = Result<Response<ResBody>, E>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        match self.project().inner.project() {
+            ResFutProj::BadRequest => {
+                let mut res = Response::new(ResBody::default());
+                *res.status_mut() = StatusCode::BAD_REQUEST;
+                Poll::Ready(Ok(res))
+            }
+            ResFutProj::Future { future } => future.poll(cx),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_helpers::Body;
+    use std::convert::Infallible;
+    use tower::{ServiceBuilder, ServiceExt};
+
+    async fn status(headers: &[(header::HeaderName, &'static str)]) -> StatusCode {
+        let svc = ServiceBuilder::new()
+            .layer(RejectAmbiguousLengthLayer::new())
+            .service_fn(|_: Request<Body>| async {
+                Ok::<_, Infallible>(Response::new(Body::empty()))
+            });
+
+        let mut req = Request::new(Body::empty());
+        for (name, value) in headers {
+            req.headers_mut()
+                .append(name, header::HeaderValue::from_static(value));
+        }
+        svc.oneshot(req).await.unwrap().status()
+    }
+
+    #[tokio::test]
+    async fn accepts_well_formed_requests() {
+        assert_eq!(status(&[]).await, StatusCode::OK);
+        assert_eq!(
+            status(&[(header::CONTENT_LENGTH, "5")]).await,
+            StatusCode::OK
+        );
+        assert_eq!(
+            status(&[(header::TRANSFER_ENCODING, "chunked")]).await,
+            StatusCode::OK
+        );
+        // repeated identical values are fine
+        assert_eq!(
+            status(&[
+                (header::CONTENT_LENGTH, "5, 5"),
+                (header::CONTENT_LENGTH, "5")
+            ])
+            .await,
+            StatusCode::OK
+        );
+    }
+
+    #[tokio::test]
+    async fn rejects_content_length_with_transfer_encoding() {
+        assert_eq!(
+            status(&[
+                (header::CONTENT_LENGTH, "5"),
+                (header::TRANSFER_ENCODING, "chunked")
+            ])
+            .await,
+            StatusCode::BAD_REQUEST
+        );
+    }
+
+    #[tokio::test]
+    async fn rejects_conflicting_content_lengths() {
+        assert_eq!(
+            status(&[(header::CONTENT_LENGTH, "5"), (header::CONTENT_LENGTH, "6")]).await,
+            StatusCode::BAD_REQUEST
+        );
+        assert_eq!(
+            status(&[(header::CONTENT_LENGTH, "5, 6")]).await,
+            StatusCode::BAD_REQUEST
+        );
+        assert_eq!(
+            status(&[(header::CONTENT_LENGTH, "+5")]).await,
+            StatusCode::BAD_REQUEST
+        );
+    }
+}
@@ -0,0 +1,237 @@
+//! Middleware that rewrites the authority of `Location` headers.
+//!
+//! This is useful for reverse proxies, where upstream services respond with `Location` headers
+//! that point at their internal host, rather than the public host the client connected to.
+//!
+//! Both the `Location` and `Content-Location` headers are rewritten, but only when they are
+//! absolute URIs whose authority matches the one being rewritten. Relative locations are left
+//! untouched.
+//!
+//! # Example
+//!
+//! ```
+//! use tower_http::rewrite_location::RewriteLocationLayer;
+//! use http::{header, uri::Authority, Request, Response, StatusCode};
+//! use http_body_util::Full;
+//! use bytes::Bytes;
+//! use std::convert::Infallible;
+//! use tower::{ServiceBuilder, Service, ServiceExt};
+//!
+//! async fn upstream(req: Request<Full<Bytes>>) -> Result<Response<Full<Bytes>>, Infallible> {
+//!     let res = Response::builder()
+//!         .status(StatusCode::FOUND)
+//!         .header(header::LOCATION, "http://backend.internal:8080/login")
+//!         .body(Full::default())
+//!         .unwrap();
+//!     Ok(res)
+//! }
+//!
+//! # #[tokio::main]
+//! # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+//! let mut service = ServiceBuilder::new()
+//!     .layer(RewriteLocationLayer::new(
+//!         Authority::from_static("backend.internal:8080"),
+//!         Authority::from_static("example.com"),
+//!     ))
+//!     .service_fn(upstream);
+//!
+//! let request = Request::builder().body(Full::default())?;
+//!
+//! let response = service.ready().await?.call(request).await?;
+//!
+//! assert_eq!(response.headers()[header::LOCATION], "http://example.com/login");
+//! #
+//! # Ok(())
+//! # }
+//! ```
+
+use http::{header, uri::Authority, HeaderMap, HeaderValue, Request, Response, Uri};
+use pin_project_lite::pin_project;
+use std::{
+    future::Future,
+    pin::Pin,
+    task::{ready, Context, Poll},
+};
+use tower_layer::Layer;
+use tower_service::Service;
+
+/// Layer that applies [`RewriteLocation`] which rewrites the authority of `Location` headers.
+///
+/// See the [module docs](self) for more details.
+#[derive(Debug, Clone)]
+pub struct RewriteLocationLayer {
+    from: Authority,
+    to: Authority,
+}
+
+impl RewriteLocationLayer {
+    /// Create a new [`RewriteLocationLayer`].
+    ///
+    /// Locations whose authority is `from` will be rewritten to have the authority `to`.
+    pub fn new(from: Authority, to: Authority) -> Self {
+        RewriteLocationLayer { from, to }
+    }
+}
+
+impl<S> Layer<S> for RewriteLocationLayer {
+    type Service = RewriteLocation<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        RewriteLocation::new(inner, self.from.clone(), self.to.clone())
+    }
+}
+
+/// Middleware that rewrites the authority of `Location` headers.
+///
+/// See the [module docs](self) for more details.
+#[derive(Debug, Clone)]
+pub struct RewriteLocation<S> {
+    inner: S,
+    from: Authority,
+    to: Authority,
+}
+
+impl<S> RewriteLocation<S> {
+    /// Create a new [`RewriteLocation`].
+    ///
+    /// Locations whose authority is `from` will be rewritten to have the authority `to`.
+    pub fn new(inner: S, from: Authority, to: Authority) -> Self {
+        Self { inner, from, to }
+    }
+
+    define_inner_service_accessors!();
+
+    /// Returns a new [`Layer`] that wraps services with a `RewriteLocation` middleware.
+    ///
+    /// [`Layer`]: tower_layer::Layer
+    pub fn layer(from: Authority, to: Authority) -> RewriteLocationLayer {
+        RewriteLocationLayer::new(from, to)
+    }
+}
+
+impl<S, ReqBody, ResBody> Service<Request<ReqBody>> for RewriteLocation<S>
+where
+    S: Service<Request<ReqBody>, Response = Response<ResBody>>,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = ResponseFuture<S::Future>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, req: Request<ReqBody>) -> Self::Future {
+        ResponseFuture {
+            inner: self.inner.call(req),
+            from: self.from.clone(),
+            to: self.to.clone(),
+        }
+    }
+}
+
+pin_project! {
+    /// Response future for [`RewriteLocation`].
+    pub struct ResponseFuture<F> {
+        #[pin]
+        inner: F,
+        from: Authority,
+        to: Authority,
+    }
+}
+
+impl<F, B, E> Future for ResponseFuture<F>
+where
+    F: Future<Output = Result<Response<B>, E>>,
+{
+    type Output = F::Output;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = self.project();
+        let mut response = ready!(this.inner.poll(cx)?);
+        rewrite_locations(response.headers_mut(), this.from, this.to);
+        Poll::Ready(Ok(response))
+    }
+}
+
+fn rewrite_locations(headers: &mut HeaderMap, from: &Authority, to: &Authority) {
+    for name in [header::LOCATION, header::CONTENT_LOCATION] {
+        if let Some(value) = headers.get_mut(name) {
+            if let Some(rewritten) = rewrite_location(value, from, to) {
+                *value = rewritten;
+            }
+        }
+    }
+}
+
+fn rewrite_location(value: &HeaderValue, from: &Authority, to: &Authority) -> Option<HeaderValue> {
+    let uri = value.to_str().ok()?.parse::<Uri>().ok()?;
+    if uri.authority() != Some(from) {
+        return None;
+    }
+
+    let mut parts = uri.into_parts();
+    parts.authority = Some(to.clone());
+    let uri = Uri::from_parts(parts).ok()?;
+    HeaderValue::from_str(&uri.to_string()).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::convert::Infallible;
+    use tower::{ServiceBuilder, ServiceExt};
+
+    async fn rewrite(location: &'static str) -> Response<()> {
+        let svc = ServiceBuilder::new()
+            .layer(RewriteLocationLayer::new(
+                Authority::from_static("backend.internal:8080"),
+                Authority::from_static("example.com"),
+            ))
+            .service_fn(move |_: Request<()>| async move {
+                let res = Response::builder()
+                    .header(header::LOCATION, location)
+                    .header(header::CONTENT_LOCATION, location)
+                    .body(())
+                    .unwrap();
+                Ok::<_, Infallible>(res)
+            });
+
+        svc.oneshot(Request::new(())).await.unwrap()
+    }
+
+    #[tokio::test]
+    async fn rewrites_matching_absolute_location() {
+        let res = rewrite("http://backend.internal:8080/login?next=%2F").await;
+        assert_eq!(
+            res.headers()[header::LOCATION],
+            "http://example.com/login?next=%2F"
+        );
+        assert_eq!(
+            res.headers()[header::CONTENT_LOCATION],
+            "http://example.com/login?next=%2F"
+        );
+    }
+
+    #[tokio::test]
+    async fn leaves_non_matching_absolute_location() {
+        let res = rewrite("http://backend.internal:9090/login").await;
+        assert_eq!(
+            res.headers()[header::LOCATION],
+            "http://backend.internal:9090/login"
+        );
+
+        let res = rewrite("https://other.example.com/login").await;
+        assert_eq!(
+            res.headers()[header::LOCATION],
+            "https://other.example.com/login"
+        );
+    }
+
+    #[tokio::test]
+    async fn leaves_relative_location() {
+        let res = rewrite("/login").await;
+        assert_eq!(res.headers()[header::LOCATION], "/login");
+        assert_eq!(res.headers()[header::CONTENT_LOCATION], "/login");
+    }
+}
@@ -0,0 +1,285 @@
+//! Middleware that adds [`Server-Timing`] headers to responses.
+//!
+//! [`ServerTiming`] measures how long the inner service took to produce a response and appends
+//! it as a `Server-Timing` entry, such as `app;dur=12.3`, where the duration is in milliseconds.
+//! Browsers show these timings in their developer tools.
+//!
+//! Inner layers and handlers can contribute additional named timings through the
+//! [`ServerTimings`] request extension. They are appended to the response after the total.
+//!
+//! # Example
+//!
+//! ```
+//! use tower_http::server_timing::{ServerTimingLayer, ServerTimings};
+//! use http::{header::HeaderName, Request, Response};
+//! use http_body_util::Full;
+//! use bytes::Bytes;
+//! use std::{convert::Infallible, time::Duration};
+//! use tower::{ServiceBuilder, Service, ServiceExt};
+//!
+//! async fn handle(req: Request<Full<Bytes>>) -> Result<Response<Full<Bytes>>, Infallible> {
+//!     let timings = req.extensions().get::<ServerTimings>().unwrap();
+//!     // contribute the time spent querying the database
+//!     timings.record("db", Duration::from_millis(4));
+//!
+//!     Ok(Response::new(Full::default()))
+//! }
+//!
+//! # #[tokio::main]
+//! # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+//! let mut service = ServiceBuilder::new()
+//!     .layer(ServerTimingLayer::new())
+//!     .service_fn(handle);
+//!
+//! let request = Request::new(Full::default());
+//! let response = service.ready().await?.call(request).await?;
+//!
+//! let timings = response
+//!     .headers()
+//!     .get_all(HeaderName::from_static("server-timing"))
+//!     .iter()
+//!     .collect::<Vec<_>>();
+//! assert!(timings[0].to_str()?.starts_with("app;dur="));
+//! assert_eq!(timings[1], "db;dur=4.0");
+//! #
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! [`Server-Timing`]: https://developer.mozilla.org/en-US/docs/Web/HTTP/Headers/Server-Timing
+
+use http::{header::HeaderName, HeaderValue, Request, Response};
+use pin_project_lite::pin_project;
+use std::{
+    borrow::Cow,
+    future::Future,
+    mem,
+    pin::Pin,
+    sync::{Arc, Mutex},
+    task::{ready, Context, Poll},
+    time::{Duration, Instant},
+};
+use tower_layer::Layer;
+use tower_service::Service;
+
+const SERVER_TIMING: HeaderName = HeaderName::from_static("server-timing");
+
+/// Layer that applies [`ServerTiming`] which adds `Server-Timing` headers to responses.
+///
+/// See the [module docs](self) for more details.
+#[derive(Debug, Clone, Copy)]
+pub struct ServerTimingLayer {
+    name: &'static str,
+}
+
+impl ServerTimingLayer {
+    /// Create a new [`ServerTimingLayer`].
+    pub fn new() -> Self {
+        ServerTimingLayer { name: "app" }
+    }
+
+    /// Set the name of the entry for the total duration of the inner service.
+    ///
+    /// Defaults to `app`.
+    pub fn name(mut self, name: &'static str) -> Self {
+        self.name = name;
+        self
+    }
+}
+
+impl Default for ServerTimingLayer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<S> Layer<S> for ServerTimingLayer {
+    type Service = ServerTiming<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        ServerTiming {
+            inner,
+            name: self.name,
+        }
+    }
+}
+
+/// Middleware that adds `Server-Timing` headers to responses.
+///
+/// See the [module docs](self) for more details.
+#[derive(Debug, Clone, Copy)]
+pub struct ServerTiming<S> {
+    inner: S,
+    name: &'static str,
+}
+
+impl<S> ServerTiming<S> {
+    /// Create a new [`ServerTiming`].
+    pub fn new(inner: S) -> Self {
+        ServerTimingLayer::new().layer(inner)
+    }
+
+    /// Set the name of the entry for the total duration of the inner service.
+    ///
+    /// Defaults to `app`.
+    pub fn name(mut self, name: &'static str) -> Self {
+        self.name = name;
+        self
+    }
+
+    define_inner_service_accessors!();
+
+    /// Returns a new [`Layer`] that wraps services with a `ServerTiming` middleware.
+    ///
+    /// [`Layer`]: tower_layer::Layer
+    pub fn layer() -> ServerTimingLayer {
+        ServerTimingLayer::new()
+    }
+}
+
+impl<S, ReqBody, ResBody> Service<Request<ReqBody>> for ServerTiming<S>
+where
+    S: Service<Request<ReqBody>, Response = Response<ResBody>>,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = ResponseFuture<S::Future>;
+
+    #[inline]
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, mut req: Request<ReqBody>) -> Self::Future {
+        let timings = ServerTimings::default();
+        req.extensions_mut().insert(timings.clone());
+
+        ResponseFuture {
+            inner: self.inner.call(req),
+            name: self.name,
+            timings,
+            start: Instant::now(),
+        }
+    }
+}
+
+pin_project! {
+    /// Response future for [`ServerTiming`].
+    pub struct ResponseFuture<F> {
+        #[pin]
+        inner: F,
+        name: &'static str,
+        timings: ServerTimings,
+        start: Instant,
+    }
+}
+
+impl<F, B, E> Future for ResponseFuture<F>
+where
+    F: Future<Output = Result<Response<B>, E>>,
+{
+    type Output = F::Output;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = self.project();
+        let mut response = ready!(this.inner.poll(cx)?);
+        let total = this.start.elapsed();
+
+        let headers = response.headers_mut();
+        append_timing(headers, this.name, total);
+        let timings = mem::take(&mut *this.timings.0.lock().unwrap());
+        for (name, duration) in timings {
+            append_timing(headers, &name, duration);
+        }
+
+        Poll::Ready(Ok(response))
+    }
+}
+
+fn append_timing(headers: &mut http::HeaderMap, name: &str, duration: Duration) {
+    let value = format!("{};dur={:.1}", name, duration.as_secs_f64() * 1000.0);
+    // names that aren't valid in a header value are skipped
+    if let Ok(value) = HeaderValue::from_str(&value) {
+        headers.append(SERVER_TIMING, value);
+    }
+}
+
+/// Request [`Extensions`][http::Extensions] value for contributing timings to the
+/// `Server-Timing` header.
+///
+/// Inserted into requests by [`ServerTiming`]. Cloning is cheap and clones share the same
+/// timings, so they can be moved into other tasks.
+///
+/// See the [module docs](self) for more details.
+#[derive(Debug, Clone, Default)]
+pub struct ServerTimings(Arc<Mutex<Vec<(Cow<'static, str>, Duration)>>>);
+
+impl ServerTimings {
+    /// Record a timing named `name`.
+    ///
+    /// The name should be a valid [token], such as `db` or `cache-lookup`. Timings are appended
+    /// to the response in the order they were recorded.
+    ///
+    /// [token]: https://www.rfc-editor.org/rfc/rfc9110#name-tokens
+    pub fn record(&self, name: impl Into<Cow<'static, str>>, duration: Duration) {
+        self.0.lock().unwrap().push((name.into(), duration));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_helpers::Body;
+    use std::convert::Infallible;
+    use tower::{ServiceBuilder, ServiceExt};
+
+    fn server_timings<B>(res: &Response<B>) -> Vec<String> {
+        res.headers()
+            .get_all(SERVER_TIMING)
+            .iter()
+            .map(|value| value.to_str().unwrap().to_owned())
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn records_total_duration() {
+        let svc = ServiceBuilder::new()
+            .layer(ServerTimingLayer::new())
+            .service_fn(|_: Request<Body>| async {
+                tokio::time::sleep(Duration::from_millis(20)).await;
+                Ok::<_, Infallible>(Response::new(Body::empty()))
+            });
+
+        let res = svc.oneshot(Request::new(Body::empty())).await.unwrap();
+
+        let timings = server_timings(&res);
+        assert_eq!(timings.len(), 1);
+        let dur: f64 = timings[0]
+            .strip_prefix("app;dur=")
+            .unwrap()
+            .parse()
+            .unwrap();
+        assert!(dur >= 20.0, "dur {}", dur);
+    }
+
+    #[tokio::test]
+    async fn appends_contributed_timings() {
+        let svc = ServiceBuilder::new()
+            .layer(ServerTimingLayer::new().name("total"))
+            .service_fn(|req: Request<Body>| async move {
+                let timings = req.extensions().get::<ServerTimings>().unwrap();
+                timings.record("db", Duration::from_micros(4300));
+                timings.record(String::from("cache"), Duration::from_millis(1));
+                timings.record("invalid\nname", Duration::from_millis(1));
+                Ok::<_, Infallible>(Response::new(Body::empty()))
+            });
+
+        let res = svc.oneshot(Request::new(Body::empty())).await.unwrap();
+
+        let timings = server_timings(&res);
+        assert_eq!(timings.len(), 3);
+        assert!(timings[0].starts_with("total;dur="));
+        assert_eq!(timings[1], "db;dur=4.3");
+        assert_eq!(timings[2], "cache;dur=1.0");
+    }
+}
@@ -26,6 +26,9 @@ pub use self::{
     serve_file::ServeFile,
 };
 
+#[cfg(feature = "fs-strong-etags")]
+pub use self::serve_dir::ETagCache;
+
 pin_project! {
     // NOTE: This could potentially be upstreamed to `http-body`.
     /// Adapter that turns an [`impl AsyncRead`][tokio::io::AsyncRead] to an [`impl Body`][http_body::Body].
@@ -0,0 +1,182 @@
+use http::HeaderValue;
+#[cfg(feature = "fs-strong-etags")]
+use std::{
+    collections::{HashMap, VecDeque},
+    fmt::Write,
+    path::PathBuf,
+    sync::{Arc, Mutex},
+    time::SystemTime,
+};
+use std::{fs::Metadata, io, path::Path, time::UNIX_EPOCH};
+#[cfg(feature = "fs-strong-etags")]
+use tokio::{fs::File, io::AsyncReadExt};
+
+// read buffer used when hashing files, 64KiB
+#[cfg(feature = "fs-strong-etags")]
+const HASH_BUF_SIZE: usize = 65536;
+
+/// How `ServeDir` computes `ETag`s.
+#[derive(Clone, Debug)]
+pub(super) enum ETags {
+    #[cfg(feature = "fs-strong-etags")]
+    Strong(ETagCache),
+    Weak,
+}
+
+impl ETags {
+    #[cfg_attr(not(feature = "fs-strong-etags"), allow(unused_variables))]
+    pub(super) async fn etag(
+        &self,
+        path: &Path,
+        meta: &Metadata,
+    ) -> io::Result<Option<HeaderValue>> {
+        match self {
+            #[cfg(feature = "fs-strong-etags")]
+            ETags::Strong(cache) => cache.etag(path, meta).await.map(Some),
+            ETags::Weak => Ok(weak_etag(meta)),
+        }
+    }
+}
+
+// `W/"<mtime>-<size>"`, with the modification time in nanoseconds since the epoch, both in hex
+fn weak_etag(meta: &Metadata) -> Option<HeaderValue> {
+    let modified = meta.modified().ok()?.duration_since(UNIX_EPOCH).ok()?;
+    let etag = format!("W/\"{:x}-{:x}\"", modified.as_nanos(), meta.len());
+    Some(HeaderValue::from_str(&etag).unwrap())
+}
+
+/// Bounded cache of strong `ETag`s computed from the SHA-256 hash of file contents.
+///
+/// Used with [`ServeDir::strong_etags_cached`]. Entries are keyed by path and are only reused
+/// while the modification time and size of the file are unchanged. When the cache is full, the
+/// oldest entry is evicted.
+///
+/// Cloning the cache is cheap and clones share the same entries.
+///
+/// [`ServeDir::strong_etags_cached`]: super::ServeDir::strong_etags_cached
+#[cfg(feature = "fs-strong-etags")]
+#[derive(Clone, Debug)]
+pub struct ETagCache {
+    inner: Arc<Mutex<Inner>>,
+}
+
+#[cfg(feature = "fs-strong-etags")]
+#[derive(Debug)]
+struct Inner {
+    capacity: usize,
+    entries: HashMap<PathBuf, Entry>,
+    // insertion order of the paths in `entries`, oldest first
+    order: VecDeque<PathBuf>,
+    #[cfg(test)]
+    hashed: usize,
+}
+
+#[cfg(feature = "fs-strong-etags")]
+#[derive(Debug)]
+struct Entry {
+    modified: SystemTime,
+    len: u64,
+    etag: HeaderValue,
+}
+
+#[cfg(feature = "fs-strong-etags")]
+impl ETagCache {
+    /// Create a new [`ETagCache`] holding at most `capacity` entries.
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            inner: Arc::new(Mutex::new(Inner {
+                capacity,
+                entries: HashMap::new(),
+                order: VecDeque::new(),
+                #[cfg(test)]
+                hashed: 0,
+            })),
+        }
+    }
+
+    /// Get the strong `ETag` for the file at `path`, hashing its contents if there is no
+    /// up to date entry in the cache.
+    pub(super) async fn etag(&self, path: &Path, meta: &Metadata) -> io::Result<HeaderValue> {
+        let modified = meta.modified().ok();
+
+        if let Some(modified) = modified {
+            if let Some(etag) = self.get(path, modified, meta.len()) {
+                return Ok(etag);
+            }
+        }
+
+        let etag = hash_file(path).await?;
+        #[cfg(test)]
+        {
+            self.inner.lock().unwrap().hashed += 1;
+        }
+
+        // without a modification time there is no way to tell whether the entry is stale
+        if let Some(modified) = modified {
+            self.insert(path, modified, meta.len(), etag.clone());
+        }
+
+        Ok(etag)
+    }
+
+    fn get(&self, path: &Path, modified: SystemTime, len: u64) -> Option<HeaderValue> {
+        let inner = self.inner.lock().unwrap();
+        inner
+            .entries
+            .get(path)
+            .filter(|entry| entry.modified == modified && entry.len == len)
+            .map(|entry| entry.etag.clone())
+    }
+
+    fn insert(&self, path: &Path, modified: SystemTime, len: u64, etag: HeaderValue) {
+        let mut inner = self.inner.lock().unwrap();
+        if inner.capacity == 0 {
+            return;
+        }
+
+        let entry = Entry {
+            modified,
+            len,
+            etag,
+        };
+        if let Some(existing) = inner.entries.get_mut(path) {
+            *existing = entry;
+            return;
+        }
+
+        if inner.entries.len() >= inner.capacity {
+            if let Some(oldest) = inner.order.pop_front() {
+                inner.entries.remove(&oldest);
+            }
+        }
+        inner.entries.insert(path.to_owned(), entry);
+        inner.order.push_back(path.to_owned());
+    }
+
+    #[cfg(test)]
+    pub(super) fn hashed(&self) -> usize {
+        self.inner.lock().unwrap().hashed
+    }
+}
+
+#[cfg(feature = "fs-strong-etags")]
+async fn hash_file(path: &Path) -> io::Result<HeaderValue> {
+    let mut file = File::open(path).await?;
+    let mut hasher = openssl::sha::Sha256::new();
+    let mut buf = vec![0; HASH_BUF_SIZE];
+    loop {
+        let n = file.read(&mut buf).await?;
+        if n == 0 {
+            break;
+        }
+        hasher.update(&buf[..n]);
+    }
+
+    let mut etag = String::with_capacity(66);
+    etag.push('"');
+    for byte in hasher.finish() {
+        write!(etag, "{:02x}", byte).unwrap();
+    }
+    etag.push('"');
+    Ok(HeaderValue::from_str(&etag).unwrap())
+}
@@ -34,11 +34,17 @@ impl<ReqBody, F> ResponseFuture<ReqBody, F> {
     pub(super) fn open_file_future(
         future: BoxFuture<'static, io::Result<OpenFileOutput>>,
         fallback_and_request: Option<(F, Request<ReqBody>)>,
+        vary_accept_encoding: bool,
+        vary_accept: bool,
+        cache_control: Option<HeaderValue>,
     ) -> Self {
         Self {
             inner: ResponseFutureInner::OpenFileFuture {
                 future,
                 fallback_and_request,
+                vary_accept_encoding,
+                vary_accept,
+                cache_control,
             },
         }
     }
@@ -51,9 +57,9 @@ impl<ReqBody, F> ResponseFuture<ReqBody, F> {
         }
     }
 
-    pub(super) fn method_not_allowed() -> Self {
+    pub(super) fn method_not_allowed(allow_options: bool) -> Self {
         Self {
-            inner: ResponseFutureInner::MethodNotAllowed,
+            inner: ResponseFutureInner::MethodNotAllowed { allow_options },
         }
     }
 }
@@ -65,6 +71,9 @@ pin_project! {
             #[pin]
             future: BoxFuture<'static, io::Result<OpenFileOutput>>,
             fallback_and_request: Option<(F, Request<ReqBody>)>,
+            vary_accept_encoding: bool,
+            vary_accept: bool,
+            cache_control: Option<HeaderValue>,
         },
         FallbackFuture {
             future: BoxFuture<'static, Result<Response<ResponseBody>, Infallible>>,
@@ -72,7 +81,9 @@ pin_project! {
         InvalidPath {
             fallback_and_request: Option<(F, Request<ReqBody>)>,
         },
-        MethodNotAllowed,
+        MethodNotAllowed {
+            allow_options: bool,
+        },
     }
 }
 
@@ -93,9 +104,23 @@ where
                 ResponseFutureInnerProj::OpenFileFuture {
                     future: open_file_future,
                     fallback_and_request,
+                    vary_accept_encoding,
+                    vary_accept,
+                    cache_control,
                 } => match ready!(open_file_future.poll(cx)) {
                     Ok(OpenFileOutput::FileOpened(file_output)) => {
-                        break Poll::Ready(Ok(build_response(*file_output)));
+                        let mut res = build_response(*file_output);
+                        if *vary_accept_encoding {
+                            append_vary(&mut res, header::ACCEPT_ENCODING);
+                        }
+                        if *vary_accept {
+                            append_vary(&mut res, header::ACCEPT);
+                        }
+                        if let Some(cache_control) = cache_control.take() {
+                            res.headers_mut()
+                                .insert(header::CACHE_CONTROL, cache_control);
+                        }
+                        break Poll::Ready(Ok(res));
                     }
 
                     Ok(OpenFileOutput::Redirect { location }) => {
@@ -112,14 +137,34 @@ where
                         }
                     }
 
+                    Ok(OpenFileOutput::Options) => {
+                        let mut res = response_with_status(StatusCode::OK);
+                        res.headers_mut().insert(ALLOW, allow(true));
+                        break Poll::Ready(Ok(res));
+                    }
+
                     Ok(OpenFileOutput::PreconditionFailed) => {
                         break Poll::Ready(Ok(response_with_status(
                             StatusCode::PRECONDITION_FAILED,
                         )));
                     }
 
-                    Ok(OpenFileOutput::NotModified) => {
-                        break Poll::Ready(Ok(response_with_status(StatusCode::NOT_MODIFIED)));
+                    Ok(OpenFileOutput::NotModified { etag }) => {
+                        let mut res = response_with_status(StatusCode::NOT_MODIFIED);
+                        if let Some(etag) = etag {
+                            res.headers_mut().insert(header::ETAG, etag);
+                        }
+                        if *vary_accept_encoding {
+                            append_vary(&mut res, header::ACCEPT_ENCODING);
+                        }
+                        if *vary_accept {
+                            append_vary(&mut res, header::ACCEPT);
+                        }
+                        if let Some(cache_control) = cache_control.take() {
+                            res.headers_mut()
+                                .insert(header::CACHE_CONTROL, cache_control);
+                        }
+                        break Poll::Ready(Ok(res));
                     }
 
                     Err(err) => {
@@ -162,10 +207,9 @@ where
                     }
                 }
 
-                ResponseFutureInnerProj::MethodNotAllowed => {
+                ResponseFutureInnerProj::MethodNotAllowed { allow_options } => {
                     let mut res = response_with_status(StatusCode::METHOD_NOT_ALLOWED);
-                    res.headers_mut()
-                        .insert(ALLOW, HeaderValue::from_static("GET,HEAD"));
+                    res.headers_mut().insert(ALLOW, allow(*allow_options));
                     break Poll::Ready(Ok(res));
                 }
             };
@@ -182,6 +226,14 @@ fn response_with_status(status: StatusCode) -> Response<ResponseBody> {
         .unwrap()
 }
 
+fn allow(options: bool) -> HeaderValue {
+    if options {
+        HeaderValue::from_static("GET,HEAD,OPTIONS")
+    } else {
+        HeaderValue::from_static("GET,HEAD")
+    }
+}
+
 fn not_found() -> Response<ResponseBody> {
     response_with_status(StatusCode::NOT_FOUND)
 }
@@ -216,6 +268,14 @@ where
     ResponseFutureInner::FallbackFuture { future }
 }
 
+// The response depends on the `Accept-Encoding` header when precompressed variants are enabled,
+// and on the `Accept` header when image formats are negotiated, even if the requested file was
+// served.
+fn append_vary<B>(res: &mut Response<B>, name: header::HeaderName) {
+    res.headers_mut()
+        .append(header::VARY, HeaderValue::from(name));
+}
+
 fn build_response(output: FileOpened) -> Response<ResponseBody> {
     let (maybe_file, size) = match output.extent {
         FileRequestExtent::Full(file, meta) => (Some(file), meta.len()),
@@ -233,10 +293,20 @@ fn build_response(output: FileOpened) -> Response<ResponseBody> {
         builder = builder.header(header::CONTENT_ENCODING, encoding.into_header_value());
     }
 
+    if output.force_download {
+        builder = builder
+            .header(header::CONTENT_DISPOSITION, "attachment")
+            .header(header::X_CONTENT_TYPE_OPTIONS, "nosniff");
+    }
+
     if let Some(last_modified) = output.last_modified {
         builder = builder.header(header::LAST_MODIFIED, last_modified.0.to_string());
     }
 
+    if let Some(etag) = output.etag {
+        builder = builder.header(header::ETAG, etag);
+    }
+
     match output.maybe_range {
         Some(Ok(ranges)) => {
             if let Some(range) = ranges.first() {
@@ -43,3 +43,36 @@ impl IfUnmodifiedSince {
             .map(|time| IfUnmodifiedSince(time.into()))
     }
 }
+
+pub(super) struct IfNoneMatch(HeaderValue);
+
+impl IfNoneMatch {
+    /// Check if any of the entity tags matches the given one, using the weak comparison.
+    pub(super) fn matches(&self, etag: &HeaderValue) -> bool {
+        let etag = strip_weak(etag.as_bytes());
+        let tags = trim(self.0.as_bytes());
+        tags == b"*"
+            || tags
+                .split(|&b| b == b',')
+                .map(|tag| strip_weak(trim(tag)))
+                .any(|tag| tag == etag)
+    }
+
+    pub(super) fn from_header_value(value: &HeaderValue) -> IfNoneMatch {
+        IfNoneMatch(value.clone())
+    }
+}
+
+fn strip_weak(tag: &[u8]) -> &[u8] {
+    tag.strip_prefix(b"W/").unwrap_or(tag)
+}
+
+fn trim(mut bytes: &[u8]) -> &[u8] {
+    while let [b' ' | b'\t', rest @ ..] = bytes {
+        bytes = rest;
+    }
+    while let [rest @ .., b' ' | b'\t'] = bytes {
+        bytes = rest;
+    }
+    bytes
+}
@@ -0,0 +1,115 @@
+use crate::content_encoding::QValue;
+use http::{header, HeaderMap};
+use std::path::{Path, PathBuf};
+
+/// Alternate image formats that can be served in place of a requested image.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(super) enum ImageFormat {
+    Avif,
+    Webp,
+}
+
+impl ImageFormat {
+    fn parse(s: &str) -> Option<Self> {
+        if s.eq_ignore_ascii_case("image/avif") {
+            Some(Self::Avif)
+        } else if s.eq_ignore_ascii_case("image/webp") {
+            Some(Self::Webp)
+        } else {
+            None
+        }
+    }
+
+    fn extension(self) -> &'static str {
+        match self {
+            Self::Avif => "avif",
+            Self::Webp => "webp",
+        }
+    }
+}
+
+// Returns the path of the first of the accepted image formats that has a file next to the
+// requested one, or else the requested path. `photo.jpg` becomes `photo.avif` for example, whose
+// `Content-Type` is then guessed like for any other file.
+pub(super) async fn alternate_path(
+    path_to_file: PathBuf,
+    image_formats: &[ImageFormat],
+) -> PathBuf {
+    for format in image_formats {
+        let path = path_to_file.with_extension(format.extension());
+        if tokio::fs::metadata(&path)
+            .await
+            .map_or(false, |meta| meta.is_file())
+        {
+            return path;
+        }
+    }
+    path_to_file
+}
+
+// Whether alternate formats are looked up for the file at `path`.
+pub(super) fn is_negotiable(path: &Path) -> bool {
+    path.extension().map_or(false, |ext| {
+        ["jpg", "jpeg", "png"]
+            .iter()
+            .any(|candidate| ext.eq_ignore_ascii_case(candidate))
+    })
+}
+
+// Returns the image formats accepted by the `Accept` header, most preferred first. Wildcards
+// aren't taken into account, so only formats that are explicitly listed are returned.
+pub(super) fn accepted_formats(headers: &HeaderMap) -> Vec<ImageFormat> {
+    let mut formats: Vec<(ImageFormat, QValue)> = headers
+        .get_all(header::ACCEPT)
+        .iter()
+        .filter_map(|hval| hval.to_str().ok())
+        .flat_map(|s| s.split(','))
+        .filter_map(|v| {
+            let mut v = v.splitn(2, ';');
+            let format = ImageFormat::parse(v.next().unwrap().trim())?;
+            let qval = if let Some(qval) = v.next() {
+                QValue::parse(qval.trim())?
+            } else {
+                QValue::one()
+            };
+            Some((format, qval))
+        })
+        .filter(|(_, qval)| !qval.is_zero())
+        .collect();
+    // stable, so formats with equal q-values keep their order
+    formats.sort_by(|(_, a), (_, b)| b.cmp(a));
+
+    let mut accepted = Vec::new();
+    for (format, _) in formats {
+        if !accepted.contains(&format) {
+            accepted.push(format);
+        }
+    }
+    accepted
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use http::HeaderValue;
+
+    fn accepted(accept: &'static str) -> Vec<ImageFormat> {
+        let mut headers = HeaderMap::new();
+        headers.insert(header::ACCEPT, HeaderValue::from_static(accept));
+        accepted_formats(&headers)
+    }
+
+    #[test]
+    fn accepted_formats_respect_qvalues() {
+        assert_eq!(
+            accepted("image/avif,image/webp,*/*"),
+            [ImageFormat::Avif, ImageFormat::Webp]
+        );
+        assert_eq!(
+            accepted("image/avif;q=0.5, image/webp"),
+            [ImageFormat::Webp, ImageFormat::Avif]
+        );
+        assert_eq!(accepted("image/avif;q=0, image/webp"), [ImageFormat::Webp]);
+        assert_eq!(accepted("image/*, */*;q=0.8"), []);
+    }
+}
@@ -1,4 +1,4 @@
-use self::future::ResponseFuture;
+use self::{etag::ETags, future::ResponseFuture};
 use crate::{
     body::UnsyncBoxBody,
     content_encoding::{encodings, SupportedEncodings},
@@ -6,21 +6,29 @@ use crate::{
 };
 use bytes::Bytes;
 use futures_util::FutureExt;
-use http::{header, HeaderValue, Method, Request, Response, StatusCode};
+use http::{HeaderValue, Method, Request, Response, StatusCode};
 use http_body_util::{BodyExt, Empty};
+use mime::Mime;
 use percent_encoding::percent_decode;
 use std::{
     convert::Infallible,
-    io,
+    fmt, io,
     path::{Component, Path, PathBuf},
+    sync::Arc,
     task::{Context, Poll},
+    time::Duration,
 };
 use tower_service::Service;
 
+mod etag;
 pub(crate) mod future;
 mod headers;
+mod image_format;
 mod open_file;
 
+#[cfg(feature = "fs-strong-etags")]
+pub use self::etag::ETagCache;
+
 #[cfg(test)]
 mod tests;
 
@@ -59,6 +67,13 @@ pub struct ServeDir<F = DefaultServeDirFallback> {
     variant: ServeVariant,
     fallback: Option<F>,
     call_fallback_on_method_not_allowed: bool,
+    respond_to_options: bool,
+    force_download: ForceDownload,
+    serve_source_maps: bool,
+    etags: Option<ETags>,
+    negotiate_image_format: bool,
+    immutable_when: Option<ImmutableWhen>,
+    max_age: Option<Duration>,
 }
 
 impl ServeDir<DefaultServeDirFallback> {
@@ -79,6 +94,13 @@ impl ServeDir<DefaultServeDirFallback> {
             },
             fallback: None,
             call_fallback_on_method_not_allowed: false,
+            respond_to_options: false,
+            force_download: ForceDownload::default(),
+            serve_source_maps: true,
+            etags: None,
+            negotiate_image_format: false,
+            immutable_when: None,
+            max_age: None,
         }
     }
 
@@ -93,6 +115,13 @@ impl ServeDir<DefaultServeDirFallback> {
             variant: ServeVariant::SingleFile { mime },
             fallback: None,
             call_fallback_on_method_not_allowed: false,
+            respond_to_options: false,
+            force_download: ForceDownload::default(),
+            serve_source_maps: true,
+            etags: None,
+            negotiate_image_format: false,
+            immutable_when: None,
+            max_age: None,
         }
     }
 }
@@ -133,6 +162,7 @@ impl<F> ServeDir<F> {
     /// the uncompressed version will be served instead.
     /// Both the precompressed version and the uncompressed version are expected
     /// to be present in the directory. Different precompressed variants can be combined.
+    /// File responses include a `Vary: Accept-Encoding` header, whichever version is served.
     pub fn precompressed_gzip(mut self) -> Self {
         self.precompressed_variants
             .get_or_insert(Default::default())
@@ -150,6 +180,7 @@ impl<F> ServeDir<F> {
     /// the uncompressed version will be served instead.
     /// Both the precompressed version and the uncompressed version are expected
     /// to be present in the directory. Different precompressed variants can be combined.
+    /// File responses include a `Vary: Accept-Encoding` header, whichever version is served.
     pub fn precompressed_br(mut self) -> Self {
         self.precompressed_variants
             .get_or_insert(Default::default())
@@ -167,6 +198,7 @@ impl<F> ServeDir<F> {
     /// the uncompressed version will be served instead.
     /// Both the precompressed version and the uncompressed version are expected
     /// to be present in the directory. Different precompressed variants can be combined.
+    /// File responses include a `Vary: Accept-Encoding` header, whichever version is served.
     pub fn precompressed_deflate(mut self) -> Self {
         self.precompressed_variants
             .get_or_insert(Default::default())
@@ -184,6 +216,7 @@ impl<F> ServeDir<F> {
     /// the uncompressed version will be served instead.
     /// Both the precompressed version and the uncompressed version are expected
     /// to be present in the directory. Different precompressed variants can be combined.
+    /// File responses include a `Vary: Accept-Encoding` header, whichever version is served.
     pub fn precompressed_zstd(mut self) -> Self {
         self.precompressed_variants
             .get_or_insert(Default::default())
@@ -217,6 +250,13 @@ impl<F> ServeDir<F> {
             variant: self.variant,
             fallback: Some(new_fallback),
             call_fallback_on_method_not_allowed: self.call_fallback_on_method_not_allowed,
+            respond_to_options: self.respond_to_options,
+            force_download: self.force_download,
+            serve_source_maps: self.serve_source_maps,
+            etags: self.etags,
+            negotiate_image_format: self.negotiate_image_format,
+            immutable_when: self.immutable_when,
+            max_age: self.max_age,
         }
     }
 
@@ -249,6 +289,191 @@ impl<F> ServeDir<F> {
         self
     }
 
+    /// Customize whether or not to respond to `OPTIONS` requests.
+    ///
+    /// If enabled, `OPTIONS` requests for existing files get a `200 OK` response with an
+    /// `Allow: GET,HEAD,OPTIONS` header, and requests for missing files are handled like `GET`
+    /// requests for missing files, by responding with `404 Not Found` or calling the fallback.
+    ///
+    /// Defaults to `false`, which handles `OPTIONS` like other methods that aren't `GET` or
+    /// `HEAD`. See [`call_fallback_on_method_not_allowed`](Self::call_fallback_on_method_not_allowed).
+    pub fn respond_to_options(mut self, respond_to_options: bool) -> Self {
+        self.respond_to_options = respond_to_options;
+        self
+    }
+
+    /// Force files with any of the given content types to be downloaded instead of displayed
+    /// inline.
+    ///
+    /// Responses for matching files will have the `Content-Disposition: attachment` and
+    /// `X-Content-Type-Options: nosniff` headers set. This is useful when serving user uploads,
+    /// to prevent content types such as `text/html` or `image/svg+xml` from being rendered by
+    /// browsers, which could lead to stored cross-site scripting.
+    ///
+    /// Content types are matched on their type and subtype, ignoring any parameters.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use tower_http::services::ServeDir;
+    ///
+    /// let service = ServeDir::new("uploads").force_download_for(&[
+    ///     mime::TEXT_HTML,
+    ///     mime::IMAGE_SVG,
+    ///     "application/xhtml+xml".parse().unwrap(),
+    /// ]);
+    /// ```
+    pub fn force_download_for(mut self, mimes: &[Mime]) -> Self {
+        self.force_download.mimes = mimes.to_vec();
+        self
+    }
+
+    /// Customize whether files matched by [`ServeDir::force_download_for`] should also be served
+    /// with `Content-Type: application/octet-stream` instead of their guessed content type.
+    ///
+    /// Defaults to `false`.
+    pub fn force_download_as_octet_stream(mut self, octet_stream: bool) -> Self {
+        self.force_download.octet_stream = octet_stream;
+        self
+    }
+
+    /// Customize whether or not to serve source maps, that is files with the `.map` extension.
+    ///
+    /// When disabled, requests for source maps are handled as if the file didn't exist, even if
+    /// it does. This is useful in production, to avoid leaking the original sources of bundled
+    /// JavaScript or CSS.
+    ///
+    /// Defaults to `true`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use tower_http::services::ServeDir;
+    ///
+    /// let service = ServeDir::new("dist").serve_source_maps(false);
+    /// ```
+    pub fn serve_source_maps(mut self, serve_source_maps: bool) -> Self {
+        self.serve_source_maps = serve_source_maps;
+        self
+    }
+
+    /// Set a strong `ETag` header on responses, computed from the SHA-256 hash of the contents of
+    /// the file.
+    ///
+    /// Since hashing requires reading the whole file, the `ETag`s are stored in the given
+    /// [`ETagCache`] and only recomputed when the modification time or the size of the file
+    /// changes. Requests with an `If-None-Match` header matching the `ETag` get a
+    /// `304 Not Modified` response.
+    ///
+    /// Precompressed variants get their own `ETag`, computed from the compressed file.
+    ///
+    /// Requires the `fs-strong-etags` feature, which hashes with OpenSSL.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use tower_http::services::{fs::ETagCache, ServeDir};
+    ///
+    /// let service = ServeDir::new("assets").strong_etags_cached(ETagCache::new(1024));
+    /// ```
+    #[cfg(feature = "fs-strong-etags")]
+    pub fn strong_etags_cached(mut self, cache: ETagCache) -> Self {
+        self.etags = Some(ETags::Strong(cache));
+        self
+    }
+
+    /// Set a weak `ETag` header on responses, derived from the modification time and the size of
+    /// the file.
+    ///
+    /// Unlike [`strong_etags_cached`](Self::strong_etags_cached), this doesn't read the contents
+    /// of the file, but the `ETag` also changes when the file is modified without changing its
+    /// contents. Requests with an `If-None-Match` header matching the `ETag` get a
+    /// `304 Not Modified` response.
+    ///
+    /// No `ETag` is set if the platform doesn't provide modification times.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use tower_http::services::ServeDir;
+    ///
+    /// let service = ServeDir::new("assets").weak_etags();
+    /// ```
+    pub fn weak_etags(mut self) -> Self {
+        self.etags = Some(ETags::Weak);
+        self
+    }
+
+    /// Serve AVIF or WebP versions of JPEG and PNG images to clients that accept them.
+    ///
+    /// Assuming the `dir` directory is being served and `dir/photo.jpg` is requested, a client
+    /// with an `Accept: image/avif,image/webp,*/*` header will receive `dir/photo.avif` if it
+    /// exists, otherwise `dir/photo.webp` if it exists, and otherwise `dir/photo.jpg`. Formats are
+    /// tried in order of their q-value in the `Accept` header, and wildcards don't match them.
+    ///
+    /// Responses for JPEG and PNG images include a `Vary: Accept` header, whichever version is
+    /// served. Defaults to `false`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use tower_http::services::ServeDir;
+    ///
+    /// let service = ServeDir::new("images").negotiate_image_format(true);
+    /// ```
+    pub fn negotiate_image_format(mut self, negotiate_image_format: bool) -> Self {
+        self.negotiate_image_format = negotiate_image_format;
+        self
+    }
+
+    /// Set a `Cache-Control` header on responses, marking files for which `predicate` returns
+    /// `true` as immutable.
+    ///
+    /// Asset pipelines often put a hash of the contents in file names, such as `app.3f9a2b.js`,
+    /// so that such a file never changes. Responses for files matching `predicate` get a
+    /// `Cache-Control: public, max-age=31536000, immutable` header, and responses for other files
+    /// get a `Cache-Control: no-cache` header, so that they are revalidated on every use, unless
+    /// [`max_age`](Self::max_age) is set.
+    ///
+    /// `predicate` is called with the path of the requested file.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use std::path::Path;
+    /// use tower_http::services::ServeDir;
+    ///
+    /// // such as `app.3f9a2b.js`
+    /// let service = ServeDir::new("dist").immutable_when(|path: &Path| {
+    ///     path.file_name()
+    ///         .and_then(|name| name.to_str())
+    ///         .map_or(false, |name| {
+    ///             name.split('.').skip(1).any(|part| {
+    ///                 part.len() >= 6 && part.bytes().all(|b| b.is_ascii_hexdigit())
+    ///             })
+    ///         })
+    /// });
+    /// ```
+    pub fn immutable_when<P>(mut self, predicate: P) -> Self
+    where
+        P: Fn(&Path) -> bool + Send + Sync + 'static,
+    {
+        self.immutable_when = Some(ImmutableWhen(Arc::new(predicate)));
+        self
+    }
+
+    /// Set a `Cache-Control: public, max-age=<max_age>` header on responses for files that aren't
+    /// marked as immutable by [`immutable_when`](Self::immutable_when).
+    ///
+    /// `max_age` is rounded down to whole seconds.
+    ///
+    /// Defaults to no `Cache-Control` header, or `Cache-Control: no-cache` if `immutable_when` is
+    /// set.
+    pub fn max_age(mut self, max_age: Duration) -> Self {
+        self.max_age = Some(max_age);
+        self
+    }
+
     /// Call the service and get a future that contains any `std::io::Error` that might have
     /// happened.
     ///
@@ -313,7 +538,10 @@ impl<F> ServeDir<F> {
         FResBody: http_body::Body<Data = Bytes> + Send + 'static,
         FResBody::Error: Into<Box<dyn std::error::Error + Send + Sync>>,
     {
-        if req.method() != Method::GET && req.method() != Method::HEAD {
+        let allowed = req.method() == Method::GET
+            || req.method() == Method::HEAD
+            || (self.respond_to_options && req.method() == Method::OPTIONS);
+        if !allowed {
             if self.call_fallback_on_method_not_allowed {
                 if let Some(fallback) = &mut self.fallback {
                     return ResponseFuture {
@@ -321,7 +549,7 @@ impl<F> ServeDir<F> {
                     };
                 }
             } else {
-                return ResponseFuture::method_not_allowed();
+                return ResponseFuture::method_not_allowed(self.respond_to_options);
             }
         }
 
@@ -358,31 +586,54 @@ impl<F> ServeDir<F> {
             }
         };
 
-        let buf_chunk_size = self.buf_chunk_size;
-        let range_header = req
-            .headers()
-            .get(header::RANGE)
-            .and_then(|value| value.to_str().ok())
-            .map(|s| s.to_owned());
+        if !self.serve_source_maps && is_source_map(&path_to_file) {
+            return ResponseFuture::invalid_path(fallback_and_request);
+        }
 
+        let buf_chunk_size = self.buf_chunk_size;
         let negotiated_encodings: Vec<_> = encodings(
             req.headers(),
             self.precompressed_variants.unwrap_or_default(),
         )
         .collect();
 
-        let variant = self.variant.clone();
+        let negotiate_image_format = self.negotiate_image_format
+            && matches!(self.variant, ServeVariant::Directory { .. })
+            && image_format::is_negotiable(&path_to_file);
+        let image_formats = if negotiate_image_format {
+            image_format::accepted_formats(req.headers())
+        } else {
+            Vec::new()
+        };
 
-        let open_file_future = Box::pin(open_file::open_file(
-            variant,
-            path_to_file,
-            req,
-            negotiated_encodings,
-            range_header,
-            buf_chunk_size,
-        ));
+        let cache_control =
+            cache_control(self.immutable_when.as_ref(), self.max_age, &path_to_file);
 
-        ResponseFuture::open_file_future(open_file_future, fallback_and_request)
+        let variant = self.variant.clone();
+        let force_download = self.force_download.clone();
+        let etags = self.etags.clone();
+
+        let open_file_future = Box::pin(async move {
+            let path_to_file = image_format::alternate_path(path_to_file, &image_formats).await;
+            open_file::open_file(
+                variant,
+                path_to_file,
+                req,
+                negotiated_encodings,
+                buf_chunk_size,
+                force_download,
+                etags,
+            )
+            .await
+        });
+
+        ResponseFuture::open_file_future(
+            open_file_future,
+            fallback_and_request,
+            self.precompressed_variants.is_some(),
+            negotiate_image_format,
+            cache_control,
+        )
     }
 }
 
@@ -487,6 +738,60 @@ impl ServeVariant {
     }
 }
 
+#[derive(Clone, Debug, Default)]
+struct ForceDownload {
+    mimes: Vec<Mime>,
+    octet_stream: bool,
+}
+
+impl ForceDownload {
+    fn matches(&self, mime: &HeaderValue) -> bool {
+        if self.mimes.is_empty() {
+            return false;
+        }
+
+        mime.to_str()
+            .ok()
+            .and_then(|mime| mime.parse::<Mime>().ok())
+            .map_or(false, |mime| {
+                self.mimes
+                    .iter()
+                    .any(|forced| forced.essence_str() == mime.essence_str())
+            })
+    }
+}
+
+#[derive(Clone)]
+struct ImmutableWhen(Arc<dyn Fn(&Path) -> bool + Send + Sync>);
+
+impl fmt::Debug for ImmutableWhen {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("ImmutableWhen").finish()
+    }
+}
+
+fn cache_control(
+    immutable_when: Option<&ImmutableWhen>,
+    max_age: Option<Duration>,
+    path: &Path,
+) -> Option<HeaderValue> {
+    match (immutable_when, max_age) {
+        (Some(ImmutableWhen(predicate)), _) if predicate(path) => Some(HeaderValue::from_static(
+            "public, max-age=31536000, immutable",
+        )),
+        (_, Some(max_age)) => {
+            HeaderValue::from_str(&format!("public, max-age={}", max_age.as_secs())).ok()
+        }
+        (Some(_), None) => Some(HeaderValue::from_static("no-cache")),
+        (None, None) => None,
+    }
+}
+
+fn is_source_map(path: &Path) -> bool {
+    path.extension()
+        .map_or(false, |ext| ext.eq_ignore_ascii_case("map"))
+}
+
 opaque_body! {
     /// Response body for [`ServeDir`] and [`ServeFile`][super::ServeFile].
     #[derive(Default)]
@@ -1,6 +1,7 @@
 use super::{
-    headers::{IfModifiedSince, IfUnmodifiedSince, LastModified},
-    ServeVariant,
+    etag::ETags,
+    headers::{IfModifiedSince, IfNoneMatch, IfUnmodifiedSince, LastModified},
+    ForceDownload, ServeVariant,
 };
 use crate::content_encoding::{Encoding, QValue};
 use bytes::Bytes;
@@ -20,8 +21,9 @@ pub(super) enum OpenFileOutput {
     FileOpened(Box<FileOpened>),
     Redirect { location: HeaderValue },
     FileNotFound,
+    Options,
     PreconditionFailed,
-    NotModified,
+    NotModified { etag: Option<HeaderValue> },
 }
 
 pub(super) struct FileOpened {
@@ -31,6 +33,8 @@ pub(super) struct FileOpened {
     pub(super) maybe_encoding: Option<Encoding>,
     pub(super) maybe_range: Option<Result<Vec<RangeInclusive<u64>>, RangeUnsatisfiableError>>,
     pub(super) last_modified: Option<LastModified>,
+    pub(super) etag: Option<HeaderValue>,
+    pub(super) force_download: bool,
 }
 
 pub(super) enum FileRequestExtent {
@@ -43,8 +47,9 @@ pub(super) async fn open_file(
     mut path_to_file: PathBuf,
     req: Request<Empty<Bytes>>,
     negotiated_encodings: Vec<(Encoding, QValue)>,
-    range_header: Option<String>,
     buf_chunk_size: usize,
+    force_download: ForceDownload,
+    etags: Option<ETags>,
 ) -> io::Result<OpenFileOutput> {
     let if_unmodified_since = req
         .headers()
@@ -56,6 +61,17 @@ pub(super) async fn open_file(
         .get(header::IF_MODIFIED_SINCE)
         .and_then(IfModifiedSince::from_header_value);
 
+    let range_header = req
+        .headers()
+        .get(header::RANGE)
+        .and_then(|value| value.to_str().ok())
+        .map(|s| s.to_owned());
+
+    let if_none_match = req
+        .headers()
+        .get(header::IF_NONE_MATCH)
+        .map(IfNoneMatch::from_header_value);
+
     let mime = match variant {
         ServeVariant::Directory {
             append_index_html_on_directories,
@@ -84,14 +100,36 @@ pub(super) async fn open_file(
         ServeVariant::SingleFile { mime } => mime,
     };
 
+    let forced_download = force_download.matches(&mime);
+    let mime = if forced_download && force_download.octet_stream {
+        HeaderValue::from_str(mime::APPLICATION_OCTET_STREAM.as_ref()).unwrap()
+    } else {
+        mime
+    };
+
+    if req.method() == Method::OPTIONS {
+        let meta = tokio::fs::metadata(&path_to_file).await?;
+        return Ok(if meta.is_file() {
+            OpenFileOutput::Options
+        } else {
+            OpenFileOutput::FileNotFound
+        });
+    }
+
     if req.method() == Method::HEAD {
-        let (meta, maybe_encoding) =
+        let (meta, maybe_encoding, path_to_file) =
             file_metadata_with_fallback(path_to_file, negotiated_encodings).await?;
 
         let last_modified = meta.modified().ok().map(LastModified::from);
+        let etag = match &etags {
+            Some(etags) => etags.etag(&path_to_file, &meta).await?,
+            None => None,
+        };
         if let Some(output) = check_modified_headers(
             last_modified.as_ref(),
+            etag.as_ref(),
             if_unmodified_since,
+            if_none_match,
             if_modified_since,
         ) {
             return Ok(output);
@@ -106,15 +144,23 @@ pub(super) async fn open_file(
             maybe_encoding,
             maybe_range,
             last_modified,
+            etag,
+            force_download: forced_download,
         })))
     } else {
-        let (mut file, maybe_encoding) =
+        let (mut file, maybe_encoding, path_to_file) =
             open_file_with_fallback(path_to_file, negotiated_encodings).await?;
         let meta = file.metadata().await?;
         let last_modified = meta.modified().ok().map(LastModified::from);
+        let etag = match &etags {
+            Some(etags) => etags.etag(&path_to_file, &meta).await?,
+            None => None,
+        };
         if let Some(output) = check_modified_headers(
             last_modified.as_ref(),
+            etag.as_ref(),
             if_unmodified_since,
+            if_none_match,
             if_modified_since,
         ) {
             return Ok(output);
@@ -136,13 +182,17 @@ pub(super) async fn open_file(
             maybe_encoding,
             maybe_range,
             last_modified,
+            etag,
+            force_download: forced_download,
         })))
     }
 }
 
 fn check_modified_headers(
     modified: Option<&LastModified>,
+    etag: Option<&HeaderValue>,
     if_unmodified_since: Option<IfUnmodifiedSince>,
+    if_none_match: Option<IfNoneMatch>,
     if_modified_since: Option<IfModifiedSince>,
 ) -> Option<OpenFileOutput> {
     if let Some(since) = if_unmodified_since {
@@ -156,6 +206,17 @@ fn check_modified_headers(
         }
     }
 
+    // `If-None-Match` takes precedence over `If-Modified-Since` (RFC 9110 section 13.2.2), but
+    // can only be evaluated if an `ETag` was computed
+    if let (Some(etag), Some(if_none_match)) = (etag, if_none_match) {
+        if if_none_match.matches(etag) {
+            return Some(OpenFileOutput::NotModified {
+                etag: Some(etag.clone()),
+            });
+        }
+        return None;
+    }
+
     if let Some(since) = if_modified_since {
         let unmodified = modified
             .as_ref()
@@ -163,7 +224,9 @@ fn check_modified_headers(
             // no last_modified means its always modified
             .unwrap_or(false);
         if unmodified {
-            return Some(OpenFileOutput::NotModified);
+            return Some(OpenFileOutput::NotModified {
+                etag: etag.cloned(),
+            });
         }
     }
 
@@ -202,7 +265,7 @@ fn preferred_encoding(
 async fn open_file_with_fallback(
     mut path: PathBuf,
     mut negotiated_encoding: Vec<(Encoding, QValue)>,
-) -> io::Result<(File, Option<Encoding>)> {
+) -> io::Result<(File, Option<Encoding>, PathBuf)> {
     let (file, encoding) = loop {
         // Get the preferred encoding among the negotiated ones.
         let encoding = preferred_encoding(&mut path, &negotiated_encoding);
@@ -220,7 +283,7 @@ async fn open_file_with_fallback(
             (Err(err), _) => return Err(err),
         };
     };
-    Ok((file, encoding))
+    Ok((file, encoding, path))
 }
 
 // Attempts to get the file metadata with any of the possible negotiated_encodings in the
@@ -229,7 +292,7 @@ async fn open_file_with_fallback(
 async fn file_metadata_with_fallback(
     mut path: PathBuf,
     mut negotiated_encoding: Vec<(Encoding, QValue)>,
-) -> io::Result<(Metadata, Option<Encoding>)> {
+) -> io::Result<(Metadata, Option<Encoding>, PathBuf)> {
     let (file, encoding) = loop {
         // Get the preferred encoding among the negotiated ones.
         let encoding = preferred_encoding(&mut path, &negotiated_encoding);
@@ -247,7 +310,7 @@ async fn file_metadata_with_fallback(
             (Err(err), _) => return Err(err),
         };
     };
-    Ok((file, encoding))
+    Ok((file, encoding, path))
 }
 
 async fn maybe_redirect_or_append_path(
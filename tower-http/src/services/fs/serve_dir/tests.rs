@@ -1,15 +1,19 @@
+#[cfg(feature = "fs-strong-etags")]
+use super::ETagCache;
 use crate::services::{ServeDir, ServeFile};
 use crate::test_helpers::{to_bytes, Body};
 use brotli::BrotliDecompress;
 use bytes::Bytes;
 use flate2::bufread::{DeflateDecoder, GzDecoder};
 use http::header::ALLOW;
-use http::{header, Method, Response};
+use http::{header, HeaderValue, Method, Response};
 use http::{Request, StatusCode};
 use http_body::Body as HttpBody;
 use http_body_util::BodyExt;
 use std::convert::Infallible;
 use std::io::Read;
+use std::path::Path;
+use std::time::Duration;
 use tower::{service_fn, ServiceExt};
 
 #[tokio::test]
@@ -114,6 +118,7 @@ async fn precompressed_gzip() {
 
     assert_eq!(res.headers()["content-type"], "text/plain");
     assert_eq!(res.headers()["content-encoding"], "gzip");
+    assert_eq!(res.headers()["vary"], "accept-encoding");
 
     let body = res.into_body().collect().await.unwrap().to_bytes();
     let mut decoder = GzDecoder::new(&body[..]);
@@ -226,6 +231,7 @@ async fn missing_precompressed_variant_fallbacks_to_uncompressed() {
     assert_eq!(res.headers()["content-type"], "text/plain");
     // Uncompressed file is served because compressed version is missing
     assert!(res.headers().get("content-encoding").is_none());
+    assert_eq!(res.headers()["vary"], "accept-encoding");
 
     let body = res.into_body().collect().await.unwrap().to_bytes();
     let body = String::from_utf8(body.to_vec()).unwrap();
@@ -697,6 +703,64 @@ async fn method_not_allowed() {
     assert_eq!(res.headers()[ALLOW], "GET,HEAD");
 }
 
+#[tokio::test]
+async fn options_existing_file() {
+    let svc = ServeDir::new("..").respond_to_options(true);
+
+    let req = Request::builder()
+        .method(Method::OPTIONS)
+        .uri("/README.md")
+        .body(Body::empty())
+        .unwrap();
+    let res = svc.clone().oneshot(req).await.unwrap();
+
+    assert_eq!(res.status(), StatusCode::OK);
+    assert_eq!(res.headers()[ALLOW], "GET,HEAD,OPTIONS");
+    assert!(res.headers().get(header::CONTENT_TYPE).is_none());
+    let body = body_into_text(res.into_body()).await;
+    assert!(body.is_empty());
+
+    let req = Request::builder()
+        .method(Method::POST)
+        .uri("/README.md")
+        .body(Body::empty())
+        .unwrap();
+    let res = svc.oneshot(req).await.unwrap();
+
+    assert_eq!(res.status(), StatusCode::METHOD_NOT_ALLOWED);
+    assert_eq!(res.headers()[ALLOW], "GET,HEAD,OPTIONS");
+}
+
+#[tokio::test]
+async fn options_missing_file() {
+    let svc = ServeDir::new("..").respond_to_options(true);
+
+    let req = Request::builder()
+        .method(Method::OPTIONS)
+        .uri("/doesnt-exist")
+        .body(Body::empty())
+        .unwrap();
+    let res = svc.oneshot(req).await.unwrap();
+
+    assert_eq!(res.status(), StatusCode::NOT_FOUND);
+    assert!(res.headers().get(ALLOW).is_none());
+}
+
+#[tokio::test]
+async fn options_not_allowed_by_default() {
+    let svc = ServeDir::new("..");
+
+    let req = Request::builder()
+        .method(Method::OPTIONS)
+        .uri("/README.md")
+        .body(Body::empty())
+        .unwrap();
+    let res = svc.oneshot(req).await.unwrap();
+
+    assert_eq!(res.status(), StatusCode::METHOD_NOT_ALLOWED);
+    assert_eq!(res.headers()[ALLOW], "GET,HEAD");
+}
+
 #[tokio::test]
 async fn calling_fallback_on_not_allowed() {
     async fn fallback<B>(req: Request<B>) -> Result<Response<Body>, Infallible> {
@@ -766,3 +830,398 @@ async fn calls_fallback_on_invalid_paths() {
 
     assert_eq!(res.headers()["from-fallback"], "1");
 }
+
+#[tokio::test]
+async fn force_download_for_matching_content_type() {
+    let svc = ServeDir::new("../test-files").force_download_for(&[mime::TEXT_HTML]);
+
+    let req = Request::builder()
+        .uri("/index.html")
+        .body(Body::empty())
+        .unwrap();
+    let res = svc.clone().oneshot(req).await.unwrap();
+
+    assert_eq!(res.status(), StatusCode::OK);
+    assert_eq!(res.headers()[header::CONTENT_TYPE], "text/html");
+    assert_eq!(res.headers()[header::CONTENT_DISPOSITION], "attachment");
+    assert_eq!(res.headers()[header::X_CONTENT_TYPE_OPTIONS], "nosniff");
+
+    let req = Request::builder()
+        .uri("/image.png")
+        .body(Body::empty())
+        .unwrap();
+    let res = svc.oneshot(req).await.unwrap();
+
+    assert_eq!(res.status(), StatusCode::OK);
+    assert_eq!(res.headers()[header::CONTENT_TYPE], "image/png");
+    assert!(res.headers().get(header::CONTENT_DISPOSITION).is_none());
+    assert!(res.headers().get(header::X_CONTENT_TYPE_OPTIONS).is_none());
+}
+
+#[tokio::test]
+async fn force_download_as_octet_stream() {
+    let svc = ServeDir::new("../test-files")
+        .force_download_for(&[mime::TEXT_HTML])
+        .force_download_as_octet_stream(true);
+
+    let req = Request::builder()
+        .uri("/index.html")
+        .body(Body::empty())
+        .unwrap();
+    let res = svc.oneshot(req).await.unwrap();
+
+    assert_eq!(res.status(), StatusCode::OK);
+    assert_eq!(
+        res.headers()[header::CONTENT_TYPE],
+        "application/octet-stream"
+    );
+    assert_eq!(res.headers()[header::CONTENT_DISPOSITION], "attachment");
+    assert_eq!(res.headers()[header::X_CONTENT_TYPE_OPTIONS], "nosniff");
+
+    let body = body_into_text(res.into_body()).await;
+    assert_eq!(body, "<b>HTML!</b>\n");
+}
+
+#[tokio::test]
+async fn immutable_cache_control_for_fingerprinted_files() {
+    let svc = ServeDir::new("../test-files").immutable_when(is_fingerprinted);
+
+    let req = Request::builder()
+        .uri("/app.3f9a2b.js")
+        .body(Body::empty())
+        .unwrap();
+    let res = svc.clone().oneshot(req).await.unwrap();
+    assert_eq!(res.status(), StatusCode::OK);
+    assert_eq!(
+        res.headers()[header::CACHE_CONTROL],
+        "public, max-age=31536000, immutable"
+    );
+
+    let req = Request::builder()
+        .uri("/index.html")
+        .body(Body::empty())
+        .unwrap();
+    let res = svc.oneshot(req).await.unwrap();
+    assert_eq!(res.status(), StatusCode::OK);
+    assert_eq!(res.headers()[header::CACHE_CONTROL], "no-cache");
+}
+
+#[tokio::test]
+async fn max_age_for_other_files() {
+    let svc = ServeDir::new("../test-files")
+        .immutable_when(is_fingerprinted)
+        .max_age(Duration::from_secs(600));
+
+    let req = Request::builder()
+        .uri("/app.3f9a2b.js")
+        .body(Body::empty())
+        .unwrap();
+    let res = svc.clone().oneshot(req).await.unwrap();
+    assert_eq!(
+        res.headers()[header::CACHE_CONTROL],
+        "public, max-age=31536000, immutable"
+    );
+
+    let req = Request::builder()
+        .uri("/index.html")
+        .body(Body::empty())
+        .unwrap();
+    let res = svc.oneshot(req).await.unwrap();
+    assert_eq!(res.status(), StatusCode::OK);
+    assert_eq!(res.headers()[header::CACHE_CONTROL], "public, max-age=600");
+}
+
+fn is_fingerprinted(path: &Path) -> bool {
+    path.file_name()
+        .and_then(|name| name.to_str())
+        .map_or(false, |name| name.contains(".3f9a2b."))
+}
+
+#[tokio::test]
+async fn no_cache_control_by_default() {
+    let svc = ServeDir::new("../test-files");
+
+    let req = Request::builder()
+        .uri("/app.3f9a2b.js")
+        .body(Body::empty())
+        .unwrap();
+    let res = svc.oneshot(req).await.unwrap();
+    assert_eq!(res.status(), StatusCode::OK);
+    assert!(res.headers().get(header::CACHE_CONTROL).is_none());
+}
+
+#[tokio::test]
+async fn serves_source_maps_by_default() {
+    let svc = ServeDir::new("../test-files");
+
+    let req = Request::builder()
+        .uri("/app.js.map")
+        .body(Body::empty())
+        .unwrap();
+    let res = svc.oneshot(req).await.unwrap();
+
+    assert_eq!(res.status(), StatusCode::OK);
+    let body = body_into_text(res.into_body()).await;
+    assert!(body.starts_with(r#"{"version":3"#));
+}
+
+#[tokio::test]
+async fn not_found_for_source_maps_when_disabled() {
+    let svc = ServeDir::new("../test-files").serve_source_maps(false);
+
+    let req = Request::builder()
+        .uri("/app.js.map")
+        .body(Body::empty())
+        .unwrap();
+    let res = svc.clone().oneshot(req).await.unwrap();
+
+    assert_eq!(res.status(), StatusCode::NOT_FOUND);
+    assert!(res.headers().get(header::CONTENT_TYPE).is_none());
+
+    let req = Request::builder()
+        .uri("/index.html")
+        .body(Body::empty())
+        .unwrap();
+    let res = svc.oneshot(req).await.unwrap();
+
+    assert_eq!(res.status(), StatusCode::OK);
+}
+
+#[tokio::test]
+async fn no_vary_header_without_precompressed_variants() {
+    let svc = ServeDir::new("../test-files");
+
+    let req = Request::builder()
+        .uri("/precompressed.txt")
+        .header("Accept-Encoding", "gzip")
+        .body(Body::empty())
+        .unwrap();
+    let res = svc.oneshot(req).await.unwrap();
+
+    assert_eq!(res.status(), StatusCode::OK);
+    assert!(res.headers().get("vary").is_none());
+}
+
+#[cfg(feature = "fs-strong-etags")]
+#[tokio::test]
+async fn strong_etags_cached() {
+    let cache = ETagCache::new(16);
+    let svc = ServeDir::new("..").strong_etags_cached(cache.clone());
+
+    let req = Request::builder()
+        .uri("/README.md")
+        .body(Body::empty())
+        .unwrap();
+    let res = svc.clone().oneshot(req).await.unwrap();
+    assert_eq!(res.status(), StatusCode::OK);
+    let etag = res.headers()[header::ETAG].clone();
+    // a quoted SHA-256 hash
+    assert_eq!(etag.len(), 66);
+    assert!(etag.to_str().unwrap().starts_with('"'));
+    assert_eq!(cache.hashed(), 1);
+
+    let req = Request::builder()
+        .method(Method::HEAD)
+        .uri("/README.md")
+        .body(Body::empty())
+        .unwrap();
+    let res = svc.clone().oneshot(req).await.unwrap();
+    assert_eq!(res.status(), StatusCode::OK);
+    assert_eq!(res.headers()[header::ETAG], etag);
+    assert_eq!(cache.hashed(), 1);
+
+    let req = Request::builder()
+        .uri("/README.md")
+        .header(
+            header::IF_NONE_MATCH,
+            format!("\"other\", W/{}", etag.to_str().unwrap()),
+        )
+        .body(Body::empty())
+        .unwrap();
+    let res = svc.clone().oneshot(req).await.unwrap();
+    assert_eq!(res.status(), StatusCode::NOT_MODIFIED);
+    assert_eq!(res.headers()[header::ETAG], etag);
+    assert!(res.into_body().frame().await.is_none());
+
+    // `If-None-Match` takes precedence over `If-Modified-Since`
+    let req = Request::builder()
+        .uri("/README.md")
+        .header(header::IF_NONE_MATCH, "\"other\"")
+        .header(header::IF_MODIFIED_SINCE, "Fri, 09 Aug 2096 14:21:40 GMT")
+        .body(Body::empty())
+        .unwrap();
+    let res = svc.oneshot(req).await.unwrap();
+    assert_eq!(res.status(), StatusCode::OK);
+    assert_eq!(cache.hashed(), 1);
+}
+
+#[cfg(feature = "fs-strong-etags")]
+#[tokio::test]
+async fn strong_etags_recomputed_when_file_changes() {
+    let dir = std::env::temp_dir().join(format!("tower-http-{}", uuid::Uuid::new_v4()));
+    std::fs::create_dir(&dir).unwrap();
+    std::fs::write(dir.join("file.txt"), "one").unwrap();
+
+    let cache = ETagCache::new(16);
+    let svc = ServeDir::new(&dir).strong_etags_cached(cache.clone());
+    let etag = |svc: ServeDir| async move {
+        let req = Request::builder()
+            .uri("/file.txt")
+            .body(Body::empty())
+            .unwrap();
+        let res = svc.oneshot(req).await.unwrap();
+        res.headers()[header::ETAG].clone()
+    };
+
+    let first = etag(svc.clone()).await;
+    assert_eq!(etag(svc.clone()).await, first);
+    assert_eq!(cache.hashed(), 1);
+
+    std::fs::write(dir.join("file.txt"), "three").unwrap();
+    let second = etag(svc.clone()).await;
+    assert_ne!(second, first);
+    assert_eq!(cache.hashed(), 2);
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[tokio::test]
+async fn weak_etags() {
+    let dir = std::env::temp_dir().join(format!("tower-http-{}", uuid::Uuid::new_v4()));
+    std::fs::create_dir(&dir).unwrap();
+    let path = dir.join("file.txt");
+    std::fs::write(&path, "one").unwrap();
+
+    let svc = ServeDir::new(&dir).weak_etags();
+    let get = |if_none_match: Option<HeaderValue>| {
+        let svc = svc.clone();
+        async move {
+            let mut req = Request::builder().uri("/file.txt");
+            if let Some(if_none_match) = if_none_match {
+                req = req.header(header::IF_NONE_MATCH, if_none_match);
+            }
+            svc.oneshot(req.body(Body::empty()).unwrap()).await.unwrap()
+        }
+    };
+
+    let res = get(None).await;
+    assert_eq!(res.status(), StatusCode::OK);
+    let first = res.headers()[header::ETAG].clone();
+    assert!(first.to_str().unwrap().starts_with("W/\""));
+    assert!(first.to_str().unwrap().ends_with("-3\""));
+    assert_eq!(get(None).await.headers()[header::ETAG], first);
+
+    let res = get(Some(first.clone())).await;
+    assert_eq!(res.status(), StatusCode::NOT_MODIFIED);
+    assert_eq!(res.headers()[header::ETAG], first);
+
+    // change only the modification time, keeping the size
+    let modified = std::fs::metadata(&path).unwrap().modified().unwrap();
+    while std::fs::metadata(&path).unwrap().modified().unwrap() == modified {
+        tokio::time::sleep(Duration::from_millis(10)).await;
+        std::fs::write(&path, "two").unwrap();
+    }
+
+    let res = get(Some(first.clone())).await;
+    assert_eq!(res.status(), StatusCode::OK);
+    let second = res.headers()[header::ETAG].clone();
+    assert_ne!(second, first);
+    assert!(second.to_str().unwrap().ends_with("-3\""));
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[cfg(feature = "fs-strong-etags")]
+#[tokio::test]
+async fn etag_cache_evicts_oldest_entry() {
+    let cache = ETagCache::new(1);
+    let svc = ServeDir::new("../test-files").strong_etags_cached(cache.clone());
+    let get = |uri: &'static str| {
+        let svc = svc.clone();
+        async move {
+            let req = Request::builder().uri(uri).body(Body::empty()).unwrap();
+            svc.oneshot(req).await.unwrap()
+        }
+    };
+
+    get("/precompressed.txt").await;
+    get("/precompressed.txt").await;
+    assert_eq!(cache.hashed(), 1);
+
+    get("/missing_precompressed.txt").await;
+    get("/precompressed.txt").await;
+    assert_eq!(cache.hashed(), 3);
+}
+
+#[tokio::test]
+async fn negotiate_image_format() {
+    let dir = std::env::temp_dir().join(format!("tower-http-{}", uuid::Uuid::new_v4()));
+    std::fs::create_dir(&dir).unwrap();
+    std::fs::write(dir.join("photo.jpg"), "jpeg").unwrap();
+    std::fs::write(dir.join("photo.webp"), "webp").unwrap();
+    std::fs::write(dir.join("photo.avif"), "avif").unwrap();
+
+    let svc = ServeDir::new(&dir).negotiate_image_format(true);
+    let req = Request::builder()
+        .uri("/photo.jpg")
+        .header(header::ACCEPT, "image/avif,image/webp,*/*")
+        .body(Body::empty())
+        .unwrap();
+    let res = svc.clone().oneshot(req).await.unwrap();
+
+    assert_eq!(res.status(), StatusCode::OK);
+    assert_eq!(res.headers()[header::CONTENT_TYPE], "image/avif");
+    assert_eq!(res.headers()[header::VARY], "accept");
+    assert_eq!(body_into_text(res.into_body()).await, "avif");
+
+    // without the header, the requested file is served
+    let req = Request::builder()
+        .uri("/photo.jpg")
+        .body(Body::empty())
+        .unwrap();
+    let res = svc.oneshot(req).await.unwrap();
+
+    assert_eq!(res.headers()[header::CONTENT_TYPE], "image/jpeg");
+    assert_eq!(res.headers()[header::VARY], "accept");
+    assert_eq!(body_into_text(res.into_body()).await, "jpeg");
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[tokio::test]
+async fn negotiate_image_format_without_variant() {
+    let dir = std::env::temp_dir().join(format!("tower-http-{}", uuid::Uuid::new_v4()));
+    std::fs::create_dir(&dir).unwrap();
+    std::fs::write(dir.join("photo.jpg"), "jpeg").unwrap();
+    std::fs::write(dir.join("photo.webp"), "webp").unwrap();
+    std::fs::write(dir.join("other.avif"), "avif").unwrap();
+
+    let svc = ServeDir::new(&dir).negotiate_image_format(true);
+    let get = |accept: &'static str| {
+        let svc = svc.clone();
+        async move {
+            let req = Request::builder()
+                .uri("/photo.jpg")
+                .header(header::ACCEPT, accept)
+                .body(Body::empty())
+                .unwrap();
+            svc.oneshot(req).await.unwrap()
+        }
+    };
+
+    // falls back to the next accepted format
+    let res = get("image/avif,image/webp,*/*").await;
+    assert_eq!(res.status(), StatusCode::OK);
+    assert_eq!(res.headers()[header::CONTENT_TYPE], "image/webp");
+    assert_eq!(res.headers()[header::VARY], "accept");
+    assert_eq!(body_into_text(res.into_body()).await, "webp");
+
+    // and to the requested file if no accepted format is present
+    let res = get("image/avif,*/*").await;
+    assert_eq!(res.status(), StatusCode::OK);
+    assert_eq!(res.headers()[header::CONTENT_TYPE], "image/jpeg");
+    assert_eq!(res.headers()[header::VARY], "accept");
+    assert_eq!(body_into_text(res.into_body()).await, "jpeg");
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
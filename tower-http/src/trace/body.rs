@@ -1,5 +1,9 @@
-use super::{DefaultOnBodyChunk, DefaultOnEos, DefaultOnFailure, OnBodyChunk, OnEos, OnFailure};
+use super::{
+    on_completion::PendingCompletion, record_ttfb, DefaultOnBodyChunk, DefaultOnEos,
+    DefaultOnFailure, DeferredSpan, OnBodyChunk, OnCompletion, OnEos, OnFailure,
+};
 use crate::classify::ClassifyEos;
+use bytes::Buf;
 use http_body::{Body, Frame};
 use pin_project_lite::pin_project;
 use std::{
@@ -14,20 +18,23 @@ pin_project! {
     /// Response body for [`Trace`].
     ///
     /// [`Trace`]: super::Trace
-    pub struct ResponseBody<B, C, OnBodyChunk = DefaultOnBodyChunk, OnEos = DefaultOnEos, OnFailure = DefaultOnFailure> {
+    pub struct ResponseBody<B, C, OnBodyChunk = DefaultOnBodyChunk, OnEos = DefaultOnEos, OnFailure = DefaultOnFailure, OnCompletion = ()> {
         #[pin]
         pub(crate) inner: B,
         pub(crate) classify_eos: Option<C>,
         pub(crate) on_eos: Option<(OnEos, Instant)>,
         pub(crate) on_body_chunk: OnBodyChunk,
         pub(crate) on_failure: Option<OnFailure>,
+        pub(crate) on_completion: Option<PendingCompletion<OnCompletion>>,
+        pub(crate) ttfb_start: Option<Instant>,
         pub(crate) start: Instant,
         pub(crate) span: Span,
+        pub(crate) deferred_span: Option<DeferredSpan>,
     }
 }
 
-impl<B, C, OnBodyChunkT, OnEosT, OnFailureT> Body
-    for ResponseBody<B, C, OnBodyChunkT, OnEosT, OnFailureT>
+impl<B, C, OnBodyChunkT, OnEosT, OnFailureT, OnCompletionT> Body
+    for ResponseBody<B, C, OnBodyChunkT, OnEosT, OnFailureT, OnCompletionT>
 where
     B: Body,
     B::Error: fmt::Display + 'static,
@@ -35,6 +42,7 @@ where
     OnEosT: OnEos,
     OnBodyChunkT: OnBodyChunk<B::Data>,
     OnFailureT: OnFailure<C::FailureClass>,
+    OnCompletionT: OnCompletion,
 {
     type Data = B::Data;
     type Error = B::Error;
@@ -44,17 +52,36 @@ where
         cx: &mut Context<'_>,
     ) -> Poll<Option<Result<http_body::Frame<Self::Data>, Self::Error>>> {
         let this = self.project();
+        let result = {
+            let _guard = this.span.enter();
+            ready!(this.inner.poll_frame(cx))
+        };
+
+        // the error is reported to `OnFailure`, with the span of skipped requests
+        if matches!(result, Some(Err(_))) && this.classify_eos.is_some() {
+            if let Some(deferred_span) = this.deferred_span.take() {
+                *this.span = deferred_span.make_span();
+            }
+        }
         let _guard = this.span.enter();
-        let result = ready!(this.inner.poll_frame(cx));
 
         let latency = this.start.elapsed();
         *this.start = Instant::now();
 
+        if !matches!(result, Some(Err(_))) {
+            if let Some(ttfb_start) = this.ttfb_start.take() {
+                record_ttfb(this.span, ttfb_start.elapsed());
+            }
+        }
+
         match result {
             Some(Ok(frame)) => {
                 let frame = match frame.into_data() {
                     Ok(chunk) => {
                         this.on_body_chunk.on_body_chunk(&chunk, latency, this.span);
+                        if let Some(on_completion) = this.on_completion {
+                            on_completion.add_bytes(chunk.remaining());
+                        }
                         Frame::data(chunk)
                     }
                     Err(frame) => frame,
@@ -65,6 +92,15 @@ where
                         if let Some((on_eos, stream_start)) = this.on_eos.take() {
                             on_eos.on_eos(Some(&trailers), stream_start.elapsed(), this.span);
                         }
+                        if let Some(mut on_completion) = this.on_completion.take() {
+                            let failed = this.classify_eos.take().map_or(false, |classify_eos| {
+                                classify_eos.classify_eos(Some(&trailers)).is_err()
+                            });
+                            if failed {
+                                on_completion.set_failure();
+                            }
+                            on_completion.complete(this.span);
+                        }
                         Frame::trailers(trailers)
                     }
                     Err(frame) => frame,
@@ -78,6 +114,12 @@ where
                 {
                     let failure_class = classify_eos.classify_error(&err);
                     on_failure.on_failure(failure_class, latency, this.span);
+                    if let Some(on_completion) = this.on_completion {
+                        on_completion.set_failure();
+                    }
+                }
+                if let Some(on_completion) = this.on_completion.take() {
+                    on_completion.complete(this.span);
                 }
 
                 Poll::Ready(Some(Err(err)))
@@ -86,6 +128,15 @@ where
                 if let Some((on_eos, stream_start)) = this.on_eos.take() {
                     on_eos.on_eos(None, stream_start.elapsed(), this.span);
                 }
+                if let Some(mut on_completion) = this.on_completion.take() {
+                    let failed = this.classify_eos.take().map_or(false, |classify_eos| {
+                        classify_eos.classify_eos(None).is_err()
+                    });
+                    if failed {
+                        on_completion.set_failure();
+                    }
+                    on_completion.complete(this.span);
+                }
 
                 Poll::Ready(None)
             }
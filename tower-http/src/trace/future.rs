@@ -1,6 +1,7 @@
 use super::{
-    DefaultOnBodyChunk, DefaultOnEos, DefaultOnFailure, DefaultOnResponse, OnBodyChunk, OnEos,
-    OnFailure, OnResponse, ResponseBody,
+    on_completion::PendingCompletion, record_error_message, record_ttfb, DefaultOnBodyChunk,
+    DefaultOnEos, DefaultOnFailure, DefaultOnResponse, DeferredSpan, OnBodyChunk, OnCompletion,
+    OnEos, OnFailure, OnResponse, ResponseBody,
 };
 use crate::classify::{ClassifiedResponse, ClassifyResponse};
 use http::Response;
@@ -18,21 +19,25 @@ pin_project! {
     /// Response future for [`Trace`].
     ///
     /// [`Trace`]: super::Trace
-    pub struct ResponseFuture<F, C, OnResponse = DefaultOnResponse, OnBodyChunk = DefaultOnBodyChunk, OnEos = DefaultOnEos, OnFailure = DefaultOnFailure> {
+    pub struct ResponseFuture<F, C, OnResponse = DefaultOnResponse, OnBodyChunk = DefaultOnBodyChunk, OnEos = DefaultOnEos, OnFailure = DefaultOnFailure, OnCompletion = ()> {
         #[pin]
         pub(crate) inner: F,
         pub(crate) span: Span,
+        pub(crate) deferred_span: Option<DeferredSpan>,
         pub(crate) classifier: Option<C>,
         pub(crate) on_response: Option<OnResponse>,
         pub(crate) on_body_chunk: Option<OnBodyChunk>,
         pub(crate) on_eos: Option<OnEos>,
         pub(crate) on_failure: Option<OnFailure>,
+        pub(crate) on_completion: Option<PendingCompletion<OnCompletion>>,
+        pub(crate) record_ttfb: bool,
+        pub(crate) record_error_message: Option<usize>,
         pub(crate) start: Instant,
     }
 }
 
-impl<Fut, ResBody, E, C, OnResponseT, OnBodyChunkT, OnEosT, OnFailureT> Future
-    for ResponseFuture<Fut, C, OnResponseT, OnBodyChunkT, OnEosT, OnFailureT>
+impl<Fut, ResBody, E, C, OnResponseT, OnBodyChunkT, OnEosT, OnFailureT, OnCompletionT> Future
+    for ResponseFuture<Fut, C, OnResponseT, OnBodyChunkT, OnEosT, OnFailureT, OnCompletionT>
 where
     Fut: Future<Output = Result<Response<ResBody>, E>>,
     ResBody: Body,
@@ -43,28 +48,54 @@ where
     OnFailureT: OnFailure<C::FailureClass>,
     OnBodyChunkT: OnBodyChunk<ResBody::Data>,
     OnEosT: OnEos,
+    OnCompletionT: OnCompletion,
 {
     type Output = Result<
-        Response<ResponseBody<ResBody, C::ClassifyEos, OnBodyChunkT, OnEosT, OnFailureT>>,
+        Response<
+            ResponseBody<ResBody, C::ClassifyEos, OnBodyChunkT, OnEosT, OnFailureT, OnCompletionT>,
+        >,
         E,
     >;
 
     fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
         let this = self.project();
-        let _guard = this.span.enter();
-        let result = ready!(this.inner.poll(cx));
+        let result = {
+            let _guard = this.span.enter();
+            ready!(this.inner.poll(cx))
+        };
         let latency = this.start.elapsed();
 
         let classifier = this.classifier.take().unwrap();
         let on_eos = this.on_eos.take();
         let on_body_chunk = this.on_body_chunk.take().unwrap();
         let mut on_failure = this.on_failure.take().unwrap();
+        let mut on_completion = this.on_completion.take();
 
         match result {
             Ok(res) => {
                 let classification = classifier.classify_response(&res);
+                if matches!(classification, ClassifiedResponse::Ready(Err(_))) {
+                    if let Some(deferred_span) = this.deferred_span.take() {
+                        *this.span = deferred_span.make_span();
+                    }
+                }
+                let _guard = this.span.enter();
+                if let Some(on_completion) = &mut on_completion {
+                    on_completion.set_status(res.status());
+                }
                 let start = *this.start;
 
+                // The time to first byte of responses with an empty body is the time until the
+                // response was produced.
+                let ttfb_start = if !*this.record_ttfb {
+                    None
+                } else if res.body().is_end_stream() {
+                    record_ttfb(this.span, latency);
+                    None
+                } else {
+                    Some(start)
+                };
+
                 this.on_response
                     .take()
                     .unwrap()
@@ -74,6 +105,16 @@ where
                     ClassifiedResponse::Ready(classification) => {
                         if let Err(failure_class) = classification {
                             on_failure.on_failure(failure_class, latency, this.span);
+                            if let Some(on_completion) = &mut on_completion {
+                                on_completion.set_failure();
+                            }
+                        }
+
+                        // empty bodies might never be polled
+                        if res.body().is_end_stream() {
+                            if let Some(on_completion) = on_completion.take() {
+                                on_completion.complete(this.span);
+                            }
                         }
 
                         let span = this.span.clone();
@@ -83,22 +124,29 @@ where
                             on_eos: None,
                             on_body_chunk,
                             on_failure: Some(on_failure),
+                            on_completion,
+                            ttfb_start,
                             start,
                             span,
+                            deferred_span: None,
                         });
 
                         Poll::Ready(Ok(res))
                     }
                     ClassifiedResponse::RequiresEos(classify_eos) => {
                         let span = this.span.clone();
+                        let deferred_span = this.deferred_span.take();
                         let res = res.map(|body| ResponseBody {
                             inner: body,
                             classify_eos: Some(classify_eos),
                             on_eos: on_eos.zip(Some(Instant::now())),
                             on_body_chunk,
                             on_failure: Some(on_failure),
+                            on_completion,
+                            ttfb_start,
                             start,
                             span,
+                            deferred_span,
                         });
 
                         Poll::Ready(Ok(res))
@@ -106,8 +154,19 @@ where
                 }
             }
             Err(err) => {
+                if let Some(deferred_span) = this.deferred_span.take() {
+                    *this.span = deferred_span.make_span();
+                }
+                let _guard = this.span.enter();
+                if let Some(max_len) = *this.record_error_message {
+                    record_error_message(this.span, &err, max_len);
+                }
                 let failure_class = classifier.classify_error(&err);
                 on_failure.on_failure(failure_class, latency, this.span);
+                if let Some(mut on_completion) = on_completion {
+                    on_completion.set_failure();
+                    on_completion.complete(this.span);
+                }
 
                 Poll::Ready(Err(err))
             }
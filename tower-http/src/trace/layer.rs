@@ -1,6 +1,6 @@
 use super::{
     DefaultMakeSpan, DefaultOnBodyChunk, DefaultOnEos, DefaultOnFailure, DefaultOnRequest,
-    DefaultOnResponse, GrpcMakeClassifier, HttpMakeClassifier, Trace,
+    DefaultOnResponse, GrpcMakeClassifier, HttpMakeClassifier, SkipSpanWhen, Trace,
 };
 use crate::classify::{
     GrpcErrorsAsFailures, MakeClassifier, ServerErrorsAsFailures, SharedClassifier,
@@ -23,6 +23,7 @@ pub struct TraceLayer<
     OnBodyChunk = DefaultOnBodyChunk,
     OnEos = DefaultOnEos,
     OnFailure = DefaultOnFailure,
+    OnCompletion = (),
 > {
     pub(crate) make_classifier: M,
     pub(crate) make_span: MakeSpan,
@@ -31,6 +32,9 @@ pub struct TraceLayer<
     pub(crate) on_body_chunk: OnBodyChunk,
     pub(crate) on_eos: OnEos,
     pub(crate) on_failure: OnFailure,
+    pub(crate) on_completion: OnCompletion,
+    pub(crate) record_ttfb: bool,
+    pub(crate) record_error_message: Option<usize>,
 }
 
 impl<M> TraceLayer<M> {
@@ -43,6 +47,9 @@ impl<M> TraceLayer<M> {
             make_classifier,
             make_span: DefaultMakeSpan::new(),
             on_failure: DefaultOnFailure::default(),
+            record_ttfb: false,
+            record_error_message: None,
+            on_completion: (),
             on_request: DefaultOnRequest::default(),
             on_eos: DefaultOnEos::default(),
             on_body_chunk: DefaultOnBodyChunk::default(),
@@ -51,8 +58,8 @@ impl<M> TraceLayer<M> {
     }
 }
 
-impl<M, MakeSpan, OnRequest, OnResponse, OnBodyChunk, OnEos, OnFailure>
-    TraceLayer<M, MakeSpan, OnRequest, OnResponse, OnBodyChunk, OnEos, OnFailure>
+impl<M, MakeSpan, OnRequest, OnResponse, OnBodyChunk, OnEos, OnFailure, OnCompletion>
+    TraceLayer<M, MakeSpan, OnRequest, OnResponse, OnBodyChunk, OnEos, OnFailure, OnCompletion>
 {
     /// Customize what to do when a request is received.
     ///
@@ -62,7 +69,16 @@ impl<M, MakeSpan, OnRequest, OnResponse, OnBodyChunk, OnEos, OnFailure>
     pub fn on_request<NewOnRequest>(
         self,
         new_on_request: NewOnRequest,
-    ) -> TraceLayer<M, MakeSpan, NewOnRequest, OnResponse, OnBodyChunk, OnEos, OnFailure> {
+    ) -> TraceLayer<
+        M,
+        MakeSpan,
+        NewOnRequest,
+        OnResponse,
+        OnBodyChunk,
+        OnEos,
+        OnFailure,
+        OnCompletion,
+    > {
         TraceLayer {
             on_request: new_on_request,
             on_failure: self.on_failure,
@@ -71,6 +87,9 @@ impl<M, MakeSpan, OnRequest, OnResponse, OnBodyChunk, OnEos, OnFailure>
             make_span: self.make_span,
             on_response: self.on_response,
             make_classifier: self.make_classifier,
+            record_ttfb: self.record_ttfb,
+            record_error_message: self.record_error_message,
+            on_completion: self.on_completion,
         }
     }
 
@@ -82,7 +101,16 @@ impl<M, MakeSpan, OnRequest, OnResponse, OnBodyChunk, OnEos, OnFailure>
     pub fn on_response<NewOnResponse>(
         self,
         new_on_response: NewOnResponse,
-    ) -> TraceLayer<M, MakeSpan, OnRequest, NewOnResponse, OnBodyChunk, OnEos, OnFailure> {
+    ) -> TraceLayer<
+        M,
+        MakeSpan,
+        OnRequest,
+        NewOnResponse,
+        OnBodyChunk,
+        OnEos,
+        OnFailure,
+        OnCompletion,
+    > {
         TraceLayer {
             on_response: new_on_response,
             on_request: self.on_request,
@@ -91,6 +119,9 @@ impl<M, MakeSpan, OnRequest, OnResponse, OnBodyChunk, OnEos, OnFailure>
             on_failure: self.on_failure,
             make_span: self.make_span,
             make_classifier: self.make_classifier,
+            record_ttfb: self.record_ttfb,
+            record_error_message: self.record_error_message,
+            on_completion: self.on_completion,
         }
     }
 
@@ -102,7 +133,16 @@ impl<M, MakeSpan, OnRequest, OnResponse, OnBodyChunk, OnEos, OnFailure>
     pub fn on_body_chunk<NewOnBodyChunk>(
         self,
         new_on_body_chunk: NewOnBodyChunk,
-    ) -> TraceLayer<M, MakeSpan, OnRequest, OnResponse, NewOnBodyChunk, OnEos, OnFailure> {
+    ) -> TraceLayer<
+        M,
+        MakeSpan,
+        OnRequest,
+        OnResponse,
+        NewOnBodyChunk,
+        OnEos,
+        OnFailure,
+        OnCompletion,
+    > {
         TraceLayer {
             on_body_chunk: new_on_body_chunk,
             on_eos: self.on_eos,
@@ -111,6 +151,9 @@ impl<M, MakeSpan, OnRequest, OnResponse, OnBodyChunk, OnEos, OnFailure>
             make_span: self.make_span,
             on_response: self.on_response,
             make_classifier: self.make_classifier,
+            record_ttfb: self.record_ttfb,
+            record_error_message: self.record_error_message,
+            on_completion: self.on_completion,
         }
     }
 
@@ -122,7 +165,16 @@ impl<M, MakeSpan, OnRequest, OnResponse, OnBodyChunk, OnEos, OnFailure>
     pub fn on_eos<NewOnEos>(
         self,
         new_on_eos: NewOnEos,
-    ) -> TraceLayer<M, MakeSpan, OnRequest, OnResponse, OnBodyChunk, NewOnEos, OnFailure> {
+    ) -> TraceLayer<
+        M,
+        MakeSpan,
+        OnRequest,
+        OnResponse,
+        OnBodyChunk,
+        NewOnEos,
+        OnFailure,
+        OnCompletion,
+    > {
         TraceLayer {
             on_eos: new_on_eos,
             on_body_chunk: self.on_body_chunk,
@@ -131,6 +183,9 @@ impl<M, MakeSpan, OnRequest, OnResponse, OnBodyChunk, OnEos, OnFailure>
             make_span: self.make_span,
             on_response: self.on_response,
             make_classifier: self.make_classifier,
+            record_ttfb: self.record_ttfb,
+            record_error_message: self.record_error_message,
+            on_completion: self.on_completion,
         }
     }
 
@@ -142,7 +197,16 @@ impl<M, MakeSpan, OnRequest, OnResponse, OnBodyChunk, OnEos, OnFailure>
     pub fn on_failure<NewOnFailure>(
         self,
         new_on_failure: NewOnFailure,
-    ) -> TraceLayer<M, MakeSpan, OnRequest, OnResponse, OnBodyChunk, OnEos, NewOnFailure> {
+    ) -> TraceLayer<
+        M,
+        MakeSpan,
+        OnRequest,
+        OnResponse,
+        OnBodyChunk,
+        OnEos,
+        NewOnFailure,
+        OnCompletion,
+    > {
         TraceLayer {
             on_failure: new_on_failure,
             on_request: self.on_request,
@@ -151,6 +215,41 @@ impl<M, MakeSpan, OnRequest, OnResponse, OnBodyChunk, OnEos, OnFailure>
             make_span: self.make_span,
             on_response: self.on_response,
             make_classifier: self.make_classifier,
+            record_ttfb: self.record_ttfb,
+            record_error_message: self.record_error_message,
+            on_completion: self.on_completion,
+        }
+    }
+
+    /// Customize what to do when a request has completed.
+    ///
+    /// `NewOnCompletion` is expected to implement [`OnCompletion`].
+    ///
+    /// [`OnCompletion`]: super::OnCompletion
+    pub fn on_completion<NewOnCompletion>(
+        self,
+        new_on_completion: NewOnCompletion,
+    ) -> TraceLayer<
+        M,
+        MakeSpan,
+        OnRequest,
+        OnResponse,
+        OnBodyChunk,
+        OnEos,
+        OnFailure,
+        NewOnCompletion,
+    > {
+        TraceLayer {
+            on_completion: new_on_completion,
+            make_span: self.make_span,
+            on_request: self.on_request,
+            on_response: self.on_response,
+            on_body_chunk: self.on_body_chunk,
+            on_eos: self.on_eos,
+            on_failure: self.on_failure,
+            make_classifier: self.make_classifier,
+            record_ttfb: self.record_ttfb,
+            record_error_message: self.record_error_message,
         }
     }
 
@@ -163,7 +262,16 @@ impl<M, MakeSpan, OnRequest, OnResponse, OnBodyChunk, OnEos, OnFailure>
     pub fn make_span_with<NewMakeSpan>(
         self,
         new_make_span: NewMakeSpan,
-    ) -> TraceLayer<M, NewMakeSpan, OnRequest, OnResponse, OnBodyChunk, OnEos, OnFailure> {
+    ) -> TraceLayer<
+        M,
+        NewMakeSpan,
+        OnRequest,
+        OnResponse,
+        OnBodyChunk,
+        OnEos,
+        OnFailure,
+        OnCompletion,
+    > {
         TraceLayer {
             make_span: new_make_span,
             on_request: self.on_request,
@@ -172,8 +280,124 @@ impl<M, MakeSpan, OnRequest, OnResponse, OnBodyChunk, OnEos, OnFailure>
             on_eos: self.on_eos,
             on_response: self.on_response,
             make_classifier: self.make_classifier,
+            record_ttfb: self.record_ttfb,
+            record_error_message: self.record_error_message,
+            on_completion: self.on_completion,
+        }
+    }
+
+    /// Don't make a [`Span`] for requests matching `predicate`, unless they fail.
+    ///
+    /// This is useful for excluding noisy requests, such as health checks, from traces. The
+    /// predicate wraps the current [`MakeSpan`], so it should be called after
+    /// [`make_span_with`](Self::make_span_with).
+    ///
+    /// Matching requests are handled with a [`Span::none`]. If a matching request fails, its span
+    /// is made as soon as the failure is known, before [`OnFailure`] is called with it, by calling
+    /// the wrapped `MakeSpan` with the head of the request and a default body. So a failing health
+    /// check is still traced, but only from its failure on. This requires the request body to
+    /// implement [`Default`].
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use bytes::Bytes;
+    /// use http::Request;
+    /// use http_body_util::Full;
+    /// use tower_http::trace::TraceLayer;
+    ///
+    /// let layer = TraceLayer::new_for_http()
+    ///     .skip_span_when(|req: &Request<Full<Bytes>>| req.uri().path() == "/healthz");
+    /// ```
+    ///
+    /// [`MakeSpan`]: super::MakeSpan
+    /// [`OnFailure`]: super::OnFailure
+    /// [`Span`]: tracing::Span
+    /// [`Span::none`]: tracing::Span::none
+    pub fn skip_span_when<F>(
+        self,
+        predicate: F,
+    ) -> TraceLayer<
+        M,
+        SkipSpanWhen<MakeSpan, F>,
+        OnRequest,
+        OnResponse,
+        OnBodyChunk,
+        OnEos,
+        OnFailure,
+        OnCompletion,
+    > {
+        TraceLayer {
+            make_span: SkipSpanWhen {
+                make_span: self.make_span,
+                predicate,
+            },
+            on_request: self.on_request,
+            on_failure: self.on_failure,
+            on_body_chunk: self.on_body_chunk,
+            on_eos: self.on_eos,
+            on_response: self.on_response,
+            make_classifier: self.make_classifier,
+            record_ttfb: self.record_ttfb,
+            record_error_message: self.record_error_message,
+            on_completion: self.on_completion,
         }
     }
+
+    /// Record the time to first byte on the [`Span`], as the `http.ttfb` field.
+    ///
+    /// The time to first byte is the duration from receiving the request until the first data
+    /// frame of the response body has been produced. For responses with an empty body, it is the
+    /// duration until the response was produced.
+    ///
+    /// [`DefaultMakeSpan`] declares the `http.ttfb` field. Custom [`MakeSpan`]s must declare
+    /// it themselves, for example with `http.ttfb = tracing::field::Empty`, since `tracing` only
+    /// records fields that were declared when the span was created.
+    ///
+    /// Defaults to `false`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use tower_http::trace::TraceLayer;
+    ///
+    /// let layer = TraceLayer::new_for_http().record_ttfb(true);
+    /// ```
+    ///
+    /// [`DefaultMakeSpan`]: super::DefaultMakeSpan
+    /// [`MakeSpan`]: super::MakeSpan
+    /// [`Span`]: tracing::Span
+    pub fn record_ttfb(mut self, record_ttfb: bool) -> Self {
+        self.record_ttfb = record_ttfb;
+        self
+    }
+
+    /// Record the [`Display`] representation of errors returned by the inner service on the
+    /// [`Span`], as the `error.message` field.
+    ///
+    /// Messages longer than `max_len` bytes are truncated, and end with `…`.
+    ///
+    /// [`DefaultMakeSpan`] declares the `error.message` field. Custom [`MakeSpan`]s must declare
+    /// it themselves, for example with `error.message = tracing::field::Empty`.
+    ///
+    /// By default, error messages aren't recorded on the span.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use tower_http::trace::TraceLayer;
+    ///
+    /// let layer = TraceLayer::new_for_http().record_error_message(1024);
+    /// ```
+    ///
+    /// [`Display`]: std::fmt::Display
+    /// [`DefaultMakeSpan`]: super::DefaultMakeSpan
+    /// [`MakeSpan`]: super::MakeSpan
+    /// [`Span`]: tracing::Span
+    pub fn record_error_message(mut self, max_len: usize) -> Self {
+        self.record_error_message = Some(max_len);
+        self
+    }
 }
 
 impl TraceLayer<HttpMakeClassifier> {
@@ -188,6 +412,9 @@ impl TraceLayer<HttpMakeClassifier> {
             on_body_chunk: DefaultOnBodyChunk::default(),
             on_eos: DefaultOnEos::default(),
             on_failure: DefaultOnFailure::default(),
+            record_ttfb: false,
+            record_error_message: None,
+            on_completion: (),
         }
     }
 }
@@ -204,12 +431,15 @@ impl TraceLayer<GrpcMakeClassifier> {
             on_body_chunk: DefaultOnBodyChunk::default(),
             on_eos: DefaultOnEos::default(),
             on_failure: DefaultOnFailure::default(),
+            record_ttfb: false,
+            record_error_message: None,
+            on_completion: (),
         }
     }
 }
 
-impl<S, M, MakeSpan, OnRequest, OnResponse, OnBodyChunk, OnEos, OnFailure> Layer<S>
-    for TraceLayer<M, MakeSpan, OnRequest, OnResponse, OnBodyChunk, OnEos, OnFailure>
+impl<S, M, MakeSpan, OnRequest, OnResponse, OnBodyChunk, OnEos, OnFailure, OnCompletion> Layer<S>
+    for TraceLayer<M, MakeSpan, OnRequest, OnResponse, OnBodyChunk, OnEos, OnFailure, OnCompletion>
 where
     M: Clone,
     MakeSpan: Clone,
@@ -218,8 +448,10 @@ where
     OnEos: Clone,
     OnBodyChunk: Clone,
     OnFailure: Clone,
+    OnCompletion: Clone,
 {
-    type Service = Trace<S, M, MakeSpan, OnRequest, OnResponse, OnBodyChunk, OnEos, OnFailure>;
+    type Service =
+        Trace<S, M, MakeSpan, OnRequest, OnResponse, OnBodyChunk, OnEos, OnFailure, OnCompletion>;
 
     fn layer(&self, inner: S) -> Self::Service {
         Trace {
@@ -231,6 +463,9 @@ where
             on_body_chunk: self.on_body_chunk.clone(),
             on_response: self.on_response.clone(),
             on_failure: self.on_failure.clone(),
+            record_ttfb: self.record_ttfb,
+            record_error_message: self.record_error_message,
+            on_completion: self.on_completion.clone(),
         }
     }
 }
@@ -1,7 +1,8 @@
 use http::Request;
+use std::fmt;
 use tracing::{Level, Span};
 
-use super::DEFAULT_MESSAGE_LEVEL;
+use super::{http_flavor, DEFAULT_MESSAGE_LEVEL};
 
 /// Trait used to generate [`Span`]s from requests. [`Trace`] wraps all request handling in this
 /// span.
@@ -11,6 +12,52 @@ use super::DEFAULT_MESSAGE_LEVEL;
 pub trait MakeSpan<B> {
     /// Make a span from a request.
     fn make_span(&mut self, request: &Request<B>) -> Span;
+
+    /// Defer making the span of a request until its response is known to be a failure.
+    ///
+    /// If this returns a [`DeferredSpan`], [`Trace`] handles the request with [`Span::none`]
+    /// instead of calling [`make_span`](MakeSpan::make_span). If the request fails, the span is
+    /// made with the `DeferredSpan` as soon as the failure is known, before [`OnFailure`] is
+    /// called with it.
+    ///
+    /// The default implementation returns `None`.
+    ///
+    /// [`Span::none`]: tracing::Span::none
+    /// [`OnFailure`]: super::OnFailure
+    fn defer_span(&mut self, _request: &Request<B>) -> Option<DeferredSpan> {
+        None
+    }
+}
+
+/// A [`Span`] that is only made if a request fails.
+///
+/// See [`MakeSpan::defer_span`] for more details.
+///
+/// [`Span`]: tracing::Span
+pub struct DeferredSpan {
+    make_span: Box<dyn FnOnce() -> Span + Send + Sync>,
+}
+
+impl DeferredSpan {
+    /// Create a new [`DeferredSpan`] that is made by calling `make_span`.
+    pub fn new<F>(make_span: F) -> Self
+    where
+        F: FnOnce() -> Span + Send + Sync + 'static,
+    {
+        Self {
+            make_span: Box::new(make_span),
+        }
+    }
+
+    pub(crate) fn make_span(self) -> Span {
+        (self.make_span)()
+    }
+}
+
+impl fmt::Debug for DeferredSpan {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("DeferredSpan").finish()
+    }
 }
 
 impl<B> MakeSpan<B> for Span {
@@ -30,8 +77,15 @@ where
 
 /// The default way [`Span`]s will be created for [`Trace`].
 ///
+/// The version of the request is recorded as the `http.flavor` field, as `1.1`, `2` or `3` for
+/// example.
+///
+/// If the `redact-query` feature is enabled and the request has a [`RedactedTarget`] extension,
+/// it is recorded instead of the request URI.
+///
 /// [`Span`]: tracing::Span
 /// [`Trace`]: super::Trace
+/// [`RedactedTarget`]: crate::redact_query::RedactedTarget
 #[derive(Debug, Clone)]
 pub struct DefaultMakeSpan {
     level: Level,
@@ -76,6 +130,11 @@ impl Default for DefaultMakeSpan {
 
 impl<B> MakeSpan<B> for DefaultMakeSpan {
     fn make_span(&mut self, request: &Request<B>) -> Span {
+        let uri: &dyn fmt::Display = match redacted_target(request) {
+            Some(target) => target,
+            None => request.uri(),
+        };
+
         // This ugly macro is needed, unfortunately, because `tracing::span!`
         // required the level argument to be static. Meaning we can't just pass
         // `self.level`.
@@ -86,17 +145,25 @@ impl<B> MakeSpan<B> for DefaultMakeSpan {
                         $level,
                         "request",
                         method = %request.method(),
-                        uri = %request.uri(),
+                        uri = %uri,
                         version = ?request.version(),
+                        http.flavor = http_flavor(request.version()),
                         headers = ?request.headers(),
+                        http.ttfb = tracing::field::Empty,
+                        http.redirects = tracing::field::Empty,
+                        error.message = tracing::field::Empty,
                     )
                 } else {
                     tracing::span!(
                         $level,
                         "request",
                         method = %request.method(),
-                        uri = %request.uri(),
+                        uri = %uri,
                         version = ?request.version(),
+                        http.flavor = http_flavor(request.version()),
+                        http.ttfb = tracing::field::Empty,
+                        http.redirects = tracing::field::Empty,
+                        error.message = tracing::field::Empty,
                     )
                 }
             }
@@ -111,3 +178,76 @@ impl<B> MakeSpan<B> for DefaultMakeSpan {
         }
     }
 }
+
+#[cfg(feature = "redact-query")]
+fn redacted_target<B>(request: &Request<B>) -> Option<&dyn fmt::Display> {
+    request
+        .extensions()
+        .get::<crate::redact_query::RedactedTarget>()
+        .map(|target| target as &dyn fmt::Display)
+}
+
+#[cfg(not(feature = "redact-query"))]
+fn redacted_target<B>(_request: &Request<B>) -> Option<&dyn fmt::Display> {
+    None
+}
+
+/// [`MakeSpan`] that doesn't make a [`Span`] for requests matching a predicate, unless they fail.
+///
+/// Created with [`TraceLayer::skip_span_when`] or [`Trace::skip_span_when`]. The span of matching
+/// requests is [deferred](MakeSpan::defer_span): the inner `MakeSpan` is called with the head of
+/// the request and a default body if the request fails.
+///
+/// [`Span`]: tracing::Span
+/// [`TraceLayer::skip_span_when`]: super::TraceLayer::skip_span_when
+/// [`Trace::skip_span_when`]: super::Trace::skip_span_when
+#[derive(Clone, Copy)]
+pub struct SkipSpanWhen<M, F> {
+    pub(crate) make_span: M,
+    pub(crate) predicate: F,
+}
+
+impl<M, F> fmt::Debug for SkipSpanWhen<M, F>
+where
+    M: fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SkipSpanWhen")
+            .field("make_span", &self.make_span)
+            .field("predicate", &std::any::type_name::<F>())
+            .finish()
+    }
+}
+
+impl<M, F, B> MakeSpan<B> for SkipSpanWhen<M, F>
+where
+    M: MakeSpan<B> + Clone + Send + Sync + 'static,
+    F: Fn(&Request<B>) -> bool,
+    B: Default + 'static,
+{
+    fn make_span(&mut self, request: &Request<B>) -> Span {
+        self.make_span.make_span(request)
+    }
+
+    fn defer_span(&mut self, request: &Request<B>) -> Option<DeferredSpan> {
+        if !(self.predicate)(request) {
+            return None;
+        }
+
+        let mut make_span = self.make_span.clone();
+        let method = request.method().clone();
+        let uri = request.uri().clone();
+        let version = request.version();
+        let headers = request.headers().clone();
+        let extensions = request.extensions().clone();
+        Some(DeferredSpan::new(move || {
+            let mut request = Request::new(B::default());
+            *request.method_mut() = method;
+            *request.uri_mut() = uri;
+            *request.version_mut() = version;
+            *request.headers_mut() = headers;
+            *request.extensions_mut() = extensions;
+            make_span.make_span(&request)
+        }))
+    }
+}
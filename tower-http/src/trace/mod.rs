@@ -238,6 +238,19 @@
 //! - [`Body::poll_frame`] returns an error.
 //! - An end-of-stream is classified as a failure.
 //!
+//! ### `on_completion`
+//!
+//! The `on_completion` callback is called once per request, with a [`Completion`] summarizing
+//! the request, when:
+//!
+//! - The inner [`Service`]'s response future resolves to an error.
+//! - A response with an empty body is produced.
+//! - The response body ends, or [`Body::poll_frame`] returns an error.
+//!
+//! It isn't called if the response body is dropped before it ends. Unlike the other callbacks,
+//! nothing is done by default. Use [`DefaultOnCompletion`] to emit a single `request.completed`
+//! event per request, both for successes and failures.
+//!
 //! # Recording fields on the span
 //!
 //! All callbacks receive a reference to the [tracing] [`Span`], corresponding to this request,
@@ -386,14 +399,16 @@
 
 use std::{fmt, time::Duration};
 
-use tracing::Level;
+use http::Version;
+use tracing::{Level, Span};
 
 pub use self::{
     body::ResponseBody,
     future::ResponseFuture,
     layer::TraceLayer,
-    make_span::{DefaultMakeSpan, MakeSpan},
+    make_span::{DefaultMakeSpan, DeferredSpan, MakeSpan, SkipSpanWhen},
     on_body_chunk::{DefaultOnBodyChunk, OnBodyChunk},
+    on_completion::{Completion, DefaultOnCompletion, OnCompletion},
     on_eos::{DefaultOnEos, OnEos},
     on_failure::{DefaultOnFailure, OnFailure},
     on_request::{DefaultOnRequest, OnRequest},
@@ -463,6 +478,7 @@ mod future;
 mod layer;
 mod make_span;
 mod on_body_chunk;
+mod on_completion;
 mod on_eos;
 mod on_failure;
 mod on_request;
@@ -488,20 +504,60 @@ impl fmt::Display for Latency {
     }
 }
 
+// The version as recorded in the `http.flavor` field.
+fn http_flavor(version: Version) -> Option<&'static str> {
+    match version {
+        Version::HTTP_09 => Some("0.9"),
+        Version::HTTP_10 => Some("1.0"),
+        Version::HTTP_11 => Some("1.1"),
+        Version::HTTP_2 => Some("2"),
+        Version::HTTP_3 => Some("3"),
+        _ => None,
+    }
+}
+
+fn record_ttfb(span: &Span, ttfb: Duration) {
+    let ttfb = Latency {
+        unit: LatencyUnit::Millis,
+        duration: ttfb,
+    };
+    span.record("http.ttfb", tracing::field::display(ttfb));
+}
+
+fn record_error_message(span: &Span, err: &dyn fmt::Display, max_len: usize) {
+    let mut message = err.to_string();
+    if message.len() > max_len {
+        let mut end = max_len;
+        while !message.is_char_boundary(end) {
+            end -= 1;
+        }
+        message.truncate(end);
+        message.push('…');
+    }
+    span.record("error.message", message.as_str());
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate::classify::ServerErrorsFailureClass;
     use crate::test_helpers::Body;
     use bytes::Bytes;
-    use http::{HeaderMap, Request, Response};
+    use http::{HeaderMap, Request, Response, StatusCode};
     use once_cell::sync::Lazy;
     use std::{
-        sync::atomic::{AtomicU32, Ordering},
+        sync::{
+            atomic::{AtomicU32, Ordering},
+            Arc, Mutex,
+        },
         time::Duration,
     };
     use tower::{BoxError, Service, ServiceBuilder, ServiceExt};
-    use tracing::Span;
+    use tracing::{
+        field::{Field, Visit},
+        span, Span,
+    };
+    use tracing_subscriber::{layer::Context, prelude::*, Layer};
 
     #[tokio::test]
     async fn unary_request() {
@@ -615,6 +671,351 @@ mod tests {
         assert_eq!(0, ON_FAILURE.load(Ordering::SeqCst), "failure");
     }
 
+    #[tokio::test]
+    async fn skip_span_when() {
+        static MAKE_SPAN_COUNT: Lazy<AtomicU32> = Lazy::new(|| AtomicU32::new(0));
+        static ON_FAILURE: Lazy<AtomicU32> = Lazy::new(|| AtomicU32::new(0));
+
+        // spans are disabled without a subscriber
+        let _subscriber = tracing::subscriber::set_default(tracing_subscriber::registry());
+
+        let trace_layer = TraceLayer::new_for_http()
+            .make_span_with(|_req: &Request<Body>| {
+                MAKE_SPAN_COUNT.fetch_add(1, Ordering::SeqCst);
+                tracing::info_span!("test-span")
+            })
+            .skip_span_when(|req: &Request<Body>| req.uri().path() == "/healthz")
+            .on_failure(
+                |_class: ServerErrorsFailureClass, _latency: Duration, span: &Span| {
+                    assert_eq!(span.metadata().map(|meta| meta.name()), Some("test-span"));
+                    ON_FAILURE.fetch_add(1, Ordering::SeqCst);
+                },
+            );
+
+        let mut svc =
+            ServiceBuilder::new()
+                .layer(trace_layer)
+                .service_fn(|req: Request<Body>| async move {
+                    let status = if req.uri().query() == Some("fail") {
+                        StatusCode::INTERNAL_SERVER_ERROR
+                    } else {
+                        StatusCode::OK
+                    };
+                    let res = Response::builder().status(status).body(Body::empty());
+                    Ok::<_, BoxError>(res.unwrap())
+                });
+
+        let req = Request::builder()
+            .uri("/healthz")
+            .body(Body::empty())
+            .unwrap();
+        svc.ready().await.unwrap().call(req).await.unwrap();
+        assert_eq!(0, MAKE_SPAN_COUNT.load(Ordering::SeqCst), "make span");
+        assert_eq!(0, ON_FAILURE.load(Ordering::SeqCst), "failure");
+
+        // the span of a failing request is made once the response is classified
+        let req = Request::builder()
+            .uri("/healthz?fail")
+            .body(Body::empty())
+            .unwrap();
+        svc.ready().await.unwrap().call(req).await.unwrap();
+        assert_eq!(1, MAKE_SPAN_COUNT.load(Ordering::SeqCst), "make span");
+        assert_eq!(1, ON_FAILURE.load(Ordering::SeqCst), "failure");
+
+        let req = Request::builder().uri("/").body(Body::empty()).unwrap();
+        svc.ready().await.unwrap().call(req).await.unwrap();
+        assert_eq!(2, MAKE_SPAN_COUNT.load(Ordering::SeqCst), "make span");
+    }
+
+    #[tokio::test]
+    async fn record_ttfb() {
+        use std::time::Instant;
+
+        async fn delayed_body(req: Request<Body>) -> Result<Response<Body>, BoxError> {
+            if req.uri().path() == "/empty" {
+                return Ok(Response::new(Body::empty()));
+            }
+
+            let stream = futures_util::stream::unfold(0, |n| async move {
+                match n {
+                    0 => Some((Ok::<_, BoxError>(Bytes::from("one")), 1)),
+                    1 => {
+                        tokio::time::sleep(Duration::from_millis(100)).await;
+                        Some((Ok::<_, BoxError>(Bytes::from("two")), 2))
+                    }
+                    _ => None,
+                }
+            });
+
+            Ok(Response::new(Body::from_stream(stream)))
+        }
+
+        let recorded = RecordedField::new("http.ttfb");
+        let _guard = recorded.set_default();
+
+        let mut svc = ServiceBuilder::new()
+            .layer(TraceLayer::new_for_http().record_ttfb(true))
+            .service_fn(delayed_body);
+
+        let start = Instant::now();
+        let req = Request::builder().uri("/").body(Body::empty()).unwrap();
+        let res = svc.ready().await.unwrap().call(req).await.unwrap();
+        crate::test_helpers::to_bytes(res.into_body())
+            .await
+            .unwrap();
+        let total = start.elapsed().as_millis();
+
+        let ttfb = recorded
+            .values()
+            .iter()
+            .map(|ttfb| ttfb.strip_suffix(" ms").unwrap().parse().unwrap())
+            .collect::<Vec<u128>>();
+        assert_eq!(ttfb.len(), 1);
+        assert!(ttfb[0] < total, "ttfb {} ms, total {} ms", ttfb[0], total);
+        assert!(total >= 100);
+
+        let req = Request::builder()
+            .uri("/empty")
+            .body(Body::empty())
+            .unwrap();
+        let res = svc.ready().await.unwrap().call(req).await.unwrap();
+        crate::test_helpers::to_bytes(res.into_body())
+            .await
+            .unwrap();
+        assert_eq!(recorded.values().len(), 2);
+    }
+
+    #[cfg(feature = "follow-redirect")]
+    #[tokio::test]
+    async fn record_redirects() {
+        use crate::follow_redirect::RedirectCount;
+
+        let recorded = RecordedField::new("http.redirects");
+        let _guard = recorded.set_default();
+
+        let mut svc = ServiceBuilder::new()
+            .layer(TraceLayer::new_for_http())
+            .service_fn(|req: Request<Body>| async move {
+                let mut res = Response::new(Body::empty());
+                if req.uri().path() == "/redirected" {
+                    res.extensions_mut().insert(RedirectCount(2));
+                }
+                Ok::<_, BoxError>(res)
+            });
+
+        let req = Request::builder().uri("/").body(Body::empty()).unwrap();
+        svc.ready().await.unwrap().call(req).await.unwrap();
+        assert!(recorded.values().is_empty());
+
+        let req = Request::builder()
+            .uri("/redirected")
+            .body(Body::empty())
+            .unwrap();
+        svc.ready().await.unwrap().call(req).await.unwrap();
+        assert_eq!(recorded.values(), ["2"]);
+    }
+
+    #[tokio::test]
+    async fn record_flavor() {
+        use http::Version;
+
+        let recorded = RecordedField::new("http.flavor");
+        let _guard = recorded.set_default();
+
+        let mut svc = ServiceBuilder::new()
+            .layer(TraceLayer::new_for_http())
+            .service_fn(|req: Request<Body>| async move {
+                let mut res = Response::new(Body::empty());
+                match req.uri().path() {
+                    "/h2" => *res.version_mut() = Version::HTTP_2,
+                    "/h3" => *res.version_mut() = Version::HTTP_3,
+                    // left to the default
+                    _ => {}
+                }
+                Ok::<_, BoxError>(res)
+            });
+
+        for (path, flavors) in [
+            ("/h3", &["2", "3"][..]),
+            ("/h2", &["2"][..]),
+            ("/", &["2"][..]),
+        ] {
+            recorded.clear();
+            let req = Request::builder()
+                .version(Version::HTTP_2)
+                .uri(path)
+                .body(Body::empty())
+                .unwrap();
+            svc.ready().await.unwrap().call(req).await.unwrap();
+            assert_eq!(recorded.values(), flavors, "{}", path);
+        }
+    }
+
+    #[tokio::test]
+    async fn record_error_message() {
+        #[derive(Debug)]
+        struct MyError(&'static str);
+
+        impl fmt::Display for MyError {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                write!(f, "my error: {}", self.0)
+            }
+        }
+
+        let recorded = RecordedField::new("error.message");
+        let _guard = recorded.set_default();
+
+        let mut svc = ServiceBuilder::new()
+            .layer(TraceLayer::new_for_http().record_error_message(16))
+            .service_fn(|req: Request<Body>| async move {
+                match req.uri().path() {
+                    "/short" => Err(MyError("boom")),
+                    "/long" => Err(MyError("connection reset by peer")),
+                    _ => Ok(Response::new(Body::empty())),
+                }
+            });
+
+        for path in ["/", "/short", "/long"] {
+            let req = Request::builder().uri(path).body(Body::empty()).unwrap();
+            let _ = svc.ready().await.unwrap().call(req).await;
+        }
+        assert_eq!(recorded.values(), ["my error: boom", "my error: connec…"]);
+    }
+
+    #[tokio::test]
+    async fn on_completion() {
+        use http::Method;
+
+        let completions = Arc::new(Mutex::new(Vec::new()));
+        let trace_layer = TraceLayer::new_for_http().on_completion({
+            let completions = completions.clone();
+            move |completion: &Completion, _span: &Span| {
+                completions.lock().unwrap().push(completion.clone());
+            }
+        });
+        let mut svc =
+            ServiceBuilder::new()
+                .layer(trace_layer)
+                .service_fn(|req: Request<Body>| async move {
+                    match req.uri().path() {
+                        "/ok" => Ok(Response::new(Body::from("foobar"))),
+                        "/fail" => Ok(Response::builder()
+                            .status(StatusCode::INTERNAL_SERVER_ERROR)
+                            .body(Body::from("oops"))
+                            .unwrap()),
+                        _ => Err(BoxError::from("boom")),
+                    }
+                });
+
+        for (method, path) in [(Method::GET, "/ok"), (Method::POST, "/fail")] {
+            let req = Request::builder()
+                .method(method)
+                .uri(path)
+                .body(Body::empty())
+                .unwrap();
+            let res = svc.ready().await.unwrap().call(req).await.unwrap();
+            // completed once the body has been sent
+            assert_eq!(completions.lock().unwrap().len(), 0);
+            crate::test_helpers::to_bytes(res.into_body())
+                .await
+                .unwrap();
+            assert_eq!(completions.lock().unwrap().len(), 1);
+
+            let completion = completions.lock().unwrap().pop().unwrap();
+            assert_eq!(completion.uri().path(), path);
+            if path == "/ok" {
+                assert_eq!(completion.method(), Method::GET);
+                assert_eq!(completion.status(), Some(StatusCode::OK));
+                assert_eq!(completion.bytes(), 6);
+                assert!(!completion.is_failure());
+            } else {
+                assert_eq!(completion.method(), Method::POST);
+                assert_eq!(completion.status(), Some(StatusCode::INTERNAL_SERVER_ERROR));
+                assert_eq!(completion.bytes(), 4);
+                assert!(completion.is_failure());
+            }
+        }
+
+        let req = Request::builder()
+            .uri("/error")
+            .body(Body::empty())
+            .unwrap();
+        let res = svc.ready().await.unwrap().call(req).await;
+        assert!(res.is_err());
+        let completion = completions.lock().unwrap().pop().unwrap();
+        assert_eq!(completion.status(), None);
+        assert_eq!(completion.bytes(), 0);
+        assert!(completion.is_failure());
+    }
+
+    #[tokio::test]
+    async fn no_completion_by_default() {
+        let mut svc = ServiceBuilder::new()
+            .layer(TraceLayer::new_for_http())
+            .service_fn(|_: Request<Body>| async {
+                Ok::<_, BoxError>(Response::new(Body::empty()))
+            });
+
+        // the request isn't captured for the `()` callback
+        let future = svc.ready().await.unwrap().call(Request::new(Body::empty()));
+        assert!(future.on_completion.is_none());
+        let res = future.await.unwrap();
+        assert!(res.into_body().on_completion.is_none());
+    }
+
+    // Records the values of a span field, as strings.
+    #[derive(Clone)]
+    struct RecordedField {
+        name: &'static str,
+        values: Arc<Mutex<Vec<String>>>,
+    }
+
+    impl RecordedField {
+        fn new(name: &'static str) -> Self {
+            Self {
+                name,
+                values: Default::default(),
+            }
+        }
+
+        // record the field of the spans of the current thread
+        fn set_default(&self) -> tracing::subscriber::DefaultGuard {
+            tracing::subscriber::set_default(tracing_subscriber::registry().with(self.clone()))
+        }
+
+        fn values(&self) -> Vec<String> {
+            self.values.lock().unwrap().clone()
+        }
+
+        fn clear(&self) {
+            self.values.lock().unwrap().clear();
+        }
+    }
+
+    impl<S: tracing::Subscriber> Layer<S> for RecordedField {
+        fn on_new_span(&self, attrs: &span::Attributes<'_>, _: &tracing::Id, _: Context<'_, S>) {
+            attrs.record(&mut &*self);
+        }
+
+        fn on_record(&self, _: &tracing::Id, values: &span::Record<'_>, _: Context<'_, S>) {
+            values.record(&mut &*self);
+        }
+    }
+
+    impl Visit for &RecordedField {
+        fn record_str(&mut self, field: &Field, value: &str) {
+            if field.name() == self.name {
+                self.values.lock().unwrap().push(value.to_owned());
+            }
+        }
+
+        fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+            if field.name() == self.name {
+                self.values.lock().unwrap().push(format!("{:?}", value));
+            }
+        }
+    }
+
     async fn echo(req: Request<Body>) -> Result<Response<Body>, BoxError> {
         Ok(Response::new(req.into_body()))
     }
@@ -0,0 +1,232 @@
+use super::{Latency, DEFAULT_MESSAGE_LEVEL};
+use crate::LatencyUnit;
+use http::{Method, StatusCode, Uri};
+use std::time::{Duration, Instant};
+use tracing::{Level, Span};
+
+/// Trait used to tell [`Trace`] what to do when a request has completed.
+///
+/// See the [module docs](../trace/index.html#on_completion) for details on exactly when the
+/// `on_completion` callback is called.
+///
+/// [`Trace`]: super::Trace
+pub trait OnCompletion {
+    /// Do the thing.
+    ///
+    /// `completion` describes the request and how it completed.
+    ///
+    /// `span` is the `tracing` [`Span`], corresponding to this request, produced by the closure
+    /// passed to [`TraceLayer::make_span_with`]. It can be used to [record field values][record]
+    /// that weren't known when the span was created.
+    ///
+    /// [`Span`]: https://docs.rs/tracing/latest/tracing/span/index.html
+    /// [record]: https://docs.rs/tracing/latest/tracing/span/struct.Span.html#method.record
+    /// [`TraceLayer::make_span_with`]: crate::trace::TraceLayer::make_span_with
+    fn on_completion(self, completion: &Completion, span: &Span);
+
+    /// Returns whether [`on_completion`](Self::on_completion) should be called.
+    ///
+    /// If `false`, [`Trace`] doesn't keep track of the request for this callback, so it costs
+    /// nothing. Defaults to `true`.
+    ///
+    /// [`Trace`]: super::Trace
+    fn is_enabled(&self) -> bool {
+        true
+    }
+}
+
+impl OnCompletion for () {
+    #[inline]
+    fn on_completion(self, _: &Completion, _: &Span) {}
+
+    #[inline]
+    fn is_enabled(&self) -> bool {
+        false
+    }
+}
+
+impl<F> OnCompletion for F
+where
+    F: FnOnce(&Completion, &Span),
+{
+    fn on_completion(self, completion: &Completion, span: &Span) {
+        self(completion, span)
+    }
+}
+
+/// A completed request, passed to [`OnCompletion`].
+#[derive(Clone, Debug)]
+pub struct Completion {
+    method: Method,
+    uri: Uri,
+    status: Option<StatusCode>,
+    latency: Duration,
+    bytes: u64,
+    failure: bool,
+}
+
+impl Completion {
+    /// Returns the method of the request.
+    pub fn method(&self) -> &Method {
+        &self.method
+    }
+
+    /// Returns the URI of the request.
+    pub fn uri(&self) -> &Uri {
+        &self.uri
+    }
+
+    /// Returns the status of the response, or `None` if the inner service failed.
+    pub fn status(&self) -> Option<StatusCode> {
+        self.status
+    }
+
+    /// Returns the duration since the request was received.
+    pub fn latency(&self) -> Duration {
+        self.latency
+    }
+
+    /// Returns the number of bytes of the response body that were sent.
+    pub fn bytes(&self) -> u64 {
+        self.bytes
+    }
+
+    /// Returns `true` if the request was classified as a failure.
+    ///
+    /// The failure class itself is passed to [`OnFailure`](super::OnFailure).
+    pub fn is_failure(&self) -> bool {
+        self.failure
+    }
+}
+
+// A completion that is waiting for the response, or the end of its body.
+pub(crate) struct PendingCompletion<OnCompletion> {
+    on_completion: OnCompletion,
+    completion: Completion,
+    start: Instant,
+}
+
+impl<T> PendingCompletion<T>
+where
+    T: OnCompletion,
+{
+    pub(crate) fn new(on_completion: T, method: Method, uri: Uri, start: Instant) -> Self {
+        Self {
+            on_completion,
+            completion: Completion {
+                method,
+                uri,
+                status: None,
+                latency: Duration::ZERO,
+                bytes: 0,
+                failure: false,
+            },
+            start,
+        }
+    }
+
+    pub(crate) fn set_status(&mut self, status: StatusCode) {
+        self.completion.status = Some(status);
+    }
+
+    pub(crate) fn set_failure(&mut self) {
+        self.completion.failure = true;
+    }
+
+    pub(crate) fn add_bytes(&mut self, bytes: usize) {
+        self.completion.bytes += bytes as u64;
+    }
+
+    pub(crate) fn complete(mut self, span: &Span) {
+        self.completion.latency = self.start.elapsed();
+        self.on_completion.on_completion(&self.completion, span);
+    }
+}
+
+/// An [`OnCompletion`] implementation that logs completed requests.
+///
+/// It emits a single `request.completed` event per request, with the method, path, status,
+/// latency, number of bytes and classification of the request as fields.
+///
+/// Unlike the other default callbacks, it isn't used by [`Trace`] unless it is set with
+/// [`TraceLayer::on_completion`]:
+///
+/// ```
+/// use tower_http::trace::{DefaultOnCompletion, TraceLayer};
+/// use tracing::Level;
+///
+/// let layer = TraceLayer::new_for_http()
+///     .on_completion(DefaultOnCompletion::new().level(Level::INFO));
+/// ```
+///
+/// [`Trace`]: super::Trace
+/// [`TraceLayer::on_completion`]: super::TraceLayer::on_completion
+#[derive(Clone, Debug)]
+pub struct DefaultOnCompletion {
+    level: Level,
+    latency_unit: LatencyUnit,
+}
+
+impl Default for DefaultOnCompletion {
+    fn default() -> Self {
+        Self {
+            level: DEFAULT_MESSAGE_LEVEL,
+            latency_unit: LatencyUnit::Millis,
+        }
+    }
+}
+
+impl DefaultOnCompletion {
+    /// Create a new `DefaultOnCompletion`.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the [`Level`] used for [tracing events].
+    ///
+    /// Defaults to [`Level::DEBUG`].
+    ///
+    /// [tracing events]: https://docs.rs/tracing/latest/tracing/#events
+    /// [`Level::DEBUG`]: https://docs.rs/tracing/latest/tracing/struct.Level.html#associatedconstant.DEBUG
+    pub fn level(mut self, level: Level) -> Self {
+        self.level = level;
+        self
+    }
+
+    /// Set the [`LatencyUnit`] latencies will be reported in.
+    ///
+    /// Defaults to [`LatencyUnit::Millis`].
+    pub fn latency_unit(mut self, latency_unit: LatencyUnit) -> Self {
+        self.latency_unit = latency_unit;
+        self
+    }
+}
+
+impl OnCompletion for DefaultOnCompletion {
+    fn on_completion(self, completion: &Completion, _: &Span) {
+        let latency = Latency {
+            unit: self.latency_unit,
+            duration: completion.latency,
+        };
+        let method = completion.method.as_str();
+        let path = completion.uri.path();
+        let status = completion.status.map(|status| status.as_u16());
+        let bytes = completion.bytes;
+        let classification = if completion.failure {
+            "failure"
+        } else {
+            "success"
+        };
+
+        event_dynamic_lvl!(
+            self.level,
+            method,
+            path,
+            status,
+            %latency,
+            bytes,
+            classification,
+            "request.completed"
+        );
+    }
+}
@@ -1,6 +1,6 @@
-use super::{Latency, DEFAULT_MESSAGE_LEVEL};
+use super::{http_flavor, Latency, DEFAULT_MESSAGE_LEVEL};
 use crate::LatencyUnit;
-use http::Response;
+use http::{Request, Response, Version};
 use std::time::Duration;
 use tracing::Level;
 use tracing::Span;
@@ -24,6 +24,17 @@ pub trait OnResponse<B> {
     /// [record]: https://docs.rs/tracing/latest/tracing/span/struct.Span.html#method.record
     /// [`TraceLayer::make_span_with`]: crate::trace::TraceLayer::make_span_with
     fn on_response(self, response: &Response<B>, latency: Duration, span: &Span);
+
+    /// Inspect the request whose response this callback will be called with.
+    ///
+    /// [`Trace`] calls this on the callback of each request before passing the request to the
+    /// inner service. [`DefaultOnResponse`] uses it to compare the versions of the request and
+    /// the response.
+    ///
+    /// The default implementation does nothing.
+    ///
+    /// [`Trace`]: super::Trace
+    fn inspect_request<ReqBody>(&mut self, _request: &Request<ReqBody>) {}
 }
 
 impl<B> OnResponse<B> for () {
@@ -42,12 +53,24 @@ where
 
 /// The default [`OnResponse`] implementation used by [`Trace`].
 ///
+/// If the `follow-redirect` feature is enabled and the response has a [`RedirectCount`]
+/// extension, it is recorded as the `http.redirects` field of the span.
+///
+/// If the version of the response differs from the version of the request, it is recorded as the
+/// `http.flavor` field of the span, in place of the version of the request recorded by
+/// [`DefaultMakeSpan`]. With clients, the version of the response is the one that was actually
+/// used, for example after ALPN negotiation. Services usually leave the version of their responses
+/// to the default, so responses with the default version, HTTP/1.1, aren't recorded.
+///
 /// [`Trace`]: super::Trace
+/// [`DefaultMakeSpan`]: super::DefaultMakeSpan
+/// [`RedirectCount`]: crate::follow_redirect::RedirectCount
 #[derive(Clone, Debug)]
 pub struct DefaultOnResponse {
     level: Level,
     latency_unit: LatencyUnit,
     include_headers: bool,
+    request_version: Option<Version>,
 }
 
 impl Default for DefaultOnResponse {
@@ -56,6 +79,7 @@ impl Default for DefaultOnResponse {
             level: DEFAULT_MESSAGE_LEVEL,
             latency_unit: LatencyUnit::Millis,
             include_headers: false,
+            request_version: None,
         }
     }
 }
@@ -102,7 +126,17 @@ impl DefaultOnResponse {
 }
 
 impl<B> OnResponse<B> for DefaultOnResponse {
-    fn on_response(self, response: &Response<B>, latency: Duration, _: &Span) {
+    fn on_response(self, response: &Response<B>, latency: Duration, span: &Span) {
+        record_redirects(response, span);
+        let version = response.version();
+        if self
+            .request_version
+            .map_or(false, |request| request != version)
+            && version != Version::HTTP_11
+        {
+            span.record("http.flavor", http_flavor(version));
+        }
+
         let latency = Latency {
             unit: self.latency_unit,
             duration: latency,
@@ -119,8 +153,25 @@ impl<B> OnResponse<B> for DefaultOnResponse {
             "finished processing request"
         );
     }
+
+    fn inspect_request<ReqBody>(&mut self, request: &Request<ReqBody>) {
+        self.request_version = Some(request.version());
+    }
 }
 
+#[cfg(feature = "follow-redirect")]
+fn record_redirects<B>(res: &Response<B>, span: &Span) {
+    if let Some(count) = res
+        .extensions()
+        .get::<crate::follow_redirect::RedirectCount>()
+    {
+        span.record("http.redirects", count.0);
+    }
+}
+
+#[cfg(not(feature = "follow-redirect"))]
+fn record_redirects<B>(_res: &Response<B>, _span: &Span) {}
+
 fn status<B>(res: &Response<B>) -> Option<i32> {
     use crate::classify::grpc_errors_as_failures::ParsedGrpcStatus;
 
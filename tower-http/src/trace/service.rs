@@ -1,7 +1,8 @@
 use super::{
-    DefaultMakeSpan, DefaultOnBodyChunk, DefaultOnEos, DefaultOnFailure, DefaultOnRequest,
-    DefaultOnResponse, GrpcMakeClassifier, HttpMakeClassifier, MakeSpan, OnBodyChunk, OnEos,
-    OnFailure, OnRequest, OnResponse, ResponseBody, ResponseFuture, TraceLayer,
+    on_completion::PendingCompletion, DefaultMakeSpan, DefaultOnBodyChunk, DefaultOnEos,
+    DefaultOnFailure, DefaultOnRequest, DefaultOnResponse, GrpcMakeClassifier, HttpMakeClassifier,
+    MakeSpan, OnBodyChunk, OnCompletion, OnEos, OnFailure, OnRequest, OnResponse, ResponseBody,
+    ResponseFuture, SkipSpanWhen, TraceLayer,
 };
 use crate::classify::{
     GrpcErrorsAsFailures, MakeClassifier, ServerErrorsAsFailures, SharedClassifier,
@@ -14,6 +15,7 @@ use std::{
     time::Instant,
 };
 use tower_service::Service;
+use tracing::Span;
 
 /// Middleware that adds high level [tracing] to a [`Service`].
 ///
@@ -31,6 +33,7 @@ pub struct Trace<
     OnBodyChunk = DefaultOnBodyChunk,
     OnEos = DefaultOnEos,
     OnFailure = DefaultOnFailure,
+    OnCompletion = (),
 > {
     pub(crate) inner: S,
     pub(crate) make_classifier: M,
@@ -40,6 +43,9 @@ pub struct Trace<
     pub(crate) on_body_chunk: OnBodyChunk,
     pub(crate) on_eos: OnEos,
     pub(crate) on_failure: OnFailure,
+    pub(crate) on_completion: OnCompletion,
+    pub(crate) record_ttfb: bool,
+    pub(crate) record_error_message: Option<usize>,
 }
 
 impl<S, M> Trace<S, M> {
@@ -57,6 +63,9 @@ impl<S, M> Trace<S, M> {
             on_body_chunk: DefaultOnBodyChunk::default(),
             on_eos: DefaultOnEos::default(),
             on_failure: DefaultOnFailure::default(),
+            record_ttfb: false,
+            record_error_message: None,
+            on_completion: (),
         }
     }
 
@@ -71,8 +80,8 @@ impl<S, M> Trace<S, M> {
     }
 }
 
-impl<S, M, MakeSpan, OnRequest, OnResponse, OnBodyChunk, OnEos, OnFailure>
-    Trace<S, M, MakeSpan, OnRequest, OnResponse, OnBodyChunk, OnEos, OnFailure>
+impl<S, M, MakeSpan, OnRequest, OnResponse, OnBodyChunk, OnEos, OnFailure, OnCompletion>
+    Trace<S, M, MakeSpan, OnRequest, OnResponse, OnBodyChunk, OnEos, OnFailure, OnCompletion>
 {
     define_inner_service_accessors!();
 
@@ -84,7 +93,8 @@ impl<S, M, MakeSpan, OnRequest, OnResponse, OnBodyChunk, OnEos, OnFailure>
     pub fn on_request<NewOnRequest>(
         self,
         new_on_request: NewOnRequest,
-    ) -> Trace<S, M, MakeSpan, NewOnRequest, OnResponse, OnBodyChunk, OnEos, OnFailure> {
+    ) -> Trace<S, M, MakeSpan, NewOnRequest, OnResponse, OnBodyChunk, OnEos, OnFailure, OnCompletion>
+    {
         Trace {
             on_request: new_on_request,
             inner: self.inner,
@@ -94,6 +104,9 @@ impl<S, M, MakeSpan, OnRequest, OnResponse, OnBodyChunk, OnEos, OnFailure>
             make_span: self.make_span,
             on_response: self.on_response,
             make_classifier: self.make_classifier,
+            record_ttfb: self.record_ttfb,
+            record_error_message: self.record_error_message,
+            on_completion: self.on_completion,
         }
     }
 
@@ -105,7 +118,8 @@ impl<S, M, MakeSpan, OnRequest, OnResponse, OnBodyChunk, OnEos, OnFailure>
     pub fn on_response<NewOnResponse>(
         self,
         new_on_response: NewOnResponse,
-    ) -> Trace<S, M, MakeSpan, OnRequest, NewOnResponse, OnBodyChunk, OnEos, OnFailure> {
+    ) -> Trace<S, M, MakeSpan, OnRequest, NewOnResponse, OnBodyChunk, OnEos, OnFailure, OnCompletion>
+    {
         Trace {
             on_response: new_on_response,
             inner: self.inner,
@@ -115,6 +129,9 @@ impl<S, M, MakeSpan, OnRequest, OnResponse, OnBodyChunk, OnEos, OnFailure>
             on_eos: self.on_eos,
             make_span: self.make_span,
             make_classifier: self.make_classifier,
+            record_ttfb: self.record_ttfb,
+            record_error_message: self.record_error_message,
+            on_completion: self.on_completion,
         }
     }
 
@@ -126,7 +143,8 @@ impl<S, M, MakeSpan, OnRequest, OnResponse, OnBodyChunk, OnEos, OnFailure>
     pub fn on_body_chunk<NewOnBodyChunk>(
         self,
         new_on_body_chunk: NewOnBodyChunk,
-    ) -> Trace<S, M, MakeSpan, OnRequest, OnResponse, NewOnBodyChunk, OnEos, OnFailure> {
+    ) -> Trace<S, M, MakeSpan, OnRequest, OnResponse, NewOnBodyChunk, OnEos, OnFailure, OnCompletion>
+    {
         Trace {
             on_body_chunk: new_on_body_chunk,
             on_eos: self.on_eos,
@@ -136,6 +154,9 @@ impl<S, M, MakeSpan, OnRequest, OnResponse, OnBodyChunk, OnEos, OnFailure>
             on_request: self.on_request,
             on_response: self.on_response,
             make_classifier: self.make_classifier,
+            record_ttfb: self.record_ttfb,
+            record_error_message: self.record_error_message,
+            on_completion: self.on_completion,
         }
     }
 
@@ -147,7 +168,8 @@ impl<S, M, MakeSpan, OnRequest, OnResponse, OnBodyChunk, OnEos, OnFailure>
     pub fn on_eos<NewOnEos>(
         self,
         new_on_eos: NewOnEos,
-    ) -> Trace<S, M, MakeSpan, OnRequest, OnResponse, OnBodyChunk, NewOnEos, OnFailure> {
+    ) -> Trace<S, M, MakeSpan, OnRequest, OnResponse, OnBodyChunk, NewOnEos, OnFailure, OnCompletion>
+    {
         Trace {
             on_eos: new_on_eos,
             make_span: self.make_span,
@@ -157,6 +179,9 @@ impl<S, M, MakeSpan, OnRequest, OnResponse, OnBodyChunk, OnEos, OnFailure>
             on_body_chunk: self.on_body_chunk,
             on_response: self.on_response,
             make_classifier: self.make_classifier,
+            record_ttfb: self.record_ttfb,
+            record_error_message: self.record_error_message,
+            on_completion: self.on_completion,
         }
     }
 
@@ -168,7 +193,8 @@ impl<S, M, MakeSpan, OnRequest, OnResponse, OnBodyChunk, OnEos, OnFailure>
     pub fn on_failure<NewOnFailure>(
         self,
         new_on_failure: NewOnFailure,
-    ) -> Trace<S, M, MakeSpan, OnRequest, OnResponse, OnBodyChunk, OnEos, NewOnFailure> {
+    ) -> Trace<S, M, MakeSpan, OnRequest, OnResponse, OnBodyChunk, OnEos, NewOnFailure, OnCompletion>
+    {
         Trace {
             on_failure: new_on_failure,
             inner: self.inner,
@@ -178,6 +204,34 @@ impl<S, M, MakeSpan, OnRequest, OnResponse, OnBodyChunk, OnEos, OnFailure>
             on_eos: self.on_eos,
             on_response: self.on_response,
             make_classifier: self.make_classifier,
+            record_ttfb: self.record_ttfb,
+            record_error_message: self.record_error_message,
+            on_completion: self.on_completion,
+        }
+    }
+
+    /// Customize what to do when a request has completed.
+    ///
+    /// `NewOnCompletion` is expected to implement [`OnCompletion`].
+    ///
+    /// [`OnCompletion`]: super::OnCompletion
+    pub fn on_completion<NewOnCompletion>(
+        self,
+        new_on_completion: NewOnCompletion,
+    ) -> Trace<S, M, MakeSpan, OnRequest, OnResponse, OnBodyChunk, OnEos, OnFailure, NewOnCompletion>
+    {
+        Trace {
+            on_completion: new_on_completion,
+            inner: self.inner,
+            make_span: self.make_span,
+            on_request: self.on_request,
+            on_response: self.on_response,
+            on_body_chunk: self.on_body_chunk,
+            on_eos: self.on_eos,
+            on_failure: self.on_failure,
+            make_classifier: self.make_classifier,
+            record_ttfb: self.record_ttfb,
+            record_error_message: self.record_error_message,
         }
     }
 
@@ -190,7 +244,8 @@ impl<S, M, MakeSpan, OnRequest, OnResponse, OnBodyChunk, OnEos, OnFailure>
     pub fn make_span_with<NewMakeSpan>(
         self,
         new_make_span: NewMakeSpan,
-    ) -> Trace<S, M, NewMakeSpan, OnRequest, OnResponse, OnBodyChunk, OnEos, OnFailure> {
+    ) -> Trace<S, M, NewMakeSpan, OnRequest, OnResponse, OnBodyChunk, OnEos, OnFailure, OnCompletion>
+    {
         Trace {
             make_span: new_make_span,
             inner: self.inner,
@@ -200,8 +255,70 @@ impl<S, M, MakeSpan, OnRequest, OnResponse, OnBodyChunk, OnEos, OnFailure>
             on_response: self.on_response,
             on_eos: self.on_eos,
             make_classifier: self.make_classifier,
+            record_ttfb: self.record_ttfb,
+            record_error_message: self.record_error_message,
+            on_completion: self.on_completion,
         }
     }
+
+    /// Don't make a [`Span`] for requests matching `predicate`, unless they fail.
+    ///
+    /// See [`TraceLayer::skip_span_when`] for more details.
+    ///
+    /// [`Span`]: tracing::Span
+    pub fn skip_span_when<F>(
+        self,
+        predicate: F,
+    ) -> Trace<
+        S,
+        M,
+        SkipSpanWhen<MakeSpan, F>,
+        OnRequest,
+        OnResponse,
+        OnBodyChunk,
+        OnEos,
+        OnFailure,
+        OnCompletion,
+    > {
+        Trace {
+            make_span: SkipSpanWhen {
+                make_span: self.make_span,
+                predicate,
+            },
+            inner: self.inner,
+            on_failure: self.on_failure,
+            on_request: self.on_request,
+            on_body_chunk: self.on_body_chunk,
+            on_response: self.on_response,
+            on_eos: self.on_eos,
+            make_classifier: self.make_classifier,
+            record_ttfb: self.record_ttfb,
+            record_error_message: self.record_error_message,
+            on_completion: self.on_completion,
+        }
+    }
+
+    /// Record the time to first byte on the [`Span`], as the `http.ttfb` field.
+    ///
+    /// See [`TraceLayer::record_ttfb`] for more details.
+    ///
+    /// [`Span`]: tracing::Span
+    pub fn record_ttfb(mut self, record_ttfb: bool) -> Self {
+        self.record_ttfb = record_ttfb;
+        self
+    }
+
+    /// Record the [`Display`] representation of errors returned by the inner service on the
+    /// [`Span`], as the `error.message` field.
+    ///
+    /// See [`TraceLayer::record_error_message`] for more details.
+    ///
+    /// [`Display`]: std::fmt::Display
+    /// [`Span`]: tracing::Span
+    pub fn record_error_message(mut self, max_len: usize) -> Self {
+        self.record_error_message = Some(max_len);
+        self
+    }
 }
 
 impl<S>
@@ -228,6 +345,9 @@ impl<S>
             on_body_chunk: DefaultOnBodyChunk::default(),
             on_eos: DefaultOnEos::default(),
             on_failure: DefaultOnFailure::default(),
+            record_ttfb: false,
+            record_error_message: None,
+            on_completion: (),
         }
     }
 }
@@ -256,6 +376,9 @@ impl<S>
             on_body_chunk: DefaultOnBodyChunk::default(),
             on_eos: DefaultOnEos::default(),
             on_failure: DefaultOnFailure::default(),
+            record_ttfb: false,
+            record_error_message: None,
+            on_completion: (),
         }
     }
 }
@@ -270,9 +393,20 @@ impl<
         OnFailureT,
         OnBodyChunkT,
         OnEosT,
+        OnCompletionT,
         MakeSpanT,
     > Service<Request<ReqBody>>
-    for Trace<S, M, MakeSpanT, OnRequestT, OnResponseT, OnBodyChunkT, OnEosT, OnFailureT>
+    for Trace<
+        S,
+        M,
+        MakeSpanT,
+        OnRequestT,
+        OnResponseT,
+        OnBodyChunkT,
+        OnEosT,
+        OnFailureT,
+        OnCompletionT,
+    >
 where
     S: Service<Request<ReqBody>, Response = Response<ResBody>>,
     ReqBody: Body,
@@ -287,12 +421,21 @@ where
     OnBodyChunkT: OnBodyChunk<ResBody::Data> + Clone,
     OnEosT: OnEos + Clone,
     OnFailureT: OnFailure<M::FailureClass> + Clone,
+    OnCompletionT: OnCompletion + Clone,
 {
-    type Response =
-        Response<ResponseBody<ResBody, M::ClassifyEos, OnBodyChunkT, OnEosT, OnFailureT>>;
+    type Response = Response<
+        ResponseBody<ResBody, M::ClassifyEos, OnBodyChunkT, OnEosT, OnFailureT, OnCompletionT>,
+    >;
     type Error = S::Error;
-    type Future =
-        ResponseFuture<S::Future, M::Classifier, OnResponseT, OnBodyChunkT, OnEosT, OnFailureT>;
+    type Future = ResponseFuture<
+        S::Future,
+        M::Classifier,
+        OnResponseT,
+        OnBodyChunkT,
+        OnEosT,
+        OnFailureT,
+        OnCompletionT,
+    >;
 
     fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
         self.inner.poll_ready(cx)
@@ -301,10 +444,25 @@ where
     fn call(&mut self, req: Request<ReqBody>) -> Self::Future {
         let start = Instant::now();
 
-        let span = self.make_span.make_span(&req);
+        let (span, deferred_span) = match self.make_span.defer_span(&req) {
+            Some(deferred_span) => (Span::none(), Some(deferred_span)),
+            None => (self.make_span.make_span(&req), None),
+        };
 
         let classifier = self.make_classifier.make_classifier(&req);
 
+        let mut on_response = self.on_response.clone();
+        on_response.inspect_request(&req);
+
+        let on_completion = self.on_completion.is_enabled().then(|| {
+            PendingCompletion::new(
+                self.on_completion.clone(),
+                req.method().clone(),
+                req.uri().clone(),
+                start,
+            )
+        });
+
         let future = {
             let _guard = span.enter();
             self.on_request.on_request(&req, &span);
@@ -314,11 +472,15 @@ where
         ResponseFuture {
             inner: future,
             span,
+            deferred_span,
             classifier: Some(classifier),
-            on_response: Some(self.on_response.clone()),
+            on_response: Some(on_response),
             on_body_chunk: Some(self.on_body_chunk.clone()),
             on_eos: Some(self.on_eos.clone()),
             on_failure: Some(self.on_failure.clone()),
+            record_ttfb: self.record_ttfb,
+            on_completion,
+            record_error_message: self.record_error_message,
             start,
         }
     }
@@ -0,0 +1,323 @@
+//! Middleware that rejects requests with duplicate, invalid or conflicting `Host` headers.
+//!
+//! Proxies and servers that disagree about which host a request is for open the door to request
+//! smuggling and cache poisoning. [`ValidateHost`] rejects requests with `400 Bad Request`,
+//! without calling the inner service, when:
+//!
+//! - More than one `Host` header is present.
+//! - The `Host` header isn't a valid authority.
+//! - For HTTP/2 and later, the `Host` header doesn't match the `:authority` pseudo-header.
+//!
+//! Optionally, the host of requests can also be checked against a list of expected hosts. The
+//! host is then taken from the `:authority` pseudo-header, or else the `Host` header, and
+//! requests without either are rejected.
+//!
+//! # Example
+//!
+//! ```
+//! use tower_http::validate_host::ValidateHostLayer;
+//! use http::{header::HOST, Request, Response, StatusCode};
+//! use http_body_util::Full;
+//! use bytes::Bytes;
+//! use std::convert::Infallible;
+//! use tower::{ServiceBuilder, Service, ServiceExt};
+//!
+//! async fn handle(req: Request<Full<Bytes>>) -> Result<Response<Full<Bytes>>, Infallible> {
+//!     Ok(Response::new(Full::default()))
+//! }
+//!
+//! # #[tokio::main]
+//! # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+//! let mut service = ServiceBuilder::new()
+//!     .layer(ValidateHostLayer::new().allow_hosts(&["example.com"]))
+//!     .service_fn(handle);
+//!
+//! let request = Request::builder()
+//!     .header(HOST, "example.com:8080")
+//!     .body(Full::default())?;
+//! let response = service.ready().await?.call(request).await?;
+//! assert_eq!(response.status(), StatusCode::OK);
+//!
+//! let request = Request::builder()
+//!     .header(HOST, "example.com")
+//!     .header(HOST, "evil.example")
+//!     .body(Full::default())?;
+//! let response = service.ready().await?.call(request).await?;
+//! assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+//! #
+//! # Ok(())
+//! # }
+//! ```
+
+use http::{header::HOST, uri::Authority, Request, Response, StatusCode, Version};
+use pin_project_lite::pin_project;
+use std::{
+    future::Future,
+    pin::Pin,
+    sync::Arc,
+    task::{Context, Poll},
+};
+use tower_layer::Layer;
+use tower_service::Service;
+
+/// Layer that applies [`ValidateHost`] which rejects requests with duplicate, invalid or
+/// conflicting `Host` headers.
+///
+/// See the [module docs](self) for more details.
+#[derive(Debug, Clone, Default)]
+pub struct ValidateHostLayer {
+    allowed: Option<Arc<[Box<str>]>>,
+}
+
+impl ValidateHostLayer {
+    /// Create a new [`ValidateHostLayer`] that accepts any host.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Only accept requests for one of the given hosts.
+    ///
+    /// Hosts are compared case-insensitively and without the port.
+    pub fn allow_hosts(mut self, hosts: &[&str]) -> Self {
+        let hosts = hosts
+            .iter()
+            .map(|&host| Box::from(host))
+            .collect::<Vec<_>>();
+        self.allowed = Some(hosts.into());
+        self
+    }
+}
+
+impl<S> Layer<S> for ValidateHostLayer {
+    type Service = ValidateHost<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        ValidateHost {
+            inner,
+            allowed: self.allowed.clone(),
+        }
+    }
+}
+
+/// Middleware that rejects requests with duplicate, invalid or conflicting `Host` headers.
+///
+/// See the [module docs](self) for more details.
+#[derive(Debug, Clone)]
+pub struct ValidateHost<S> {
+    inner: S,
+    allowed: Option<Arc<[Box<str>]>>,
+}
+
+impl<S> ValidateHost<S> {
+    /// Create a new [`ValidateHost`] that accepts any host.
+    pub fn new(inner: S) -> Self {
+        ValidateHostLayer::new().layer(inner)
+    }
+
+    /// Only accept requests for one of the given hosts.
+    ///
+    /// Hosts are compared case-insensitively and without the port.
+    pub fn allow_hosts(mut self, hosts: &[&str]) -> Self {
+        self.allowed = ValidateHostLayer::new().allow_hosts(hosts).allowed;
+        self
+    }
+
+    define_inner_service_accessors!();
+
+    /// Returns a new [`Layer`] that wraps services with a `ValidateHost` middleware.
+    ///
+    /// [`Layer`]: tower_layer::Layer
+    pub fn layer() -> ValidateHostLayer {
+        ValidateHostLayer::new()
+    }
+}
+
+impl<ReqBody, ResBody, S> Service<Request<ReqBody>> for ValidateHost<S>
+where
+    S: Service<Request<ReqBody>, Response = Response<ResBody>>,
+    ResBody: Default,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = ResponseFuture<S::Future>;
+
+    #[inline]
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, req: Request<ReqBody>) -> Self::Future {
+        if !is_valid(&req, self.allowed.as_deref()) {
+            return ResponseFuture {
+                inner: ResponseFutureInner::BadRequest,
+            };
+        }
+
+        ResponseFuture {
+            inner: ResponseFutureInner::Future {
+                future: self.inner.call(req),
+            },
+        }
+    }
+}
+
+fn is_valid<B>(req: &Request<B>, allowed: Option<&[Box<str>]>) -> bool {
+    let mut hosts = req.headers().get_all(HOST).iter();
+    let host = match (hosts.next(), hosts.next()) {
+        (None, _) => None,
+        (Some(host), None) => match host
+            .to_str()
+            .ok()
+            .and_then(|host| host.parse::<Authority>().ok())
+        {
+            Some(host) => Some(host),
+            None => return false,
+        },
+        (Some(_), Some(_)) => return false,
+    };
+
+    // in HTTP/1, the `Host` header takes precedence over an absolute-form target, so only
+    // conflicts with the `:authority` pseudo-header of later versions are rejected
+    let authority = req.uri().authority();
+    if req.version() >= Version::HTTP_2 {
+        if let (Some(host), Some(authority)) = (&host, authority) {
+            if host != authority {
+                return false;
+            }
+        }
+    }
+
+    match allowed {
+        None => true,
+        Some(allowed) => {
+            let host = match (authority, &host) {
+                (Some(authority), _) if req.version() >= Version::HTTP_2 => authority.host(),
+                (_, Some(host)) => host.host(),
+                (Some(authority), None) => authority.host(),
+                (None, None) => return false,
+            };
+            allowed
+                .iter()
+                .any(|allowed| allowed.eq_ignore_ascii_case(host))
+        }
+    }
+}
+
+pin_project! {
+    /// Response future for [`ValidateHost`].
+    pub struct ResponseFuture<F> {
+        #[pin]
+        inner: ResponseFutureInner<F>,
+    }
+}
+
+pin_project! {
+    #[project = ResFutProj]
+    enum ResponseFutureInner<F> {
+        BadRequest,
+        Future {
+            #[pin]
+            future: F,
+        }
+    }
+}
+
+impl<ResBody, F, E> Future for ResponseFuture<F>
+where
+    ResBody: Default,
+    F: Future<Output = Result<Response<ResBody>, E>>,
+{
+    type Output = Result<Response<ResBody>, E>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        match self.project().inner.project() {
+            ResFutProj::BadRequest => {
+                let mut res = Response::new(ResBody::default());
+                *res.status_mut() = StatusCode::BAD_REQUEST;
+                Poll::Ready(Ok(res))
+            }
+            ResFutProj::Future { future } => future.poll(cx),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_helpers::Body;
+    use std::convert::Infallible;
+    use tower::{ServiceBuilder, ServiceExt};
+
+    async fn status(layer: ValidateHostLayer, req: Request<Body>) -> StatusCode {
+        let svc = ServiceBuilder::new()
+            .layer(layer)
+            .service_fn(|_: Request<Body>| async {
+                Ok::<_, Infallible>(Response::new(Body::empty()))
+            });
+        svc.oneshot(req).await.unwrap().status()
+    }
+
+    #[tokio::test]
+    async fn accepts_single_host() {
+        let req = Request::builder()
+            .header(HOST, "example.com")
+            .body(Body::empty())
+            .unwrap();
+        assert_eq!(status(ValidateHostLayer::new(), req).await, StatusCode::OK);
+
+        let req = Request::builder()
+            .version(Version::HTTP_2)
+            .uri("https://Example.com/")
+            .header(HOST, "example.com")
+            .body(Body::empty())
+            .unwrap();
+        assert_eq!(status(ValidateHostLayer::new(), req).await, StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn rejects_duplicate_host() {
+        let req = Request::builder()
+            .header(HOST, "example.com")
+            .header(HOST, "example.com")
+            .body(Body::empty())
+            .unwrap();
+        assert_eq!(
+            status(ValidateHostLayer::new(), req).await,
+            StatusCode::BAD_REQUEST
+        );
+    }
+
+    #[tokio::test]
+    async fn rejects_host_conflicting_with_authority() {
+        let req = Request::builder()
+            .version(Version::HTTP_2)
+            .uri("https://example.com/")
+            .header(HOST, "evil.example")
+            .body(Body::empty())
+            .unwrap();
+        assert_eq!(
+            status(ValidateHostLayer::new(), req).await,
+            StatusCode::BAD_REQUEST
+        );
+    }
+
+    #[tokio::test]
+    async fn checks_allowed_hosts() {
+        let layer = ValidateHostLayer::new().allow_hosts(&["example.com"]);
+
+        let req = Request::builder()
+            .header(HOST, "EXAMPLE.com:8080")
+            .body(Body::empty())
+            .unwrap();
+        assert_eq!(status(layer.clone(), req).await, StatusCode::OK);
+
+        let req = Request::builder()
+            .header(HOST, "evil.example")
+            .body(Body::empty())
+            .unwrap();
+        assert_eq!(status(layer.clone(), req).await, StatusCode::BAD_REQUEST);
+
+        let req = Request::builder().body(Body::empty()).unwrap();
+        assert_eq!(status(layer, req).await, StatusCode::BAD_REQUEST);
+    }
+}
@@ -117,6 +117,7 @@
 //! ```
 
 use http::{header, Request, Response, StatusCode};
+use http_body::Body;
 use mime::{Mime, MimeIter};
 use pin_project_lite::pin_project;
 use std::{
@@ -168,6 +169,34 @@ impl<ResBody> ValidateRequestHeaderLayer<AcceptHeader<ResBody>> {
     }
 }
 
+impl<ResBody> ValidateRequestHeaderLayer<ContentTypeHeader<ResBody>> {
+    /// Validate requests with a body have one of the given `Content-Type`s.
+    ///
+    /// Requests with a body and a missing or different `Content-Type` get a
+    /// `415 Unsupported Media Type` response. Parameters such as `charset` are ignored when
+    /// comparing content types. Requests without a body are always allowed through.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use http_body_util::Full;
+    /// use bytes::Bytes;
+    /// use tower_http::validate_request::{ContentTypeHeader, ValidateRequestHeaderLayer};
+    ///
+    /// let layer = ValidateRequestHeaderLayer::<ContentTypeHeader<Full<Bytes>>>::content_type(&[
+    ///     mime::APPLICATION_JSON,
+    /// ]);
+    /// ```
+    ///
+    /// [`Content-Type`]: https://developer.mozilla.org/en-US/docs/Web/HTTP/Headers/Content-Type
+    pub fn content_type(mimes: &[Mime]) -> Self
+    where
+        ResBody: Default,
+    {
+        Self::custom(ContentTypeHeader::new(mimes))
+    }
+}
+
 impl<T> ValidateRequestHeaderLayer<T> {
     /// Validate requests using a custom method.
     pub fn custom(validate: T) -> ValidateRequestHeaderLayer<T> {
@@ -220,6 +249,18 @@ impl<S, ResBody> ValidateRequestHeader<S, AcceptHeader<ResBody>> {
     }
 }
 
+impl<S, ResBody> ValidateRequestHeader<S, ContentTypeHeader<ResBody>> {
+    /// Validate requests with a body have one of the given `Content-Type`s.
+    ///
+    /// See [`ValidateRequestHeaderLayer::content_type`] for more details.
+    pub fn content_type(inner: S, mimes: &[Mime]) -> Self
+    where
+        ResBody: Default,
+    {
+        Self::custom(inner, ContentTypeHeader::new(mimes))
+    }
+}
+
 impl<S, T> ValidateRequestHeader<S, T> {
     /// Validate requests using a custom method.
     pub fn custom(inner: S, validate: T) -> ValidateRequestHeader<S, T> {
@@ -411,6 +452,73 @@ where
     }
 }
 
+/// Type that performs validation of the Content-Type header.
+pub struct ContentTypeHeader<ResBody> {
+    mimes: Arc<[Mime]>,
+    _ty: PhantomData<fn() -> ResBody>,
+}
+
+impl<ResBody> ContentTypeHeader<ResBody> {
+    fn new(mimes: &[Mime]) -> Self
+    where
+        ResBody: Default,
+    {
+        Self {
+            mimes: mimes.into(),
+            _ty: PhantomData,
+        }
+    }
+
+    fn matches(&self, content_type: &str) -> bool {
+        content_type.parse::<Mime>().map_or(false, |mime| {
+            self.mimes
+                .iter()
+                .any(|allowed| allowed.essence_str() == mime.essence_str())
+        })
+    }
+}
+
+impl<ResBody> Clone for ContentTypeHeader<ResBody> {
+    fn clone(&self) -> Self {
+        Self {
+            mimes: self.mimes.clone(),
+            _ty: PhantomData,
+        }
+    }
+}
+
+impl<ResBody> fmt::Debug for ContentTypeHeader<ResBody> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ContentTypeHeader")
+            .field("mimes", &self.mimes)
+            .finish()
+    }
+}
+
+impl<B, ResBody> ValidateRequest<B> for ContentTypeHeader<ResBody>
+where
+    B: Body,
+    ResBody: Default,
+{
+    type ResponseBody = ResBody;
+
+    fn validate(&mut self, req: &mut Request<B>) -> Result<(), Response<Self::ResponseBody>> {
+        if req.body().is_end_stream() {
+            return Ok(());
+        }
+        let content_type = req
+            .headers()
+            .get(header::CONTENT_TYPE)
+            .and_then(|value| value.to_str().ok());
+        if content_type.map_or(false, |content_type| self.matches(content_type)) {
+            return Ok(());
+        }
+        let mut res = Response::new(ResBody::default());
+        *res.status_mut() = StatusCode::UNSUPPORTED_MEDIA_TYPE;
+        Err(res)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     #[allow(unused_imports)]
@@ -581,6 +689,90 @@ mod tests {
         assert_eq!(res.status(), StatusCode::NOT_ACCEPTABLE);
     }
 
+    #[tokio::test]
+    async fn valid_content_type_header() {
+        let mut service = ServiceBuilder::new()
+            .layer(ValidateRequestHeaderLayer::content_type(&[
+                mime::APPLICATION_JSON,
+            ]))
+            .service_fn(echo);
+
+        let request = Request::post("/")
+            .header(header::CONTENT_TYPE, "application/json")
+            .body(Body::from("{}"))
+            .unwrap();
+
+        let res = service.ready().await.unwrap().call(request).await.unwrap();
+
+        assert_eq!(res.status(), StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn valid_content_type_header_with_parameters() {
+        let mut service = ServiceBuilder::new()
+            .layer(ValidateRequestHeaderLayer::content_type(&[
+                mime::APPLICATION_JSON,
+            ]))
+            .service_fn(echo);
+
+        let request = Request::post("/")
+            .header(header::CONTENT_TYPE, "application/json; charset=utf-8")
+            .body(Body::from("{}"))
+            .unwrap();
+
+        let res = service.ready().await.unwrap().call(request).await.unwrap();
+
+        assert_eq!(res.status(), StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn invalid_content_type_header() {
+        let mut service = ServiceBuilder::new()
+            .layer(ValidateRequestHeaderLayer::content_type(&[
+                mime::APPLICATION_JSON,
+            ]))
+            .service_fn(echo);
+
+        let request = Request::post("/")
+            .header(header::CONTENT_TYPE, "text/plain")
+            .body(Body::from("{}"))
+            .unwrap();
+
+        let res = service.ready().await.unwrap().call(request).await.unwrap();
+
+        assert_eq!(res.status(), StatusCode::UNSUPPORTED_MEDIA_TYPE);
+
+        let request = Request::post("/").body(Body::from("{}")).unwrap();
+
+        let res = service.ready().await.unwrap().call(request).await.unwrap();
+
+        assert_eq!(res.status(), StatusCode::UNSUPPORTED_MEDIA_TYPE);
+    }
+
+    #[tokio::test]
+    async fn content_type_header_not_required_without_body() {
+        let mut service = ServiceBuilder::new()
+            .layer(ValidateRequestHeaderLayer::content_type(&[
+                mime::APPLICATION_JSON,
+            ]))
+            .service_fn(echo);
+
+        let request = Request::get("/").body(Body::empty()).unwrap();
+
+        let res = service.ready().await.unwrap().call(request).await.unwrap();
+
+        assert_eq!(res.status(), StatusCode::OK);
+
+        let request = Request::post("/")
+            .header(header::CONTENT_TYPE, "text/plain")
+            .body(Body::empty())
+            .unwrap();
+
+        let res = service.ready().await.unwrap().call(request).await.unwrap();
+
+        assert_eq!(res.status(), StatusCode::OK);
+    }
+
     async fn echo(req: Request<Body>) -> Result<Response<Body>, BoxError> {
         Ok(Response::new(req.into_body()))
     }